@@ -8,6 +8,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         "proto/instrument.proto",
         "proto/resources.proto",
         "proto/async_ops.proto",
+        "proto/runtime.proto",
     ];
     let dirs = &["proto"];
 
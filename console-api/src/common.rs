@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
@@ -76,6 +77,28 @@ impl fmt::Display for field::Value {
             field::Value::U64Val(v) => fmt::Display::fmt(v, f)?,
             field::Value::DebugVal(v) => fmt::Display::fmt(v, f)?,
             field::Value::I64Val(v) => fmt::Display::fmt(v, f)?,
+            field::Value::F64Val(v) => fmt::Display::fmt(v, f)?,
+            field::Value::U128Val(v) => fmt::Display::fmt(&u128::from(v), f)?,
+            field::Value::I128Val(v) => fmt::Display::fmt(&i128::from(v), f)?,
+            field::Value::DurationVal(v) => match std::time::Duration::try_from(v.clone()) {
+                Ok(v) => fmt::Debug::fmt(&v, f)?,
+                // A negative duration is returned as its absolute value.
+                Err(v) => write!(f, "-{:?}", v)?,
+            },
+            // Seconds since the Unix epoch, since RFC 3339 would need a date
+            // library.
+            field::Value::TimestampVal(v) => {
+                let mut v = v.clone();
+                v.normalize();
+                // Normalized nanoseconds always count forwards from
+                // `seconds`, so before the epoch they're subtracted from the
+                // magnitude, e.g. -1s + 0.5s is -0.5s.
+                if v.seconds < 0 && v.nanos > 0 {
+                    write!(f, "-{}.{:09}", -(v.seconds + 1), 1_000_000_000 - v.nanos)?
+                } else {
+                    write!(f, "{}.{:09}", v.seconds, v.nanos)?
+                }
+            }
         }
 
         Ok(())
@@ -166,6 +189,36 @@ impl From<u64> for field::Value {
     }
 }
 
+impl From<f64> for field::Value {
+    fn from(val: f64) -> Self {
+        field::Value::F64Val(val)
+    }
+}
+
+impl From<u128> for field::Value {
+    fn from(val: u128) -> Self {
+        field::Value::U128Val(val.into())
+    }
+}
+
+impl From<i128> for field::Value {
+    fn from(val: i128) -> Self {
+        field::Value::I128Val(val.into())
+    }
+}
+
+impl From<std::time::Duration> for field::Value {
+    fn from(val: std::time::Duration) -> Self {
+        field::Value::DurationVal(val.into())
+    }
+}
+
+impl From<std::time::SystemTime> for field::Value {
+    fn from(val: std::time::SystemTime) -> Self {
+        field::Value::TimestampVal(val.into())
+    }
+}
+
 impl From<bool> for field::Value {
     fn from(val: bool) -> Self {
         field::Value::BoolVal(val)
@@ -190,6 +243,33 @@ impl From<&dyn std::fmt::Debug> for field::Value {
     }
 }
 
+impl From<u128> for Int128 {
+    fn from(val: u128) -> Self {
+        Int128 {
+            high: (val >> 64) as u64,
+            low: val as u64,
+        }
+    }
+}
+
+impl From<i128> for Int128 {
+    fn from(val: i128) -> Self {
+        (val as u128).into()
+    }
+}
+
+impl From<&Int128> for u128 {
+    fn from(val: &Int128) -> Self {
+        (u128::from(val.high) << 64) | u128::from(val.low)
+    }
+}
+
+impl From<&Int128> for i128 {
+    fn from(val: &Int128) -> Self {
+        u128::from(val) as i128
+    }
+}
+
 // Clippy warns when a type derives `PartialEq` but has a manual `Hash` impl,
 // or vice versa. However, this is unavoidable here, because `prost` generates
 // a struct with `#[derive(PartialEq)]`, but we cannot add`#[derive(Hash)]` to the
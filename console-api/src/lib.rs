@@ -2,6 +2,7 @@ pub mod async_ops;
 mod common;
 pub mod instrument;
 pub mod resources;
+pub mod runtime;
 pub mod tasks;
 pub mod trace;
 pub use common::*;
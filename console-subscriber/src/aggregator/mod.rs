@@ -1,5 +1,5 @@
-use super::{AttributeUpdate, AttributeUpdateOp, Command, Event, WakeOp, Watch};
-use crate::{record::Recorder, WatchRequest};
+use super::{AttributeUpdate, AttributeUpdateOp, Command, Event, Subscription, WakeOp, Watch};
+use crate::{record::Recorder, runtime::RuntimeMetricsFn, WatchRequest};
 use console_api as proto;
 use proto::resources::resource;
 use proto::resources::stats::Attribute;
@@ -10,7 +10,7 @@ use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     convert::TryInto,
     sync::{
-        atomic::{AtomicBool, Ordering::*},
+        atomic::{AtomicBool, AtomicUsize, Ordering::*},
         Arc,
     },
     time::{Duration, SystemTime},
@@ -45,12 +45,18 @@ pub(crate) struct Aggregator {
     /// Triggers a flush when the event buffer is approaching capacity.
     flush_capacity: Arc<Flush>,
 
+    /// Counts events dropped because the event buffer was at capacity.
+    dropped_events: Arc<DroppedEvents>,
+
     /// Currently active RPCs streaming task events.
-    watchers: ShrinkVec<Watch<proto::instrument::Update>>,
+    watchers: ShrinkVec<(Watch<proto::instrument::Update>, Subscription)>,
 
     /// Currently active RPCs streaming task details events, by task ID.
     details_watchers: ShrinkMap<Id, Vec<Watch<proto::tasks::TaskDetails>>>,
 
+    /// Currently active RPCs streaming resource details events, by resource ID.
+    resource_details_watchers: ShrinkMap<Id, Vec<Watch<proto::resources::ResourceDetails>>>,
+
     /// *All* metadata for task spans and user-defined spans that we care about.
     ///
     /// This is sent to new clients as part of the initial state.
@@ -97,6 +103,17 @@ pub(crate) struct Aggregator {
 
     /// The time "state" of the aggregator, such as paused or live.
     temporality: Temporality,
+
+    /// Static information about the instrumented process, sent to new clients
+    /// as part of the initial state.
+    process_info: proto::instrument::ProcessInfo,
+
+    /// Collects the runtime's metrics, if the process reports them.
+    runtime_metrics: Option<RuntimeMetricsFn>,
+
+    /// The number of significant figures new tasks' poll duration histograms
+    /// are recorded with.
+    histogram_significant_figures: u8,
 }
 
 #[derive(Debug)]
@@ -105,6 +122,15 @@ pub(crate) struct Flush {
     triggered: AtomicBool,
 }
 
+/// Counts of events that were dropped because the event buffer was full,
+/// since the last update was published.
+#[derive(Debug, Default)]
+pub(crate) struct DroppedEvents {
+    tasks: AtomicUsize,
+    resources: AtomicUsize,
+    async_ops: AtomicUsize,
+}
+
 // An entity (e.g Task, Resource) that at some point in
 // time can be dropped. This generally refers to spans that
 // have been closed indicating that a task, async op or a
@@ -152,6 +178,9 @@ struct Resource {
     concrete_type: String,
     kind: resource::Kind,
     location: Option<proto::Location>,
+    /// The runtime the resource was created on, if its thread was
+    /// registered with one.
+    runtime: Option<Arc<str>>,
 }
 
 /// Represents a key for a `proto::field::Name`. Because the
@@ -176,6 +205,8 @@ struct Task {
     metadata: &'static Metadata<'static>,
     fields: Vec<proto::Field>,
     location: Option<proto::Location>,
+    /// The task which spawned this task, if any.
+    parent: Option<Id>,
 }
 
 struct TaskStats {
@@ -192,6 +223,12 @@ struct TaskStats {
 
     poll_times_histogram: Histogram<u64>,
     poll_stats: PollStats,
+
+    /// The runtime which first polled the task, if its thread was
+    /// registered with one.
+    runtime: Option<Arc<str>>,
+    /// The resource which most recently woke the task.
+    last_woken_by: Option<Id>,
 }
 
 struct AsyncOp {
@@ -256,6 +293,17 @@ impl PollStats {
     }
 }
 
+impl TaskStats {
+    /// The default number of significant figures for poll duration
+    /// histograms.
+    ///
+    /// Significant figures should be in the [0-5] range and memory usage
+    /// grows exponentially with a higher sigfig.
+    const DEFAULT_SIGNIFICANT_FIGURES: u8 = 2;
+    /// The most significant figures a client can request.
+    const MAX_SIGNIFICANT_FIGURES: u8 = 5;
+}
+
 impl Default for TaskStats {
     fn default() -> Self {
         TaskStats {
@@ -266,10 +314,10 @@ impl Default for TaskStats {
             waker_drops: 0,
             self_wakes: 0,
             last_wake: None,
-            // significant figures should be in the [0-5] range and memory usage
-            // grows exponentially with higher a sigfig
-            poll_times_histogram: Histogram::<u64>::new(2).unwrap(),
+            poll_times_histogram: Histogram::<u64>::new(Self::DEFAULT_SIGNIFICANT_FIGURES).unwrap(),
             poll_stats: PollStats::default(),
+            runtime: None,
+            last_woken_by: None,
         }
     }
 }
@@ -285,12 +333,14 @@ impl Aggregator {
                 should_flush: Notify::new(),
                 triggered: AtomicBool::new(false),
             }),
+            dropped_events: Arc::new(DroppedEvents::default()),
             rpcs,
             publish_interval: builder.publish_interval,
             retention: builder.retention,
             events,
             watchers: Default::default(),
             details_watchers: Default::default(),
+            resource_details_watchers: Default::default(),
             all_metadata: Default::default(),
             new_metadata: Default::default(),
             tasks: IdData::default(),
@@ -307,6 +357,9 @@ impl Aggregator {
                 .as_ref()
                 .map(|path| Recorder::new(path).expect("creating recorder")),
             temporality: Temporality::Live,
+            process_info: process_info(),
+            runtime_metrics: builder.runtime_metrics.clone(),
+            histogram_significant_figures: TaskStats::DEFAULT_SIGNIFICANT_FIGURES,
         }
     }
 
@@ -314,6 +367,18 @@ impl Aggregator {
         &self.flush_capacity
     }
 
+    pub(crate) fn dropped_events(&self) -> &Arc<DroppedEvents> {
+        &self.dropped_events
+    }
+
+    /// The shortest publish interval a client may request. Publishing much
+    /// more often than this would spend most of the process' time preparing
+    /// updates.
+    const MIN_PUBLISH_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// The longest publish interval a client may request.
+    const MAX_PUBLISH_INTERVAL: Duration = Duration::from_secs(60);
+
     pub(crate) async fn run(mut self) {
         let mut publish = tokio::time::interval(self.publish_interval);
         loop {
@@ -335,18 +400,30 @@ impl Aggregator {
                 // a new command from a client
                 cmd = self.rpcs.recv() => {
                     match cmd {
-                        Some(Command::Instrument(subscription)) => {
-                            self.add_instrument_subscription(subscription);
+                        Some(Command::Instrument(watch, subscription)) => {
+                            self.add_instrument_subscription(watch, subscription);
                         },
                         Some(Command::WatchTaskDetail(watch_request)) => {
                             self.add_task_detail_subscription(watch_request);
                         },
+                        Some(Command::WatchResourceDetail(watch_request)) => {
+                            self.add_resource_detail_subscription(watch_request);
+                        },
                         Some(Command::Pause) => {
                             self.temporality = Temporality::Paused;
                         }
                         Some(Command::Resume) => {
                             self.temporality = Temporality::Live;
                         }
+                        Some(Command::SetPublishInterval(interval, tx)) => {
+                            let interval = interval
+                                .max(Self::MIN_PUBLISH_INTERVAL)
+                                .min(Self::MAX_PUBLISH_INTERVAL);
+                            tracing::debug!(?interval, "setting publish interval");
+                            self.publish_interval = interval;
+                            publish = tokio::time::interval(interval);
+                            let _ = tx.send(interval);
+                        }
                         None => {
                             tracing::debug!("rpc channel closed, terminating");
                             return;
@@ -427,8 +504,12 @@ impl Aggregator {
     }
 
     /// Add the task subscription to the watchers after sending the first update
-    fn add_instrument_subscription(&mut self, subscription: Watch<proto::instrument::Update>) {
-        tracing::debug!("new instrument subscription");
+    fn add_instrument_subscription(
+        &mut self,
+        watch: Watch<proto::instrument::Update>,
+        subscription: Subscription,
+    ) {
+        tracing::debug!(?subscription, "new instrument subscription");
         let now = SystemTime::now();
         // Send the initial state --- if this fails, the subscription is already dead
         let update = &proto::instrument::Update {
@@ -439,6 +520,7 @@ impl Aggregator {
                     .map(|(_, value)| value.to_proto())
                     .collect(),
                 stats_update: self.task_stats.as_proto(Include::All),
+                dropped_events: 0,
             }),
             resource_update: Some(proto::resources::ResourceUpdate {
                 new_resources: self
@@ -448,6 +530,7 @@ impl Aggregator {
                     .collect(),
                 stats_update: self.resource_stats.as_proto(Include::All),
                 new_poll_ops: (*self.all_poll_ops).clone(),
+                dropped_events: 0,
             }),
             async_op_update: Some(proto::async_ops::AsyncOpUpdate {
                 new_async_ops: self
@@ -456,15 +539,38 @@ impl Aggregator {
                     .map(|(_, value)| value.to_proto())
                     .collect(),
                 stats_update: self.async_op_stats.as_proto(Include::All),
+                dropped_events: 0,
             }),
             now: Some(now.into()),
             new_metadata: Some(proto::RegisterMetadata {
                 metadata: (*self.all_metadata).clone(),
             }),
+            process_info: Some(self.process_info.clone()),
+            runtime_update: self.runtime_metrics.as_ref().map(RuntimeMetricsFn::collect),
         };
 
-        if subscription.update(update) {
-            self.watchers.push(subscription)
+        if watch.update_with(|| subscription.filter(update)) {
+            self.watchers.push((watch, subscription));
+            self.update_histogram_significant_figures();
+        }
+    }
+
+    /// Sets the precision new tasks' poll duration histograms are recorded
+    /// with to the highest precision any connected client has asked for, or
+    /// the default if none of them have.
+    ///
+    /// Histograms are shared by every client, so this is recomputed as
+    /// clients connect and disconnect, rather than only ever increasing.
+    fn update_histogram_significant_figures(&mut self) {
+        let sigfig = self
+            .watchers
+            .iter()
+            .filter_map(|(_, subscription)| subscription.histogram_significant_figures)
+            .max()
+            .unwrap_or(TaskStats::DEFAULT_SIGNIFICANT_FIGURES);
+        if sigfig != self.histogram_significant_figures {
+            tracing::debug!(sigfig, "changing poll duration histogram precision");
+            self.histogram_significant_figures = sigfig;
         }
     }
 
@@ -502,6 +608,36 @@ impl Aggregator {
         // If the task is not found, drop `stream_sender` which will result in a not found error
     }
 
+    /// Add the resource details subscription to the watchers after sending the
+    /// first update, if the resource is found.
+    fn add_resource_detail_subscription(
+        &mut self,
+        watch_request: WatchRequest<proto::resources::ResourceDetails>,
+    ) {
+        let WatchRequest {
+            id,
+            stream_sender,
+            buffer,
+        } = watch_request;
+        tracing::debug!(id = ?id, "new resource details subscription");
+        if self.resource_stats.get(&id).is_some() {
+            let (tx, rx) = mpsc::channel(buffer);
+            let subscription = Watch(tx);
+            let now = SystemTime::now();
+            // Send back the stream receiver.
+            // Then send the initial state --- if this fails, the subscription is already dead.
+            if stream_sender.send(rx).is_ok()
+                && subscription.update(&resource_details(&self.async_op_stats, id, now))
+            {
+                self.resource_details_watchers
+                    .entry(id)
+                    .or_insert_with(Vec::new)
+                    .push(subscription);
+            }
+        }
+        // If the resource is not found, drop `stream_sender` which will result in a not found error
+    }
+
     /// Publish the current state to all active watchers.
     ///
     /// This drops any watchers which have closed the RPC, or whose update
@@ -528,6 +664,7 @@ impl Aggregator {
                     .map(|(_, value)| value.to_proto())
                     .collect(),
                 stats_update: self.task_stats.as_proto(Include::UpdatedOnly),
+                dropped_events: self.dropped_events.tasks.swap(0, AcqRel) as u64,
             }),
             resource_update: Some(proto::resources::ResourceUpdate {
                 new_resources: self
@@ -537,6 +674,7 @@ impl Aggregator {
                     .collect(),
                 stats_update: self.resource_stats.as_proto(Include::UpdatedOnly),
                 new_poll_ops,
+                dropped_events: self.dropped_events.resources.swap(0, AcqRel) as u64,
             }),
             async_op_update: Some(proto::async_ops::AsyncOpUpdate {
                 new_async_ops: self
@@ -545,11 +683,21 @@ impl Aggregator {
                     .map(|(_, value)| value.to_proto())
                     .collect(),
                 stats_update: self.async_op_stats.as_proto(Include::UpdatedOnly),
+                dropped_events: self.dropped_events.async_ops.swap(0, AcqRel) as u64,
             }),
+            // process info never changes, so it's only sent with the initial state.
+            process_info: None,
+            runtime_update: self.runtime_metrics.as_ref().map(RuntimeMetricsFn::collect),
         };
 
-        self.watchers
-            .retain_and_shrink(|watch: &Watch<proto::instrument::Update>| watch.update(&update));
+        self.watchers.retain_and_shrink(|(watch, subscription)| {
+            if subscription.is_everything() {
+                watch.update(&update)
+            } else {
+                watch.update_with(|| subscription.filter(&update))
+            }
+        });
+        self.update_histogram_significant_figures();
 
         let stats = &self.task_stats;
         // Assuming there are much fewer task details subscribers than there are
@@ -568,6 +716,19 @@ impl Aggregator {
                 false
             }
         });
+
+        let resource_stats = &self.resource_stats;
+        let async_op_stats = &self.async_op_stats;
+        self.resource_details_watchers
+            .retain_and_shrink(|&id, watchers| {
+                if resource_stats.get(&id).is_some() {
+                    let details = resource_details(async_op_stats, id, now);
+                    watchers.retain(|watch| watch.update(&details));
+                    !watchers.is_empty()
+                } else {
+                    false
+                }
+            });
     }
 
     /// Update the current state with data from a single event.
@@ -585,8 +746,10 @@ impl Aggregator {
                 at,
                 fields,
                 location,
+                parent,
             } => {
                 let id = self.ids.id_for(id);
+                let parent = parent.map(|parent| self.ids.id_for(parent));
                 self.tasks.insert(
                     id,
                     Task {
@@ -594,7 +757,7 @@ impl Aggregator {
                         metadata,
                         fields,
                         location,
-                        // TODO: parents
+                        parent,
                     },
                 );
 
@@ -602,15 +765,23 @@ impl Aggregator {
                     id,
                     TaskStats {
                         created_at: Some(at),
+                        poll_times_histogram: Histogram::<u64>::new(
+                            self.histogram_significant_figures,
+                        )
+                        .unwrap(),
                         ..Default::default()
                     },
                 );
             }
 
-            Event::Enter { id, at } => {
+            Event::Enter { id, at, runtime } => {
                 let id = self.ids.id_for(id);
                 if let Some(mut task_stats) = self.task_stats.update(&id) {
+                    let first_poll = task_stats.poll_stats.first_poll.is_none();
                     task_stats.poll_stats.update_on_span_enter(at);
+                    if first_poll {
+                        task_stats.runtime = runtime;
+                    }
                 }
 
                 if let Some(mut async_op_stats) = self.async_op_stats.update(&id) {
@@ -650,8 +821,14 @@ impl Aggregator {
                 }
             }
 
-            Event::Waker { id, op, at } => {
+            Event::Waker {
+                id,
+                op,
+                at,
+                resource,
+            } => {
                 let id = self.ids.id_for(id);
+                let resource = resource.map(|resource| self.ids.id_for(resource));
                 // It's possible for wakers to exist long after a task has
                 // finished. We don't want those cases to create a "new"
                 // task that isn't closed, just to insert some waker stats.
@@ -663,6 +840,7 @@ impl Aggregator {
                         WakeOp::Wake { self_wake } | WakeOp::WakeByRef { self_wake } => {
                             task_stats.wakes += 1;
                             task_stats.last_wake = Some(at);
+                            task_stats.last_woken_by = resource;
 
                             // If the  task has woken itself, increment the
                             // self-wake count.
@@ -699,7 +877,7 @@ impl Aggregator {
                 kind,
                 concrete_type,
                 location,
-                ..
+                runtime,
             } => {
                 let id = self.ids.id_for(id);
                 self.resources.insert(
@@ -710,6 +888,7 @@ impl Aggregator {
                         metadata,
                         concrete_type,
                         location,
+                        runtime,
                     },
                 );
 
@@ -839,10 +1018,41 @@ impl Flush {
     }
 }
 
+// ==== impl DroppedEvents ===
+
+impl DroppedEvents {
+    /// Records that `event` was dropped because the event buffer was full.
+    pub(crate) fn record(&self, event: &Event) {
+        let count = match event {
+            // Metadata is re-registered if it's needed again.
+            Event::Metadata(_) => return,
+            // Span enter, exit, and close events are most often for tasks.
+            Event::Spawn { .. }
+            | Event::Enter { .. }
+            | Event::Exit { .. }
+            | Event::Close { .. }
+            | Event::Waker { .. } => &self.tasks,
+            Event::Resource { .. } | Event::PollOp { .. } | Event::StateUpdate { .. } => {
+                &self.resources
+            }
+            Event::AsyncResourceOp { .. } => &self.async_ops,
+        };
+        count.fetch_add(1, Release);
+    }
+}
+
 impl<T: Clone> Watch<T> {
     fn update(&self, update: &T) -> bool {
+        self.update_with(|| update.clone())
+    }
+}
+
+impl<T> Watch<T> {
+    /// Sends the update returned by `f`, which is only called if the watcher
+    /// has room for another update.
+    fn update_with(&self, f: impl FnOnce() -> T) -> bool {
         if let Ok(reserve) = self.0.try_reserve() {
-            reserve.send(Ok(update.clone()));
+            reserve.send(Ok(f()));
             true
         } else {
             false
@@ -850,6 +1060,45 @@ impl<T: Clone> Watch<T> {
     }
 }
 
+impl Subscription {
+    fn is_everything(&self) -> bool {
+        self.tasks && self.resources
+    }
+
+    /// Returns a copy of `update` with only the data this subscription asked
+    /// for.
+    fn filter(&self, update: &proto::instrument::Update) -> proto::instrument::Update {
+        proto::instrument::Update {
+            now: update.now.clone(),
+            new_metadata: update.new_metadata.clone(),
+            process_info: update.process_info.clone(),
+            runtime_update: update.runtime_update.clone(),
+            task_update: self.tasks.then(|| update.task_update.clone()).flatten(),
+            resource_update: self
+                .resources
+                .then(|| update.resource_update.clone())
+                .flatten(),
+            async_op_update: self
+                .resources
+                .then(|| update.async_op_update.clone())
+                .flatten(),
+        }
+    }
+}
+
+impl From<proto::instrument::InstrumentRequest> for Subscription {
+    fn from(request: proto::instrument::InstrumentRequest) -> Self {
+        Self {
+            tasks: !request.exclude_tasks,
+            resources: !request.exclude_resources,
+            histogram_significant_figures: match request.histogram_significant_figures {
+                0 => None,
+                sigfig => Some(sigfig.min(TaskStats::MAX_SIGNIFICANT_FIGURES as u32) as u8),
+            },
+        }
+    }
+}
+
 impl ToProto for PollStats {
     type Output = proto::PollStats;
 
@@ -876,6 +1125,7 @@ impl ToProto for Task {
             parents: Vec::new(), // TODO: implement parents nicely
             fields: self.fields.clone(),
             location: self.location.clone(),
+            parent_task_id: self.parent.map(Into::into),
         }
     }
 }
@@ -893,6 +1143,8 @@ impl ToProto for TaskStats {
             self_wakes: self.self_wakes,
             waker_drops: self.waker_drops,
             last_wake: self.last_wake.map(Into::into),
+            runtime: self.runtime.as_deref().unwrap_or_default().to_string(),
+            last_woken_by: self.last_woken_by.map(Into::into),
         }
     }
 }
@@ -907,6 +1159,7 @@ impl ToProto for Resource {
             metadata: Some(self.metadata.into()),
             concrete_type: self.concrete_type.clone(),
             location: self.location.clone(),
+            runtime: self.runtime.as_deref().unwrap_or_default().to_string(),
         }
     }
 }
@@ -980,6 +1233,62 @@ impl Ids {
     }
 }
 
+fn process_info() -> proto::instrument::ProcessInfo {
+    let executable = std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+
+    // There's no portable way to get the hostname in `std`, so try the usual
+    // environment variables before falling back to `/etc/hostname`.
+    let hostname = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .unwrap_or_default();
+
+    proto::instrument::ProcessInfo {
+        pid: std::process::id(),
+        executable,
+        hostname,
+        // Tokio doesn't expose its version at runtime, so we can't know it here.
+        tokio_version: None,
+        subscriber_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// Builds the details for the resource with the given ID.
+///
+/// The resource's histogram is constructed from its async ops' stats here,
+/// rather than being recorded as events come in, so that resources nobody is
+/// watching don't pay for it.
+fn resource_details(
+    async_op_stats: &IdData<AsyncOpStats>,
+    id: Id,
+    now: SystemTime,
+) -> proto::resources::ResourceDetails {
+    let mut histogram = Histogram::<u64>::new(2).unwrap();
+    let mut async_ops = 0;
+    for (_, stats) in async_op_stats.all() {
+        if stats.resource_id != Some(id) {
+            continue;
+        }
+        async_ops += 1;
+        let busy_time = stats.poll_stats.busy_time.as_nanos().try_into();
+        if let Ok(busy_time) = busy_time {
+            // Values which don't fit in the histogram's range are skipped.
+            let _ = histogram.record(busy_time);
+        }
+    }
+    proto::resources::ResourceDetails {
+        resource_id: Some(id.into()),
+        now: Some(now.into()),
+        async_ops,
+        async_op_busy_times_histogram: serialize_histogram(&histogram).ok(),
+    }
+}
+
 fn serialize_histogram(histogram: &Histogram<u64>) -> Result<Vec<u8>, V2SerializeError> {
     let mut serializer = V2Serializer::new();
     let mut buf = Vec::new();
@@ -1013,6 +1322,19 @@ fn update_attribute(attribute: &mut Attribute, update: AttributeUpdate) {
             ),
         },
 
+        (Some(F64Val(v)), Some(F64Val(upd))) => match update.op {
+            Some(AttributeUpdateOp::Add) => *v += upd,
+
+            Some(AttributeUpdateOp::Sub) => *v -= upd,
+
+            Some(AttributeUpdateOp::Override) => *v = upd,
+
+            None => tracing::warn!(
+                "numeric attribute update {:?} needs to have an op field",
+                update_name
+            ),
+        },
+
         (Some(I64Val(v)), Some(I64Val(upd))) => match update.op {
             Some(AttributeUpdateOp::Add) => *v += upd,
 
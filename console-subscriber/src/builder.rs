@@ -1,4 +1,4 @@
-use super::{Server, TasksLayer};
+use super::{runtime::RuntimeMetricsFn, RuntimeMetrics, Server, TasksLayer};
 use std::{
     net::{SocketAddr, ToSocketAddrs},
     path::PathBuf,
@@ -27,6 +27,12 @@ pub struct Builder {
 
     /// If and where to save a recording of the events.
     pub(super) recording_path: Option<PathBuf>,
+
+    /// If and where to register the RPC server for discovery by consoles.
+    pub(super) registry_dir: Option<PathBuf>,
+
+    /// If and how to collect runtime metrics to forward to consoles.
+    pub(super) runtime_metrics: Option<RuntimeMetricsFn>,
 }
 
 impl Default for Builder {
@@ -38,6 +44,8 @@ impl Default for Builder {
             retention: TasksLayer::DEFAULT_RETENTION,
             server_addr: SocketAddr::new(Server::DEFAULT_IP, Server::DEFAULT_PORT),
             recording_path: None,
+            registry_dir: None,
+            runtime_metrics: None,
         }
     }
 }
@@ -113,6 +121,54 @@ impl Builder {
         }
     }
 
+    /// Sets a directory in which to register the RPC server's address, so that
+    /// consoles run with `--discover` can find it.
+    ///
+    /// The registration is removed when the server stops.
+    pub fn registry_dir(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            registry_dir: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Sets a function returning the runtime's current metrics, which are
+    /// forwarded to consoles with every update, to be displayed in the
+    /// console's runtime view.
+    ///
+    /// The function is called once per publish interval, from the aggregator
+    /// task. With a version of Tokio that provides `RuntimeMetrics`, it might
+    /// look like this:
+    ///
+    /// ```rust,ignore
+    /// let handle = tokio::runtime::Handle::current();
+    /// let builder = console_subscriber::Builder::default().runtime_metrics(move || {
+    ///     let metrics = handle.metrics();
+    ///     console_subscriber::RuntimeMetrics {
+    ///         injection_queue_depth: metrics.injection_queue_depth(),
+    ///         blocking_threads: metrics.num_blocking_threads(),
+    ///         workers: (0..metrics.num_workers())
+    ///             .map(|i| console_subscriber::WorkerMetrics {
+    ///                 park_count: metrics.worker_park_count(i),
+    ///                 noop_count: metrics.worker_noop_count(i),
+    ///                 steal_count: metrics.worker_steal_count(i),
+    ///                 poll_count: metrics.worker_poll_count(i),
+    ///                 busy_duration: metrics.worker_total_busy_duration(i),
+    ///                 local_schedule_count: metrics.worker_local_schedule_count(i),
+    ///                 overflow_count: metrics.worker_overflow_count(i),
+    ///                 local_queue_depth: metrics.worker_local_queue_depth(i),
+    ///             })
+    ///             .collect(),
+    ///     }
+    /// });
+    /// ```
+    pub fn runtime_metrics(self, f: impl Fn() -> RuntimeMetrics + Send + Sync + 'static) -> Self {
+        Self {
+            runtime_metrics: Some(RuntimeMetricsFn::new(f)),
+            ..self
+        }
+    }
+
     /// Completes the builder, returning a [`TasksLayer`] and [`Server`] task.
     pub fn build(self) -> (TasksLayer, Server) {
         TasksLayer::build(self)
@@ -126,6 +182,7 @@ impl Builder {
     /// | `TOKIO_CONSOLE_BIND`             | a HOST:PORT description, such as `localhost:1234`            | `127.0.0.1:6669`  |
     /// | `TOKIO_CONSOLE_PUBLISH_INTERVAL` | The duration to wait between sending updates to the console  | 1000ms (1s)       |
     /// | `TOKIO_CONSOLE_RECORD_PATH`      | The file path to save a recording                            | None              |
+    /// | `TOKIO_CONSOLE_REGISTRY_DIR`     | A directory to register the server in for discovery          | None              |
     pub fn with_default_env(mut self) -> Self {
         if let Some(retention) = duration_from_env("TOKIO_CONSOLE_RETENTION") {
             self.retention = retention;
@@ -147,6 +204,10 @@ impl Builder {
             self.recording_path = Some(path.into());
         }
 
+        if let Ok(dir) = std::env::var("TOKIO_CONSOLE_REGISTRY_DIR") {
+            self.registry_dir = Some(dir.into());
+        }
+
         self
     }
 }
@@ -3,8 +3,10 @@ use proto::resources::resource;
 use serde::Serialize;
 use std::{
     cell::RefCell,
+    convert::TryInto,
     fmt,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
     sync::Arc,
     time::{Duration, SystemTime},
 };
@@ -23,6 +25,8 @@ mod builder;
 mod callsites;
 mod init;
 mod record;
+mod registry;
+mod runtime;
 mod stack;
 pub(crate) mod sync;
 mod visitors;
@@ -30,6 +34,7 @@ mod visitors;
 use aggregator::Aggregator;
 pub use builder::Builder;
 use callsites::Callsites;
+pub use runtime::{set_runtime_name, RuntimeMetrics, WorkerMetrics};
 use stack::SpanStack;
 use visitors::{AsyncOpVisitor, ResourceVisitor, TaskVisitor, WakerVisitor};
 
@@ -42,6 +47,7 @@ pub struct TasksLayer {
     current_spans: ThreadLocal<RefCell<SpanStack>>,
     tx: mpsc::Sender<Event>,
     flush: Arc<aggregator::Flush>,
+    dropped_events: Arc<aggregator::DroppedEvents>,
     /// When the channel capacity goes under this number, a flush in the aggregator
     /// will be triggered.
     flush_under_capacity: usize,
@@ -89,15 +95,28 @@ pub struct Server {
     addr: SocketAddr,
     aggregator: Option<Aggregator>,
     client_buffer: usize,
+    registry_dir: Option<PathBuf>,
 }
 
 struct Watch<T>(mpsc::Sender<Result<T, tonic::Status>>);
 
 enum Command {
-    Instrument(Watch<proto::instrument::Update>),
+    Instrument(Watch<proto::instrument::Update>, Subscription),
     WatchTaskDetail(WatchRequest<proto::tasks::TaskDetails>),
+    WatchResourceDetail(WatchRequest<proto::resources::ResourceDetails>),
     Pause,
     Resume,
+    SetPublishInterval(Duration, oneshot::Sender<Duration>),
+}
+
+/// The kinds of data an instrument subscription asked to receive.
+#[derive(Clone, Copy, Debug)]
+struct Subscription {
+    tasks: bool,
+    resources: bool,
+    /// The number of significant figures to record poll duration histograms
+    /// with, if the client asked for a particular precision.
+    histogram_significant_figures: Option<u8>,
 }
 
 struct WatchRequest<T> {
@@ -115,10 +134,16 @@ enum Event {
         at: SystemTime,
         fields: Vec<proto::Field>,
         location: Option<proto::Location>,
+        /// The task which spawned this task, if it was spawned from within
+        /// a task.
+        parent: Option<span::Id>,
     },
     Enter {
         id: span::Id,
         at: SystemTime,
+        /// The runtime the thread entering the span belongs to, if it is a
+        /// task span, so that the runtime polling the task can be reported.
+        runtime: Option<Arc<str>>,
     },
     Exit {
         id: span::Id,
@@ -132,6 +157,9 @@ enum Event {
         id: span::Id,
         op: WakeOp,
         at: SystemTime,
+        /// The resource whose span was entered when the waker was woken, if
+        /// any.
+        resource: Option<span::Id>,
     },
     Resource {
         id: span::Id,
@@ -140,6 +168,8 @@ enum Event {
         concrete_type: String,
         kind: resource::Kind,
         location: Option<proto::Location>,
+        /// The runtime the thread creating the resource belongs to.
+        runtime: Option<Arc<str>>,
     },
     PollOp {
         metadata: &'static Metadata<'static>,
@@ -213,6 +243,7 @@ impl TasksLayer {
             ?config.retention,
             ?config.server_addr,
             ?config.recording_path,
+            ?config.registry_dir,
             "configured console subscriber"
         );
 
@@ -221,6 +252,7 @@ impl TasksLayer {
 
         let aggregator = Aggregator::new(events, rpcs, &config);
         let flush = aggregator.flush().clone();
+        let dropped_events = aggregator.dropped_events().clone();
 
         // Conservatively, start to trigger a flush when half the channel is full.
         // This tries to reduce the chance of losing events to a full channel.
@@ -231,10 +263,12 @@ impl TasksLayer {
             addr: config.server_addr,
             subscribe,
             client_buffer: config.client_buffer_capacity,
+            registry_dir: config.registry_dir,
         };
         let layer = Self {
             tx,
             flush,
+            dropped_events,
             flush_under_capacity,
             spawn_callsites: Callsites::default(),
             waker_callsites: Callsites::default(),
@@ -329,7 +363,9 @@ impl TasksLayer {
                 // this shouldn't happen, since we trigger a flush when
                 // approaching the high water line...but if the executor wait
                 // time is very high, maybe the aggregator task hasn't been
-                // polled yet. so... eek?!
+                // polled yet. so... eek?! at least let the console know that
+                // some data is missing.
+                self.dropped_events.record(&event);
             }
         }
 
@@ -361,19 +397,25 @@ where
         subscriber::Interest::always()
     }
 
-    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, _: Context<'_, S>) {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, cx: Context<'_, S>) {
         let metadata = attrs.metadata();
         if self.is_spawn(metadata) {
             let at = SystemTime::now();
             let mut task_visitor = TaskVisitor::new(metadata.into());
             attrs.record(&mut task_visitor);
             let (fields, location) = task_visitor.result();
+            // If the task is spawned while another task is being polled, that
+            // task spawned it.
+            let parent = self.current_spans.get().and_then(|stack| {
+                self.first_entered(&stack.borrow(), |id| self.is_id_spawned(id, &cx))
+            });
             self.send(Event::Spawn {
                 id: id.clone(),
                 at,
                 metadata,
                 fields,
                 location,
+                parent,
             });
         } else if self.is_resource(metadata) {
             let mut resource_visitor = ResourceVisitor::default();
@@ -387,6 +429,7 @@ where
                     concrete_type,
                     kind,
                     location,
+                    runtime: runtime::current_runtime_name(),
                 });
             } // else unknown resource span format
         } else if self.is_async_op(metadata) {
@@ -412,6 +455,7 @@ where
             let mut visitor = WakerVisitor::default();
             event.record(&mut visitor);
             if let Some((id, mut op)) = visitor.result() {
+                let mut resource = None;
                 if op.is_wake() {
                     // Are we currently inside the task's span? If so, the task
                     // has woken itself.
@@ -421,8 +465,19 @@ where
                         .map(|spans| spans.borrow().iter().any(|span| span == &id))
                         .unwrap_or(false);
                     op = op.self_wake(self_wake);
+
+                    // Was the task woken by a resource, such as a channel
+                    // waking its receiver when a message is sent?
+                    resource = self.current_spans.get().and_then(|stack| {
+                        self.first_entered(&stack.borrow(), |id| self.is_id_resource(id, &ctx))
+                    });
                 }
-                self.send(Event::Waker { id, op, at });
+                self.send(Event::Waker {
+                    id,
+                    op,
+                    at,
+                    resource,
+                });
             }
             // else unknown waker event... what to do? can't trace it from here...
         } else if self.poll_op_callsites.contains(event.metadata()) {
@@ -494,6 +549,11 @@ where
         self.send(Event::Enter {
             at: SystemTime::now(),
             id: id.clone(),
+            runtime: if self.is_id_spawned(id, &cx) {
+                runtime::current_runtime_name()
+            } else {
+                None
+            },
         });
     }
 
@@ -558,11 +618,16 @@ impl Server {
             .expect("cannot start server multiple times");
         let aggregate = spawn_named(aggregate.run(), "console::aggregate");
         let addr = self.addr;
-        let serve = builder
-            .add_service(proto::instrument::instrument_server::InstrumentServer::new(
-                self,
-            ))
-            .serve(addr);
+        let _registration = self
+            .registry_dir
+            .as_deref()
+            .and_then(|dir| registry::Registration::register(dir, addr));
+        let service = proto::instrument::instrument_server::InstrumentServer::new(self);
+        // Only compresses updates for clients which advertise that they accept
+        // gzip, so this doesn't break consoles that don't.
+        #[cfg(feature = "compression")]
+        let service = service.send_gzip().accept_gzip();
+        let serve = builder.add_service(service).serve(addr);
         let res = spawn_named(serve, "console::serve").await;
         aggregate.abort();
         res?.map_err(Into::into)
@@ -575,6 +640,9 @@ impl proto::instrument::instrument_server::Instrument for Server {
         tokio_stream::wrappers::ReceiverStream<Result<proto::instrument::Update, tonic::Status>>;
     type WatchTaskDetailsStream =
         tokio_stream::wrappers::ReceiverStream<Result<proto::tasks::TaskDetails, tonic::Status>>;
+    type WatchResourceDetailsStream = tokio_stream::wrappers::ReceiverStream<
+        Result<proto::resources::ResourceDetails, tonic::Status>,
+    >;
     async fn watch_updates(
         &self,
         req: tonic::Request<proto::instrument::InstrumentRequest>,
@@ -586,8 +654,9 @@ impl proto::instrument::instrument_server::Instrument for Server {
         let permit = self.subscribe.reserve().await.map_err(|_| {
             tonic::Status::internal("cannot start new watch, aggregation task is not running")
         })?;
+        let subscription = Subscription::from(req.into_inner());
         let (tx, rx) = mpsc::channel(self.client_buffer);
-        permit.send(Command::Instrument(Watch(tx)));
+        permit.send(Command::Instrument(Watch(tx), subscription));
         tracing::debug!("watch started");
         let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
         Ok(tonic::Response::new(stream))
@@ -623,6 +692,36 @@ impl proto::instrument::instrument_server::Instrument for Server {
         Ok(tonic::Response::new(stream))
     }
 
+    async fn watch_resource_details(
+        &self,
+        req: tonic::Request<proto::instrument::ResourceDetailsRequest>,
+    ) -> Result<tonic::Response<Self::WatchResourceDetailsStream>, tonic::Status> {
+        let resource_id = req
+            .into_inner()
+            .id
+            .ok_or_else(|| tonic::Status::invalid_argument("missing resource_id"))?;
+        let permit = self.subscribe.reserve().await.map_err(|_| {
+            tonic::Status::internal("cannot start new watch, aggregation task is not running")
+        })?;
+
+        // Check with the aggregator task to request a stream if the resource exists.
+        let (stream_sender, stream_recv) = oneshot::channel();
+        permit.send(Command::WatchResourceDetail(WatchRequest {
+            id: resource_id.into(),
+            stream_sender,
+            buffer: self.client_buffer,
+        }));
+        // If the aggregator drops the sender, the resource doesn't exist.
+        let rx = stream_recv.await.map_err(|_| {
+            tracing::warn!(id = ?resource_id, "requested resource not found");
+            tonic::Status::not_found("resource not found")
+        })?;
+
+        tracing::debug!(id = ?resource_id, "resource details watch started");
+        let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+        Ok(tonic::Response::new(stream))
+    }
+
     async fn pause(
         &self,
         _req: tonic::Request<proto::instrument::PauseRequest>,
@@ -642,6 +741,35 @@ impl proto::instrument::instrument_server::Instrument for Server {
         })?;
         Ok(tonic::Response::new(proto::instrument::ResumeResponse {}))
     }
+
+    async fn set_publish_interval(
+        &self,
+        req: tonic::Request<proto::instrument::SetPublishIntervalRequest>,
+    ) -> Result<tonic::Response<proto::instrument::SetPublishIntervalResponse>, tonic::Status> {
+        let interval: Duration = req
+            .into_inner()
+            .interval
+            .ok_or_else(|| tonic::Status::invalid_argument("missing publish interval"))?
+            .try_into()
+            .map_err(|_| tonic::Status::invalid_argument("publish interval must be positive"))?;
+        let (tx, rx) = oneshot::channel();
+        self.subscribe
+            .send(Command::SetPublishInterval(interval, tx))
+            .await
+            .map_err(|_| {
+                tonic::Status::internal(
+                    "cannot set publish interval, aggregation task is not running",
+                )
+            })?;
+        let interval = rx.await.map_err(|_| {
+            tonic::Status::internal("cannot set publish interval, aggregation task is not running")
+        })?;
+        Ok(tonic::Response::new(
+            proto::instrument::SetPublishIntervalResponse {
+                interval: Some(interval.into()),
+            },
+        ))
+    }
 }
 
 impl WakeOp {
@@ -103,7 +103,7 @@ impl Recorder {
                 at: *at,
                 fields: SerializeFields(fields),
             },
-            crate::Event::Enter { id, at } => Event::Enter {
+            crate::Event::Enter { id, at, .. } => Event::Enter {
                 id: id.into_u64(),
                 at: *at,
             },
@@ -115,7 +115,7 @@ impl Recorder {
                 id: id.into_u64(),
                 at: *at,
             },
-            crate::Event::Waker { id, op, at } => Event::Waker {
+            crate::Event::Waker { id, op, at, .. } => Event::Waker {
                 id: id.into_u64(),
                 at: *at,
                 op: *op,
@@ -218,6 +218,15 @@ impl serde::Serialize for SerializeField<'_> {
             proto::field::Value::BoolVal(v) => {
                 ser.serialize_field("value", v)?;
             }
+            proto::field::Value::F64Val(v) => {
+                ser.serialize_field("value", v)?;
+            }
+            v @ proto::field::Value::U128Val(_)
+            | v @ proto::field::Value::I128Val(_)
+            | v @ proto::field::Value::DurationVal(_)
+            | v @ proto::field::Value::TimestampVal(_) => {
+                ser.serialize_field("value", &v.to_string())?;
+            }
         }
         ser.end()
     }
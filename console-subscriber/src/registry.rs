@@ -0,0 +1,70 @@
+//! Registers the instrument server in a registry directory, so that consoles
+//! can discover it without being told its address up front.
+//!
+//! Each process writes a file named after its PID to the registry directory,
+//! containing `key=value` lines describing the instrumented process. The file
+//! is removed when the server stops. Consoles should be prepared for stale
+//! entries left behind by processes that exited uncleanly.
+use std::{
+    fmt::Write as _,
+    fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+/// A registry entry for a running server, which is removed when dropped.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    path: PathBuf,
+}
+
+impl Registration {
+    /// Registers a server listening on `addr` in the given registry
+    /// directory, logging a warning if it could not be registered.
+    pub(crate) fn register(dir: &Path, addr: SocketAddr) -> Option<Self> {
+        match Self::new(dir, addr) {
+            Ok(registration) => Some(registration),
+            Err(error) => {
+                tracing::warn!(%error, dir = %dir.display(), "failed to register console server");
+                None
+            }
+        }
+    }
+
+    fn new(dir: &Path, addr: SocketAddr) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let pid = std::process::id();
+        let path = dir.join(format!("{}.console", pid));
+
+        // A server bound to all interfaces is reachable on loopback.
+        let ip = match addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        let addr = SocketAddr::new(ip, addr.port());
+
+        let mut contents = String::new();
+        let _ = writeln!(contents, "addr=http://{}", addr);
+        let _ = writeln!(contents, "pid={}", pid);
+        if let Some(name) = std::env::current_exe()
+            .ok()
+            .as_ref()
+            .and_then(|exe| exe.file_name())
+        {
+            let _ = writeln!(contents, "name={}", name.to_string_lossy());
+        }
+        fs::write(&path, contents)?;
+
+        tracing::debug!(path = %path.display(), %addr, "registered console server");
+        Ok(Self { path })
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_file(&self.path) {
+            tracing::debug!(%error, path = %self.path.display(), "failed to remove registration");
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Forwarding runtime-level metrics to consoles, and identifying the runtime
+//! each thread belongs to.
+use console_api as proto;
+use std::{cell::RefCell, fmt, sync::Arc, time::Duration};
+
+thread_local! {
+    /// The name of the runtime the current thread belongs to, if it was
+    /// registered with [`set_runtime_name`].
+    static RUNTIME_NAME: RefCell<Option<Arc<str>>> = RefCell::new(None);
+}
+
+/// A snapshot of the metrics describing an async runtime's scheduler.
+///
+/// This mirrors the metrics reported by Tokio's `RuntimeMetrics`. Counts and
+/// durations are cumulative over the lifetime of the runtime.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeMetrics {
+    /// The number of tasks waiting in the runtime's global (injection) queue.
+    pub injection_queue_depth: usize,
+    /// The number of threads the runtime has spawned to run blocking
+    /// operations.
+    pub blocking_threads: usize,
+    /// Metrics for each of the runtime's worker threads, indexed by worker.
+    pub workers: Vec<WorkerMetrics>,
+}
+
+/// A snapshot of the metrics for a single worker thread.
+#[derive(Clone, Debug, Default)]
+pub struct WorkerMetrics {
+    /// The number of times the worker has parked, waiting for work.
+    pub park_count: u64,
+    /// The number of times the worker woke up without finding any work.
+    pub noop_count: u64,
+    /// The number of tasks the worker has stolen from other workers.
+    pub steal_count: u64,
+    /// The number of tasks the worker has polled.
+    pub poll_count: u64,
+    /// The total time the worker has spent polling tasks.
+    pub busy_duration: Duration,
+    /// The number of tasks scheduled on the worker's local queue from within
+    /// the runtime.
+    pub local_schedule_count: u64,
+    /// The number of times the worker's local queue overflowed into the
+    /// injection queue.
+    pub overflow_count: u64,
+    /// The number of tasks currently waiting in the worker's local queue.
+    pub local_queue_depth: usize,
+}
+
+/// A function returning the current runtime metrics, which is called each
+/// time an update is published.
+#[derive(Clone)]
+pub(crate) struct RuntimeMetricsFn(Arc<dyn Fn() -> RuntimeMetrics + Send + Sync>);
+
+// === impl RuntimeMetricsFn ===
+
+impl RuntimeMetricsFn {
+    pub(crate) fn new(f: impl Fn() -> RuntimeMetrics + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn collect(&self) -> proto::runtime::RuntimeUpdate {
+        (self.0)().into()
+    }
+}
+
+impl fmt::Debug for RuntimeMetricsFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RuntimeMetricsFn").finish()
+    }
+}
+
+// === impl RuntimeMetrics ===
+
+impl From<RuntimeMetrics> for proto::runtime::RuntimeUpdate {
+    fn from(metrics: RuntimeMetrics) -> Self {
+        Self {
+            injection_queue_depth: metrics.injection_queue_depth as u64,
+            blocking_threads: metrics.blocking_threads as u64,
+            workers: metrics.workers.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<WorkerMetrics> for proto::runtime::WorkerStats {
+    fn from(metrics: WorkerMetrics) -> Self {
+        Self {
+            park_count: metrics.park_count,
+            noop_count: metrics.noop_count,
+            steal_count: metrics.steal_count,
+            poll_count: metrics.poll_count,
+            busy_duration: Some(metrics.busy_duration.into()),
+            local_schedule_count: metrics.local_schedule_count,
+            overflow_count: metrics.overflow_count,
+            local_queue_depth: metrics.local_queue_depth as u64,
+        }
+    }
+}
+
+/// Records that the current thread belongs to the runtime named `name`, so
+/// that the tasks polled on it and the resources created on it are reported
+/// as that runtime's.
+///
+/// Applications running several runtimes should call this at the start of
+/// each runtime's threads, using
+/// [`tokio::runtime::Builder::on_thread_start`]. A current-thread runtime
+/// polls its tasks on the thread which calls `block_on`, so it should be
+/// called on that thread as well.
+///
+/// ```no_run
+/// let io = tokio::runtime::Builder::new_multi_thread()
+///     .on_thread_start(|| console_subscriber::set_runtime_name("io"))
+///     .enable_all()
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Tasks and resources on threads which were never registered are reported
+/// without a runtime.
+///
+/// [`tokio::runtime::Builder::on_thread_start`]: https://docs.rs/tokio/latest/tokio/runtime/struct.Builder.html#method.on_thread_start
+pub fn set_runtime_name(name: &str) {
+    RUNTIME_NAME.with(|runtime| *runtime.borrow_mut() = Some(name.into()));
+}
+
+/// Returns the name of the runtime the current thread was registered with,
+/// if it was.
+pub(crate) fn current_runtime_name() -> Option<Arc<str>> {
+    RUNTIME_NAME.with(|runtime| runtime.borrow().clone())
+}
@@ -251,6 +251,10 @@ impl Visit for TaskVisitor {
         self.field_visitor.record_i64(field, value);
     }
 
+    fn record_f64(&mut self, field: &tracing_core::Field, value: f64) {
+        self.field_visitor.record_f64(field, value);
+    }
+
     fn record_u64(&mut self, field: &tracing_core::Field, value: u64) {
         match field.name() {
             LOCATION_LINE => self.line = Some(value as u32),
@@ -289,6 +293,14 @@ impl Visit for FieldVisitor {
         });
     }
 
+    fn record_f64(&mut self, field: &tracing_core::Field, value: f64) {
+        self.fields.push(proto::Field {
+            name: Some(field.name().into()),
+            value: Some(value.into()),
+            metadata_id: Some(self.meta_id.clone()),
+        });
+    }
+
     fn record_u64(&mut self, field: &tracing_core::Field, value: u64) {
         self.fields.push(proto::Field {
             name: Some(field.name().into()),
@@ -450,6 +462,18 @@ impl Visit for StateUpdateVisitor {
         }
     }
 
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        if !field.name().ends_with(Self::STATE_OP_SUFFIX)
+            && !field.name().ends_with(Self::STATE_UNIT_SUFFIX)
+        {
+            self.field = Some(proto::Field {
+                name: Some(field.name().into()),
+                value: Some(value.into()),
+                metadata_id: Some(self.meta_id.clone()),
+            });
+        }
+    }
+
     fn record_u64(&mut self, field: &field::Field, value: u64) {
         if !field.name().ends_with(Self::STATE_OP_SUFFIX)
             && !field.name().ends_with(Self::STATE_UNIT_SUFFIX)
@@ -0,0 +1,80 @@
+//! Formatting wall-clock times in the local timezone, for correlating the
+//! console's data with timestamps in logs.
+//!
+//! The standard library can't determine the local timezone, so its offset
+//! from UTC is asked of `date` once. If that fails, times are formatted in
+//! UTC instead.
+use once_cell::sync::OnceCell;
+use std::{
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+/// The local timezone's offset from UTC.
+#[derive(Debug)]
+struct Offset {
+    /// The offset in seconds east of UTC.
+    secs: i64,
+    /// The offset as `date` formats it, such as `+0100`.
+    label: String,
+}
+
+/// Formats `at` as a local wall-clock time with millisecond precision, such
+/// as `2021-11-02 14:03:27.153 +0100`.
+pub(crate) fn format(at: SystemTime) -> String {
+    let (local, label) = offset()
+        .and_then(|offset| {
+            let shift = Duration::from_secs(offset.secs.unsigned_abs());
+            let local = if offset.secs >= 0 {
+                at.checked_add(shift)
+            } else {
+                at.checked_sub(shift)
+            };
+            Some((local?, offset.label.as_str()))
+        })
+        .unwrap_or((at, "UTC"));
+    let formatted = humantime::format_rfc3339_millis(local).to_string();
+    // The time may not be in UTC, so drop the `Z` suffix, and use a space
+    // rather than a `T` for readability.
+    format!(
+        "{} {}",
+        formatted.trim_end_matches('Z').replacen('T', " ", 1),
+        label
+    )
+}
+
+fn offset() -> Option<&'static Offset> {
+    static OFFSET: OnceCell<Option<Offset>> = OnceCell::new();
+    OFFSET
+        .get_or_init(|| {
+            let date = Command::new("date").arg("+%z").output();
+            tracing::debug!(?date, "checking `date +%z`");
+            let stdout = String::from_utf8(date.ok()?.stdout).ok()?;
+            let label = stdout.trim().to_string();
+            match parse_offset(&label) {
+                Some(secs) => Some(Offset { secs, label }),
+                None => {
+                    tracing::warn!(offset = ?label, "invalid UTC offset from `date +%z`, using UTC");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Parses an offset formatted like `+0100` or `-0530` into seconds east of
+/// UTC.
+fn parse_offset(s: &str) -> Option<i64> {
+    let sign = match s.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &s[1..];
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours = digits[..2].parse::<i64>().ok()?;
+    let minutes = digits[2..].parse::<i64>().ok()?;
+    Some(sign * (hours * 60 * 60 + minutes * 60))
+}
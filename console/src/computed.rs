@@ -0,0 +1,101 @@
+//! User-defined columns in the tasks table, computed from each task's
+//! statistics.
+//!
+//! A computed column is written as `<name> = <expression>`, where the
+//! expression is written like the left side of a comparison in a filter,
+//! combining the values filters may compare and numbers with `+`, `-`, `*`,
+//! `/`, and parentheses. For example:
+//!
+//! ```text
+//! busy_pct = busy / total * 100
+//! wakes_per_poll = wakes / polls
+//! ```
+//!
+//! Durations are in seconds. A task field with a numeric value may be used
+//! as `field:<name>`. If a task doesn't have a value used by the expression,
+//! or the expression divides by zero, the column is left empty for that
+//! task.
+use crate::{
+    state::{tasks::Task, window::Window},
+    view::{filter::Expr, tasks::TasksTable},
+};
+use std::{cmp::Ordering, fmt, str::FromStr, time::SystemTime};
+
+/// A column computed from each task's statistics, parsed from a string like
+/// `wakes_per_poll = wakes / polls`.
+#[derive(Debug, Clone)]
+pub(crate) struct ComputedColumn {
+    name: String,
+    expr: Expr,
+    source: String,
+}
+
+// === impl ComputedColumn ===
+
+impl ComputedColumn {
+    /// Parses a column with the given name, which evaluates `expr`.
+    pub(crate) fn new(name: &str, expr: &str) -> Result<Self, String> {
+        let parsed = Expr::parse(expr)?;
+        parsed.check::<TasksTable>()?;
+        Ok(Self {
+            name: name.to_string(),
+            expr: parsed,
+            source: format!("{} = {}", name, expr.trim()),
+        })
+    }
+
+    /// Returns the column's header.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Evaluates the column for `task` as of `now`, using its statistics
+    /// within `window` if one is open, like the other columns.
+    pub(crate) fn eval(
+        &self,
+        task: &Task,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<f64> {
+        self.expr.eval::<TasksTable>(task, now, window)
+    }
+
+    /// Formats a value of the column.
+    pub(crate) fn format(value: f64) -> String {
+        if value.fract() == 0.0 && value.abs() < 1e15 {
+            format!("{:.0}", value)
+        } else {
+            format!("{:.3}", value)
+        }
+    }
+
+    /// Compares the values of the column for two tasks, sorting tasks
+    /// without a value first.
+    pub(crate) fn compare(a: Option<f64>, b: Option<f64>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+impl fmt::Display for ComputedColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for ComputedColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, expr) = s
+            .split_once('=')
+            .ok_or_else(|| "expected a column like `wakes_per_poll = wakes / polls`".to_string())?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("expected a name before `=`".to_string());
+        }
+        Self::new(name, expr)
+    }
+}
@@ -1,9 +1,28 @@
-use crate::view::Palette;
-use clap::{ArgGroup, Parser as Clap, ValueHint};
+use crate::{
+    computed::ComputedColumn,
+    debug_log::DebugLog,
+    dump, exit,
+    export::{Otlp, StatsD},
+    hook::{HookCommand, WarningHook},
+    notify,
+    state::Retention,
+    stream, top,
+    view::{filter::SavedFilter, Palette},
+    watch::{Lint, Watch},
+    webhook::Webhook,
+};
+use clap::{ArgGroup, ArgMatches, FromArgMatches, IntoApp, Parser as Clap, ValueHint};
+use std::ffi::OsString;
+use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
 use std::time::Duration;
-use tonic::transport::Uri;
+use tonic::{
+    metadata::{AsciiMetadataKey, AsciiMetadataValue},
+    transport::Uri,
+};
+
+mod file;
 
 #[derive(Clap, Debug)]
 #[clap(
@@ -16,17 +35,50 @@ use tonic::transport::Uri;
 pub struct Config {
     /// The address of a console-enabled process to connect to.
     ///
-    /// This may be an IP address and port, or a DNS name.
-    #[clap(default_value = "http://127.0.0.1:6669", value_hint = ValueHint::Url)]
-    pub(crate) target_addr: Uri,
+    /// This may be an IP address and port, or a DNS name. On Linux, processes
+    /// running inside a virtual machine may be connected to over `vsock`,
+    /// using an address of the form `vsock://<cid>:<port>`. On Windows, a named
+    /// pipe path of the form `\\<server>\pipe\<name>` may be used instead.
+    ///
+    /// Up to nine addresses may be given, in which case the console connects
+    /// to all of them, and the number keys switch between them. When set by
+    /// the environment variable, addresses are separated by commas.
+    #[clap(
+        default_value = "http://127.0.0.1:6669",
+        value_name = "TARGET_ADDR",
+        env = "TOKIO_CONSOLE_TARGET",
+        use_delimiter = true,
+        value_hint = ValueHint::Url,
+        parse(try_from_str = parse_target),
+    )]
+    pub(crate) target_addrs: Vec<Uri>,
 
     /// Log level filter for the console's internal diagnostics.
     ///
     /// The console will log to stderr if a log level filter is provided. Since
     /// the console application runs interactively, stderr should generally be
-    /// redirected to a file to avoid interfering with the console's text output.
-    #[clap(long = "log", env = "RUST_LOG", default_value = "off")]
-    pub(crate) env_filter: tracing_subscriber::EnvFilter,
+    /// redirected to a file to avoid interfering with the console's text output,
+    /// or `--log-file` should be used instead.
+    ///
+    /// If this is not set, the `RUST_LOG` environment variable is used. If
+    /// neither is set, nothing is logged, unless `--log-file` is set, in
+    /// which case `info` level diagnostics are logged. Either way, recent
+    /// diagnostics are shown in the debug log pane, toggled with `F12`.
+    #[clap(long = "log", alias = "log-filter", env = "TOKIO_CONSOLE_LOG")]
+    pub(crate) env_filter: Option<tracing_subscriber::EnvFilter>,
+
+    /// Write the console's internal diagnostics to the given file, rather
+    /// than to stderr or the system journal.
+    ///
+    /// The file is appended to if it already exists. This makes it possible to
+    /// debug the console without its diagnostics interfering with the display.
+    #[clap(
+        long = "log-file",
+        env = "TOKIO_CONSOLE_LOG_FILE",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath
+    )]
+    log_file: Option<PathBuf>,
 
     #[clap(flatten)]
     pub(crate) view_options: ViewOptions,
@@ -59,18 +111,725 @@ pub struct Config {
     /// * `months`, `month`, `M` -- defined as 30.44 days
     ///
     /// * `years`, `year`, `y` -- defined as 365.25 days
-    #[clap(long = "retain-for", default_value = "6s")]
+    #[clap(
+        long = "retain-for",
+        env = "TOKIO_CONSOLE_RETAIN_FOR",
+        default_value = "6s"
+    )]
     retain_for: RetainFor,
+
+    /// How long to continue displaying completed tasks after they have
+    /// completed, overriding `--retain-for`.
+    ///
+    /// This accepts a duration or `none`, like `--retain-for`.
+    #[clap(long = "retain-tasks-for", env = "TOKIO_CONSOLE_RETAIN_TASKS_FOR")]
+    retain_tasks_for: Option<RetainFor>,
+
+    /// How long to continue displaying tasks which completed with warnings
+    /// after they have completed, overriding `--retain-tasks-for`.
+    ///
+    /// Keeping these tasks for longer than other completed tasks leaves time
+    /// to investigate them. This accepts a duration or `none`, like
+    /// `--retain-for`.
+    #[clap(
+        long = "retain-warned-tasks-for",
+        env = "TOKIO_CONSOLE_RETAIN_WARNED_TASKS_FOR"
+    )]
+    retain_warned_tasks_for: Option<RetainFor>,
+
+    /// How long to continue displaying dropped resources, and their async
+    /// operations, after they have been dropped, overriding `--retain-for`.
+    ///
+    /// This accepts a duration or `none`, like `--retain-for`.
+    #[clap(
+        long = "retain-resources-for",
+        env = "TOKIO_CONSOLE_RETAIN_RESOURCES_FOR"
+    )]
+    retain_resources_for: Option<RetainFor>,
+
+    /// How often to ask the instrumented process to publish updates, such
+    /// as `500ms` or `5s`.
+    ///
+    /// Updates are aggregated once for every client, so this changes the
+    /// interval for every console connected to the process. The process may
+    /// clamp the interval to the range it supports. If this is not set, the
+    /// process' configured interval is used. The interval can also be halved
+    /// or doubled at runtime with the `[` and `]` keys.
+    #[clap(long = "publish-interval", env = "TOKIO_CONSOLE_PUBLISH_INTERVAL")]
+    publish_interval: Option<humantime::Duration>,
+
+    /// A condition to alert on, such as `tasks > 10_000` or
+    /// `task.busy > 90% for 30s`.
+    ///
+    /// Watches are checked on every update, and fire through the warnings
+    /// list. Watches on the process as a whole may use the metrics `tasks`,
+    /// `running_tasks`, `idle_tasks`, `resources`, `spawn_rate` (tasks
+    /// spawned per second during the latest update interval), and
+    /// `spawn_rate_jump` (how many times the preceding intervals' mean spawn
+    /// rate the latest one is), and, if the process
+    /// reports runtime metrics, `runtime.busy` (the workers' mean busy
+    /// percentage), `runtime.max_worker_busy`, `runtime.injection_queue`,
+    /// `runtime.local_queue`, and `runtime.blocking_threads`. Watches on
+    /// individual tasks may use `task.busy` (the percentage of the task's lifetime spent
+    /// being polled), `task.polls`, `task.wakes`, `task.self_wakes` (the
+    /// percentage of wakeups that were self-wakes), and `task.wakers`, and
+    /// are raised as warnings on the tasks which match them. The comparison
+    /// may be `>`, `>=`, `<`, or `<=`. A watch ending in `for <duration>`
+    /// only fires once its condition has held for that long.
+    ///
+    /// This may be repeated to add several watches. When set by the
+    /// environment variable, watches are separated by commas.
+    #[clap(
+        long = "watch",
+        env = "TOKIO_CONSOLE_WATCH",
+        use_delimiter = true,
+        value_name = "WATCH",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    watches: Vec<Watch>,
+
+    /// The number of tasks spawned per second above which to warn that
+    /// tasks are being spawned too quickly, such as `1000`.
+    ///
+    /// The spawn rate is measured over each update interval. Regardless of
+    /// this setting, a warning is raised whenever the spawn rate jumps
+    /// tenfold over its recent mean, which often means a retry storm or a
+    /// task being spawned per request by accident.
+    #[clap(
+        long = "max-spawn-rate",
+        env = "TOKIO_CONSOLE_MAX_SPAWN_RATE",
+        value_name = "RATE"
+    )]
+    max_spawn_rate: Option<f64>,
+
+    /// A warning to raise on the tasks matching a condition, such as
+    /// `warn "chatty" when wakes/polls>50 for 10s`.
+    ///
+    /// The condition is written like a filter on the tasks table, whose
+    /// terms must all be comparisons, such as `busy>1s` or
+    /// `busy/total*100>=90`. A lint ending in `for <duration>` only fires for
+    /// a task once its condition has held for that long. Lints are raised as
+    /// warnings, along with the built-in ones.
+    ///
+    /// This may be repeated to add several lints. When set by the
+    /// environment variable, lints are separated by commas.
+    #[clap(
+        long = "lint",
+        env = "TOKIO_CONSOLE_LINTS",
+        use_delimiter = true,
+        value_name = "LINT",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub(crate) lints: Vec<Lint>,
+
+    /// The name of a task field to display in a sortable column of its own
+    /// in the tasks table, such as `request_id`.
+    ///
+    /// This may be repeated to add several columns, which are displayed in
+    /// the order they are given. When set by the environment variable,
+    /// field names are separated by commas.
+    #[clap(
+        long = "field-column",
+        env = "TOKIO_CONSOLE_FIELD_COLUMNS",
+        use_delimiter = true,
+        value_name = "FIELD",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub(crate) field_columns: Vec<String>,
+
+    /// A column in the tasks table computed from each task's statistics,
+    /// such as `wakes_per_poll = wakes / polls`.
+    ///
+    /// Expressions are written like the left side of a comparison in a
+    /// filter. They may combine the values filters compare, such as `busy`
+    /// and `wake_delay` (in seconds), `polls`, and `wakes`, numbers, and
+    /// numeric fields, written as `field:<name>`, with `+`, `-`, `*`, `/`,
+    /// and parentheses. Computed columns are displayed after any field
+    /// columns, and may be sorted by like the built-in columns.
+    ///
+    /// This may be repeated to add several columns. When set by the
+    /// environment variable, columns are separated by commas.
+    #[clap(
+        long = "computed-column",
+        env = "TOKIO_CONSOLE_COMPUTED_COLUMNS",
+        use_delimiter = true,
+        value_name = "NAME = EXPR",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub(crate) computed_columns: Vec<ComputedColumn>,
+
+    /// A filter for the tasks and resources tables saved under a name, such
+    /// as `hot = busy>1s` or `mylib = target=my_crate`.
+    ///
+    /// Saved filters are listed by pressing `F` in either table, and applied
+    /// by pressing their number. A filter is written like a filter typed
+    /// after pressing `/`.
+    ///
+    /// This may be repeated to save several filters. When set by the
+    /// environment variable, filters are separated by commas.
+    #[clap(
+        long = "filter",
+        env = "TOKIO_CONSOLE_FILTERS",
+        use_delimiter = true,
+        value_name = "NAME = QUERY",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub(crate) saved_filters: Vec<SavedFilter>,
+
+    /// The number of significant figures, from 1 to 5, to ask the
+    /// instrumented process to record poll duration histograms with.
+    ///
+    /// Higher precision allows microsecond-level analysis of poll times, at
+    /// the cost of more memory in the instrumented process. Histograms are
+    /// shared by every console connected to the process, so the highest
+    /// precision requested by a connected console is used, and only for
+    /// tasks spawned while it is. Each task's precision is shown in its poll
+    /// times histogram's title. If this is not set, the process' default
+    /// precision is used.
+    #[clap(
+        long = "histogram-precision",
+        env = "TOKIO_CONSOLE_HISTOGRAM_PRECISION",
+        possible_values = &["1", "2", "3", "4", "5"]
+    )]
+    pub(crate) histogram_precision: Option<u8>,
+
+    /// How long a poll must take to be counted as slow, such as `10ms`.
+    ///
+    /// The number of each task's slow polls is shown in the tasks table and
+    /// the task view. Only the duration of the most recent poll in each
+    /// update is known, so this counts a task's other polls since the
+    /// previous update as a single slow poll if they were slow on average,
+    /// and the count is a lower bound.
+    #[clap(
+        long = "slow-poll",
+        env = "TOKIO_CONSOLE_SLOW_POLL",
+        default_value = "10ms"
+    )]
+    slow_poll: humantime::Duration,
+
+    /// Only subscribe to updates about tasks, and not about resources.
+    ///
+    /// This reduces the amount of data sent by the instrumented process when
+    /// only tasks are of interest. The resources view will be empty.
+    #[clap(
+        long = "tasks-only",
+        env = "TOKIO_CONSOLE_TASKS_ONLY",
+        conflicts_with = "resources-only"
+    )]
+    pub(crate) tasks_only: bool,
+
+    /// Only subscribe to updates about resources, and not about tasks.
+    ///
+    /// This reduces the amount of data sent by the instrumented process when
+    /// only resources are of interest. The tasks view will be empty.
+    #[clap(long = "resources-only", env = "TOKIO_CONSOLE_RESOURCES_ONLY")]
+    pub(crate) resources_only: bool,
+
+    /// Request that the instrumented process compress the updates it sends.
+    ///
+    /// This can significantly reduce bandwidth when attaching to a process
+    /// with many tasks over a slow network, at the cost of some CPU time on
+    /// both ends. The instrumented process must have been built with
+    /// `console-subscriber`'s `compression` feature; otherwise, updates are
+    /// sent uncompressed.
+    ///
+    /// Currently, only `gzip` is supported.
+    #[clap(
+        long = "compression",
+        env = "TOKIO_CONSOLE_COMPRESSION",
+        default_value = "none",
+        possible_values = &["gzip", "none"]
+    )]
+    pub(crate) compression: Compression,
+
+    /// Additional metadata to attach to every request sent to the
+    /// instrumented process, formatted as `KEY=VALUE`.
+    ///
+    /// This may be repeated to send several headers. This is useful when the
+    /// instrumented process is behind a proxy which requires authentication.
+    #[clap(
+        long = "header",
+        short = 'H',
+        env = "TOKIO_CONSOLE_HEADER",
+        use_delimiter = true,
+        value_name = "KEY=VALUE",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    headers: Vec<Header>,
+
+    /// A bearer token to send in the `authorization` header of every request
+    /// to the instrumented process.
+    #[clap(
+        long = "bearer-token",
+        env = "TOKIO_CONSOLE_BEARER_TOKEN",
+        hide_env_values = true
+    )]
+    bearer_token: Option<String>,
+
+    /// An HTTP proxy to tunnel the connection to the instrumented process
+    /// through, using the `CONNECT` method.
+    ///
+    /// If this is not set, the proxy configured by the `HTTPS_PROXY`
+    /// environment variable is used, unless the target matches the
+    /// `NO_PROXY` environment variable or is a loopback address.
+    #[clap(long = "proxy", env = "TOKIO_CONSOLE_PROXY", value_hint = ValueHint::Url)]
+    proxy: Option<Uri>,
+
+    /// A URL to POST a JSON payload to whenever a warning or watch fires.
+    ///
+    /// The payload's `text` member summarizes the warnings which fired, so
+    /// that it can be sent to a chat service's incoming webhook as-is, and
+    /// its `warnings` member describes each of them in detail.
+    #[clap(
+        long = "webhook",
+        env = "TOKIO_CONSOLE_WEBHOOK",
+        hide_env_values = true,
+        value_hint = ValueHint::Url
+    )]
+    webhook: Option<Uri>,
+
+    /// The shortest time between posts of the same kind of warning, or of
+    /// the same watch, to the `--webhook`.
+    ///
+    /// Warnings which fire less than this long after one of the same kind
+    /// was last posted are not posted.
+    #[clap(
+        long = "webhook-interval",
+        env = "TOKIO_CONSOLE_WEBHOOK_INTERVAL",
+        default_value = "1m"
+    )]
+    webhook_interval: humantime::Duration,
+
+    /// A command to run whenever a warning or watch fires, such as
+    /// `scripts/capture.sh {task_id} {lint}`.
+    ///
+    /// The command is split on whitespace into the program and its
+    /// arguments, and is not run by a shell. In each argument, `{task_id}` is
+    /// replaced with the ID of the task the warning is for (or nothing, for
+    /// watches on the whole process), `{lint}` with the warning's summary or
+    /// the watch, `{message}` with the warning's description, and `{target}`
+    /// with the instrumented process' address. The command's output is
+    /// discarded.
+    #[clap(
+        long = "on-warning",
+        env = "TOKIO_CONSOLE_ON_WARNING",
+        value_name = "COMMAND"
+    )]
+    on_warning: Option<HookCommand>,
+
+    /// The shortest time between runs of the `--on-warning` command.
+    ///
+    /// Warnings which fire less than this long after the command last ran
+    /// don't run it again.
+    #[clap(
+        long = "on-warning-interval",
+        env = "TOKIO_CONSOLE_ON_WARNING_INTERVAL",
+        default_value = "1m"
+    )]
+    on_warning_interval: humantime::Duration,
+
+    /// The events to show desktop notifications for: `warning` for warnings
+    /// and alerts which aren't critical, `critical` for critical warnings,
+    /// and `reconnect` for the connection to the instrumented process being
+    /// re-established.
+    ///
+    /// This requires the console to be built with the
+    /// `desktop-notifications` feature. When set by the environment
+    /// variable, events are separated by commas.
+    #[clap(
+        long = "notify",
+        env = "TOKIO_CONSOLE_NOTIFY",
+        use_delimiter = true,
+        value_name = "EVENT",
+        possible_values = &["warning", "critical", "reconnect"],
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub(crate) notify: Vec<notify::Event>,
+
+    /// The address of a StatsD server, such as the Datadog agent, to send
+    /// aggregated metrics to, formatted as `HOST:PORT`.
+    ///
+    /// Every interval, the console sends gauges of the number of live,
+    /// running, idle, and completed tasks, live resources, and warnings, and
+    /// counters of the tasks spawned, polls, wakes, and busy time during the
+    /// interval.
+    #[clap(
+        long = "statsd",
+        env = "TOKIO_CONSOLE_STATSD",
+        value_name = "HOST:PORT"
+    )]
+    statsd: Option<String>,
+
+    /// The prefix of the names of the metrics sent to StatsD.
+    #[clap(
+        long = "statsd-prefix",
+        env = "TOKIO_CONSOLE_STATSD_PREFIX",
+        default_value = "tokio_console"
+    )]
+    statsd_prefix: String,
+
+    /// How often to send metrics to StatsD.
+    #[clap(
+        long = "statsd-interval",
+        env = "TOKIO_CONSOLE_STATSD_INTERVAL",
+        default_value = "10s"
+    )]
+    statsd_interval: humantime::Duration,
+
+    /// Tag the metrics sent to StatsD with the address of the target they
+    /// are for, using the DogStatsD extension supported by Datadog.
+    ///
+    /// Without tags, the metrics for every target are sent under the same
+    /// names.
+    #[clap(
+        long = "statsd-tags",
+        env = "TOKIO_CONSOLE_STATSD_TAGS",
+        requires = "statsd"
+    )]
+    statsd_tags: bool,
+
+    /// The URL of an OpenTelemetry collector to send aggregated metrics to,
+    /// using OTLP over HTTP with the JSON encoding, such as
+    /// `http://localhost:4318`.
+    ///
+    /// If the URL has no path, metrics are sent to `/v1/metrics`. The same
+    /// metrics as for `--statsd` are sent, named starting with
+    /// `tokio_console.` and with a `target` attribute. If sending them
+    /// fails, the error is shown in the header until they are sent.
+    #[clap(
+        long = "otlp-endpoint",
+        env = "TOKIO_CONSOLE_OTLP_ENDPOINT",
+        value_hint = ValueHint::Url
+    )]
+    otlp_endpoint: Option<Uri>,
+
+    /// How often to send metrics to the OpenTelemetry collector.
+    #[clap(
+        long = "otlp-interval",
+        env = "TOKIO_CONSOLE_OTLP_INTERVAL",
+        default_value = "10s"
+    )]
+    otlp_interval: humantime::Duration,
+
+    /// Connect to a process running in a Kubernetes pod, formatted as
+    /// `<POD>[:<PORT>]`.
+    ///
+    /// This runs `kubectl port-forward` to the pod's instrument port (6669 by
+    /// default) and connects through the forwarded port, instead of
+    /// connecting to `TARGET_ADDR`. The port forward is torn down when the
+    /// console exits. Resources other than pods may be forwarded to by
+    /// prefixing their kind, such as `svc/my-service`.
+    #[clap(
+        long = "kube-pod",
+        env = "TOKIO_CONSOLE_KUBE_POD",
+        value_name = "POD[:PORT]",
+        conflicts_with = "via-ssh"
+    )]
+    pub(crate) kube_pod: Option<KubePod>,
+
+    /// The Kubernetes namespace of the pod passed to `--kube-pod`.
+    ///
+    /// If this is not set, `kubectl`'s current namespace is used.
+    #[clap(
+        long = "kube-namespace",
+        env = "TOKIO_CONSOLE_KUBE_NAMESPACE",
+        requires = "kube-pod"
+    )]
+    pub(crate) kube_namespace: Option<String>,
+
+    /// Connect to the target through an SSH local port forward via the
+    /// given destination, such as `user@host`.
+    ///
+    /// The first `TARGET_ADDR` is resolved from the SSH destination, so it
+    /// should usually be left as the default loopback address. `ssh` is run
+    /// non-interactively, so logging in must not require a password prompt
+    /// (use `ssh-agent` or a key without a passphrase). If the SSH connection
+    /// drops, the tunnel is re-established automatically.
+    #[clap(
+        long = "via-ssh",
+        env = "TOKIO_CONSOLE_VIA_SSH",
+        value_name = "DESTINATION"
+    )]
+    pub(crate) via_ssh: Option<String>,
+
+    /// Discover instrumented processes registered in the registry directory,
+    /// and pick one to connect to, instead of connecting to `TARGET_ADDR`.
+    ///
+    /// If more than one process is found, the console asks which one to
+    /// connect to before starting.
+    #[clap(
+        long = "discover",
+        env = "TOKIO_CONSOLE_DISCOVER",
+        requires = "registry-dir",
+        conflicts_with_all = &["kube-pod", "via-ssh"]
+    )]
+    pub(crate) discover: bool,
+
+    /// The registry directory that instrumented processes register
+    /// themselves in for `--discover`.
+    ///
+    /// This should match the directory the instrumented processes were
+    /// configured with, such as by `TOKIO_CONSOLE_REGISTRY_DIR`.
+    #[clap(
+        long = "registry-dir",
+        env = "TOKIO_CONSOLE_REGISTRY_DIR",
+        value_hint = ValueHint::DirPath
+    )]
+    pub(crate) registry_dir: Option<PathBuf>,
+
+    /// The directory to save UI sessions in.
+    ///
+    /// When the console exits, the current view and each table's sorting,
+    /// filter, and resized columns are saved for every target, and restored
+    /// the next time the console connects to the same target. If this is not set, sessions are saved in
+    /// `$XDG_STATE_HOME/tokio-console/sessions`, or
+    /// `$HOME/.local/state/tokio-console/sessions`.
+    #[clap(
+        long = "session-dir",
+        env = "TOKIO_CONSOLE_SESSION_DIR",
+        value_hint = ValueHint::DirPath
+    )]
+    session_dir: Option<PathBuf>,
+
+    /// Don't save or restore UI sessions.
+    #[clap(
+        long = "no-session",
+        env = "TOKIO_CONSOLE_NO_SESSION",
+        conflicts_with = "session-dir"
+    )]
+    no_session: bool,
+
+    /// Record what the console displays to the given file, as an asciinema
+    /// cast, which can be played back with `asciinema play`.
+    #[clap(
+        long = "record-terminal",
+        env = "TOKIO_CONSOLE_RECORD_TERMINAL",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) record_terminal: Option<PathBuf>,
+
+    /// Press keys at scripted times, read from the given file, so that demos
+    /// and tests can drive the console without anyone at the keyboard.
+    ///
+    /// Each line of the script is `wait <duration>`, `key <key>...` (such as
+    /// `key down down enter` or `key ctrl-c`), or `type <text>`. Keys can
+    /// still be pressed while the script runs.
+    #[clap(
+        long = "input-script",
+        env = "TOKIO_CONSOLE_INPUT_SCRIPT",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) input_script: Option<PathBuf>,
+
+    /// Read settings from the given configuration file.
+    ///
+    /// Each setting in the file is named after the command-line option that
+    /// sets it, without the leading `--`. Options passed on the command line
+    /// take precedence over environment variables, which take precedence over
+    /// the file. If this is not set,
+    /// `$XDG_CONFIG_HOME/tokio-console/console.toml` (or
+    /// `$HOME/.config/tokio-console/console.toml`) is read, if it exists. A
+    /// configuration file can be generated with `tokio-console gen-config`.
+    #[clap(
+        long = "config",
+        env = "TOKIO_CONSOLE_CONFIG",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath
+    )]
+    config: Option<PathBuf>,
+
+    /// A command to run instead of starting the console.
+    #[clap(subcommand)]
+    pub(crate) subcmd: Option<OptionalCmd>,
+
+    /// The parsed arguments, which `gen-config` generates a configuration
+    /// file from.
+    #[clap(skip)]
+    matches: Option<ArgMatches>,
+}
+
+/// Commands which can be run instead of starting the console.
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum OptionalCmd {
+    /// Print a configuration file with every setting documented, reflecting
+    /// the default settings and any options passed on the command line.
+    ///
+    /// The output can be saved to `$XDG_CONFIG_HOME/tokio-console/console.toml`
+    /// (or `$HOME/.config/tokio-console/console.toml`) to use it by default.
+    GenConfig,
+
+    /// Print the updates from the instrumented process to stdout, instead of
+    /// starting the console.
+    ///
+    /// Each update is printed with the current statistics of the tasks and
+    /// resources that it changed. If more than one target is given, only the
+    /// first is streamed from.
+    ///
+    /// Once stdout is closed, this exits with code 1 if warnings fired while
+    /// streaming, and 0 otherwise. If no update is received within the
+    /// timeout, this exits with code 2 if the process could not be connected
+    /// to, and 3 if it was connected to, but its updates could not be
+    /// received.
+    Stream {
+        /// The format to print updates in.
+        #[clap(long = "format", default_value = "json-lines", possible_values = &["json-lines"])]
+        format: stream::Format,
+
+        /// Print the statistics of every task and resource with each update,
+        /// rather than only of those that changed.
+        #[clap(long = "summary")]
+        summary: bool,
+
+        /// How long to wait for each update before giving up.
+        #[clap(long = "timeout", default_value = "10s")]
+        timeout: humantime::Duration,
+
+        /// Which warnings make the command exit with a failure code: any
+        /// `warning`, only `critical` ones, or `never`.
+        #[clap(
+            long = "fail-on",
+            value_name = "SEVERITY",
+            default_value = "warning",
+            possible_values = &["warning", "critical", "never"]
+        )]
+        fail_on: exit::FailOn,
+    },
+
+    /// Display the most active tasks in a compact table, like `top`, instead
+    /// of starting the console.
+    ///
+    /// Tasks are ranked by their activity since the previous update. If more
+    /// than one target is given, only the first is displayed.
+    Top {
+        /// The number of tasks to display.
+        #[clap(long = "count", short = 'n', default_value = "10")]
+        count: usize,
+
+        /// The metric to rank tasks by.
+        #[clap(long = "sort", default_value = "busy", possible_values = &["busy", "polls", "wakes"])]
+        sort: top::Metric,
+    },
+
+    /// Collect updates for a while, then write a snapshot of every task and
+    /// resource and exit, instead of starting the console.
+    ///
+    /// This exits with code 0 if no warnings fired while collecting updates,
+    /// 1 if warnings fired, 2 if the process could not be connected to, and 3
+    /// if it was connected to, but its updates could not be received.
+    Dump {
+        /// The address of the process to take a snapshot of.
+        ///
+        /// If this is not set, the first `TARGET_ADDR` is used.
+        #[clap(long = "target", value_hint = ValueHint::Url, parse(try_from_str = parse_target))]
+        target: Option<Uri>,
+
+        /// How long to collect updates for before writing the snapshot.
+        #[clap(long = "wait", default_value = "5s")]
+        wait: humantime::Duration,
+
+        /// The format to write the snapshot in.
+        #[clap(long = "format", default_value = "json", possible_values = &["json", "csv"])]
+        format: dump::Format,
+
+        /// The file to write the snapshot to. If this is not set, the snapshot
+        /// is written to stdout.
+        #[clap(long = "output", short = 'o', value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Which warnings make the command exit with a failure code: any
+        /// `warning`, only `critical` ones, or `never`.
+        #[clap(
+            long = "fail-on",
+            value_name = "SEVERITY",
+            default_value = "warning",
+            possible_values = &["warning", "critical", "never"]
+        )]
+        fail_on: exit::FailOn,
+    },
+
+    /// Compare two snapshots written by `dump --format json`, printing the
+    /// tasks and resources which appeared, disappeared, or changed
+    /// significantly between them.
+    ///
+    /// Since task IDs differ between runs, tasks are matched by their name and
+    /// spawn location, and resources by their type and location.
+    Diff {
+        /// The snapshot taken before.
+        #[clap(value_hint = ValueHint::FilePath)]
+        before: PathBuf,
+
+        /// The snapshot taken after.
+        #[clap(value_hint = ValueHint::FilePath)]
+        after: PathBuf,
+
+        /// How much, in percent, busy time, poll counts, or the number of
+        /// tasks must change by to be reported.
+        #[clap(long = "threshold", default_value = "10")]
+        threshold: f64,
+    },
+
+    /// Display a synthetic process, which spawns tasks and creates resources
+    /// locally, instead of connecting to a target.
+    ///
+    /// This is useful for trying out the console, and for seeing how it
+    /// performs with many tasks.
+    Demo {
+        /// The number of tasks to keep alive.
+        #[clap(long = "tasks", default_value = "50")]
+        tasks: usize,
+
+        /// The number of resources to keep alive.
+        #[clap(long = "resources", default_value = "20")]
+        resources: usize,
+
+        /// How often the synthetic process publishes updates.
+        #[clap(long = "interval", default_value = "1s")]
+        interval: humantime::Duration,
+    },
 }
 
 #[derive(Debug)]
 struct RetainFor(Option<Duration>);
 
+/// A `KEY=VALUE` pair of gRPC metadata.
+#[derive(Debug, Clone)]
+pub(crate) struct Header {
+    pub(crate) key: AsciiMetadataKey,
+    pub(crate) value: AsciiMetadataValue,
+}
+
+/// A Kubernetes pod (or other resource) to forward the instrument port of.
+#[derive(Debug, Clone)]
+pub(crate) struct KubePod {
+    pub(crate) pod: String,
+    pub(crate) port: u16,
+}
+
+/// A compression encoding to negotiate for the instrument connection.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+}
+
 #[derive(Clap, Debug, Clone)]
 #[clap(group = ArgGroup::new("colors").conflicts_with("no-colors"))]
 pub struct ViewOptions {
     /// Disable ANSI colors entirely.
-    #[clap(name = "no-colors", long = "no-colors")]
+    #[clap(
+        name = "no-colors",
+        long = "no-colors",
+        env = "TOKIO_CONSOLE_NO_COLORS"
+    )]
     no_colors: bool,
 
     /// Overrides the terminal's default language.
@@ -78,7 +837,7 @@ pub struct ViewOptions {
     lang: String,
 
     /// Explicitly use only ASCII characters.
-    #[clap(long = "ascii-only")]
+    #[clap(long = "ascii-only", env = "TOKIO_CONSOLE_ASCII_ONLY")]
     ascii_only: bool,
 
     /// Overrides the value of the `COLORTERM` environment variable.
@@ -96,6 +855,7 @@ pub struct ViewOptions {
     /// Explicitly set which color palette to use.
     #[clap(
         long,
+        env = "TOKIO_CONSOLE_PALETTE",
         possible_values = &["8", "16", "256", "all", "off"],
         group = "colors",
         conflicts_with_all = &["no-colors", "truecolor"]
@@ -104,33 +864,217 @@ pub struct ViewOptions {
 
     #[clap(flatten)]
     toggles: ColorToggles,
+
+    #[clap(flatten)]
+    durations: DurationFormat,
+}
+
+/// How durations are displayed.
+#[derive(Clap, Debug, Copy, Clone)]
+pub struct DurationFormat {
+    /// Display every duration in a fixed unit, rather than in the unit best
+    /// suited to its magnitude.
+    ///
+    /// If this is not set, durations of an hour or more are displayed in
+    /// hours, minutes, and seconds, and durations of a day or more in days,
+    /// hours, and minutes.
+    #[clap(
+        long = "duration-unit",
+        env = "TOKIO_CONSOLE_DURATION_UNIT",
+        possible_values = &["ns", "us", "ms", "s"],
+    )]
+    pub(crate) unit: Option<DurationUnit>,
+
+    /// The number of digits displayed after the decimal point in durations.
+    ///
+    /// Some narrow columns display fewer digits.
+    #[clap(
+        long = "duration-precision",
+        env = "TOKIO_CONSOLE_DURATION_PRECISION",
+        default_value = "4",
+        possible_values = &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"],
+    )]
+    pub(crate) precision: usize,
+}
+
+/// A unit to display every duration in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum DurationUnit {
+    Nanos,
+    Micros,
+    Millis,
+    Secs,
 }
 
 /// Toggles on and off color coding for individual UI elements.
 #[derive(Clap, Debug, Copy, Clone)]
 pub struct ColorToggles {
     /// Disable color-coding for duration units.
-    #[clap(long = "no-duration-colors", parse(from_flag = std::ops::Not::not), group = "colors")]
+    #[clap(
+        long = "no-duration-colors",
+        env = "TOKIO_CONSOLE_NO_DURATION_COLORS",
+        parse(from_flag = std::ops::Not::not),
+        group = "colors"
+    )]
     pub(crate) color_durations: bool,
 
     /// Disable color-coding for terminated tasks.
-    #[clap(long = "no-terminated-colors", parse(from_flag = std::ops::Not::not), group = "colors")]
+    #[clap(
+        long = "no-terminated-colors",
+        env = "TOKIO_CONSOLE_NO_TERMINATED_COLORS",
+        parse(from_flag = std::ops::Not::not),
+        group = "colors"
+    )]
     pub(crate) color_terminated: bool,
+
+    /// Disable color-coding for task states.
+    #[clap(
+        long = "no-state-colors",
+        env = "TOKIO_CONSOLE_NO_STATE_COLORS",
+        parse(from_flag = std::ops::Not::not),
+        group = "colors"
+    )]
+    pub(crate) color_states: bool,
 }
 
 // === impl Config ===
 
 impl Config {
-    pub fn trace_init(&mut self) -> color_eyre::Result<()> {
-        let filter = std::mem::take(&mut self.env_filter);
+    /// Parses the command-line arguments, filling in any options that weren't
+    /// passed on the command line (or set by an environment variable) from
+    /// the configuration file.
+    pub(crate) fn load() -> color_eyre::Result<Self> {
+        let cli: Vec<OsString> = std::env::args_os().collect();
+        let config_path = Self::config_path(&cli)
+            .or_else(|| std::env::var_os("TOKIO_CONSOLE_CONFIG").map(PathBuf::from));
+        let file = match config_path {
+            Some(path) => file::ConfigFile::read(&path, true)?,
+            None => match file::default_path() {
+                Some(path) => file::ConfigFile::read(&path, false)?,
+                None => None,
+            },
+        };
+
+        let mut args = cli.clone();
+        if let Some(ref file) = file {
+            // Insert the file's settings before the command-line arguments,
+            // so that any subcommand remains last.
+            let file_args = file.args(&Self::into_app(), &cli[1..])?;
+            args.splice(1..1, file_args.into_iter().map(OsString::from));
+        }
+
+        let matches = Self::into_app().get_matches_from(args);
+        let mut config = Self::from_arg_matches(&matches)
+            .ok_or_else(|| color_eyre::eyre::eyre!("failed to parse arguments"))?;
+
+        // The target addresses are positional, so they can't be inserted
+        // before the command-line arguments like the other settings.
+        let targets = file.as_ref().and_then(file::ConfigFile::targets);
+        if let Some(targets) = targets {
+            let in_env = std::env::var_os("TOKIO_CONSOLE_TARGET").is_some();
+            if matches.occurrences_of("target-addrs") == 0 && !in_env {
+                config.target_addrs = targets
+                    .iter()
+                    .map(|target| parse_target(target))
+                    .collect::<Result<_, _>>()
+                    .map_err(|error| color_eyre::eyre::eyre!("{}", error))?;
+            }
+        }
+
+        config.matches = Some(matches);
+        Ok(config)
+    }
+
+    /// Returns the path to the configuration file passed on the command
+    /// line, if any.
+    ///
+    /// This is needed before the arguments are parsed, since the file's
+    /// settings are parsed along with them.
+    fn config_path(cli: &[OsString]) -> Option<PathBuf> {
+        let mut args = cli.iter().skip(1);
+        while let Some(arg) = args.next() {
+            let arg = match arg.to_str() {
+                Some(arg) => arg,
+                None => continue,
+            };
+            if arg == "--config" {
+                return args.next().map(PathBuf::from);
+            }
+            if let Some(path) = arg.strip_prefix("--config=") {
+                return Some(PathBuf::from(path));
+            }
+        }
+        None
+    }
+
+    /// Generates a configuration file with every setting documented,
+    /// reflecting the current settings.
+    pub(crate) fn gen_config(&self) -> String {
+        let matches = self
+            .matches
+            .as_ref()
+            .expect("configs are only generated from loaded arguments");
+        file::generate(&Self::into_app(), matches)
+    }
+
+    /// Installs the console's own `tracing` subscriber, returning the debug
+    /// log that recent diagnostics are captured in.
+    pub(crate) fn trace_init(&mut self) -> color_eyre::Result<DebugLog> {
         use tracing_subscriber::prelude::*;
 
-        // If we're on a Linux distro with journald, try logging to the system
-        // journal so we don't interfere with text output.
-        let journald = tracing_journald::layer().ok();
+        // Diagnostics are only written anywhere if a filter or a log file was
+        // provided, but they're always captured in the debug log.
+        if self.env_filter.is_none() {
+            self.env_filter = std::env::var("RUST_LOG")
+                .ok()
+                .filter(|filter| !filter.is_empty())
+                .map(tracing_subscriber::EnvFilter::new);
+        }
+        let logging = self.env_filter.is_some() || self.log_file.is_some();
+        let default_filter = if self.log_file.is_some() {
+            "info"
+        } else {
+            DebugLog::DEFAULT_FILTER
+        };
+        let filter = self
+            .env_filter
+            .take()
+            .unwrap_or_else(|| tracing_subscriber::EnvFilter::new(default_filter));
+
+        // If a log file was provided, log to it, so that we don't interfere
+        // with text output.
+        let file = match self.log_file {
+            Some(ref path) => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|error| {
+                        color_eyre::eyre::eyre!(
+                            "failed to open log file {}: {}",
+                            path.display(),
+                            error
+                        )
+                    })?;
+                Some(
+                    tracing_subscriber::fmt::layer()
+                        .with_writer(std::sync::Mutex::new(file))
+                        .with_ansi(false),
+                )
+            }
+            None => None,
+        };
+
+        // Otherwise, if we're on a Linux distro with journald, try logging to
+        // the system journal so we don't interfere with text output.
+        let journald = if logging && file.is_none() {
+            tracing_journald::layer().ok()
+        } else {
+            None
+        };
 
         // Otherwise, log to stderr and rely on the user redirecting output.
-        let fmt = if journald.is_none() {
+        let fmt = if logging && file.is_none() && journald.is_none() {
             Some(
                 tracing_subscriber::fmt::layer()
                     .with_writer(std::io::stderr)
@@ -140,17 +1084,195 @@ impl Config {
             None
         };
 
+        let debug_log = DebugLog::default();
         tracing_subscriber::registry()
+            .with(debug_log.clone())
+            .with(file)
             .with(journald)
             .with(fmt)
             .with(filter)
             .try_init()?;
 
-        Ok(())
+        Ok(debug_log)
+    }
+
+    /// Returns an exporter sending `target`'s metrics to the configured
+    /// StatsD server, if there is one.
+    pub(crate) fn statsd(&self, target: &Uri) -> color_eyre::Result<Option<StatsD>> {
+        let addr = match self.statsd {
+            Some(ref addr) => addr,
+            None => return Ok(None),
+        };
+        let statsd = StatsD::new(addr, &self.statsd_prefix, self.statsd_interval.into()).map_err(
+            |error| color_eyre::eyre::eyre!("invalid StatsD address `{}`: {}", addr, error),
+        )?;
+        let target = target.to_string();
+        let tags = if self.statsd_tags {
+            vec![("target", target.as_str())]
+        } else {
+            Vec::new()
+        };
+        Ok(Some(statsd.with_tags(tags)))
+    }
+
+    /// Returns an exporter sending `target`'s metrics to the configured
+    /// OpenTelemetry collector, if there is one.
+    pub(crate) fn otlp(&self, target: &Uri) -> Option<Otlp> {
+        let endpoint = self.otlp_endpoint.clone()?;
+        Some(Otlp::new(endpoint, self.otlp_interval.into(), target))
+    }
+
+    /// Returns the webhook to post warnings to, if one is set.
+    pub(crate) fn webhook(&self) -> Option<Webhook> {
+        let url = self.webhook.clone()?;
+        Some(Webhook::new(url, self.webhook_interval.into()))
+    }
+
+    /// Returns the hook to run the `--on-warning` command, if one is set.
+    pub(crate) fn warning_hook(&self) -> Option<WarningHook> {
+        let command = self.on_warning.clone()?;
+        Some(WarningHook::new(command, self.on_warning_interval.into()))
+    }
+
+    pub(crate) fn slow_poll(&self) -> Duration {
+        self.slow_poll.into()
+    }
+
+    pub(crate) fn publish_interval(&self) -> Option<Duration> {
+        self.publish_interval.map(Into::into)
+    }
+
+    /// Returns the watches to check, including the built-in watches on the
+    /// rate at which tasks are spawned.
+    pub(crate) fn watches(&self) -> Vec<Watch> {
+        let builtin = |expr: String, label: &str| {
+            expr.parse::<Watch>()
+                .expect("built-in watches are valid")
+                .with_label(label)
+        };
+        let mut watches = self.watches.clone();
+        watches.push(builtin(
+            "spawn_rate_jump >= 10".to_string(),
+            "the spawn rate jumped tenfold over its recent mean",
+        ));
+        if let Some(max) = self.max_spawn_rate {
+            watches.push(builtin(
+                format!("spawn_rate > {}", max),
+                "tasks are being spawned too quickly",
+            ));
+        }
+        watches
+    }
+
+    /// Returns how long completed tasks and dropped resources are retained
+    /// for, with `--retain-for` applying to whichever isn't set separately.
+    pub(crate) fn retention(&self) -> Retention {
+        let retain =
+            |retain_for: &Option<RetainFor>| retain_for.as_ref().unwrap_or(&self.retain_for).0;
+        let tasks = retain(&self.retain_tasks_for);
+        Retention {
+            tasks,
+            warned_tasks: self
+                .retain_warned_tasks_for
+                .as_ref()
+                .map_or(tasks, |retain_for| retain_for.0),
+            resources: retain(&self.retain_resources_for),
+        }
+    }
+
+    /// Returns the directory to save UI sessions in, or `None` if sessions
+    /// are disabled.
+    pub(crate) fn session_dir(&self) -> Option<PathBuf> {
+        if self.no_session {
+            return None;
+        }
+        if let Some(ref dir) = self.session_dir {
+            return Some(dir.clone());
+        }
+        let state_dir = std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+            })?;
+        Some(state_dir.join("tokio-console").join("sessions"))
+    }
+
+    /// Returns the HTTP proxy to connect to `target` through, if any.
+    pub(crate) fn proxy(&self, target: &Uri) -> Option<Uri> {
+        if let Some(ref proxy) = self.proxy {
+            return Some(proxy.clone());
+        }
+
+        let proxy = std::env::var("HTTPS_PROXY")
+            .or_else(|_| std::env::var("https_proxy"))
+            .ok()
+            .filter(|proxy| !proxy.is_empty())?;
+        if Self::bypass_proxy(target) {
+            tracing::debug!(target_addr = %target, "not using `HTTPS_PROXY` for target");
+            return None;
+        }
+
+        match proxy.parse() {
+            Ok(proxy) => Some(proxy),
+            Err(error) => {
+                tracing::warn!(%error, %proxy, "invalid `HTTPS_PROXY`, ignoring it");
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if proxies configured through the environment should not
+    /// be used for the target address.
+    fn bypass_proxy(target: &Uri) -> bool {
+        if !matches!(target.scheme_str(), Some("http") | Some("https")) {
+            return true;
+        }
+
+        let host = match target.host() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+            None => return true,
+        };
+
+        let is_loopback = host.eq_ignore_ascii_case("localhost")
+            || host
+                .parse::<std::net::IpAddr>()
+                .map(|ip| ip.is_loopback())
+                .unwrap_or(false);
+        if is_loopback {
+            return true;
+        }
+
+        let no_proxy = std::env::var("NO_PROXY")
+            .or_else(|_| std::env::var("no_proxy"))
+            .unwrap_or_default();
+        no_proxy
+            .split(',')
+            .map(|pattern| pattern.trim().trim_start_matches('.'))
+            .filter(|pattern| !pattern.is_empty())
+            .any(|pattern| {
+                pattern == "*"
+                    || host.eq_ignore_ascii_case(pattern)
+                    || host
+                        .to_ascii_lowercase()
+                        .ends_with(&format!(".{}", pattern.to_ascii_lowercase()))
+            })
     }
 
-    pub(crate) fn retain_for(&self) -> Option<Duration> {
-        self.retain_for.0
+    /// Returns the metadata to attach to every request sent to the
+    /// instrumented process, including the bearer token, if one was provided.
+    pub(crate) fn headers(&self) -> color_eyre::Result<Vec<Header>> {
+        let mut headers = self.headers.clone();
+        if let Some(ref token) = self.bearer_token {
+            let value = format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| color_eyre::eyre::eyre!("bearer token is not valid ASCII"))?;
+            headers.push(Header {
+                key: AsciiMetadataKey::from_static("authorization"),
+                value,
+            });
+        }
+        Ok(headers)
     }
 }
 
@@ -210,6 +1332,30 @@ impl ViewOptions {
     pub(crate) fn toggles(&self) -> ColorToggles {
         self.toggles
     }
+
+    pub(crate) fn durations(&self) -> DurationFormat {
+        self.durations
+    }
+}
+
+/// Parses a target address, translating Windows named pipe paths
+/// (`\\<server>\pipe\<name>`) into `pipe://<server>/<name>` URIs.
+fn parse_target(s: &str) -> Result<Uri, String> {
+    if let Some(pipe) = s.strip_prefix(r"\\") {
+        return match pipe.split_once('\\') {
+            Some((server, name)) if name.to_ascii_lowercase().starts_with(r"pipe\") => {
+                format!("pipe://{}/{}", server, &name["pipe\\".len()..])
+                    .parse()
+                    .map_err(|err| format!("invalid named pipe path `{}`: {}", s, err))
+            }
+            _ => Err(format!(
+                "invalid named pipe path `{}`: expected `\\\\<server>\\pipe\\<name>`",
+                s
+            )),
+        };
+    }
+    s.parse()
+        .map_err(|err| format!("invalid target address `{}`: {}", s, err))
 }
 
 fn parse_true_color(s: &str) -> bool {
@@ -217,6 +1363,76 @@ fn parse_true_color(s: &str) -> bool {
     s.eq_ignore_ascii_case("truecolor") || s.eq_ignore_ascii_case("24bit")
 }
 
+impl FromStr for Header {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected a header formatted as `KEY=VALUE`, got `{}`", s))?;
+        let key = key
+            .trim()
+            .parse::<AsciiMetadataKey>()
+            .map_err(|err| format!("invalid header name `{}`: {}", key, err))?;
+        let value = value
+            .trim()
+            .parse::<AsciiMetadataValue>()
+            .map_err(|err| format!("invalid header value for `{}`: {}", key, err))?;
+        Ok(Header { key, value })
+    }
+}
+
+impl FromStr for KubePod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const DEFAULT_PORT: u16 = 6669;
+
+        let (pod, port) = match s.rsplit_once(':') {
+            Some((pod, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|err| format!("invalid port `{}`: {}", port, err))?;
+                (pod, port)
+            }
+            None => (s, DEFAULT_PORT),
+        };
+        if pod.is_empty() {
+            return Err("pod name must not be empty".to_string());
+        }
+        Ok(KubePod {
+            pod: pod.to_string(),
+            port,
+        })
+    }
+}
+
+impl FromStr for Compression {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("none") => Ok(Compression::None),
+            s if s.eq_ignore_ascii_case("gzip") => Ok(Compression::Gzip),
+            _ => Err("unsupported compression encoding"),
+        }
+    }
+}
+
+impl FromStr for DurationUnit {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "ns" => Ok(DurationUnit::Nanos),
+            "us" | "µs" => Ok(DurationUnit::Micros),
+            "ms" => Ok(DurationUnit::Millis),
+            "s" => Ok(DurationUnit::Secs),
+            _ => Err("unsupported duration unit"),
+        }
+    }
+}
+
 impl FromStr for RetainFor {
     type Err = humantime::DurationError;
 
@@ -0,0 +1,249 @@
+//! Reading settings from, and generating, configuration files.
+//!
+//! A configuration file is a TOML file in which each setting is named after
+//! the command-line option that sets it, without the leading `--`. Settings
+//! are strings, numbers, booleans, or arrays of strings and numbers.
+use clap::{App, ArgMatches, ArgSettings};
+use std::{
+    ffi::OsString,
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The setting for the positional `TARGET_ADDR` arguments.
+pub(super) const TARGET_KEY: &str = "target";
+
+/// The settings read from a configuration file, in the order they appear.
+#[derive(Debug)]
+pub(super) struct ConfigFile {
+    path: PathBuf,
+    settings: Vec<(String, Value)>,
+}
+
+#[derive(Debug)]
+enum Value {
+    Str(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+/// Arguments which don't make sense to set in a configuration file.
+const SKIPPED: &[&str] = &["help", "version", "config"];
+
+impl ConfigFile {
+    /// Reads the configuration file at `path`.
+    ///
+    /// If `required` is `false` and the file does not exist, this returns
+    /// `None`, rather than an error.
+    pub(super) fn read(path: &Path, required: bool) -> color_eyre::Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if !required && error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => {
+                return Err(color_eyre::eyre::eyre!(
+                    "failed to read config file {}: {}",
+                    path.display(),
+                    error
+                ))
+            }
+        };
+        let settings = parse(&contents)
+            .map_err(|error| color_eyre::eyre::eyre!("{}: {}", path.display(), error))?;
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            settings,
+        }))
+    }
+
+    /// Returns the command-line arguments for every setting in the file
+    /// which isn't already set on the command line, `cli`, or by an
+    /// environment variable.
+    pub(super) fn args(&self, app: &App<'_>, cli: &[OsString]) -> color_eyre::Result<Vec<String>> {
+        let mut args = Vec::new();
+        for (key, value) in &self.settings {
+            if key == TARGET_KEY {
+                continue;
+            }
+            let arg = app
+                .get_arguments()
+                .filter(|arg| !SKIPPED.contains(&arg.get_name()))
+                .find(|arg| arg.get_long() == Some(key.as_str()))
+                .ok_or_else(|| {
+                    color_eyre::eyre::eyre!(
+                        "unknown setting `{}` in config file {}",
+                        key,
+                        self.path.display()
+                    )
+                })?;
+
+            let long = format!("--{}", key);
+            let short = arg.get_short().map(|short| format!("-{}", short));
+            let on_cli = cli.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+                arg == long
+                    || arg.starts_with(&format!("{}=", long))
+                    || matches!(short, Some(ref short) if arg.starts_with(short))
+            });
+            let in_env = matches!(arg.get_env(), Some(env) if std::env::var_os(env).is_some());
+            if on_cli || in_env {
+                continue;
+            }
+
+            match value {
+                Value::Bool(true) => args.push(long),
+                Value::Bool(false) => {}
+                Value::Str(value) => args.push(format!("{}={}", long, value)),
+                Value::List(values) => {
+                    args.extend(values.iter().map(|value| format!("{}={}", long, value)))
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    /// Returns the target addresses set in the file, if any.
+    pub(super) fn targets(&self) -> Option<Vec<String>> {
+        let (_, value) = self.settings.iter().find(|(key, _)| key == TARGET_KEY)?;
+        match value {
+            Value::Str(target) => Some(vec![target.clone()]),
+            Value::List(targets) => Some(targets.clone()),
+            Value::Bool(_) => None,
+        }
+    }
+}
+
+/// Returns the path of the configuration file that is read by default.
+pub(super) fn default_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("tokio-console").join("console.toml"))
+}
+
+/// Generates a configuration file documenting every setting, with the values
+/// in `matches`.
+pub(super) fn generate(app: &App<'_>, matches: &ArgMatches) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# tokio-console configuration");
+    let _ = writeln!(out, "#");
+    let _ = writeln!(
+        out,
+        "# Each setting is named after the command-line option that sets it. Options"
+    );
+    let _ = writeln!(
+        out,
+        "# passed on the command line take precedence over environment variables,"
+    );
+    let _ = writeln!(out, "# which take precedence over this file.");
+
+    for arg in app.get_arguments() {
+        if SKIPPED.contains(&arg.get_name()) {
+            continue;
+        }
+        let key = match (arg.get_long(), arg.get_index()) {
+            (Some(long), _) => long,
+            (None, Some(_)) => TARGET_KEY,
+            (None, None) => continue,
+        };
+
+        out.push('\n');
+        if let Some(about) = arg.get_long_about().or_else(|| arg.get_about()) {
+            comment(&mut out, about);
+        }
+        if let Some(env) = arg.get_env() {
+            let _ = writeln!(out, "#\n# Environment variable: {}", env.to_string_lossy());
+        }
+
+        if !arg.is_set(ArgSettings::TakesValue) {
+            let _ = writeln!(out, "{} = {}", key, matches.is_present(arg.get_name()));
+            continue;
+        }
+
+        let values: Vec<&str> = matches
+            .values_of(arg.get_name())
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        // Don't write secrets into the file, even if they were set.
+        let hidden = arg.is_set(ArgSettings::HideEnvValues);
+        let multiple =
+            arg.is_set(ArgSettings::MultipleOccurrences) || arg.is_set(ArgSettings::MultipleValues);
+        match values.as_slice() {
+            [] => {
+                let _ = writeln!(out, "# {} = {}", key, if multiple { "[]" } else { "\"\"" });
+            }
+            _ if hidden => {
+                let _ = writeln!(out, "# {} = \"\"", key);
+            }
+            [value] if !multiple => {
+                let _ = writeln!(out, "{} = {}", key, quote(value));
+            }
+            values => {
+                let values: Vec<String> = values.iter().map(|value| quote(value)).collect();
+                let _ = writeln!(out, "{} = [{}]", key, values.join(", "));
+            }
+        }
+    }
+    out
+}
+
+/// Writes `text` as comment lines, wrapped to fit in 80 columns.
+fn comment(out: &mut String, text: &str) {
+    const WIDTH: usize = 78;
+    for paragraph in text.lines() {
+        let mut line = String::from("#");
+        for word in paragraph.split_whitespace() {
+            if line.len() > 1 && line.len() + 1 + word.len() > WIDTH {
+                let _ = writeln!(out, "{}", line);
+                line = String::from("#");
+            }
+            line.push(' ');
+            line.push_str(word);
+        }
+        let _ = writeln!(out, "{}", line);
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parses the settings in a configuration file, returning a description of
+/// the first error, if any.
+fn parse(contents: &str) -> Result<Vec<(String, Value)>, String> {
+    let table =
+        toml::from_str::<toml::value::Table>(contents).map_err(|error| error.to_string())?;
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::Boolean(value) => Value::Bool(value),
+                toml::Value::Array(values) => Value::List(
+                    values
+                        .into_iter()
+                        .map(scalar)
+                        .collect::<Result<_, _>>()
+                        .map_err(|error| format!("{} in the array for `{}`", error, key))?,
+                ),
+                value => {
+                    Value::Str(scalar(value).map_err(|error| format!("{} for `{}`", error, key))?)
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Returns a string or number setting as the string to pass on the command
+/// line.
+fn scalar(value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(value) => Ok(value),
+        toml::Value::Integer(value) => Ok(value.to_string()),
+        toml::Value::Float(value) => Ok(value.to_string()),
+        value => Err(format!(
+            "expected a string or a number, found {}",
+            value.type_str()
+        )),
+    }
+}
@@ -0,0 +1,451 @@
+use self::proxy::ProxyConnector;
+use crate::config::{Compression, Header};
+use console_api::instrument::{
+    instrument_client::InstrumentClient, InstrumentRequest, PauseRequest, ResourceDetailsRequest,
+    ResumeRequest, SetPublishIntervalRequest, TaskDetailsRequest, Update,
+};
+use console_api::{resources::ResourceDetails, tasks::TaskDetails};
+use futures::stream::StreamExt;
+use std::{convert::TryInto, error::Error, fmt, future::Future, pin::Pin, time::Duration};
+use tonic::{
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Channel, Endpoint, Uri},
+    Streaming,
+};
+
+#[cfg(windows)]
+mod pipe;
+mod proxy;
+mod stats;
+#[cfg(target_os = "linux")]
+mod vsock;
+
+pub(crate) use self::stats::Stats;
+
+#[derive(Debug)]
+pub struct Connection {
+    target: Uri,
+    state: State,
+    /// The attempt to reconnect that is in progress, if any.
+    ///
+    /// This is kept across calls to `connect`, so that giving up on waiting
+    /// for it (such as when another target's update arrives first) doesn't
+    /// restart the backoff or the connection attempt.
+    attempt: Option<Attempt>,
+    compression: Compression,
+    headers: Headers,
+    proxy: Option<Uri>,
+    stats: Stats,
+    /// The publish interval requested from the instrumented process, if any.
+    publish_interval: Option<Duration>,
+    /// Which updates to subscribe to.
+    subscription: InstrumentRequest,
+}
+
+/// Attaches user-provided metadata, such as authentication headers, to every
+/// request sent to the instrumented process.
+#[derive(Clone, Debug, Default)]
+struct Headers(Vec<Header>);
+
+type Client = InstrumentClient<InterceptedService<Channel, Headers>>;
+
+type ConnectError = Box<dyn Error + Send + Sync>;
+
+/// The connected state and the publish interval that was set, if any.
+type Connected = Result<(State, Option<Duration>), ConnectError>;
+
+/// Waits out the backoff, then connects and subscribes to updates.
+struct Attempt(Pin<Box<dyn Future<Output = Connected> + Send>>);
+
+// clippy doesn't like that the "connected" case is much larger than the
+// disconnected case, and suggests boxing the connected side's stream.
+// however, this is rarely disconnected; it's normally connected. boxing the
+// stream just adds a heap pointer dereference, slightly penalizing polling
+// the stream in most cases. so, don't listen to clippy on this.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+enum State {
+    Connected {
+        client: Client,
+        stream: Streaming<Update>,
+    },
+    Disconnected(Duration),
+}
+
+macro_rules! with_client {
+    ($me:ident, $client:ident, $block:expr) => ({
+        loop {
+            match $me.state {
+                State::Connected { client: ref mut $client, .. } => {
+                    match $block {
+                        Ok(resp) => break Ok(resp),
+                        // If the error is a `h2::Error`, that indicates
+                        // something went wrong at the connection level, rather
+                        // than the server returning an error code. In that
+                        // case, let's try reconnecting...
+                        Err(error) if error.source().iter().any(|src| src.is::<h2::Error>()) => {
+                            tracing::warn!(
+                                error = %error,
+                                "connection error sending command"
+                            );
+                            $me.state = State::Disconnected(Self::BACKOFF);
+                        }
+                        // Otherwise, return the error.
+                        Err(e) => {
+                            break Err(e);
+                        }
+                    }
+                }
+                State::Disconnected(_) => $me.connect().await,
+            }
+        }
+    })
+}
+
+impl Connection {
+    const BACKOFF: Duration = Duration::from_millis(500);
+    pub fn new(target: Uri) -> Self {
+        Self {
+            target,
+            state: State::Disconnected(Duration::from_secs(0)),
+            attempt: None,
+            compression: Compression::None,
+            headers: Headers::default(),
+            proxy: None,
+            stats: Stats::default(),
+            publish_interval: None,
+            subscription: InstrumentRequest::default(),
+        }
+    }
+
+    pub(crate) fn with_publish_interval(mut self, publish_interval: Option<Duration>) -> Self {
+        self.publish_interval = publish_interval;
+        self
+    }
+
+    /// Sets which kinds of updates to subscribe to. Leaving out the kinds
+    /// that aren't needed reduces the data sent by the instrumented process.
+    pub(crate) fn with_subscription(mut self, tasks: bool, resources: bool) -> Self {
+        self.subscription.exclude_tasks = !tasks;
+        self.subscription.exclude_resources = !resources;
+        self
+    }
+
+    /// Asks the instrumented process to record poll duration histograms with
+    /// `sigfig` significant figures, if set.
+    pub(crate) fn with_histogram_precision(mut self, sigfig: Option<u8>) -> Self {
+        self.subscription.histogram_significant_figures = sigfig.map_or(0, u32::from);
+        self
+    }
+
+    pub(crate) fn target(&self) -> &Uri {
+        &self.target
+    }
+
+    pub(crate) fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub(crate) fn with_proxy(mut self, proxy: Option<Uri>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub(crate) fn with_headers(mut self, headers: Vec<Header>) -> Self {
+        self.headers = Headers(headers);
+        self
+    }
+
+    pub(crate) fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    async fn connect(&mut self) {
+        const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+        while let State::Disconnected(backoff) = self.state {
+            if self.attempt.is_none() {
+                if backoff == Duration::from_secs(0) {
+                    tracing::debug!(to = %self.target, "connecting");
+                } else {
+                    tracing::debug!(reconnect_in = ?backoff, "reconnecting");
+                }
+                self.attempt = Some(self.start_attempt(backoff));
+            }
+            let connected = match self.attempt {
+                Some(Attempt(ref mut attempt)) => attempt.await,
+                None => unreachable!("an attempt to connect was just started"),
+            };
+            self.attempt = None;
+            self.state = match connected {
+                Ok((connected, publish_interval)) => {
+                    tracing::debug!("connected successfully!");
+                    self.stats.record_connect();
+                    self.stats.publish_interval = publish_interval;
+                    connected
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "error connecting");
+                    // A status means the process was connected to, but
+                    // refused to stream updates.
+                    match error.downcast_ref::<tonic::Status>() {
+                        Some(status) => self.stats.record_status(status),
+                        None => self.stats.record_error(&*error),
+                    }
+                    let backoff = std::cmp::max(backoff + Self::BACKOFF, MAX_BACKOFF);
+                    State::Disconnected(backoff)
+                }
+            };
+        }
+    }
+
+    /// Starts an attempt to connect after waiting for `backoff`.
+    ///
+    /// The attempt owns everything it needs, so that it can outlive the call
+    /// to `connect` that started it.
+    fn start_attempt(&self, backoff: Duration) -> Attempt {
+        let target = self.target.clone();
+        let proxy = self.proxy.clone();
+        let headers = self.headers.clone();
+        let compression = self.compression;
+        let subscription = self.subscription.clone();
+        let publish_interval = self.publish_interval;
+        Attempt(Box::pin(async move {
+            if backoff > Duration::from_secs(0) {
+                tokio::time::sleep(backoff).await;
+            }
+            let channel = connect_channel(&target, proxy.as_ref()).await?;
+            let mut client = InstrumentClient::with_interceptor(channel, headers);
+            if compression == Compression::Gzip {
+                client = client.accept_gzip();
+            }
+            let request = tonic::Request::new(subscription);
+            let stream = client.watch_updates(request).await?.into_inner();
+            // Re-apply the requested publish interval on every connection,
+            // since the process may have restarted since we last asked.
+            let publish_interval = match publish_interval {
+                Some(interval) => request_publish_interval(&mut client, interval)
+                    .await
+                    .map_err(|status| {
+                        tracing::warn!(%status, "failed to set the publish interval");
+                    })
+                    .ok(),
+                None => None,
+            };
+            Ok((State::Connected { client, stream }, publish_interval))
+        }))
+    }
+
+    pub async fn next_update(&mut self) -> Update {
+        loop {
+            match self.state {
+                State::Connected { ref mut stream, .. } => match Pin::new(stream).next().await {
+                    Some(Ok(update)) => {
+                        self.stats.record_update(&update);
+                        return update;
+                    }
+                    Some(Err(status)) => {
+                        tracing::warn!(%status, "error from stream");
+                        self.stats.record_status(&status);
+                        self.state = State::Disconnected(Self::BACKOFF);
+                    }
+                    None => {
+                        tracing::error!("stream closed by server");
+                        self.state = State::Disconnected(Self::BACKOFF);
+                    }
+                },
+                State::Disconnected(_) => self.connect().await,
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn watch_details(
+        &mut self,
+        task_id: u64,
+    ) -> Result<Streaming<TaskDetails>, tonic::Status> {
+        with_client!(self, client, {
+            let request = tonic::Request::new(TaskDetailsRequest {
+                id: Some(task_id.into()),
+            });
+            client.watch_task_details(request).await
+        })
+        .map(|watch| watch.into_inner())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn watch_resource_details(
+        &mut self,
+        resource_id: u64,
+    ) -> Result<Streaming<ResourceDetails>, tonic::Status> {
+        with_client!(self, client, {
+            let request = tonic::Request::new(ResourceDetailsRequest {
+                id: Some(resource_id.into()),
+            });
+            client.watch_resource_details(request).await
+        })
+        .map(|watch| watch.into_inner())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn pause(&mut self) {
+        let res = with_client!(self, client, {
+            let request = tonic::Request::new(PauseRequest {});
+            client.pause(request).await
+        });
+
+        if let Err(e) = res {
+            tracing::error!(error = %e, "rpc error sending pause command");
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn resume(&mut self) {
+        let res = with_client!(self, client, {
+            let request = tonic::Request::new(ResumeRequest {});
+            client.resume(request).await
+        });
+
+        if let Err(e) = res {
+            tracing::error!(error = %e, "rpc error sending resume command");
+        }
+    }
+
+    /// Asks the instrumented process to publish updates `factor` times as
+    /// often as it currently does.
+    #[tracing::instrument(skip(self))]
+    pub(crate) async fn scale_publish_interval(&mut self, factor: f64) {
+        const DEFAULT_PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+        let current = self
+            .stats
+            .publish_interval
+            .or_else(|| self.stats.observed_interval())
+            .unwrap_or(DEFAULT_PUBLISH_INTERVAL);
+        let requested = current.mul_f64(factor);
+        let res = with_client!(self, client, {
+            request_publish_interval(client, requested).await
+        });
+
+        match res {
+            Ok(interval) => {
+                tracing::debug!(?requested, ?interval, "set publish interval");
+                self.publish_interval = Some(interval);
+                self.stats.publish_interval = Some(interval);
+            }
+            Err(e) => tracing::error!(error = %e, "rpc error setting publish interval"),
+        }
+    }
+
+    pub fn render(&self, styles: &crate::view::Styles) -> tui::text::Spans {
+        use tui::{
+            style::{Color, Modifier},
+            text::{Span, Spans},
+        };
+        let state = match self.state {
+            State::Connected { .. } => Span::styled(
+                "(CONNECTED)",
+                styles.fg(Color::Green).add_modifier(Modifier::BOLD),
+            ),
+            State::Disconnected(d) if d == Duration::from_secs(0) => Span::styled(
+                "(CONNECTING)",
+                styles.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            State::Disconnected(d) => Span::styled(
+                format!("(RECONNECTING IN {:?})", d),
+                styles.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+        };
+        let mut spans = vec![
+            Span::raw("connection: "),
+            Span::raw(self.target.to_string()),
+        ];
+        if let Some(ref proxy) = self.proxy {
+            spans.push(Span::raw(format!(" via {}", proxy)));
+        }
+        spans.push(Span::raw(" "));
+        spans.push(state);
+        Spans::from(spans)
+    }
+}
+
+/// Connects a channel to the instrumented process at `target`, through
+/// `proxy` if one is set.
+async fn connect_channel(target: &Uri, proxy: Option<&Uri>) -> Result<Channel, ConnectError> {
+    // Connectors for transports other than TCP ignore the endpoint's URI,
+    // but the endpoint still needs a valid HTTP URI to build requests.
+    const LOCAL_ENDPOINT: &str = "http://localhost";
+
+    let channel = match target.scheme_str() {
+        #[cfg(target_os = "linux")]
+        Some("vsock") => {
+            let connector = vsock::VsockConnector::from_target(target)?;
+            Endpoint::from_static(LOCAL_ENDPOINT)
+                .connect_with_connector(connector)
+                .await?
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some("vsock") => return Err("vsock targets are only supported on Linux".into()),
+        #[cfg(windows)]
+        Some("pipe") => {
+            let connector = pipe::PipeConnector::from_target(target)?;
+            Endpoint::from_static(LOCAL_ENDPOINT)
+                .connect_with_connector(connector)
+                .await?
+        }
+        #[cfg(not(windows))]
+        Some("pipe") => return Err("named pipe targets are only supported on Windows".into()),
+        _ => {
+            let endpoint = Endpoint::from(target.clone());
+            match proxy {
+                Some(proxy) => {
+                    endpoint
+                        .connect_with_connector(ProxyConnector::new(proxy.clone()))
+                        .await?
+                }
+                None => endpoint.connect().await?,
+            }
+        }
+    };
+    Ok(channel)
+}
+
+/// Requests a publish interval from the instrumented process, returning the
+/// interval it will actually use.
+async fn request_publish_interval(
+    client: &mut Client,
+    interval: Duration,
+) -> Result<Duration, tonic::Status> {
+    let request = tonic::Request::new(SetPublishIntervalRequest {
+        interval: Some(interval.into()),
+    });
+    let response = client.set_publish_interval(request).await?.into_inner();
+    Ok(response
+        .interval
+        .and_then(|interval| interval.try_into().ok())
+        .unwrap_or(interval))
+}
+
+// === impl Attempt ===
+
+impl fmt::Debug for Attempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Attempt").finish()
+    }
+}
+
+// === impl Headers ===
+
+impl Interceptor for Headers {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        let metadata = request.metadata_mut();
+        for Header { key, value } in &self.0 {
+            metadata.append(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
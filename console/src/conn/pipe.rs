@@ -0,0 +1,72 @@
+//! Support for connecting to processes over a Windows named pipe, with targets
+//! of the form `\\<server>\pipe\<name>`.
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient};
+use tonic::{codegen::Service, transport::Uri};
+
+/// A connector which opens a fixed named pipe, ignoring the URI of the
+/// channel's endpoint.
+#[derive(Clone, Debug)]
+pub(crate) struct PipeConnector {
+    path: String,
+}
+
+impl PipeConnector {
+    /// `ERROR_PIPE_BUSY`: the pipe exists, but all of its instances are
+    /// currently connected to other clients.
+    const ERROR_PIPE_BUSY: i32 = 231;
+    const BUSY_RETRY: Duration = Duration::from_millis(50);
+    const MAX_BUSY_RETRIES: usize = 20;
+
+    /// Returns a connector for a `pipe://<server>/<name>` target, as produced
+    /// by parsing a `\\<server>\pipe\<name>` path.
+    pub(crate) fn from_target(target: &Uri) -> io::Result<Self> {
+        let server = target.host().unwrap_or(".");
+        let name = target.path().trim_start_matches('/');
+        if name.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid named pipe target `{}`: missing pipe name", target),
+            ));
+        }
+        Ok(Self {
+            path: format!(r"\\{}\pipe\{}", server, name),
+        })
+    }
+}
+
+impl Service<Uri> for PipeConnector {
+    type Response = NamedPipeClient;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<NamedPipeClient>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move {
+            tracing::debug!(%path, "opening named pipe");
+            let mut retries = 0;
+            loop {
+                match ClientOptions::new().open(&path) {
+                    Err(e)
+                        if e.raw_os_error() == Some(Self::ERROR_PIPE_BUSY)
+                            && retries < Self::MAX_BUSY_RETRIES =>
+                    {
+                        retries += 1;
+                        tokio::time::sleep(Self::BUSY_RETRY).await;
+                    }
+                    res => return res,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,124 @@
+//! Support for tunneling the instrument connection through an HTTP proxy,
+//! using the `CONNECT` method.
+use percent_encoding::percent_decode_str;
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tonic::{codegen::Service, transport::Uri};
+
+/// A connector which establishes a tunnel to the target through an HTTP
+/// proxy.
+#[derive(Clone, Debug)]
+pub(crate) struct ProxyConnector {
+    proxy: Uri,
+}
+
+impl ProxyConnector {
+    /// The maximum length of the proxy's response headers. If a proxy sends us
+    /// more than this, something has gone terribly wrong.
+    const MAX_RESPONSE_LEN: usize = 8 * 1024;
+
+    pub(crate) fn new(proxy: Uri) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: Uri) -> Self::Future {
+        Box::pin(tunnel(self.proxy.clone(), target))
+    }
+}
+
+async fn tunnel(proxy: Uri, target: Uri) -> io::Result<TcpStream> {
+    let proxy_addr = host_and_port(&proxy)?;
+    let target_addr = host_and_port(&target)?;
+    tracing::debug!(%proxy_addr, %target_addr, "connecting through proxy");
+
+    let mut stream = TcpStream::connect(&proxy_addr).await?;
+    stream.set_nodelay(true)?;
+
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target_addr);
+    if let Some(credentials) = credentials(&proxy) {
+        request.push_str("Proxy-Authorization: Basic ");
+        request.push_str(&base64::encode(credentials));
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read the proxy's response a byte at a time, so that we don't consume any
+    // bytes sent by the target once the tunnel is established.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= ProxyConnector::MAX_RESPONSE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proxy response headers were too long",
+            ));
+        }
+        response.push(stream.read_u8().await?);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    tracing::debug!(status_line, "proxy responded");
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!(
+                "proxy refused to tunnel to {}: {}",
+                target_addr, status_line
+            ),
+        )),
+    }
+}
+
+/// Returns the `host:port` of a URI, using the default port for its scheme if
+/// it doesn't specify one.
+fn host_and_port(uri: &Uri) -> io::Result<String> {
+    let host = uri.host().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` does not include a host", uri),
+        )
+    })?;
+    let port = uri.port_u16().unwrap_or_else(|| match uri.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    });
+    Ok(format!("{}:{}", host, port))
+}
+
+/// Returns the `user:password` credentials included in a proxy URI, if there
+/// are any.
+///
+/// The user and password are percent-decoded, since characters such as `@`
+/// and `:` must be percent-encoded to appear in a URI.
+fn credentials(uri: &Uri) -> Option<Vec<u8>> {
+    let authority = uri.authority()?.as_str();
+    let (credentials, _) = authority.rsplit_once('@')?;
+    let mut decoded = Vec::with_capacity(credentials.len());
+    for (i, part) in credentials.splitn(2, ':').enumerate() {
+        if i > 0 {
+            decoded.push(b':');
+        }
+        decoded.extend(percent_decode_str(part));
+    }
+    Some(decoded)
+}
@@ -0,0 +1,161 @@
+//! Health statistics for the connection to the instrumented process.
+use console_api::instrument::Update;
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Statistics describing the health of the update stream.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    /// The number of updates received, across all connections.
+    pub(crate) updates: u64,
+    /// The total encoded size of the updates received, in bytes.
+    pub(crate) bytes: u64,
+    /// The number of messages from the stream which could not be decoded.
+    pub(crate) decode_errors: u64,
+    /// The number of other errors returned by the stream.
+    pub(crate) stream_errors: u64,
+    /// The number of times a connection was established.
+    pub(crate) connects: u64,
+    /// When the last update was received, in the console's clock.
+    pub(crate) last_update_at: Option<SystemTime>,
+    /// The difference between when the last update was received and when the
+    /// instrumented process sent it.
+    ///
+    /// This includes any clock skew between the two machines.
+    pub(crate) last_latency: Option<Duration>,
+    /// The last error encountered by the connection, if any.
+    pub(crate) last_error: Option<String>,
+    /// The publish interval negotiated with the instrumented process, if one
+    /// was requested.
+    pub(crate) publish_interval: Option<Duration>,
+    /// The times and sizes of updates received within the last
+    /// `RATE_WINDOW`, used to calculate the throughput.
+    recent: VecDeque<(Instant, usize)>,
+    /// The smallest difference between the console's clock when receiving an
+    /// update and the instrumented process' clock when sending it, in seconds,
+    /// seen on the current connection.
+    ///
+    /// This approximates the clock skew between the two machines plus the
+    /// network latency, so that updates arriving later than this are behind.
+    min_offset: Option<f64>,
+    /// The offset of the most recent update.
+    last_offset: Option<f64>,
+    /// When the instrumented process sent the most recent update.
+    last_sent: Option<SystemTime>,
+    /// The observed interval between updates sent by the instrumented process.
+    interval: Option<Duration>,
+}
+
+/// How far the console has fallen behind the update stream.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Lag {
+    /// How much later than expected the last update was received.
+    pub(crate) delay: Duration,
+    /// Approximately how many updates are queued behind it.
+    pub(crate) queued: u64,
+}
+
+impl Stats {
+    /// The period over which throughput is averaged.
+    const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+    pub(crate) fn record_update(&mut self, update: &Update) {
+        let now = SystemTime::now();
+        let len = update.encoded_len();
+        self.updates += 1;
+        self.bytes += len as u64;
+        self.last_update_at = Some(now);
+        let sent: Option<SystemTime> = update.now.clone().and_then(|sent| sent.try_into().ok());
+        self.last_latency = sent.and_then(|sent| now.duration_since(sent).ok());
+        if let Some(sent) = sent {
+            let offset = match now.duration_since(sent) {
+                Ok(ahead) => ahead.as_secs_f64(),
+                Err(behind) => -behind.duration().as_secs_f64(),
+            };
+            self.last_offset = Some(offset);
+            self.min_offset = Some(self.min_offset.map_or(offset, |min| min.min(offset)));
+            if let Some(interval) = self
+                .last_sent
+                .and_then(|last| sent.duration_since(last).ok())
+            {
+                self.interval = Some(interval);
+            }
+            self.last_sent = Some(sent);
+        }
+
+        let received = Instant::now();
+        self.recent.push_back((received, len));
+        while let Some(&(at, _)) = self.recent.front() {
+            if received.duration_since(at) <= Self::RATE_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    pub(crate) fn record_connect(&mut self) {
+        self.connects += 1;
+        // A new connection may have different latency (or even be to a
+        // different process), so start measuring lag from scratch.
+        self.min_offset = None;
+        self.last_offset = None;
+        self.last_sent = None;
+    }
+
+    pub(crate) fn record_status(&mut self, status: &tonic::Status) {
+        // `tonic` reports messages that fail to decode with the `Internal`
+        // status code.
+        if status.code() == tonic::Code::Internal {
+            self.decode_errors += 1;
+        } else {
+            self.stream_errors += 1;
+        }
+        self.last_error = Some(status.to_string());
+    }
+
+    pub(crate) fn record_error(&mut self, error: &dyn std::error::Error) {
+        self.last_error = Some(error.to_string());
+    }
+
+    /// Returns the interval between the last two updates, as measured by the
+    /// instrumented process.
+    pub(crate) fn observed_interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Returns the number of times the connection was re-established after
+    /// the first connection.
+    pub(crate) fn reconnects(&self) -> u64 {
+        self.connects.saturating_sub(1)
+    }
+
+    /// Returns how far behind the update stream the console is, if it has
+    /// fallen behind by more than one update interval.
+    pub(crate) fn lag(&self) -> Option<Lag> {
+        let delay = self.last_offset? - self.min_offset?;
+        let interval = self.interval?.as_secs_f64();
+        if interval <= 0.0 || delay < interval {
+            return None;
+        }
+        Some(Lag {
+            delay: Duration::from_secs_f64(delay),
+            queued: (delay / interval) as u64,
+        })
+    }
+
+    /// Returns the average throughput of the update stream over the last
+    /// `RATE_WINDOW`, in bytes per second.
+    pub(crate) fn bytes_per_sec(&self) -> f64 {
+        let bytes: usize = self
+            .recent
+            .iter()
+            .filter(|(at, _)| at.elapsed() <= Self::RATE_WINDOW)
+            .map(|&(_, len)| len)
+            .sum();
+        bytes as f64 / Self::RATE_WINDOW.as_secs_f64()
+    }
+}
@@ -0,0 +1,60 @@
+//! Support for connecting to processes running inside a virtual machine over
+//! a `vsock` socket, with targets of the form `vsock://<cid>:<port>`.
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio_vsock::VsockStream;
+use tonic::{codegen::Service, transport::Uri};
+
+/// A connector which connects to a fixed `vsock` address, ignoring the URI of
+/// the channel's endpoint.
+#[derive(Clone, Debug)]
+pub(crate) struct VsockConnector {
+    cid: u32,
+    port: u32,
+}
+
+impl VsockConnector {
+    /// Returns a connector for a `vsock://<cid>:<port>` target.
+    pub(crate) fn from_target(target: &Uri) -> io::Result<Self> {
+        let invalid = |msg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid vsock target `{}`: {}", target, msg),
+            )
+        };
+        let cid = target
+            .host()
+            .ok_or_else(|| invalid("missing context ID"))?
+            .parse()
+            .map_err(|_| invalid("context ID must be a number"))?;
+        let port = target
+            .port()
+            .ok_or_else(|| invalid("missing port"))?
+            .as_str()
+            .parse()
+            .map_err(|_| invalid("port must be a number"))?;
+        Ok(Self { cid, port })
+    }
+}
+
+impl Service<Uri> for VsockConnector {
+    type Response = VsockStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<VsockStream>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let Self { cid, port } = *self;
+        Box::pin(async move {
+            tracing::debug!(cid, port, "connecting over vsock");
+            VsockStream::connect(cid, port).await
+        })
+    }
+}
@@ -0,0 +1,99 @@
+//! Capturing the console's own recent diagnostics in memory, so that they can
+//! be displayed in the debug log pane without restarting with a log file.
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// A `tracing` layer which keeps the most recent events in memory.
+///
+/// The debug log is cheap to clone, and clones share the same events.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DebugLog {
+    entries: Arc<Mutex<VecDeque<Entry>>>,
+}
+
+/// A single captured event.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub(crate) at: SystemTime,
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    /// The event's message, followed by its other fields.
+    pub(crate) message: String,
+}
+
+/// Formats an event's fields as its message followed by `name=value` pairs.
+struct Message(String);
+
+impl DebugLog {
+    /// The filter used for the console's diagnostics when none is provided:
+    /// the console's own diagnostics are captured in detail, and its
+    /// dependencies' warnings are captured too.
+    pub(crate) const DEFAULT_FILTER: &'static str = "warn,tokio_console=debug";
+
+    /// The maximum number of events kept.
+    const CAPACITY: usize = 500;
+
+    /// Returns up to `max` of the most recent events, oldest first.
+    pub(crate) fn recent(&self, max: usize) -> Vec<Entry> {
+        let entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            // Don't panic inside the UI just because a logging thread did.
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let skip = entries.len().saturating_sub(max);
+        entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DebugLog {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        let entry = Entry {
+            at: SystemTime::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: message.0,
+        };
+
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if entries.len() >= Self::CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{}", value));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, "{}={:?}", field.name(), value);
+        }
+    }
+}
@@ -0,0 +1,959 @@
+//! A synthetic instrumented process, which generates tasks, resources, and
+//! warnings locally, for showing off the console without a real process to
+//! connect to, and for stress-testing how it renders many tasks.
+//!
+//! The synthetic process is served on a local port, just like a process
+//! instrumented with `console-subscriber`, so that the console connects to it
+//! the same way it would connect to a real one.
+use color_eyre::eyre::WrapErr;
+use console_api as proto;
+use futures::stream::{self, Stream, StreamExt};
+use hdrhistogram::{
+    serialization::{Serializer, V2Serializer},
+    Histogram,
+};
+use proto::instrument::instrument_server::{Instrument, InstrumentServer};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    net::Ipv4Addr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tokio::sync::watch;
+use tonic::{transport::Uri, Request, Response, Status};
+
+/// How many tasks and resources the synthetic process keeps alive, and how
+/// often it publishes updates.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Options {
+    pub(crate) tasks: usize,
+    pub(crate) resources: usize,
+    pub(crate) interval: Duration,
+}
+
+/// The gRPC service which streams the synthetic process' updates.
+struct Demo {
+    sim: Arc<Mutex<Sim>>,
+    ticks: watch::Receiver<u64>,
+}
+
+/// The synthetic process, which is advanced once per publish interval.
+struct Sim {
+    options: Options,
+    rng: Rng,
+    /// The number of times the process has been advanced.
+    tick: u64,
+    /// When the process was last advanced.
+    now: SystemTime,
+    paused: bool,
+    next_id: u64,
+    tasks: HashMap<u64, SimTask>,
+    resources: HashMap<u64, SimResource>,
+}
+
+/// What the synthetic process has sent to one client so far.
+struct Client {
+    sim: Arc<Mutex<Sim>>,
+    ticks: watch::Receiver<u64>,
+    /// Whether the metadata and process info have been sent.
+    started: bool,
+    /// The tick of the last update sent.
+    tick: u64,
+    tasks: HashSet<u64>,
+    resources: HashSet<u64>,
+}
+
+/// A kind of synthetic task, and how tasks of that kind behave.
+struct TaskProfile {
+    name: &'static str,
+    target: &'static str,
+    location: (&'static str, u32),
+    /// How often tasks of this kind are spawned, relative to other kinds.
+    weight: u32,
+    /// How long tasks of this kind live, in seconds.
+    lifetime: (f64, f64),
+    /// How many times tasks of this kind are polled each second, on average.
+    poll_rate: f64,
+    /// How long each poll takes, on average.
+    poll_time: Duration,
+    /// The fraction of wakes which a task of this kind does itself.
+    self_wakes: f64,
+    /// Whether tasks of this kind eventually drop their waker without waking
+    /// it, so they're never woken again.
+    loses_waker: bool,
+    /// The fields of a task of this kind, besides its name.
+    fields: fn(&mut Rng) -> Vec<(&'static str, proto::field::Value)>,
+}
+
+/// A resource attribute's name, value, and unit, if it has one.
+type Attribute = (&'static str, proto::field::Value, Option<&'static str>);
+
+/// A kind of synthetic resource.
+struct ResourceProfile {
+    concrete_type: &'static str,
+    /// The resource's kind, if it isn't a timer.
+    kind: Option<&'static str>,
+    target: &'static str,
+    location: (&'static str, u32),
+    weight: u32,
+    /// How long resources of this kind live, in seconds.
+    lifetime: (f64, f64),
+    /// The resource's attributes, which change each time it's updated.
+    attributes: fn(&mut Rng) -> Vec<Attribute>,
+}
+
+struct SimTask {
+    profile: &'static TaskProfile,
+    parent: Option<u64>,
+    fields: Vec<proto::Field>,
+    created_at: SystemTime,
+    ends_at: SystemTime,
+    dropped_at: Option<SystemTime>,
+    /// When the task loses its waker, if its kind does.
+    loses_waker_at: Option<SystemTime>,
+    polls: u64,
+    busy: Duration,
+    wakes: u64,
+    self_wakes: u64,
+    waker_clones: u64,
+    waker_drops: u64,
+    first_poll: Option<SystemTime>,
+    last_poll_started: Option<SystemTime>,
+    last_poll_ended: Option<SystemTime>,
+    last_wake: Option<SystemTime>,
+    /// The tick in which the task last changed.
+    changed: u64,
+}
+
+struct SimResource {
+    profile: &'static ResourceProfile,
+    created_at: SystemTime,
+    ends_at: SystemTime,
+    dropped_at: Option<SystemTime>,
+    attributes: Vec<proto::resources::stats::Attribute>,
+    changed: u64,
+}
+
+/// A small pseudorandom number generator (xorshift64*), since the demo
+/// doesn't need a good one.
+struct Rng(u64);
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// How long completed tasks and dropped resources are kept, so that clients
+/// see them complete.
+const KEEP_DROPPED: Duration = Duration::from_secs(30);
+
+const RUNTIME: &str = "demo";
+
+static TASKS: &[TaskProfile] = &[
+    TaskProfile {
+        name: "http::request",
+        target: "demo::http",
+        location: ("src/http/server.rs", 112),
+        weight: 10,
+        lifetime: (0.2, 5.0),
+        poll_rate: 40.0,
+        poll_time: Duration::from_micros(80),
+        self_wakes: 0.0,
+        loses_waker: false,
+        fields: |rng| {
+            let routes = ["/api/users", "/api/orders", "/health", "/api/search"];
+            let methods = ["GET", "GET", "POST", "PUT"];
+            vec![
+                ("method", (*rng.pick(&methods)).into()),
+                ("route", (*rng.pick(&routes)).into()),
+                ("request_id", rng.below(1_000_000).into()),
+            ]
+        },
+    },
+    TaskProfile {
+        name: "db::query",
+        target: "demo::db",
+        location: ("src/db/pool.rs", 57),
+        weight: 6,
+        lifetime: (0.05, 2.0),
+        poll_rate: 15.0,
+        poll_time: Duration::from_micros(250),
+        self_wakes: 0.0,
+        loses_waker: false,
+        fields: |rng| {
+            let tables = ["users", "orders", "sessions", "products"];
+            vec![(
+                "query",
+                format!("SELECT * FROM {} WHERE id = $1", rng.pick(&tables))
+                    .as_str()
+                    .into(),
+            )]
+        },
+    },
+    TaskProfile {
+        name: "cache::refresh",
+        target: "demo::cache",
+        location: ("src/cache.rs", 203),
+        weight: 2,
+        lifetime: (30.0, 300.0),
+        poll_rate: 2.0,
+        poll_time: Duration::from_millis(1),
+        self_wakes: 0.0,
+        loses_waker: false,
+        fields: |_| vec![("interval_ms", 500u64.into())],
+    },
+    TaskProfile {
+        name: "worker::spin",
+        target: "demo::worker",
+        location: ("src/worker.rs", 88),
+        weight: 1,
+        lifetime: (20.0, 120.0),
+        poll_rate: 200.0,
+        poll_time: Duration::from_micros(40),
+        // Enough to trigger the self-wake warning.
+        self_wakes: 0.8,
+        loses_waker: false,
+        fields: |rng| vec![("worker", rng.below(8).into())],
+    },
+    TaskProfile {
+        name: "conn::reader",
+        target: "demo::conn",
+        location: ("src/conn.rs", 41),
+        weight: 1,
+        lifetime: (30.0, 180.0),
+        poll_rate: 5.0,
+        poll_time: Duration::from_micros(120),
+        self_wakes: 0.0,
+        // Triggers the lost waker warning.
+        loses_waker: true,
+        fields: |rng| {
+            vec![(
+                "peer",
+                format!("10.0.0.{}:443", rng.below(255)).as_str().into(),
+            )]
+        },
+    },
+    TaskProfile {
+        name: "report::render",
+        target: "demo::report",
+        location: ("src/report.rs", 150),
+        weight: 1,
+        lifetime: (2.0, 15.0),
+        poll_rate: 8.0,
+        // Long polls, which block the worker thread.
+        poll_time: Duration::from_millis(25),
+        self_wakes: 0.1,
+        loses_waker: false,
+        fields: |rng| vec![("report_id", rng.below(10_000).into())],
+    },
+];
+
+static RESOURCES: &[ResourceProfile] = &[
+    ResourceProfile {
+        concrete_type: "Sleep",
+        kind: None,
+        target: "tokio::time::sleep",
+        location: ("src/http/server.rs", 140),
+        weight: 6,
+        lifetime: (0.5, 10.0),
+        attributes: |rng| vec![("duration", (rng.below(10_000) + 1).into(), Some("ms"))],
+    },
+    ResourceProfile {
+        concrete_type: "Semaphore",
+        kind: Some("Sync"),
+        target: "tokio::sync::semaphore",
+        location: ("src/db/pool.rs", 23),
+        weight: 2,
+        lifetime: (60.0, 600.0),
+        attributes: |rng| vec![("permits", rng.below(16).into(), None)],
+    },
+    ResourceProfile {
+        concrete_type: "Mutex",
+        kind: Some("Sync"),
+        target: "tokio::sync::mutex",
+        location: ("src/cache.rs", 30),
+        weight: 2,
+        lifetime: (30.0, 300.0),
+        attributes: |rng| vec![("locked", (rng.unit() < 0.3).into(), None)],
+    },
+    ResourceProfile {
+        concrete_type: "Channel",
+        kind: Some("Sync"),
+        target: "tokio::sync::mpsc",
+        location: ("src/worker.rs", 19),
+        weight: 2,
+        lifetime: (30.0, 300.0),
+        attributes: |rng| vec![("queued", rng.below(128).into(), Some("messages"))],
+    },
+];
+
+/// Starts serving a synthetic process on a local port, returning the address
+/// to connect to it at.
+pub(crate) async fn serve(options: Options) -> color_eyre::Result<Uri> {
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .wrap_err("failed to bind a local port for the demo")?;
+    let addr = listener.local_addr()?;
+    let incoming = Box::pin(stream::unfold(listener, |listener| async move {
+        let conn = listener.accept().await.map(|(conn, _)| conn);
+        Some((conn, listener))
+    }));
+
+    let sim = Arc::new(Mutex::new(Sim::new(options)));
+    let (tick_tx, ticks) = watch::channel(0);
+    tokio::spawn(run(sim.clone(), tick_tx));
+
+    let service = InstrumentServer::new(Demo { sim, ticks });
+    tokio::spawn(async move {
+        let serve = tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_incoming(incoming);
+        if let Err(error) = serve.await {
+            tracing::error!(%error, "demo server failed");
+        }
+    });
+
+    format!("http://{}", addr)
+        .parse()
+        .wrap_err("invalid demo address")
+}
+
+/// Advances the synthetic process once per publish interval, unless it's
+/// paused.
+async fn run(sim: Arc<Mutex<Sim>>, ticks: watch::Sender<u64>) {
+    loop {
+        let interval = sim.lock().unwrap().options.interval;
+        tokio::time::sleep(interval).await;
+        let tick = {
+            let mut sim = sim.lock().unwrap();
+            if sim.paused {
+                continue;
+            }
+            sim.advance();
+            sim.tick
+        };
+        if ticks.send(tick).is_err() {
+            return;
+        }
+    }
+}
+
+// === impl Demo ===
+
+#[tonic::async_trait]
+impl Instrument for Demo {
+    type WatchUpdatesStream = BoxStream<proto::instrument::Update>;
+    type WatchTaskDetailsStream = BoxStream<proto::tasks::TaskDetails>;
+    type WatchResourceDetailsStream = BoxStream<proto::resources::ResourceDetails>;
+
+    async fn watch_updates(
+        &self,
+        _req: Request<proto::instrument::InstrumentRequest>,
+    ) -> Result<Response<Self::WatchUpdatesStream>, Status> {
+        let client = Client {
+            sim: self.sim.clone(),
+            ticks: self.ticks.clone(),
+            started: false,
+            tick: 0,
+            tasks: HashSet::new(),
+            resources: HashSet::new(),
+        };
+        let updates = stream::unfold(client, |mut client| async move {
+            // Send the initial state right away, and then each time the
+            // process is advanced.
+            if client.started && client.ticks.changed().await.is_err() {
+                return None;
+            }
+            let sim = client.sim.clone();
+            let update = sim.lock().unwrap().update(&mut client);
+            Some((Ok(update), client))
+        });
+        Ok(Response::new(Box::pin(updates)))
+    }
+
+    async fn watch_task_details(
+        &self,
+        req: Request<proto::instrument::TaskDetailsRequest>,
+    ) -> Result<Response<Self::WatchTaskDetailsStream>, Status> {
+        let id = req
+            .into_inner()
+            .id
+            .ok_or_else(|| Status::invalid_argument("missing task_id"))?
+            .id;
+        if !self.sim.lock().unwrap().tasks.contains_key(&id) {
+            return Err(Status::not_found("task not found"));
+        }
+        let state = (self.sim.clone(), self.ticks.clone(), true);
+        let details = stream::unfold(state, move |(sim, mut ticks, first)| async move {
+            if !first && ticks.changed().await.is_err() {
+                return None;
+            }
+            let details = sim.lock().unwrap().task_details(id)?;
+            Some((Ok(details), (sim, ticks, false)))
+        });
+        Ok(Response::new(Box::pin(details)))
+    }
+
+    async fn watch_resource_details(
+        &self,
+        req: Request<proto::instrument::ResourceDetailsRequest>,
+    ) -> Result<Response<Self::WatchResourceDetailsStream>, Status> {
+        let id = req
+            .into_inner()
+            .id
+            .ok_or_else(|| Status::invalid_argument("missing resource_id"))?;
+        if !self.sim.lock().unwrap().resources.contains_key(&id.id) {
+            return Err(Status::not_found("resource not found"));
+        }
+        // Synthetic resources have no async ops.
+        let details = stream::once(async move {
+            Ok(proto::resources::ResourceDetails {
+                resource_id: Some(id),
+                now: Some(SystemTime::now().into()),
+                async_ops: 0,
+                async_op_busy_times_histogram: None,
+            })
+        })
+        .chain(stream::pending());
+        Ok(Response::new(Box::pin(details)))
+    }
+
+    async fn pause(
+        &self,
+        _req: Request<proto::instrument::PauseRequest>,
+    ) -> Result<Response<proto::instrument::PauseResponse>, Status> {
+        self.sim.lock().unwrap().paused = true;
+        Ok(Response::new(proto::instrument::PauseResponse {}))
+    }
+
+    async fn resume(
+        &self,
+        _req: Request<proto::instrument::ResumeRequest>,
+    ) -> Result<Response<proto::instrument::ResumeResponse>, Status> {
+        self.sim.lock().unwrap().paused = false;
+        Ok(Response::new(proto::instrument::ResumeResponse {}))
+    }
+
+    async fn set_publish_interval(
+        &self,
+        req: Request<proto::instrument::SetPublishIntervalRequest>,
+    ) -> Result<Response<proto::instrument::SetPublishIntervalResponse>, Status> {
+        let interval: Duration = req
+            .into_inner()
+            .interval
+            .ok_or_else(|| Status::invalid_argument("missing publish interval"))?
+            .try_into()
+            .map_err(|_| Status::invalid_argument("publish interval must be positive"))?;
+        let interval = interval.max(Duration::from_millis(100));
+        self.sim.lock().unwrap().options.interval = interval;
+        Ok(Response::new(
+            proto::instrument::SetPublishIntervalResponse {
+                interval: Some(interval.into()),
+            },
+        ))
+    }
+}
+
+// === impl Sim ===
+
+impl Sim {
+    fn new(options: Options) -> Self {
+        let mut sim = Self {
+            options,
+            rng: Rng::new(),
+            tick: 0,
+            now: SystemTime::now(),
+            paused: false,
+            next_id: 1,
+            tasks: HashMap::new(),
+            resources: HashMap::new(),
+        };
+        sim.spawn();
+        sim
+    }
+
+    /// Advances every task and resource to the current time.
+    fn advance(&mut self) {
+        let now = SystemTime::now();
+        let elapsed = now.duration_since(self.now).unwrap_or_default();
+        self.now = now;
+        self.tick += 1;
+
+        let (tick, rng) = (self.tick, &mut self.rng);
+        for task in self.tasks.values_mut() {
+            task.advance(rng, tick, now, elapsed);
+        }
+        for resource in self.resources.values_mut() {
+            resource.advance(rng, tick, now);
+        }
+
+        let expired = |dropped_at: Option<SystemTime>| {
+            matches!(
+                dropped_at,
+                Some(dropped_at) if now.duration_since(dropped_at).unwrap_or_default() > KEEP_DROPPED
+            )
+        };
+        self.tasks.retain(|_, task| !expired(task.dropped_at));
+        self.resources
+            .retain(|_, resource| !expired(resource.dropped_at));
+        self.spawn();
+    }
+
+    /// Spawns tasks and creates resources until there are as many alive as
+    /// configured.
+    fn spawn(&mut self) {
+        let live_tasks = self
+            .tasks
+            .values()
+            .filter(|task| task.dropped_at.is_none())
+            .count();
+        for _ in live_tasks..self.options.tasks {
+            let profile = self.rng.weighted(TASKS, |profile| profile.weight);
+            // Database queries are spawned by requests.
+            let parent = if profile.name == "db::query" {
+                self.tasks
+                    .iter()
+                    .find(|(_, task)| {
+                        task.profile.name == "http::request" && task.dropped_at.is_none()
+                    })
+                    .map(|(&id, _)| id)
+            } else {
+                None
+            };
+            let id = self.next_id();
+            let task = SimTask::new(profile, id, parent, &mut self.rng, self.tick, self.now);
+            self.tasks.insert(id, task);
+        }
+
+        let live_resources = self
+            .resources
+            .values()
+            .filter(|resource| resource.dropped_at.is_none())
+            .count();
+        for _ in live_resources..self.options.resources {
+            let profile = self.rng.weighted(RESOURCES, |profile| profile.weight);
+            let id = self.next_id();
+            let resource = SimResource::new(profile, &mut self.rng, self.tick, self.now);
+            self.resources.insert(id, resource);
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Returns the update for `client`, with the tasks and resources it
+    /// hasn't seen yet, and those which changed since its last update.
+    fn update(&self, client: &mut Client) -> proto::instrument::Update {
+        let mut update = proto::instrument::Update {
+            now: Some(self.now.into()),
+            task_update: Some(Default::default()),
+            resource_update: Some(Default::default()),
+            ..Default::default()
+        };
+        if !client.started {
+            client.started = true;
+            update.new_metadata = Some(proto::RegisterMetadata {
+                metadata: metadata(),
+            });
+            update.process_info = Some(proto::instrument::ProcessInfo {
+                pid: std::process::id(),
+                executable: "tokio-console demo".to_string(),
+                hostname: String::new(),
+                tokio_version: None,
+                subscriber_version: "demo".to_string(),
+            });
+        }
+
+        let tasks = update.task_update.as_mut().unwrap();
+        for (&id, task) in &self.tasks {
+            let new = client.tasks.insert(id);
+            if new {
+                tasks.new_tasks.push(task.to_proto(id));
+            }
+            if new || task.changed > client.tick {
+                tasks.stats_update.insert(id, task.stats());
+            }
+        }
+        client.tasks.retain(|id| self.tasks.contains_key(id));
+
+        let resources = update.resource_update.as_mut().unwrap();
+        for (&id, resource) in &self.resources {
+            let new = client.resources.insert(id);
+            if new {
+                resources.new_resources.push(resource.to_proto(id));
+            }
+            if new || resource.changed > client.tick {
+                resources.stats_update.insert(id, resource.stats());
+            }
+        }
+        client
+            .resources
+            .retain(|id| self.resources.contains_key(id));
+
+        client.tick = self.tick;
+        update
+    }
+
+    /// Returns the details of the task with the given ID, with a poll times
+    /// histogram resembling the task's kind.
+    fn task_details(&self, id: u64) -> Option<proto::tasks::TaskDetails> {
+        let task = self.tasks.get(&id)?;
+        let mut histogram = Histogram::<u64>::new_with_bounds(1, 60_000_000_000, 2).ok()?;
+        // Use the same poll times for a task each time.
+        let mut rng = Rng(id.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+        let mean = task.profile.poll_time.as_nanos() as f64;
+        for _ in 0..task.polls.min(1000) {
+            // Most polls are quick, but some take much longer.
+            let scale = if rng.unit() < 0.05 {
+                rng.range(3.0, 20.0)
+            } else {
+                rng.range(0.2, 1.5)
+            };
+            histogram.saturating_record((mean * scale) as u64);
+        }
+        let mut buf = Vec::new();
+        V2Serializer::new().serialize(&histogram, &mut buf).ok()?;
+        Some(proto::tasks::TaskDetails {
+            task_id: Some(id.into()),
+            now: Some(self.now.into()),
+            poll_times_histogram: Some(buf),
+        })
+    }
+}
+
+/// Returns the metadata of every synthetic task and resource, identified by
+/// [`meta_id`].
+fn metadata() -> Vec<proto::register_metadata::NewMetadata> {
+    let tasks = TASKS
+        .iter()
+        .map(|profile| ("runtime.spawn", profile.target, profile.location));
+    let resources = RESOURCES
+        .iter()
+        .map(|profile| ("runtime.resource", profile.target, profile.location));
+    tasks
+        .chain(resources)
+        .map(
+            |(name, target, (file, line))| proto::register_metadata::NewMetadata {
+                id: Some(meta_id(target)),
+                metadata: Some(proto::Metadata {
+                    name: name.to_string(),
+                    target: target.to_string(),
+                    module_path: target.to_string(),
+                    location: Some(location(file, line)),
+                    kind: proto::metadata::Kind::Span as i32,
+                    level: proto::metadata::Level::Trace as i32,
+                    field_names: Vec::new(),
+                }),
+            },
+        )
+        .collect()
+}
+
+/// Returns the ID of the metadata for tasks or resources with `target`,
+/// which is unique for each kind.
+fn meta_id(target: &str) -> proto::MetaId {
+    let index = TASKS
+        .iter()
+        .map(|profile| profile.target)
+        .chain(RESOURCES.iter().map(|profile| profile.target))
+        .position(|t| t == target)
+        .expect("every target has metadata");
+    proto::MetaId {
+        id: index as u64 + 1,
+    }
+}
+
+fn location(file: &str, line: u32) -> proto::Location {
+    proto::Location {
+        file: Some(file.to_string()),
+        line: Some(line),
+        column: Some(9),
+        ..Default::default()
+    }
+}
+
+fn field(target: &str, name: &str, value: proto::field::Value) -> proto::Field {
+    proto::Field {
+        name: Some(name.into()),
+        value: Some(value),
+        metadata_id: Some(meta_id(target)),
+    }
+}
+
+// === impl SimTask ===
+
+impl SimTask {
+    fn new(
+        profile: &'static TaskProfile,
+        id: u64,
+        parent: Option<u64>,
+        rng: &mut Rng,
+        tick: u64,
+        now: SystemTime,
+    ) -> Self {
+        let name = format!("{}-{}", profile.name, id);
+        let mut fields = vec![
+            field(profile.target, "task.name", name.as_str().into()),
+            field(profile.target, "kind", "task".into()),
+        ];
+        fields.extend(
+            (profile.fields)(rng)
+                .into_iter()
+                .map(|(name, value)| field(profile.target, name, value)),
+        );
+        let lifetime = Duration::from_secs_f64(rng.range(profile.lifetime.0, profile.lifetime.1));
+        let loses_waker_at = if profile.loses_waker {
+            Some(now + lifetime.mul_f64(rng.range(0.1, 0.5)))
+        } else {
+            None
+        };
+        Self {
+            profile,
+            parent,
+            fields,
+            created_at: now,
+            ends_at: now + lifetime,
+            dropped_at: None,
+            loses_waker_at,
+            polls: 0,
+            busy: Duration::ZERO,
+            wakes: 0,
+            self_wakes: 0,
+            // Whatever the task is waiting on holds a waker.
+            waker_clones: 1,
+            waker_drops: 0,
+            first_poll: None,
+            last_poll_started: None,
+            last_poll_ended: None,
+            last_wake: None,
+            changed: tick,
+        }
+    }
+
+    fn advance(&mut self, rng: &mut Rng, tick: u64, now: SystemTime, elapsed: Duration) {
+        if self.dropped_at.is_some() {
+            return;
+        }
+        if now >= self.ends_at {
+            self.dropped_at = Some(now);
+            self.waker_drops = self.waker_clones;
+            if self.is_running() {
+                self.last_poll_ended = Some(now);
+            }
+            self.changed = tick;
+            return;
+        }
+        if let Some(loses_waker_at) = self.loses_waker_at {
+            if now >= loses_waker_at {
+                // The task is never polled again, and no one can wake it.
+                if self.waker_drops != self.waker_clones {
+                    self.waker_drops = self.waker_clones;
+                    if self.is_running() {
+                        self.last_poll_ended = Some(now);
+                    }
+                    self.changed = tick;
+                }
+                return;
+            }
+        }
+
+        let polls = (self.profile.poll_rate * elapsed.as_secs_f64() * rng.range(0.5, 1.5)).round();
+        if polls < 1.0 {
+            return;
+        }
+        let polls = polls as u64;
+        let poll_time = self.profile.poll_time.mul_f64(rng.range(0.5, 2.0));
+        // A task can't have been busy for longer than it has existed.
+        let alive = now.duration_since(self.created_at).unwrap_or_default();
+        let busy = (poll_time * polls as u32).min(alive.mul_f64(0.95).saturating_sub(self.busy));
+        self.busy += busy;
+        self.polls += polls;
+        self.wakes += polls;
+        self.self_wakes += (polls as f64 * self.profile.self_wakes).round() as u64;
+        self.waker_clones += polls;
+        self.waker_drops += polls;
+        self.first_poll.get_or_insert(self.created_at);
+
+        let started = now.checked_sub(poll_time).unwrap_or(now);
+        self.last_wake = Some(
+            started
+                .checked_sub(Duration::from_micros(50))
+                .unwrap_or(started),
+        );
+        self.last_poll_started = Some(started);
+        // Tasks which spend much of their time busy are often caught running.
+        let busy_fraction = busy.as_secs_f64() / elapsed.as_secs_f64().max(f64::EPSILON);
+        if rng.unit() < busy_fraction.max(0.05) {
+            self.last_poll_started = Some(now);
+            self.last_poll_ended = Some(started);
+        } else {
+            self.last_poll_ended = Some(now);
+            // Some idle tasks have been woken, and are waiting to be polled.
+            if rng.unit() < 0.2 {
+                self.last_wake = Some(now);
+            }
+        }
+        self.changed = tick;
+    }
+
+    fn is_running(&self) -> bool {
+        self.last_poll_started > self.last_poll_ended
+    }
+
+    fn to_proto(&self, id: u64) -> proto::tasks::Task {
+        let (file, line) = self.profile.location;
+        proto::tasks::Task {
+            id: Some(id.into()),
+            metadata: Some(meta_id(self.profile.target)),
+            kind: proto::tasks::task::Kind::Spawn as i32,
+            fields: self.fields.clone(),
+            parents: Vec::new(),
+            location: Some(location(file, line)),
+            parent_task_id: self.parent.map(Into::into),
+        }
+    }
+
+    fn stats(&self) -> proto::tasks::Stats {
+        proto::tasks::Stats {
+            created_at: Some(self.created_at.into()),
+            dropped_at: self.dropped_at.map(Into::into),
+            wakes: self.wakes,
+            waker_clones: self.waker_clones,
+            waker_drops: self.waker_drops,
+            last_wake: self.last_wake.map(Into::into),
+            poll_stats: Some(proto::PollStats {
+                polls: self.polls,
+                first_poll: self.first_poll.map(Into::into),
+                last_poll_started: self.last_poll_started.map(Into::into),
+                last_poll_ended: self.last_poll_ended.map(Into::into),
+                busy_time: Some(self.busy.into()),
+            }),
+            self_wakes: self.self_wakes,
+            runtime: RUNTIME.to_string(),
+            last_woken_by: None,
+        }
+    }
+}
+
+// === impl SimResource ===
+
+impl SimResource {
+    fn new(profile: &'static ResourceProfile, rng: &mut Rng, tick: u64, now: SystemTime) -> Self {
+        let lifetime = Duration::from_secs_f64(rng.range(profile.lifetime.0, profile.lifetime.1));
+        let mut resource = Self {
+            profile,
+            created_at: now,
+            ends_at: now + lifetime,
+            dropped_at: None,
+            attributes: Vec::new(),
+            changed: tick,
+        };
+        resource.update_attributes(rng);
+        resource
+    }
+
+    fn advance(&mut self, rng: &mut Rng, tick: u64, now: SystemTime) {
+        if self.dropped_at.is_some() {
+            return;
+        }
+        if now >= self.ends_at {
+            self.dropped_at = Some(now);
+            self.changed = tick;
+        } else if rng.unit() < 0.3 {
+            self.update_attributes(rng);
+            self.changed = tick;
+        }
+    }
+
+    fn update_attributes(&mut self, rng: &mut Rng) {
+        // A timer's duration doesn't change once it's created.
+        if self.profile.kind.is_none() && !self.attributes.is_empty() {
+            return;
+        }
+        let target = self.profile.target;
+        self.attributes = (self.profile.attributes)(rng)
+            .into_iter()
+            .map(|(name, value, unit)| proto::resources::stats::Attribute {
+                field: Some(field(target, name, value)),
+                unit: unit.map(str::to_string),
+            })
+            .collect();
+    }
+
+    fn to_proto(&self, id: u64) -> proto::resources::Resource {
+        use proto::resources::resource::{kind, Kind};
+        let kind = match self.profile.kind {
+            Some(other) => kind::Kind::Other(other.to_string()),
+            None => kind::Kind::Known(kind::Known::Timer as i32),
+        };
+        let (file, line) = self.profile.location;
+        proto::resources::Resource {
+            id: Some(id.into()),
+            metadata: Some(meta_id(self.profile.target)),
+            concrete_type: self.profile.concrete_type.to_string(),
+            kind: Some(Kind { kind: Some(kind) }),
+            location: Some(location(file, line)),
+            runtime: RUNTIME.to_string(),
+        }
+    }
+
+    fn stats(&self) -> proto::resources::Stats {
+        proto::resources::Stats {
+            created_at: Some(self.created_at.into()),
+            dropped_at: self.dropped_at.map(Into::into),
+            attributes: self.attributes.clone(),
+        }
+    }
+}
+
+// === impl Rng ===
+
+impl Rng {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number from 0 up to, but not including, 1.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.unit()
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    /// Picks one of `items`, each with a chance proportional to its weight.
+    fn weighted<'a, T>(&mut self, items: &'a [T], weight: impl Fn(&T) -> u32) -> &'a T {
+        let total = items.iter().map(|item| weight(item) as u64).sum::<u64>();
+        let mut pick = self.below(total);
+        for item in items {
+            let item_weight = weight(item) as u64;
+            if pick < item_weight {
+                return item;
+            }
+            pick -= item_weight;
+        }
+        &items[items.len() - 1]
+    }
+}
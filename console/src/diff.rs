@@ -0,0 +1,210 @@
+//! Comparing two snapshots written by `dump`, such as those taken before and
+//! after a code change.
+//!
+//! Task and resource IDs are not stable between runs of a process, so tasks
+//! are matched by their name and spawn location, and resources by their type
+//! and location. Tasks or resources which share these are compared as a
+//! group.
+use color_eyre::eyre::eyre;
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, fs, path::Path, time::Duration};
+
+/// Changes in busy time smaller than this are never significant, however
+/// large they are relative to the busy time before.
+const MIN_BUSY_CHANGE: f64 = 0.001;
+
+/// The tasks or resources in a snapshot that share a name and location.
+#[derive(Debug, Default, Copy, Clone)]
+struct Group {
+    count: u64,
+    busy: f64,
+    polls: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    name: String,
+    target: String,
+    location: String,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    tasks: BTreeMap<Key, Group>,
+    resources: BTreeMap<Key, Group>,
+}
+
+/// Prints the tasks and resources which appear in `after` but not `before`,
+/// which disappear, and whose busy time, poll count, or number changed by
+/// more than `threshold` percent.
+pub(crate) fn run(before: &Path, after: &Path, threshold: f64) -> color_eyre::Result<()> {
+    let before = Snapshot::read(before)?;
+    let after = Snapshot::read(after)?;
+    let threshold = threshold / 100.0;
+
+    let mut differences = 0;
+    differences += report("tasks", &before.tasks, &after.tasks, threshold);
+    differences += report("resources", &before.resources, &after.resources, threshold);
+    if differences == 0 {
+        println!("no significant differences");
+    }
+    Ok(())
+}
+
+/// Prints the differences between two sets of groups, returning how many
+/// there were.
+fn report(
+    kind: &str,
+    before: &BTreeMap<Key, Group>,
+    after: &BTreeMap<Key, Group>,
+    threshold: f64,
+) -> usize {
+    let mut lines = Vec::new();
+    for (key, group) in after {
+        if !before.contains_key(key) {
+            lines.push(format!("  + {}: {}", key, group));
+        }
+    }
+    for (key, group) in before {
+        if !after.contains_key(key) {
+            lines.push(format!("  - {}: {}", key, group));
+        }
+    }
+    for (key, before) in before {
+        let after = match after.get(key) {
+            Some(after) => after,
+            None => continue,
+        };
+        let mut changes = Vec::new();
+        if changed(before.count as f64, after.count as f64, threshold) {
+            changes.push(format!("count {} -> {}", before.count, after.count));
+        }
+        if changed(before.busy, after.busy, threshold)
+            && (after.busy - before.busy).abs() >= MIN_BUSY_CHANGE
+        {
+            changes.push(format!(
+                "busy {:.2?} -> {:.2?} ({})",
+                secs(before.busy),
+                secs(after.busy),
+                percent(before.busy, after.busy)
+            ));
+        }
+        if changed(before.polls, after.polls, threshold) {
+            changes.push(format!(
+                "polls {} -> {} ({})",
+                before.polls,
+                after.polls,
+                percent(before.polls, after.polls)
+            ));
+        }
+        if !changes.is_empty() {
+            lines.push(format!("  ~ {}: {}", key, changes.join(", ")));
+        }
+    }
+
+    if !lines.is_empty() {
+        println!("{}:", kind);
+        for line in &lines {
+            println!("{}", line);
+        }
+    }
+    lines.len()
+}
+
+/// Returns whether `after` differs from `before` by more than `threshold`,
+/// as a fraction of `before`.
+fn changed(before: f64, after: f64, threshold: f64) -> bool {
+    if before == 0.0 {
+        return after != 0.0;
+    }
+    ((after - before) / before).abs() > threshold
+}
+
+fn percent(before: f64, after: f64) -> String {
+    if before == 0.0 {
+        return "new".to_string();
+    }
+    format!("{:+.1}%", (after - before) / before * 100.0)
+}
+
+fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs.max(0.0))
+}
+
+// === impl Snapshot ===
+
+impl Snapshot {
+    fn read(path: &Path) -> color_eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| eyre!("failed to read {}: {}", path.display(), error))?;
+        let json: Value = serde_json::from_str(&contents)
+            .map_err(|error| eyre!("{} is not a valid snapshot: {}", path.display(), error))?;
+
+        let mut snapshot = Snapshot::default();
+        for task in entries(&json, "tasks") {
+            let key = Key {
+                name: str_of(task, "name").to_string(),
+                target: str_of(task, "target").to_string(),
+                location: str_of(task, "location").to_string(),
+            };
+            let group = snapshot.tasks.entry(key).or_default();
+            group.count += 1;
+            group.busy += num_of(task, "busy");
+            group.polls += num_of(task, "polls");
+        }
+        for resource in entries(&json, "resources") {
+            let key = Key {
+                name: format!("{} {}", str_of(resource, "kind"), str_of(resource, "type")),
+                target: str_of(resource, "target").to_string(),
+                location: str_of(resource, "location").to_string(),
+            };
+            snapshot.resources.entry(key).or_default().count += 1;
+        }
+        Ok(snapshot)
+    }
+}
+
+fn entries<'a>(json: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    json.get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+}
+
+fn str_of<'a>(json: &'a Value, key: &str) -> &'a str {
+    json.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+fn num_of(json: &Value, key: &str) -> f64 {
+    json.get(key).and_then(Value::as_f64).unwrap_or(0.0)
+}
+
+// === impl Key ===
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = if self.name.trim().is_empty() {
+            "<unnamed>"
+        } else {
+            &self.name
+        };
+        write!(f, "{} ({}", name, self.target)?;
+        if !self.location.is_empty() {
+            write!(f, " at {}", self.location)?;
+        }
+        f.write_str(")")
+    }
+}
+
+// === impl Group ===
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count)?;
+        if self.busy > 0.0 || self.polls > 0.0 {
+            write!(f, ", busy {:.2?}, polls {}", secs(self.busy), self.polls)?;
+        }
+        Ok(())
+    }
+}
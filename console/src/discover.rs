@@ -0,0 +1,149 @@
+//! Discovery of instrumented processes registered in a registry directory.
+//!
+//! Instrumented processes configured with a registry directory (such as by
+//! setting `TOKIO_CONSOLE_REGISTRY_DIR`) write a file per process to it,
+//! containing `key=value` lines describing the process.
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Help,
+};
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    path::Path,
+    time::Duration,
+};
+use tokio::net::TcpStream;
+use tonic::transport::Uri;
+
+/// An instrumented process found in the registry.
+#[derive(Debug)]
+pub(crate) struct Discovered {
+    addr: Uri,
+    pid: Option<u32>,
+    name: Option<String>,
+}
+
+/// Discovers the instrumented processes registered in `dir` and asks the user
+/// to pick one, returning its address.
+///
+/// If only one process is found, it is picked without asking.
+pub(crate) async fn pick_target(dir: &Path) -> color_eyre::Result<Uri> {
+    let mut found = discover(dir).await?;
+    match found.len() {
+        0 => Err(eyre!(
+            "no instrumented processes found in `{}`",
+            dir.display()
+        ))
+        .suggestion(
+            "make sure the processes are running, and that they were configured with the \
+             same registry directory (e.g. with `TOKIO_CONSOLE_REGISTRY_DIR`)",
+        ),
+        1 => {
+            let target = found.remove(0);
+            tracing::info!(process = %target, "discovered a single instrumented process");
+            Ok(target.addr)
+        }
+        _ => prompt(found),
+    }
+}
+
+/// Reads the registry directory, returning the registered processes that are
+/// currently reachable. Entries left behind by processes that have exited are
+/// skipped.
+async fn discover(dir: &Path) -> color_eyre::Result<Vec<Discovered>> {
+    const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    let entries = std::fs::read_dir(dir)
+        .wrap_err_with(|| format!("failed to read registry directory `{}`", dir.display()))?;
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                tracing::debug!(%error, path = %path.display(), "failed to read registry entry");
+                continue;
+            }
+        };
+        let discovered = match Discovered::parse(&contents) {
+            Some(discovered) => discovered,
+            None => {
+                tracing::debug!(path = %path.display(), "invalid registry entry");
+                continue;
+            }
+        };
+
+        let host = discovered.addr.host().unwrap_or("127.0.0.1");
+        let port = discovered.addr.port_u16().unwrap_or(80);
+        match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await {
+            Ok(Ok(_)) => found.push(discovered),
+            _ => tracing::debug!(process = %discovered, "registered process is unreachable"),
+        }
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name).then(a.pid.cmp(&b.pid)));
+    Ok(found)
+}
+
+/// Prints the discovered processes and reads the user's choice from stdin.
+fn prompt(found: Vec<Discovered>) -> color_eyre::Result<Uri> {
+    let stdin = io::stdin();
+    let mut stderr = io::stderr();
+    writeln!(stderr, "discovered {} instrumented processes:", found.len())?;
+    for (i, target) in found.iter().enumerate() {
+        writeln!(stderr, "  [{}] {}", i + 1, target)?;
+    }
+
+    let mut line = String::new();
+    loop {
+        write!(stderr, "connect to [1-{}]: ", found.len())?;
+        stderr.flush()?;
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Err(eyre!("no process selected"));
+        }
+        match line.trim().parse::<usize>() {
+            Ok(i) if (1..=found.len()).contains(&i) => {
+                return Ok(found.into_iter().nth(i - 1).expect("index in bounds").addr)
+            }
+            _ => writeln!(
+                stderr,
+                "please enter a number between 1 and {}",
+                found.len()
+            )?,
+        }
+    }
+}
+
+// === impl Discovered ===
+
+impl Discovered {
+    fn parse(contents: &str) -> Option<Self> {
+        let mut addr = None;
+        let mut pid = None;
+        let mut name = None;
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some(("addr", value)) => addr = value.trim().parse().ok(),
+                Some(("pid", value)) => pid = value.trim().parse().ok(),
+                Some(("name", value)) => name = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        Some(Self {
+            addr: addr?,
+            pid,
+            name,
+        })
+    }
+}
+
+impl fmt::Display for Discovered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name.as_deref().unwrap_or("<unknown>"))?;
+        if let Some(pid) = self.pid {
+            write!(f, " (pid {})", pid)?;
+        }
+        write!(f, " at {}", self.addr)
+    }
+}
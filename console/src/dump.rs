@@ -0,0 +1,208 @@
+//! Collecting a snapshot of an instrumented process' tasks and resources, and
+//! writing it out, without starting an interactive session.
+use crate::{
+    conn::Connection,
+    exit::{FailOn, Outcome, Verdict},
+    report,
+    state::{
+        resources::Resource,
+        tasks::{Task, TaskState},
+        State,
+    },
+    view::{self, mini_histogram},
+};
+use color_eyre::eyre::eyre;
+use hdrhistogram::Histogram;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// A format to write snapshots in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Format {
+    /// A pretty-printed JSON object.
+    Json,
+    /// A CSV table, with one row per task or resource.
+    Csv,
+}
+
+/// Collects updates from `conn` for `wait`, then writes a snapshot of every
+/// task and resource to `output`, or to stdout if no output is given.
+///
+/// Returns whether warnings which `fail_on` fired while collecting updates,
+/// or, if no updates were received, why.
+pub(crate) async fn run(
+    mut conn: Connection,
+    mut state: State,
+    styles: &view::Styles,
+    wait: Duration,
+    format: Format,
+    output: Option<&Path>,
+    fail_on: FailOn,
+) -> color_eyre::Result<Outcome> {
+    let current_view = view::ViewState::TasksList;
+    let mut verdict = Verdict::new(fail_on);
+    let deadline = tokio::time::sleep(wait);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            update = conn.next_update() => {
+                state.update(styles, &current_view, update);
+                state.retain_active();
+                // There's no table to add new tasks and resources to.
+                state.tasks_state_mut().take_new_tasks().for_each(drop);
+                state.resources_state_mut().take_new_resources().for_each(drop);
+                verdict.check(&state);
+            }
+        }
+    }
+
+    let now = match state.last_updated_at() {
+        Some(now) => now,
+        None => {
+            let stats = conn.stats();
+            eprintln!(
+                "error: no updates were received from {} within {}{}",
+                conn.target(),
+                humantime::format_duration(wait),
+                stats
+                    .last_error
+                    .as_ref()
+                    .map(|error| format!(" ({})", error))
+                    .unwrap_or_default()
+            );
+            return Ok(Outcome::no_updates(stats));
+        }
+    };
+    let snapshot = match format {
+        Format::Json => to_json(&state, now)?,
+        Format::Csv => to_csv(&state, now),
+    };
+    match output {
+        Some(path) => fs::write(path, snapshot)
+            .map_err(|error| eyre!("failed to write {}: {}", path.display(), error))?,
+        None => {
+            print!("{}", snapshot);
+            // The process exits without flushing stdout.
+            io::stdout().flush()?;
+        }
+    }
+    Ok(verdict.outcome())
+}
+
+fn to_json(state: &State, now: SystemTime) -> serde_json::Result<String> {
+    let tasks = state
+        .tasks_state()
+        .tasks()
+        .map(|task| report::Task::new(&task.borrow(), now))
+        .collect();
+    let resources = state
+        .resources_state()
+        .resources()
+        .map(|resource| report::Resource::new(&resource.borrow(), now))
+        .collect();
+    let snapshot = report::Snapshot::new(now, tasks, resources);
+    Ok(serde_json::to_string_pretty(&snapshot)? + "\n")
+}
+
+fn to_csv(state: &State, now: SystemTime) -> String {
+    let mut csv = String::from("kind,id,name,target,location,state,total,busy,idle,polls,wakes\n");
+    let mut row = |fields: &[&str]| {
+        let fields: Vec<_> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    };
+
+    for task in state.tasks_state().tasks() {
+        let task: &Task = &task.borrow();
+        let status = match task.state() {
+            TaskState::Running => "running",
+            TaskState::Idle => "idle",
+            TaskState::Completed => "completed",
+        };
+        row(&[
+            "task",
+            &task.id().to_string(),
+            task.name().unwrap_or(""),
+            task.target(),
+            task.location(),
+            status,
+            &task.total(now).as_secs_f64().to_string(),
+            &task.busy(now).as_secs_f64().to_string(),
+            &task.idle(now).as_secs_f64().to_string(),
+            &task.total_polls().to_string(),
+            &task.wakes().to_string(),
+        ]);
+    }
+
+    for resource in state.resources_state().resources() {
+        let resource: &Resource = &resource.borrow();
+        let status = if resource.dropped() {
+            "dropped"
+        } else {
+            "live"
+        };
+        row(&[
+            "resource",
+            &resource.id().to_string(),
+            resource.concrete_type(),
+            resource.target(),
+            resource.location(),
+            status,
+            &resource.total(now).as_secs_f64().to_string(),
+            "",
+            "",
+            "",
+            "",
+        ]);
+    }
+
+    csv
+}
+
+/// Formats a histogram of durations in nanoseconds as a CSV table, with a row
+/// for each percentile, and a row for each bucket which has values recorded
+/// in it.
+pub(crate) fn histogram_csv(histogram: &Histogram<u64>) -> String {
+    let mut csv = String::from("kind,percentile,lower_ns,upper_ns,count\n");
+    for &percentile in &mini_histogram::PERCENTILES {
+        let value = histogram.value_at_percentile(percentile);
+        csv.push_str(&format!("percentile,{},{},{},\n", percentile, value, value));
+    }
+    for bucket in histogram.iter_recorded() {
+        let upper = bucket.value_iterated_to();
+        csv.push_str(&format!(
+            "bucket,,{},{},{}\n",
+            histogram.lowest_equivalent(upper),
+            upper,
+            bucket.count_at_value()
+        ));
+    }
+    csv
+}
+
+/// Quotes a CSV field, if necessary.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("json") => Ok(Format::Json),
+            s if s.eq_ignore_ascii_case("csv") => Ok(Format::Csv),
+            s => Err(format!("unsupported format `{}`", s)),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Exit codes for the modes which run without the console's interface, so
+//! that scripts and CI jobs can tell what happened:
+//!
+//! - 0: no warnings which should fail the run fired.
+//! - 1: warnings which should fail the run, according to `--fail-on`, fired.
+//! - 2: the instrumented process could not be connected to.
+//! - 3: the instrumented process was connected to, but its updates could not
+//!   be received.
+use crate::{conn::Stats, state::State, warnings::Level};
+use std::str::FromStr;
+
+/// What happened during a run, which determines the exit code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Outcome {
+    Clean,
+    Warned,
+    ConnectionFailed,
+    ProtocolError,
+}
+
+/// Which warnings make a run fail.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum FailOn {
+    /// Fail if any warning fired.
+    Warning,
+    /// Fail only if a critical warning fired.
+    Critical,
+    /// Never fail because of warnings.
+    Never,
+}
+
+/// Tracks the most serious warning that fired during a run.
+#[derive(Debug)]
+pub(crate) struct Verdict {
+    fail_on: FailOn,
+    worst: Option<Level>,
+}
+
+// === impl Outcome ===
+
+impl Outcome {
+    /// Returns the outcome of a run in which no updates were received on a
+    /// connection with the given `stats`.
+    pub(crate) fn no_updates(stats: &Stats) -> Self {
+        if stats.decode_errors > 0 || stats.stream_errors > 0 {
+            Outcome::ProtocolError
+        } else {
+            Outcome::ConnectionFailed
+        }
+    }
+
+    pub(crate) fn code(self) -> i32 {
+        match self {
+            Outcome::Clean => 0,
+            Outcome::Warned => 1,
+            Outcome::ConnectionFailed => 2,
+            Outcome::ProtocolError => 3,
+        }
+    }
+}
+
+// === impl FailOn ===
+
+impl FailOn {
+    fn fails(self, level: Level) -> bool {
+        match self {
+            FailOn::Warning => true,
+            FailOn::Critical => level >= Level::Critical,
+            FailOn::Never => false,
+        }
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("warning") => Ok(FailOn::Warning),
+            s if s.eq_ignore_ascii_case("critical") => Ok(FailOn::Critical),
+            s if s.eq_ignore_ascii_case("never") => Ok(FailOn::Never),
+            s => Err(format!(
+                "unknown severity `{}` (expected warning, critical, or never)",
+                s
+            )),
+        }
+    }
+}
+
+// === impl Verdict ===
+
+impl Verdict {
+    pub(crate) fn new(fail_on: FailOn) -> Self {
+        Self {
+            fail_on,
+            worst: None,
+        }
+    }
+
+    /// Records the warnings and alerts which are currently firing in `state`.
+    pub(crate) fn check(&mut self, state: &State) {
+        let alerts = state.alerts().map(|_| Level::Warning);
+        let tasks = state
+            .tasks_state()
+            .warnings()
+            .map(|warning| warning.level());
+        let resources = state
+            .resources_state()
+            .warnings()
+            .map(|warning| warning.level());
+        let worst = alerts.chain(tasks).chain(resources).max();
+        self.worst = self.worst.max(worst);
+    }
+
+    /// Returns whether the run was clean, or whether warnings which should
+    /// fail it fired.
+    pub(crate) fn outcome(&self) -> Outcome {
+        match self.worst {
+            Some(level) if self.fail_on.fails(level) => Outcome::Warned,
+            _ => Outcome::Clean,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Exporting the metrics the console computes to external monitoring
+//! systems, aggregated over an interval.
+use crate::state::{window::Totals, State};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+mod otlp;
+mod statsd;
+
+pub(crate) use self::{otlp::Otlp, statsd::StatsD};
+
+/// Metrics aggregated over the interval between two samples.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Sample {
+    // === gauges, as of the end of the interval ===
+    pub(crate) tasks_live: u64,
+    pub(crate) tasks_running: u64,
+    pub(crate) tasks_idle: u64,
+    pub(crate) tasks_completed: u64,
+    pub(crate) resources_live: u64,
+    /// The number of warnings on tasks, plus the number of watches on the
+    /// process as a whole which are firing.
+    pub(crate) warnings: u64,
+
+    // === totals over the interval ===
+    pub(crate) tasks_spawned: u64,
+    pub(crate) polls: u64,
+    pub(crate) wakes: u64,
+    pub(crate) busy: Duration,
+}
+
+/// Computes samples from the console's state, remembering each task's totals
+/// as of the previous sample.
+#[derive(Debug, Default)]
+pub(crate) struct Sampler {
+    previous: HashMap<u64, Totals>,
+    previous_at: Option<SystemTime>,
+}
+
+// === impl Sampler ===
+
+impl Sampler {
+    /// Returns the time of the previous sample, if one has been taken.
+    pub(crate) fn previous_at(&self) -> Option<SystemTime> {
+        self.previous_at
+    }
+
+    /// Samples the metrics in `state` as of `now`, aggregating them since the
+    /// previous sample.
+    ///
+    /// Tasks spawned since the previous sample count from when they were
+    /// spawned. The first sample only establishes a baseline, so its totals
+    /// cover the lifetimes of the tasks that existed when it was taken.
+    pub(crate) fn sample(&mut self, state: &State, now: SystemTime) -> Sample {
+        let mut sample = Sample::default();
+
+        let mut current = HashMap::with_capacity(self.previous.len());
+        for task in state.tasks_state().tasks() {
+            let task = task.borrow();
+            if task.is_completed() {
+                sample.tasks_completed += 1;
+            } else {
+                sample.tasks_live += 1;
+                if task.is_running() {
+                    sample.tasks_running += 1;
+                } else {
+                    sample.tasks_idle += 1;
+                }
+            }
+
+            let totals = Totals::of(&task, now);
+            let before = match self.previous.get(&task.id()) {
+                Some(before) => *before,
+                None => {
+                    sample.tasks_spawned += 1;
+                    Totals::default()
+                }
+            };
+            sample.polls += totals.polls.saturating_sub(before.polls);
+            sample.wakes += totals.wakes.saturating_sub(before.wakes);
+            sample.busy += totals.busy.saturating_sub(before.busy);
+            current.insert(task.id(), totals);
+        }
+
+        sample.resources_live = state
+            .resources_state()
+            .resources()
+            .filter(|resource| !resource.borrow().dropped())
+            .count() as u64;
+        let task_warnings: usize = state
+            .tasks_state()
+            .warnings()
+            .map(|warning| warning.count())
+            .sum();
+        sample.warnings = (task_warnings + state.alerts().count()) as u64;
+
+        self.previous = current;
+        self.previous_at = Some(now);
+        sample
+    }
+}
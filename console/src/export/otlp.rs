@@ -0,0 +1,210 @@
+//! Forwarding aggregated metrics to an OpenTelemetry collector, using OTLP
+//! over HTTP with the JSON encoding.
+use super::{Sample, Sampler};
+use crate::{http, state::State};
+use serde_json::{json, Value};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tonic::transport::Uri;
+
+/// The path that collectors receive metrics on.
+const METRICS_PATH: &str = "/v1/metrics";
+
+/// `AGGREGATION_TEMPORALITY_DELTA`, for sums covering only the interval.
+const DELTA: u8 = 1;
+
+/// Sends the metrics for a target to an OTLP collector once per interval.
+#[derive(Debug)]
+pub(crate) struct Otlp {
+    endpoint: Uri,
+    interval: Duration,
+    target: String,
+    sampler: Sampler,
+    /// Why the last request failed, if it did.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+/// A metric's value at the end of an interval, or its total over the
+/// interval.
+enum Point {
+    Gauge(u64),
+    Sum(u64),
+    SumSecs(Duration),
+}
+
+impl Otlp {
+    /// Returns an exporter sending `target`'s metrics to the collector at
+    /// `endpoint`.
+    ///
+    /// If `endpoint` has no path, metrics are sent to the collector's
+    /// standard `/v1/metrics` path.
+    pub(crate) fn new(endpoint: Uri, interval: Duration, target: &Uri) -> Self {
+        let endpoint = match endpoint.path() {
+            "" | "/" => {
+                let mut parts = endpoint.into_parts();
+                parts.path_and_query = Some(METRICS_PATH.parse().expect("path is valid"));
+                Uri::from_parts(parts).expect("only the path was changed")
+            }
+            _ => endpoint,
+        };
+        Self {
+            endpoint,
+            interval,
+            target: target.to_string(),
+            sampler: Sampler::default(),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns why metrics could not be sent to the collector, if the last
+    /// attempt failed.
+    pub(crate) fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Sends the metrics in `state`, if an interval has passed since they
+    /// were last sent.
+    ///
+    /// The request is sent in the background. If it fails, the error is
+    /// logged, and returned by [`Otlp::error`] until a request succeeds.
+    pub(crate) fn update(&mut self, state: &State) {
+        let now = match state.last_updated_at() {
+            Some(now) => now,
+            None => return,
+        };
+        let start = match self.sampler.previous_at() {
+            Some(start) => start,
+            None => {
+                // Establish a baseline, so that the first interval doesn't
+                // report every existing task's lifetime totals.
+                self.sampler.sample(state, now);
+                return;
+            }
+        };
+        if now.duration_since(start).unwrap_or_default() < self.interval {
+            return;
+        }
+
+        let sample = self.sampler.sample(state, now);
+        let payload = self.payload(&sample, start, now).to_string();
+        let endpoint = self.endpoint.clone();
+        let last_error = self.error.clone();
+        tokio::spawn(async move {
+            let result = http::post_json(&endpoint, payload).await;
+            match result {
+                Ok(()) => tracing::debug!("sent metrics to the OTLP collector"),
+                Err(ref error) => {
+                    tracing::warn!(%error, "failed to send metrics to the OTLP collector")
+                }
+            }
+            *last_error.lock().unwrap() = result.err();
+        });
+    }
+
+    /// Returns an `ExportMetricsServiceRequest` for a sample.
+    fn payload(&self, sample: &Sample, start: SystemTime, now: SystemTime) -> Value {
+        let metrics = [
+            (
+                "tokio_console.tasks.live",
+                "{tasks}",
+                Point::Gauge(sample.tasks_live),
+            ),
+            (
+                "tokio_console.tasks.running",
+                "{tasks}",
+                Point::Gauge(sample.tasks_running),
+            ),
+            (
+                "tokio_console.tasks.idle",
+                "{tasks}",
+                Point::Gauge(sample.tasks_idle),
+            ),
+            (
+                "tokio_console.tasks.completed",
+                "{tasks}",
+                Point::Gauge(sample.tasks_completed),
+            ),
+            (
+                "tokio_console.resources.live",
+                "{resources}",
+                Point::Gauge(sample.resources_live),
+            ),
+            (
+                "tokio_console.warnings",
+                "{warnings}",
+                Point::Gauge(sample.warnings),
+            ),
+            (
+                "tokio_console.tasks.spawned",
+                "{tasks}",
+                Point::Sum(sample.tasks_spawned),
+            ),
+            ("tokio_console.polls", "{polls}", Point::Sum(sample.polls)),
+            ("tokio_console.wakes", "{wakes}", Point::Sum(sample.wakes)),
+            ("tokio_console.busy", "s", Point::SumSecs(sample.busy)),
+        ];
+
+        let attributes = [attribute("target", &self.target)];
+        let metrics = metrics
+            .iter()
+            .map(|(name, unit, point)| {
+                let mut data_point = json!({
+                    "attributes": attributes,
+                    "startTimeUnixNano": unix_nanos(start),
+                    "timeUnixNano": unix_nanos(now),
+                });
+                let kind = match point {
+                    Point::Gauge(value) => {
+                        data_point["asInt"] = value.to_string().into();
+                        "gauge"
+                    }
+                    Point::Sum(value) => {
+                        data_point["asInt"] = value.to_string().into();
+                        "sum"
+                    }
+                    Point::SumSecs(value) => {
+                        data_point["asDouble"] = value.as_secs_f64().into();
+                        "sum"
+                    }
+                };
+                let mut data = json!({ "dataPoints": [data_point] });
+                if kind == "sum" {
+                    data["aggregationTemporality"] = DELTA.into();
+                    data["isMonotonic"] = true.into();
+                }
+                json!({ "name": name, "unit": unit, kind: data })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [attribute("service.name", "tokio-console")],
+                },
+                "scopeMetrics": [{
+                    "scope": {
+                        "name": clap::crate_name!(),
+                        "version": clap::crate_version!(),
+                    },
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+}
+
+/// Returns a `KeyValue` with a string value.
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Formats a timestamp as nanoseconds since the Unix epoch. 64-bit integers
+/// are encoded as strings in OTLP's JSON encoding.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
@@ -0,0 +1,143 @@
+//! Pushing aggregated metrics to a StatsD server, such as the Datadog agent.
+use super::{Sample, Sampler};
+use crate::state::State;
+use std::{
+    io,
+    net::{ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+/// Sends the metrics for a target to a StatsD server once per interval.
+#[derive(Debug)]
+pub(crate) struct StatsD {
+    socket: UdpSocket,
+    prefix: String,
+    /// DogStatsD tags to add to every metric, formatted as `|#tag,...`, or an
+    /// empty string.
+    tags: String,
+    interval: Duration,
+    sampler: Sampler,
+}
+
+impl StatsD {
+    /// The largest payload to send in a single datagram, which fits in an
+    /// Ethernet frame without fragmentation.
+    const MAX_PAYLOAD: usize = 1432;
+
+    /// Returns an exporter sending metrics to the StatsD server at `addr`,
+    /// with every metric named starting with `prefix`.
+    pub(crate) fn new(addr: &str, prefix: &str, interval: Duration) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{}` did not resolve to an address", addr),
+            )
+        })?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(addr)?;
+        // Metrics are best-effort, so never hold up the console to send them.
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+            tags: String::new(),
+            interval,
+            sampler: Sampler::default(),
+        })
+    }
+
+    /// Adds DogStatsD tags to every metric, as supported by the Datadog
+    /// agent.
+    pub(crate) fn with_tags<'a>(
+        mut self,
+        tags: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        let tags: Vec<String> = tags
+            .into_iter()
+            .map(|(key, value)| format!("{}:{}", key, sanitize_tag(value)))
+            .collect();
+        self.tags = if tags.is_empty() {
+            String::new()
+        } else {
+            format!("|#{}", tags.join(","))
+        };
+        self
+    }
+
+    /// Sends the metrics in `state`, if an interval has passed since they
+    /// were last sent.
+    pub(crate) fn update(&mut self, state: &State) {
+        let now = match state.last_updated_at() {
+            Some(now) => now,
+            None => return,
+        };
+        let previous_at = match self.sampler.previous_at() {
+            Some(previous_at) => previous_at,
+            None => {
+                // Establish a baseline, so that the first interval doesn't
+                // report every existing task's lifetime totals.
+                self.sampler.sample(state, now);
+                return;
+            }
+        };
+        if now.duration_since(previous_at).unwrap_or_default() < self.interval {
+            return;
+        }
+
+        let sample = self.sampler.sample(state, now);
+        if let Err(error) = self.send(&sample) {
+            tracing::warn!(%error, "failed to send metrics to StatsD");
+        }
+    }
+
+    fn send(&self, sample: &Sample) -> io::Result<()> {
+        let metrics = [
+            ("tasks.live", sample.tasks_live, "g"),
+            ("tasks.running", sample.tasks_running, "g"),
+            ("tasks.idle", sample.tasks_idle, "g"),
+            ("tasks.completed", sample.tasks_completed, "g"),
+            ("resources.live", sample.resources_live, "g"),
+            ("warnings", sample.warnings, "g"),
+            ("tasks.spawned", sample.tasks_spawned, "c"),
+            ("polls", sample.polls, "c"),
+            ("wakes", sample.wakes, "c"),
+            ("busy_ms", sample.busy.as_millis() as u64, "c"),
+        ];
+
+        let mut payload = String::new();
+        for (name, value, kind) in metrics.iter() {
+            let line = format!("{}.{}:{}|{}{}", self.prefix, name, value, kind, self.tags);
+            if !payload.is_empty() && payload.len() + 1 + line.len() > Self::MAX_PAYLOAD {
+                self.send_payload(&payload)?;
+                payload.clear();
+            }
+            if !payload.is_empty() {
+                payload.push('\n');
+            }
+            payload.push_str(&line);
+        }
+        self.send_payload(&payload)
+    }
+
+    fn send_payload(&self, payload: &str) -> io::Result<()> {
+        match self.socket.send(payload.as_bytes()) {
+            Ok(_) => Ok(()),
+            // Drop the metrics rather than blocking.
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                tracing::debug!("StatsD socket is busy, dropping metrics");
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Replaces the characters which delimit DogStatsD tags.
+fn sanitize_tag(value: &str) -> String {
+    value.replace(&[',', '|', '#'][..], "_")
+}
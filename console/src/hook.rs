@@ -0,0 +1,134 @@
+//! Running a user-provided command when a warning or alert fires, for example
+//! to capture a dump of the instrumented process while the problem is
+//! happening.
+use crate::{
+    state::State,
+    warnings::{Fired, Tracker},
+};
+use std::{
+    process::Stdio,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::process::Command;
+use tonic::transport::Uri;
+
+/// A command to run when a warning fires, parsed from a string like
+/// `scripts/capture.sh {task_id} {lint}`.
+///
+/// The command is split on whitespace into the program and its arguments,
+/// and is not run by a shell. In each argument, the placeholders `{task_id}`
+/// (the ID of the task the warning is for, or nothing for alerts), `{lint}`
+/// (the warning's summary, or the watch which fired), `{message}`, and
+/// `{target}` are replaced.
+#[derive(Debug, Clone)]
+pub(crate) struct HookCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+/// Runs a command for the warnings and alerts which start firing for a
+/// target, at most once every `interval`.
+#[derive(Debug)]
+pub(crate) struct WarningHook {
+    command: HookCommand,
+    interval: Duration,
+    tracker: Tracker,
+    /// When the command was last run, if it has been.
+    last_run: Option<Instant>,
+}
+
+// === impl WarningHook ===
+
+impl WarningHook {
+    pub(crate) fn new(command: HookCommand, interval: Duration) -> Self {
+        Self {
+            command,
+            interval,
+            tracker: Tracker::default(),
+            last_run: None,
+        }
+    }
+
+    /// Runs the command for the first warning or alert which started firing
+    /// in `state` since it was last checked, unless the command was run less
+    /// than the interval ago.
+    ///
+    /// The command runs in the background, and failures are logged rather
+    /// than returned.
+    pub(crate) fn check(&mut self, target: &Uri, state: &State) {
+        let fired = self.tracker.check(state);
+        let first = match fired.first() {
+            Some(first) => first,
+            None => return,
+        };
+        if let Some(last_run) = self.last_run {
+            if last_run.elapsed() < self.interval {
+                tracing::debug!(
+                    fired = fired.len(),
+                    "not running the warning hook, since it ran recently"
+                );
+                return;
+            }
+        }
+        self.last_run = Some(Instant::now());
+        if fired.len() > 1 {
+            tracing::debug!(
+                skipped = fired.len() - 1,
+                "running the warning hook for the first warning only"
+            );
+        }
+        tokio::spawn(run(self.command.command(target, first)));
+    }
+}
+
+async fn run(mut cmd: Command) {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await;
+    match output {
+        Ok(output) if output.status.success() => tracing::debug!("ran the warning hook"),
+        Ok(output) => tracing::warn!(
+            status = %output.status,
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "the warning hook failed"
+        ),
+        Err(error) => tracing::warn!(%error, "failed to run the warning hook"),
+    }
+}
+
+// === impl HookCommand ===
+
+impl HookCommand {
+    /// Returns the command to run for a warning which `fired` for `target`.
+    fn command(&self, target: &Uri, fired: &Fired) -> Command {
+        let task_id = fired.task_id.map(|id| id.to_string()).unwrap_or_default();
+        let target = target.to_string();
+        let mut cmd = Command::new(&self.program);
+        cmd.args(self.args.iter().map(|arg| {
+            arg.replace("{task_id}", &task_id)
+                .replace("{lint}", &fired.summary)
+                .replace("{message}", &fired.message)
+                .replace("{target}", &target)
+        }));
+        cmd
+    }
+}
+
+impl FromStr for HookCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace().map(str::to_string);
+        let program = words
+            .next()
+            .ok_or_else(|| "expected a command to run".to_string())?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+}
@@ -0,0 +1,46 @@
+//! Sending HTTP requests to webhooks and metrics collectors.
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+use hyper_rustls::HttpsConnector;
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use tonic::transport::Uri;
+
+/// How long to wait for a request to complete before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The client shared by every request, so that connections are reused.
+static CLIENT: Lazy<Client<HttpsConnector<HttpConnector>>> =
+    Lazy::new(|| Client::builder().build(HttpsConnector::with_webpki_roots()));
+
+/// POSTs a JSON `payload` to `url`, returning a description of the error if
+/// the request failed or the response's status was not successful.
+pub(crate) async fn post_json(url: &Uri, payload: String) -> Result<(), String> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url.clone())
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(payload))
+        .map_err(|error| error.to_string())?;
+    let send = async {
+        let response = CLIENT
+            .request(request)
+            .await
+            .map_err(|error| error.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        // The body usually explains why the request was rejected.
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap_or_default();
+        Err(format!(
+            "the server responded with {}: {}",
+            status,
+            String::from_utf8_lossy(&body).trim()
+        ))
+    };
+    tokio::time::timeout(TIMEOUT, send)
+        .await
+        .map_err(|_| format!("no response within {:?}", TIMEOUT))?
+}
@@ -1,7 +1,213 @@
 // TODO(eliza): support TUI backends other than crossterm?
 // This would probably involve using `spawn_blocking` to drive their blocking
 // input-handling mechanisms in the background...
+use color_eyre::eyre::eyre;
 pub use crossterm::event::*;
+use futures::stream::StreamExt;
+use std::{collections::VecDeque, fs, io, path::Path, time::Duration};
+use tokio::time::Instant;
+
+/// Keys recorded so that they can be replayed by pressing a single key.
+///
+/// `M` starts and stops recording, and `@` replays the keys recorded most
+/// recently, one at a time, as if they had been pressed again.
+#[derive(Debug, Default)]
+pub(crate) struct Macro {
+    /// The keys pressed since recording started, while recording.
+    recording: Option<Vec<Event>>,
+    /// The keys recorded most recently.
+    recorded: Vec<Event>,
+    /// The recorded keys which haven't been replayed yet.
+    replaying: VecDeque<Event>,
+}
+
+/// Keys pressed at scripted times, read from a file, so that the console can
+/// be driven without anyone at the keyboard.
+///
+/// Each line of a script is one of:
+///
+/// * `wait <duration>`, which waits before pressing the next key;
+/// * `key <key>...`, which presses each of the named keys, such as `t`,
+///   `enter`, `down`, or `ctrl-c`;
+/// * `type <text>`, which types the text, such as a filter query.
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default)]
+pub(crate) struct Script {
+    steps: VecDeque<ScriptStep>,
+    /// When the wait currently being waited for ends.
+    waiting_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+enum ScriptStep {
+    Wait(Duration),
+    Press(Event),
+}
+
+// === impl Macro ===
+
+impl Macro {
+    /// Returns the next key being replayed, or else the next key pressed,
+    /// either by the script or the user.
+    pub(crate) async fn next(
+        &mut self,
+        events: &mut EventStream,
+        script: &mut Script,
+    ) -> Option<io::Result<Event>> {
+        if let Some(event) = self.replaying.pop_front() {
+            return Some(Ok(event));
+        }
+        tokio::select! {
+            event = script.next() => Some(Ok(event)),
+            event = events.next() => event,
+        }
+    }
+
+    /// Starts or stops recording, or starts replaying, if the input is one
+    /// of the macro keys and text isn't being typed. Otherwise, records the
+    /// input while recording.
+    pub(crate) fn key_input(&mut self, input: &Event, typing: bool) {
+        match input {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('M'),
+                ..
+            }) if !typing => match self.recording.take() {
+                Some(recorded) => self.recorded = recorded,
+                None => self.recording = Some(Vec::new()),
+            },
+            // Replaying while recording would record the replayed keys into
+            // the macro being replayed.
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('@'),
+                ..
+            }) if !typing && self.recording.is_some() => {}
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('@'),
+                ..
+            }) if !typing => self.replaying.extend(self.recorded.iter().cloned()),
+            Event::Key(_) => {
+                if let Some(ref mut recording) = self.recording {
+                    recording.push(*input);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Returns `true` if a macro has been recorded, and can be replayed.
+    pub(crate) fn is_recorded(&self) -> bool {
+        !self.recorded.is_empty()
+    }
+}
+
+// === impl Script ===
+
+impl Script {
+    pub(crate) fn load(path: &Path) -> color_eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| eyre!("failed to read {}: {}", path.display(), error))?;
+        let mut steps = VecDeque::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let args = args.trim();
+            let invalid = |error: String| eyre!("{}:{}: {}", path.display(), i + 1, error);
+            match command {
+                "wait" => {
+                    let duration = humantime::parse_duration(args)
+                        .map_err(|error| invalid(format!("invalid duration: {}", error)))?;
+                    steps.push_back(ScriptStep::Wait(duration));
+                }
+                "key" => {
+                    for name in args.split_whitespace() {
+                        steps.push_back(ScriptStep::Press(parse_key(name).map_err(invalid)?));
+                    }
+                }
+                "type" => steps.extend(
+                    args.chars()
+                        .map(|c| ScriptStep::Press(key(KeyCode::Char(c)))),
+                ),
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown command `{}` (expected `wait`, `key`, or `type`)",
+                        command
+                    )))
+                }
+            }
+        }
+        Ok(Self {
+            steps,
+            waiting_until: None,
+        })
+    }
+
+    /// Waits for the script to press its next key. Once the script has
+    /// ended, this never completes.
+    ///
+    /// This may be cancelled, and called again, without cutting a wait
+    /// short.
+    async fn next(&mut self) -> Event {
+        loop {
+            if let Some(until) = self.waiting_until {
+                tokio::time::sleep_until(until).await;
+                self.waiting_until = None;
+            }
+            match self.steps.pop_front() {
+                Some(ScriptStep::Wait(duration)) => {
+                    self.waiting_until = Some(Instant::now() + duration);
+                }
+                Some(ScriptStep::Press(event)) => return event,
+                None => futures::future::pending().await,
+            }
+        }
+    }
+}
+
+/// Parses the name of a key in a script.
+fn parse_key(name: &str) -> Result<Event, String> {
+    use KeyCode::*;
+    let (modifiers, name) = match name.strip_prefix("ctrl-") {
+        Some(name) => (KeyModifiers::CONTROL, name),
+        None => (KeyModifiers::NONE, name),
+    };
+    let mut chars = name.chars();
+    let code = match name.to_lowercase().as_str() {
+        "enter" => Enter,
+        "esc" => Esc,
+        "tab" => Tab,
+        "backspace" => Backspace,
+        "space" => Char(' '),
+        "up" => Up,
+        "down" => Down,
+        "left" => Left,
+        "right" => Right,
+        "pgup" => PageUp,
+        "pgdn" => PageDown,
+        "home" => Home,
+        "end" => End,
+        "f12" => F(12),
+        _ => match (chars.next(), chars.next()) {
+            (Some(c), None) => Char(c),
+            _ => return Err(format!("unknown key `{}`", name)),
+        },
+    };
+    Ok(Event::Key(KeyEvent { code, modifiers }))
+}
+
+fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent {
+        code,
+        modifiers: KeyModifiers::NONE,
+    })
+}
 
 pub fn should_quit(input: &Event) -> bool {
     use Event::*;
@@ -22,6 +228,92 @@ pub fn should_quit(input: &Event) -> bool {
     }
 }
 
+/// Returns the index of the target selected by pressing one of the number keys
+/// `1` through `9`, if any.
+pub(crate) fn target_index(input: &Event) -> Option<usize> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c @ '1'..='9'),
+            ..
+        }) => Some(*c as usize - '1' as usize),
+        _ => None,
+    }
+}
+
+/// Returns the factor to scale the publish interval by, if the input is one of
+/// the keys that adjusts it: `[` to publish faster, or `]` to publish slower.
+pub(crate) fn publish_interval_scale(input: &Event) -> Option<f64> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('['),
+            ..
+        }) => Some(0.5),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(']'),
+            ..
+        }) => Some(2.0),
+        _ => None,
+    }
+}
+
+/// A direction to step through updates in while paused.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Step {
+    /// Apply the next update.
+    Forward,
+    /// Roll back the current update.
+    Back,
+}
+
+/// Returns the direction to step through updates in, if the input is one of
+/// the keys that steps while paused: `n` for the next update, or `p` for the
+/// previous update.
+pub(crate) fn step(input: &Event) -> Option<Step> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('n'),
+            ..
+        }) => Some(Step::Forward),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('p'),
+            ..
+        }) => Some(Step::Back),
+        _ => None,
+    }
+}
+
+/// Returns the character typed, if the input is a printable key pressed
+/// without the control key.
+pub(crate) fn text_char(input: &Event) -> Option<char> {
+    match input {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers,
+        }) if !modifiers.contains(KeyModifiers::CONTROL) => Some(*c),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_debug_log_toggle(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::F(12),
+            ..
+        })
+    )
+}
+
+pub(crate) fn is_window_toggle(input: &Event) -> bool {
+    matches!(
+        input,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('w'),
+            ..
+        })
+    )
+}
+
 pub(crate) fn is_space(input: &Event) -> bool {
     matches!(
         input,
@@ -1,118 +1,420 @@
 use color_eyre::{eyre::eyre, Help, SectionExt};
-use console_api::tasks::TaskDetails;
+use console_api::{resources::ResourceDetails, tasks::TaskDetails};
 use state::State;
 
-use clap::Parser as Clap;
 use futures::stream::StreamExt;
-use tokio::sync::{mpsc, watch};
+use std::time::Duration;
+use tokio::sync::{self, mpsc};
 use tui::{
     layout::{Constraint, Direction, Layout},
-    style::Color,
+    style::{Color, Modifier},
     text::{Span, Spans},
     widgets::{Paragraph, Wrap},
 };
 
+use crate::targets::{Target, Targets};
 use crate::view::{bold, UpdateKind};
 
+mod clock;
+mod computed;
 mod config;
 mod conn;
+mod debug_log;
+mod demo;
+mod diff;
+mod discover;
+mod dump;
+mod exit;
+mod export;
+mod hook;
+mod http;
 mod input;
 mod intern;
+mod notify;
+mod report;
+mod session;
 mod state;
+mod stream;
+mod targets;
 mod term;
+mod top;
+mod tunnel;
 mod util;
 mod view;
 mod warnings;
+mod watch;
+mod webhook;
 
+fn main() -> color_eyre::Result<()> {
+    let code = run()?;
+    // Exiting skips destructors, so only exit once `run` has returned, and
+    // the runtime and any tunnel to the target have been dropped.
+    std::process::exit(code)
+}
+
+/// Runs the console, or one of its commands, returning the code to exit
+/// with.
 #[tokio::main]
-async fn main() -> color_eyre::Result<()> {
-    let mut args = config::Config::parse();
-    let retain_for = args.retain_for();
-    args.trace_init()?;
-    tracing::debug!(?args.target_addr, ?args.view_options);
+async fn run() -> color_eyre::Result<i32> {
+    let mut args = config::Config::load()?;
+    if let Some(config::OptionalCmd::GenConfig) = args.subcmd {
+        print!("{}", args.gen_config());
+        return Ok(0);
+    }
+    if let Some(config::OptionalCmd::Diff {
+        ref before,
+        ref after,
+        threshold,
+    }) = args.subcmd
+    {
+        return diff::run(before, after, threshold).map(|()| 0);
+    }
+    let retention = args.retention();
+    let publish_interval = args.publish_interval();
+    let session_dir = args.session_dir();
+    let debug_log = args.trace_init()?;
+    tracing::debug!(?args.target_addrs, ?args.view_options);
 
-    let styles = view::Styles::from_config(args.view_options);
+    let styles = view::Styles::from_config(args.view_options.clone());
     styles.error_init()?;
 
-    let target = args.target_addr;
-    tracing::info!(?target, "using target addr");
+    let headers = args.headers()?;
+    if !args.notify.is_empty() && !notify::SUPPORTED {
+        return Err(eyre!(
+            "desktop notifications require tokio-console to be built with the \
+             `desktop-notifications` feature"
+        ));
+    }
+    let (target_addrs, _tunnel) = match (&args.kube_pod, &args.via_ssh) {
+        (Some(pod), _) => {
+            let (tunnel, addr) =
+                tunnel::Tunnel::kube_port_forward(pod, args.kube_namespace.as_deref()).await?;
+            (vec![addr], Some(tunnel))
+        }
+        (None, Some(destination)) => {
+            let (tunnel, addr) =
+                tunnel::Tunnel::ssh_forward(destination, &args.target_addrs[0]).await?;
+            (vec![addr], Some(tunnel))
+        }
+        (None, None) => match args.registry_dir {
+            Some(ref dir) if args.discover => (vec![discover::pick_target(dir).await?], None),
+            _ => (args.target_addrs.clone(), None),
+        },
+    };
+    let target_addrs = match args.subcmd {
+        Some(config::OptionalCmd::Demo {
+            tasks,
+            resources,
+            interval,
+        }) => {
+            let options = demo::Options {
+                tasks,
+                resources,
+                interval: interval.into(),
+            };
+            vec![demo::serve(options).await?]
+        }
+        _ => target_addrs,
+    };
+    if target_addrs.len() > Targets::MAX {
+        tracing::warn!(
+            targets = target_addrs.len(),
+            max = Targets::MAX,
+            "too many targets, ignoring the rest"
+        );
+    }
+    tracing::info!(?target_addrs, "using target addrs");
+
+    let new_conn = |target: tonic::transport::Uri| {
+        let proxy = args.proxy(&target);
+        conn::Connection::new(target)
+            .with_compression(args.compression)
+            .with_headers(headers.clone())
+            .with_proxy(proxy)
+            .with_publish_interval(publish_interval)
+            .with_subscription(!args.resources_only, !args.tasks_only)
+            .with_histogram_precision(args.histogram_precision)
+    };
+    let new_state = || {
+        State::default()
+            // TODO(eliza): allow configuring the list of linters via the
+            // CLI/possibly a config file?
+            .with_task_linters(vec![
+                warnings::Linter::new(warnings::SelfWakePercent::default()),
+                warnings::Linter::new(warnings::LostWaker),
+                warnings::Linter::new(warnings::ScheduledDelay::default()),
+                warnings::Linter::new(warnings::WakerGrowth::default()),
+            ])
+            .with_resource_linters(vec![warnings::Linter::new(
+                warnings::NeverReleased::default(),
+            )])
+            .with_watches(args.watches(), args.lints.clone())
+            .with_retention(retention)
+            .with_slow_poll(args.slow_poll())
+    };
+
+    if let Some(config::OptionalCmd::Stream {
+        format,
+        summary,
+        timeout,
+        fail_on,
+    }) = args.subcmd
+    {
+        let conn = new_conn(target_addrs[0].clone());
+        let timeout = timeout.into();
+        let outcome = stream::run(
+            conn,
+            new_state(),
+            &styles,
+            format,
+            summary,
+            timeout,
+            fail_on,
+        )
+        .await?;
+        return Ok(outcome.code());
+    }
+    if let Some(config::OptionalCmd::Top { count, sort }) = args.subcmd {
+        let conn = new_conn(target_addrs[0].clone());
+        return top::run(conn, new_state(), &styles, count, sort)
+            .await
+            .map(|()| 0);
+    }
+    if let Some(config::OptionalCmd::Dump {
+        ref target,
+        wait,
+        format,
+        ref output,
+        fail_on,
+    }) = args.subcmd
+    {
+        let target = target.clone().unwrap_or_else(|| target_addrs[0].clone());
+        let conn = new_conn(target);
+        let wait = wait.into();
+        let output = output.as_deref();
+        let outcome = dump::run(conn, new_state(), &styles, wait, format, output, fail_on).await?;
+        return Ok(outcome.code());
+    }
 
-    let (mut terminal, _cleanup) = term::init_crossterm()?;
+    let mut script = match args.input_script {
+        Some(ref path) => input::Script::load(path)?,
+        None => input::Script::default(),
+    };
+    let (mut terminal, _cleanup) = term::init_crossterm(args.record_terminal.as_deref())?;
     terminal.clear()?;
-    let mut conn = conn::Connection::new(target);
+    let mut targets = Targets::new(
+        target_addrs
+            .into_iter()
+            .take(Targets::MAX)
+            .map(|target| {
+                let statsd = args.statsd(&target)?;
+                let otlp = args.otlp(&target);
+                let conn = new_conn(target);
+                let state = new_state();
+                let mut view = view::View::new(styles.clone())
+                    .with_extra_columns(args.field_columns.clone(), args.computed_columns.clone())
+                    .with_saved_filters(args.saved_filters.clone());
+                if let Some(ref dir) = session_dir {
+                    view.restore(&session::Session::load(dir, conn.target()));
+                }
+                let webhook = args.webhook();
+                let notifier = Some(args.notify.clone())
+                    .filter(|events| !events.is_empty())
+                    .map(notify::Notifier::new);
+                let hook = args.warning_hook();
+                Ok(Target {
+                    conn,
+                    state,
+                    view,
+                    webhook,
+                    notifier,
+                    hook,
+                    statsd,
+                    otlp,
+                })
+            })
+            .collect::<color_eyre::Result<_>>()?,
+    );
     // A channel to send the outcome of `View::update_input` to the watch_details_stream task.
-    let (update_tx, update_rx) = watch::channel(UpdateKind::Other);
+    let (update_tx, update_rx) = sync::watch::channel(UpdateKind::Other);
     // A channel to send the task details update stream (no need to keep outdated details in the memory)
     let (details_tx, mut details_rx) = mpsc::channel::<TaskDetails>(2);
+    // Likewise, a channel to send the resource details update stream.
+    let (resource_details_tx, mut resource_details_rx) = mpsc::channel::<ResourceDetails>(2);
 
-    let mut state = State::default()
-        // TODO(eliza): allow configuring the list of linters via the
-        // CLI/possibly a config file?
-        .with_task_linters(vec![
-            warnings::Linter::new(warnings::SelfWakePercent::default()),
-            warnings::Linter::new(warnings::LostWaker),
-        ])
-        .with_retain_for(retain_for);
     let mut input = input::EventStream::new();
-    let mut view = view::View::new(styles);
+    let mut macros = input::Macro::default();
+    let mut show_debug_log = false;
+    // While paused, no updates arrive to trigger a redraw, so redraw
+    // periodically to keep the paused banner's age current.
+    let mut redraw = tokio::time::interval(Duration::from_secs(1));
+    // While rows which changed in the latest update are highlighted, redraw
+    // often enough for the highlight to fade smoothly.
+    let mut fade = tokio::time::interval(Duration::from_millis(100));
+    fade.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
     loop {
+        let paused = targets.current().state.is_paused();
+        let fading = matches!(
+            targets.current().state.update_age(),
+            Some(age) if age < view::Styles::CHANGED_FADE
+        );
+        // While a table's filter is being typed, redraw once typing pauses
+        // to apply it.
+        let filtering = targets.current().view.is_filter_pending();
         tokio::select! { biased;
-            input = input.next() => {
+            input = macros.next(&mut input, &mut script) => {
                 let input = input
                     .ok_or_else(|| eyre!("keyboard input stream ended early"))
                     .with_section(|| "this is probably a bug".header("Note:"))??;
-                if input::should_quit(&input) {
-                    return Ok(());
+                // While a popup is capturing text input, typed characters go
+                // to the popup, rather than being handled as commands.
+                let typing = targets.current().view.is_editing()
+                    && input::text_char(&input).is_some();
+                macros.key_input(&input, typing);
+                if !typing && input::should_quit(&input) {
+                    if let Some(ref dir) = session_dir {
+                        targets.save_sessions(dir);
+                    }
+                    return Ok(0);
                 }
 
-                if input::is_space(&input) {
-                    if state.is_paused() {
-                        conn.resume().await;
-                        state.resume();
-                    } else {
-                        conn.pause().await;
-                        state.pause();
-                    }
+                if input::is_debug_log_toggle(&input) {
+                    show_debug_log = !show_debug_log;
                 }
 
-                let update_kind = view.update_input(input, &state);
-                // Using the result of update_input to manage the details watcher task
-                let _ = update_tx.send(update_kind);
-                match update_kind {
-                    UpdateKind::SelectTask(task_id) => {
-                        match conn.watch_details(task_id).await {
-                            Ok(stream) => {
-                                tokio::spawn(watch_details_stream(task_id, stream, update_rx.clone(), details_tx.clone()));
+                match input::target_index(&input) {
+                    Some(index) if !typing && targets.len() > 1 => {
+                        if targets.select(index) {
+                            // Stop watching the previous target's details, and
+                            // start watching the new target's selected task or
+                            // resource, if it has one.
+                            let _ = update_tx.send(UpdateKind::SwitchTarget);
+                            let Target { conn, state, view, .. } = targets.current();
+                            state.unset_task_details();
+                            state.unset_resource_details();
+                            if let Some(task_id) = view.selected_task() {
+                                let _ = update_tx.send(UpdateKind::SelectTask(task_id));
+                                watch_details(conn, state, task_id, &update_rx, &details_tx).await;
+                            }
+                            if let Some(resource_id) = view.selected_resource() {
+                                let _ = update_tx.send(UpdateKind::SelectResource(resource_id));
+                                watch_resource_details(
+                                    conn,
+                                    state,
+                                    resource_id,
+                                    &update_rx,
+                                    &resource_details_tx,
+                                )
+                                .await;
+                            }
+                        }
+                    }
+                    _ => {
+                        let Target { conn, state, view, .. } = targets.current();
+                        if !typing {
+                            if input::is_space(&input) && !view.is_selecting() {
+                                if state.is_paused() {
+                                    conn.resume().await;
+                                    state.resume(&view.styles, view.current_view());
+                                } else {
+                                    conn.pause().await;
+                                    state.pause();
+                                }
+                            }
+
+                            if input::is_window_toggle(&input) {
+                                state.toggle_window();
+                            }
+
+                            match input::step(&input) {
+                                Some(input::Step::Forward) => {
+                                    state.step_forward(&view.styles, view.current_view());
+                                }
+                                Some(input::Step::Back) => {
+                                    state.step_back(&view.styles, view.current_view());
+                                }
+                                None => {}
+                            }
+
+                            if let Some(factor) = input::publish_interval_scale(&input) {
+                                conn.scale_publish_interval(factor).await;
+                            }
+                        }
+
+                        let update_kind = view.update_input(input, state);
+                        // Using the result of update_input to manage the details watcher task
+                        let _ = update_tx.send(update_kind);
+                        match update_kind {
+                            UpdateKind::SelectTask(task_id) => {
+                                watch_details(conn, state, task_id, &update_rx, &details_tx).await;
                             },
-                            Err(error) => {
-                                tracing::warn!(%error, "error watching task details");
+                            UpdateKind::ExitTaskView => {
                                 state.unset_task_details();
+                            }
+                            UpdateKind::SelectResource(resource_id) => {
+                                state.unset_task_details();
+                                watch_resource_details(
+                                    conn,
+                                    state,
+                                    resource_id,
+                                    &update_rx,
+                                    &resource_details_tx,
+                                )
+                                .await;
+                            },
+                            UpdateKind::ExitResourceView => {
+                                state.unset_resource_details();
+                            }
+                            UpdateKind::JumpToBookmark(index) => {
+                                if !state.is_paused() {
+                                    conn.pause().await;
+                                    state.pause();
+                                }
+                                state.jump_to_bookmark(index, &view.styles, view.current_view());
+                            }
+                            _ => {}
                         }
-                        }
-                    },
-                    UpdateKind::ExitTaskView => {
-                        state.unset_task_details();
                     }
-                    _ => {}
                 }
             },
-            instrument_update = conn.next_update() => {
-                state.update(&view.styles,view.current_view(), instrument_update);
+            (index, instrument_update) = targets.next_update() => {
+                targets.update(index, instrument_update).await;
             }
             details_update = details_rx.recv() => {
                 if let Some(details_update) = details_update {
-                    state.update_task_details(details_update);
+                    targets.current().state.update_task_details(details_update);
+                }
+            },
+            details_update = resource_details_rx.recv() => {
+                if let Some(details_update) = details_update {
+                    targets.current().state.update_resource_details(details_update);
                 }
             },
+            _ = redraw.tick(), if paused => {},
+            _ = fade.tick(), if fading || filtering => {},
         }
+        let target_list = targets.render();
+        let Target {
+            conn,
+            state,
+            view,
+            otlp,
+            ..
+        } = targets.current();
+        let otlp_error = otlp.as_ref().and_then(export::Otlp::error);
         terminal.draw(|f| {
+            // While paused, a banner is drawn between the header and the
+            // controls, so that it's hard to mistake the frozen data for live
+            // data.
+            let banner_height = if state.is_paused() { 1 } else { 0 };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(0)
                 .constraints(
                     [
                         Constraint::Length(1),
+                        Constraint::Length(banner_height),
                         Constraint::Length(1),
                         Constraint::Percentage(95),
                     ]
@@ -121,39 +423,239 @@ async fn main() -> color_eyre::Result<()> {
                 .split(f.size());
 
             let mut header_text = conn.render(&view.styles);
-            if state.is_paused() {
-                header_text
-                    .0
-                    .push(Span::styled(" PAUSED", view.styles.fg(Color::Red)));
+            if let Some(info) = state.process_info() {
+                header_text.0.push(Span::raw(" process: "));
+                header_text.0.push(Span::raw(info.summary()));
+            }
+            if let Some(health) = state.runtime().map(|runtime| runtime.health()) {
+                header_text.0.push(Span::raw(" runtime: "));
+                if health.is_saturated() {
+                    header_text.0.push(view.styles.warning_narrow());
+                    header_text.0.push(Span::styled(
+                        health.summary(),
+                        view.styles.fg(Color::Yellow),
+                    ));
+                } else {
+                    header_text.0.push(Span::raw(health.summary()));
+                }
+            }
+            let dropped_events = state.dropped_events().total();
+            if dropped_events > 0 {
+                header_text.0.push(Span::raw(" "));
+                header_text.0.push(view.styles.warning_narrow());
+                header_text.0.push(Span::styled(
+                    format!("{} EVENTS DROPPED", dropped_events),
+                    view.styles.fg(Color::Yellow),
+                ));
+            }
+            if let Some(ref error) = otlp_error {
+                header_text.0.push(Span::raw(" "));
+                header_text.0.push(view.styles.warning_narrow());
+                header_text.0.push(Span::styled(
+                    format!("OTLP EXPORT FAILING: {}", error),
+                    view.styles.fg(Color::Yellow),
+                ));
+            }
+            if let Some(lag) = conn.stats().lag() {
+                header_text.0.push(Span::styled(
+                    format!(
+                        " LAGGING {:.1?} (~{} updates behind)",
+                        lag.delay, lag.queued
+                    ),
+                    view.styles.fg(Color::Yellow),
+                ));
+            }
+            if macros.is_recording() {
+                header_text.0.push(Span::styled(
+                    " RECORDING MACRO (M = stop)",
+                    view.styles.fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
             }
             let header = Paragraph::new(header_text).wrap(Wrap { trim: true });
-            let view_controls = Paragraph::new(Spans::from(vec![
+            let mut controls = vec![
                 Span::raw("views: "),
                 bold("t"),
                 Span::raw(" = tasks, "),
                 bold("r"),
-                Span::raw(" = resources"),
-            ]))
-            .wrap(Wrap { trim: true });
+                Span::raw(" = resources, "),
+                bold("m"),
+                Span::raw(" = runtime, "),
+                bold("g"),
+                Span::raw(" = timeline, "),
+                bold("s"),
+                Span::raw(" = trends, "),
+                bold("P"),
+                Span::raw(" = process info, "),
+                bold("C"),
+                Span::raw(" = connection, "),
+                bold("[ ]"),
+                Span::raw(" = faster/slower updates, "),
+                bold("b"),
+                Span::raw(" = bookmark, "),
+                bold("B"),
+                Span::raw(" = bookmarks, "),
+                bold("X"),
+                Span::raw(" = clear closed, "),
+                bold("A"),
+                Span::raw(if view.styles.absolute_times {
+                    " = relative times, "
+                } else {
+                    " = absolute times, "
+                }),
+                bold("Z"),
+                Span::raw(if view.styles.anonymize {
+                    " = show names, "
+                } else {
+                    " = anonymize, "
+                }),
+                bold("M"),
+                Span::raw(if macros.is_recording() {
+                    " = stop recording, "
+                } else {
+                    " = record macro, "
+                }),
+            ];
+            if macros.is_recorded() {
+                controls.push(bold("@"));
+                controls.push(Span::raw(" = replay macro, "));
+            }
+            controls.push(bold("F12"));
+            controls.push(Span::raw(" = debug log"));
+            if state.is_paused() {
+                controls.push(Span::raw(", "));
+                controls.push(bold("n p"));
+                controls.push(Span::raw(" = next/previous update, "));
+                controls.push(bold("w"));
+                controls.push(Span::raw(if state.window().is_some() {
+                    " = end stats window"
+                } else {
+                    " = start stats window"
+                }));
+            }
+            if let Some(target_list) = target_list {
+                controls.push(Span::raw("; "));
+                controls.extend(target_list.0);
+            }
+            let view_controls = Paragraph::new(Spans::from(controls)).wrap(Wrap { trim: true });
 
             f.render_widget(header, chunks[0]);
-            f.render_widget(view_controls, chunks[1]);
-            view.render(f, chunks[2], &mut state);
+            if state.is_paused() {
+                f.render_widget(paused_banner(&view.styles, state), chunks[1]);
+            }
+            f.render_widget(view_controls, chunks[2]);
+            if show_debug_log {
+                let view_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+                    .split(chunks[3]);
+                view.render(f, view_chunks[0], state, conn.stats());
+                view.render_debug_log(f, view_chunks[1], &debug_log);
+            } else {
+                view.render(f, chunks[3], state, conn.stats());
+            }
         })?;
     }
 }
 
-/// Given the task details stream for the given task id, sends the updates
-/// to the `details_tx` channel until the currently-viewed task changes.
+/// Returns a banner stating how long the console has been paused for, and
+/// which update is being displayed.
+fn paused_banner(styles: &view::Styles, state: &State) -> Paragraph<'static> {
+    // Sub-second precision would just make the banner flicker.
+    let paused_for = state
+        .paused_for()
+        .map(|paused_for| Duration::from_secs(paused_for.as_secs()))
+        .unwrap_or_default();
+    let (update, updates) = state.update_position();
+    let at = state
+        .last_updated_at()
+        .map(|at| format!(" from {}", humantime::format_rfc3339_millis(at)))
+        .unwrap_or_default();
+    let text = format!(
+        " PAUSED for {}: showing update {} of {}{}; press space to resume ",
+        humantime::format_duration(paused_for),
+        update,
+        updates,
+        at,
+    );
+    Paragraph::new(Span::raw(text)).style(
+        styles
+            .fg(Color::Red)
+            .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+    )
+}
+
+/// Starts watching the details of the given task in the background.
+async fn watch_details(
+    conn: &mut conn::Connection,
+    state: &mut State,
+    task_id: u64,
+    update_rx: &sync::watch::Receiver<UpdateKind>,
+    details_tx: &mpsc::Sender<TaskDetails>,
+) {
+    match conn.watch_details(task_id).await {
+        Ok(stream) => {
+            tokio::spawn(watch_details_stream(
+                stream,
+                update_rx.clone(),
+                details_tx.clone(),
+                move |update| match update {
+                    // Following a task's waker to a resource leaves the
+                    // task view.
+                    UpdateKind::ExitTaskView
+                    | UpdateKind::SelectResource(_)
+                    | UpdateKind::SwitchTarget => true,
+                    UpdateKind::SelectTask(new_id) => new_id != task_id,
+                    _ => false,
+                },
+            ));
+        }
+        Err(error) => {
+            tracing::warn!(%error, "error watching task details");
+            state.unset_task_details();
+        }
+    }
+}
+
+/// Starts watching the details of the given resource in the background.
+async fn watch_resource_details(
+    conn: &mut conn::Connection,
+    state: &mut State,
+    resource_id: u64,
+    update_rx: &sync::watch::Receiver<UpdateKind>,
+    details_tx: &mpsc::Sender<ResourceDetails>,
+) {
+    match conn.watch_resource_details(resource_id).await {
+        Ok(stream) => {
+            tokio::spawn(watch_details_stream(
+                stream,
+                update_rx.clone(),
+                details_tx.clone(),
+                move |update| match update {
+                    UpdateKind::ExitResourceView | UpdateKind::SwitchTarget => true,
+                    UpdateKind::SelectResource(new_id) => new_id != resource_id,
+                    _ => false,
+                },
+            ));
+        }
+        Err(error) => {
+            tracing::warn!(%error, "error watching resource details");
+            state.unset_resource_details();
+        }
+    }
+}
+
+/// Given a task or resource details stream, sends the updates to the
+/// `details_tx` channel until `is_done` returns `true` for a change to the
+/// current view, such as when the currently-viewed task changes.
 ///
 /// This is a separate task from the main program loop mainly because there isn't
 /// always a details stream to poll and we need to react to user inputs to
 /// replace the details stream with another one.
-async fn watch_details_stream(
-    task_id: u64,
-    mut details_stream: tonic::Streaming<TaskDetails>,
-    mut watch_rx: watch::Receiver<UpdateKind>,
-    details_tx: mpsc::Sender<TaskDetails>,
+async fn watch_details_stream<T>(
+    mut details_stream: tonic::Streaming<T>,
+    mut watch_rx: sync::watch::Receiver<UpdateKind>,
+    details_tx: mpsc::Sender<T>,
+    is_done: impl Fn(UpdateKind) -> bool,
 ) {
     loop {
         tokio::select! { biased;
@@ -171,14 +673,8 @@ async fn watch_details_stream(
             },
             update = watch_rx.changed() => {
                 if update.is_ok() {
-                    match *watch_rx.borrow() {
-                        UpdateKind::ExitTaskView => {
-                            break;
-                        },
-                        UpdateKind::SelectTask(new_id) if new_id != task_id => {
-                            break;
-                        },
-                        _ => {}
+                    if is_done(*watch_rx.borrow()) {
+                        break;
                     }
                 } else {
                     break;
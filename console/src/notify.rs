@@ -0,0 +1,121 @@
+//! Showing desktop notifications when warnings fire, or when the connection to
+//! an instrumented process is re-established, so that they can be noticed
+//! while the console is minimized.
+//!
+//! Notifications are only shown if the console was built with the
+//! `desktop-notifications` feature.
+use crate::{
+    conn::Connection,
+    state::State,
+    warnings::{Level, Tracker},
+};
+use std::str::FromStr;
+
+/// Whether this build of the console can show desktop notifications.
+pub(crate) const SUPPORTED: bool = cfg!(feature = "desktop-notifications");
+
+/// Something which can be notified about.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Event {
+    /// A warning or alert which isn't critical fired.
+    Warning,
+    /// A critical warning fired.
+    Critical,
+    /// The connection to the instrumented process was re-established.
+    Reconnect,
+}
+
+/// Shows desktop notifications for the events which happen to a target.
+#[derive(Debug)]
+pub(crate) struct Notifier {
+    events: Vec<Event>,
+    tracker: Tracker,
+    /// The number of reconnections when last checked.
+    reconnects: u64,
+}
+
+// === impl Notifier ===
+
+impl Notifier {
+    pub(crate) fn new(events: Vec<Event>) -> Self {
+        Self {
+            events,
+            tracker: Tracker::default(),
+            reconnects: 0,
+        }
+    }
+
+    /// Shows a notification for the warnings and alerts which started firing
+    /// in `state`, and for any reconnection of `conn`, since they were last
+    /// checked.
+    ///
+    /// Everything which happened is summarized in a single notification.
+    pub(crate) fn check(&mut self, conn: &Connection, state: &State) {
+        let mut lines = Vec::new();
+        let reconnects = conn.stats().reconnects();
+        if reconnects > self.reconnects && self.events.contains(&Event::Reconnect) {
+            lines.push("Reconnected to the instrumented process".to_string());
+        }
+        self.reconnects = reconnects;
+
+        let events = &self.events;
+        let fired = self.tracker.check(state).into_iter().filter(|fired| {
+            let event = match fired.level {
+                Level::Warning => Event::Warning,
+                Level::Critical => Event::Critical,
+            };
+            events.contains(&event)
+        });
+        for fired in fired {
+            match fired.task_id {
+                Some(id) => lines.push(format!("task {}: {}", id, fired.message)),
+                None => lines.push(fired.message),
+            }
+        }
+
+        if !lines.is_empty() {
+            show(
+                format!("tokio-console: {}", conn.target()),
+                lines.join("\n"),
+            );
+        }
+    }
+}
+
+// === impl Event ===
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("warning") => Ok(Event::Warning),
+            s if s.eq_ignore_ascii_case("critical") => Ok(Event::Critical),
+            s if s.eq_ignore_ascii_case("reconnect") => Ok(Event::Reconnect),
+            s => Err(format!(
+                "unknown event `{}` (expected warning, critical, or reconnect)",
+                s
+            )),
+        }
+    }
+}
+
+/// Shows a notification in the background, logging any failure to show it.
+#[cfg(feature = "desktop-notifications")]
+fn show(summary: String, body: String) {
+    tokio::task::spawn_blocking(move || {
+        let shown = notify_rust::Notification::new()
+            .appname("tokio-console")
+            .summary(&summary)
+            .body(&body)
+            .show();
+        if let Err(error) = shown {
+            tracing::warn!(%error, "failed to show a desktop notification");
+        }
+    });
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn show(summary: String, body: String) {
+    tracing::debug!(%summary, %body, "desktop notifications are not supported");
+}
@@ -0,0 +1,191 @@
+//! The JSON representations of tasks, resources, and histograms, which every
+//! command and export that writes them shares.
+use crate::{
+    state::{self, tasks::TaskState},
+    view::mini_histogram,
+};
+use serde::Serialize;
+use std::time::SystemTime;
+use tui::text::Span;
+
+/// The statistics of some of a process' tasks and resources as of `now`.
+#[derive(Debug, Serialize)]
+pub(crate) struct Snapshot {
+    /// When the statistics were last updated, in RFC 3339 format.
+    now: String,
+    tasks: Vec<Task>,
+    resources: Vec<Resource>,
+}
+
+/// A task's statistics as of some time. Durations are in seconds.
+#[derive(Debug, Serialize)]
+pub(crate) struct Task {
+    id: u64,
+    name: Option<String>,
+    runtime: Option<String>,
+    target: String,
+    location: String,
+    state: &'static str,
+    fields: String,
+    total: f64,
+    busy: f64,
+    idle: f64,
+    polls: u64,
+    wakes: u64,
+    self_wakes: u64,
+    wakers: u64,
+    warnings: Vec<String>,
+}
+
+/// A resource's statistics as of some time. Durations are in seconds.
+#[derive(Debug, Serialize)]
+pub(crate) struct Resource {
+    id: u64,
+    kind: String,
+    #[serde(rename = "type")]
+    concrete_type: String,
+    target: String,
+    runtime: Option<String>,
+    location: String,
+    attributes: String,
+    total: f64,
+    dropped: bool,
+}
+
+/// A histogram of durations in nanoseconds, with its summary statistics,
+/// percentiles, and the count in each bucket which has values recorded in
+/// it.
+#[derive(Debug, Serialize)]
+pub(crate) struct Histogram {
+    significant_figures: u8,
+    count: u64,
+    min_ns: u64,
+    max_ns: u64,
+    mean_ns: f64,
+    percentiles: Vec<Percentile>,
+    buckets: Vec<Bucket>,
+}
+
+#[derive(Debug, Serialize)]
+struct Percentile {
+    percentile: f64,
+    value_ns: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Bucket {
+    lower_ns: u64,
+    upper_ns: u64,
+    count: u64,
+}
+
+// === impl Snapshot ===
+
+impl Snapshot {
+    pub(crate) fn new(now: SystemTime, tasks: Vec<Task>, resources: Vec<Resource>) -> Self {
+        Self {
+            now: humantime::format_rfc3339_nanos(now).to_string(),
+            tasks,
+            resources,
+        }
+    }
+}
+
+// === impl Task ===
+
+impl Task {
+    pub(crate) fn new(task: &state::tasks::Task, now: SystemTime) -> Self {
+        let state = match task.state() {
+            TaskState::Running => "running",
+            TaskState::Idle => "idle",
+            TaskState::Completed => "completed",
+        };
+        Self {
+            id: task.id(),
+            name: task.name().map(String::from),
+            runtime: task.runtime().map(|runtime| runtime.to_string()),
+            target: task.target().to_string(),
+            location: task.location().to_string(),
+            state,
+            fields: fields(task.formatted_fields()),
+            total: task.total(now).as_secs_f64(),
+            busy: task.busy(now).as_secs_f64(),
+            idle: task.idle(now).as_secs_f64(),
+            polls: task.total_polls(),
+            wakes: task.wakes(),
+            self_wakes: task.self_wakes(),
+            wakers: task.waker_count(),
+            warnings: task
+                .warnings()
+                .iter()
+                .map(|linter| linter.format(task))
+                .collect(),
+        }
+    }
+}
+
+// === impl Resource ===
+
+impl Resource {
+    pub(crate) fn new(resource: &state::resources::Resource, now: SystemTime) -> Self {
+        Self {
+            id: resource.id(),
+            kind: resource.kind().to_string(),
+            concrete_type: resource.concrete_type().to_string(),
+            target: resource.target().to_string(),
+            runtime: resource.runtime().map(|runtime| runtime.to_string()),
+            location: resource.location().to_string(),
+            attributes: fields(resource.formatted_attributes()),
+            total: resource.total(now).as_secs_f64(),
+            dropped: resource.dropped(),
+        }
+    }
+}
+
+// === impl Histogram ===
+
+impl Histogram {
+    pub(crate) fn new(histogram: &hdrhistogram::Histogram<u64>) -> Self {
+        let percentiles = mini_histogram::PERCENTILES
+            .iter()
+            .map(|&percentile| Percentile {
+                percentile,
+                value_ns: histogram.value_at_percentile(percentile),
+            })
+            .collect();
+        let buckets = histogram
+            .iter_recorded()
+            .map(|bucket| {
+                let upper = bucket.value_iterated_to();
+                Bucket {
+                    lower_ns: histogram.lowest_equivalent(upper),
+                    upper_ns: upper,
+                    count: bucket.count_at_value(),
+                }
+            })
+            .collect();
+        Self {
+            significant_figures: histogram.sigfig(),
+            count: histogram.len(),
+            min_ns: histogram.min(),
+            max_ns: histogram.max(),
+            mean_ns: histogram.mean(),
+            percentiles,
+            buckets,
+        }
+    }
+}
+
+/// Joins formatted fields into a single string, separated by spaces.
+fn fields(formatted: &[Vec<Span<'static>>]) -> String {
+    formatted
+        .iter()
+        .map(|field| {
+            field
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
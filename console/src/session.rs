@@ -0,0 +1,177 @@
+//! Saving the console's UI settings for a target when the console exits, and
+//! restoring them the next time the console connects to the same target.
+//!
+//! Sessions are stored as plain text files of `key = value` lines, one file
+//! per target, in the session directory. Unknown keys are ignored, so that
+//! session files written by other versions of the console can still be read.
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use tonic::transport::Uri;
+
+/// The UI settings saved for a target.
+///
+/// Rows pinned to the top of the tables aren't saved, since rows are
+/// identified by the IDs the instrumented process assigns them, which
+/// refer to other tasks and resources once it restarts.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) struct Session {
+    /// The list view that was displayed, either `tasks` or `resources`.
+    pub(crate) view: Option<String>,
+    pub(crate) tasks: Table,
+    pub(crate) resources: Table,
+}
+
+/// The settings saved for a table list.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) struct Table {
+    pub(crate) sort: Option<Sort>,
+    /// The text the table was filtered by, if it was filtered.
+    pub(crate) filter: Option<String>,
+    /// The widths of the columns the user resized, by column header.
+    pub(crate) widths: Vec<(String, u16)>,
+}
+
+/// How a table list was sorted.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Sort {
+    /// The header of the column the table was sorted by.
+    pub(crate) column: String,
+    pub(crate) descending: bool,
+}
+
+impl Session {
+    /// Loads the session saved for `target` in `dir`.
+    ///
+    /// If no session has been saved for the target, or the session file
+    /// can't be read, this returns the default session.
+    pub(crate) fn load(dir: &Path, target: &Uri) -> Self {
+        let path = path(dir, target);
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                tracing::debug!(path = %path.display(), "restoring session");
+                Self::parse(&contents)
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                tracing::warn!(path = %path.display(), %error, "failed to read session file");
+                Self::default()
+            }
+        }
+    }
+
+    /// Saves the session for `target` in `dir`, creating `dir` if it does
+    /// not exist.
+    pub(crate) fn save(&self, dir: &Path, target: &Uri) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let path = path(dir, target);
+        tracing::debug!(path = %path.display(), "saving session");
+        fs::write(path, self.to_string())
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut session = Self::default();
+        let mut tasks_sort = SortBuilder::default();
+        let mut resources_sort = SortBuilder::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            if key == "view" {
+                session.view = Some(value.to_string());
+                continue;
+            }
+            let (table, sort, key) = match key.split_once('.') {
+                Some(("tasks", key)) => (&mut session.tasks, &mut tasks_sort, key),
+                Some(("resources", key)) => (&mut session.resources, &mut resources_sort, key),
+                _ => continue,
+            };
+            match key {
+                "sort" => sort.column = Some(value.to_string()),
+                "descending" => sort.descending = value.parse().ok(),
+                "filter" if !value.is_empty() => table.filter = Some(value.to_string()),
+                "widths" => table.widths = parse_widths(value),
+                _ => {}
+            }
+        }
+        session.tasks.sort = tasks_sort.build();
+        session.resources.sort = resources_sort.build();
+        session
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# tokio-console session")?;
+        if let Some(ref view) = self.view {
+            writeln!(f, "view = {}", view)?;
+        }
+        for (prefix, table) in [("tasks", &self.tasks), ("resources", &self.resources)] {
+            if let Some(ref sort) = table.sort {
+                writeln!(f, "{}.sort = {}", prefix, sort.column)?;
+                writeln!(f, "{}.descending = {}", prefix, sort.descending)?;
+            }
+            if let Some(ref filter) = table.filter {
+                writeln!(f, "{}.filter = {}", prefix, filter)?;
+            }
+            if !table.widths.is_empty() {
+                let widths = table
+                    .widths
+                    .iter()
+                    .map(|(column, width)| format!("{}:{}", column, width))
+                    .collect::<Vec<_>>();
+                writeln!(f, "{}.widths = {}", prefix, widths.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct SortBuilder {
+    column: Option<String>,
+    descending: Option<bool>,
+}
+
+impl SortBuilder {
+    fn build(self) -> Option<Sort> {
+        Some(Sort {
+            column: self.column?,
+            descending: self.descending.unwrap_or(false),
+        })
+    }
+}
+
+/// Parses a list of resized column widths, such as `Name:30, Target:12`,
+/// skipping any which are malformed.
+fn parse_widths(value: &str) -> Vec<(String, u16)> {
+    value
+        .split(',')
+        .filter_map(|width| {
+            let (column, width) = width.rsplit_once(':')?;
+            Some((column.trim().to_string(), width.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Returns the path of the session file for `target` in `dir`.
+fn path(dir: &Path, target: &Uri) -> PathBuf {
+    let name: String = target
+        .to_string()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.session", name.trim_matches('_')))
+}
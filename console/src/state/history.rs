@@ -0,0 +1,346 @@
+//! A record of the updates received from the instrumented process, so that
+//! the console can step backwards and forwards through them while paused.
+use crate::state::Retention;
+use console_api as proto;
+use proto::instrument::Update;
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    /// Every update older than those in `updates`, merged into one.
+    base: Option<Update>,
+    /// The number of updates that have been merged into `base`.
+    merged: usize,
+    /// The most recent updates, oldest first.
+    updates: VecDeque<Entry>,
+    /// The number of `updates` that are currently applied to the state.
+    applied: usize,
+    /// The total number of updates received.
+    received: usize,
+}
+
+/// One or more updates, merged into one.
+#[derive(Debug)]
+struct Entry {
+    update: Update,
+    /// The number of updates received that were merged into `update`.
+    ///
+    /// This is more than one once updates buffered while paused have been
+    /// merged to bound how many are kept.
+    count: usize,
+}
+
+impl History {
+    /// The number of updates that are kept individually, which is how far
+    /// back it is possible to step, and how many updates are buffered while
+    /// paused before the oldest buffered updates are merged together.
+    const MAX_UPDATES: usize = 100;
+
+    /// Records a new update, without applying it.
+    pub(crate) fn push(&mut self, update: Update, retention: Retention) {
+        self.updates.push_back(Entry { update, count: 1 });
+        self.received += 1;
+        // Only updates that have been applied can be merged into the base...
+        while self.updates.len() > Self::MAX_UPDATES && self.applied > 0 {
+            let oldest = self.updates.pop_front().expect("updates cannot be empty");
+            self.applied -= 1;
+            self.merged += oldest.count;
+            let base = self.base.get_or_insert_with(Update::default);
+            merge(base, oldest.update);
+            prune(base, retention);
+        }
+        // ...so while paused, merge the oldest buffered updates into one
+        // instead, rather than buffering every update for as long as the
+        // console is paused. Stepping forward applies them all at once.
+        while self.updates.len() - self.applied > Self::MAX_UPDATES {
+            let next = self
+                .updates
+                .remove(self.applied + 1)
+                .expect("more than one update is buffered");
+            let batch = &mut self.updates[self.applied];
+            merge(&mut batch.update, next.update);
+            batch.count += next.count;
+            // The console doesn't display poll ops yet, so there's no need
+            // to keep every poll op received while paused.
+            if let Some(ref mut resources) = batch.update.resource_update {
+                resources.new_poll_ops.clear();
+            }
+        }
+    }
+
+    /// Marks the next buffered update as applied, returning it.
+    pub(crate) fn step_forward(&mut self) -> Option<Update> {
+        let update = self.updates.get(self.applied)?.update.clone();
+        self.applied += 1;
+        Some(update)
+    }
+
+    /// Marks the most recently applied update as no longer applied, returning
+    /// a single update that recreates the state as of the update before it.
+    ///
+    /// This returns `None` if there is no earlier update to step back to.
+    pub(crate) fn step_back(&mut self) -> Option<Update> {
+        if self.applied <= self.earliest() {
+            return None;
+        }
+        self.applied -= 1;
+        Some(self.snapshot())
+    }
+
+    /// Marks every update up to and including the update numbered `position`
+    /// as applied, returning a single update that recreates the state as of
+    /// that update.
+    ///
+    /// If that update is too old to step back to, the state is recreated as
+    /// of the oldest update that is still available instead, and if it was
+    /// merged with the updates after it while paused, as of the update
+    /// before them.
+    pub(crate) fn seek(&mut self, position: usize) -> Option<Update> {
+        if self.base.is_none() && self.updates.is_empty() {
+            return None;
+        }
+        let mut number = self.merged;
+        let applied = self
+            .updates
+            .iter()
+            .take_while(|entry| {
+                number += entry.count;
+                number <= position
+            })
+            .count();
+        self.applied = applied.max(self.earliest()).min(self.updates.len());
+        Some(self.snapshot())
+    }
+
+    /// Marks every buffered update as applied, returning them merged into a
+    /// single update.
+    pub(crate) fn catch_up(&mut self) -> Option<Update> {
+        let mut pending = self
+            .updates
+            .range(self.applied..)
+            .map(|entry| entry.update.clone());
+        let mut update = pending.next()?;
+        for next in pending {
+            merge(&mut update, next);
+        }
+        self.applied = self.updates.len();
+        Some(update)
+    }
+
+    /// Returns the number of the currently applied update, counting from 1,
+    /// and the total number of updates received.
+    pub(crate) fn position(&self) -> (usize, usize) {
+        let applied = self
+            .updates
+            .range(..self.applied)
+            .map(|entry| entry.count)
+            .sum::<usize>();
+        (self.merged + applied, self.received)
+    }
+
+    /// Returns the smallest number of `updates` that can be applied.
+    ///
+    /// At least one update must always be applied, so that there is some
+    /// state to display.
+    fn earliest(&self) -> usize {
+        if self.base.is_some() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Merges the base and every applied update into a single update.
+    fn snapshot(&self) -> Update {
+        let mut snapshot = self.base.clone().unwrap_or_default();
+        for entry in self.updates.range(..self.applied) {
+            merge(&mut snapshot, entry.update.clone());
+        }
+        snapshot
+    }
+}
+
+/// Merges `next` into `update`, so that applying the merged update has the
+/// same effect as applying `update` and then `next`.
+fn merge(update: &mut Update, next: Update) {
+    if next.now.is_some() {
+        update.now = next.now;
+    }
+    if next.process_info.is_some() {
+        update.process_info = next.process_info;
+    }
+    // Runtime updates report the latest metrics, rather than changes to them.
+    if next.runtime_update.is_some() {
+        update.runtime_update = next.runtime_update;
+    }
+    merge_opt(
+        &mut update.new_metadata,
+        next.new_metadata,
+        |metadata, next| metadata.metadata.extend(next.metadata),
+    );
+    merge_opt(&mut update.task_update, next.task_update, |tasks, next| {
+        tasks.new_tasks.extend(next.new_tasks);
+        tasks.stats_update.extend(next.stats_update);
+        tasks.dropped_events += next.dropped_events;
+    });
+    merge_opt(
+        &mut update.resource_update,
+        next.resource_update,
+        |resources, next| {
+            resources.new_resources.extend(next.new_resources);
+            resources.stats_update.extend(next.stats_update);
+            resources.new_poll_ops.extend(next.new_poll_ops);
+            resources.dropped_events += next.dropped_events;
+        },
+    );
+    merge_opt(
+        &mut update.async_op_update,
+        next.async_op_update,
+        |async_ops, next| {
+            async_ops.new_async_ops.extend(next.new_async_ops);
+            async_ops.stats_update.extend(next.stats_update);
+            async_ops.dropped_events += next.dropped_events;
+        },
+    );
+}
+
+fn merge_opt<T>(update: &mut Option<T>, next: Option<T>, merge: impl FnOnce(&mut T, T)) {
+    if let Some(next) = next {
+        match *update {
+            Some(ref mut update) => merge(update, next),
+            None => *update = Some(next),
+        }
+    }
+}
+
+/// Removes the tasks, resources, and async ops in a merged update that were
+/// dropped longer before it than they are retained for, since the console
+/// would discard them anyway.
+fn prune(update: &mut Update, retention: Retention) {
+    let now: SystemTime = match update.now.clone().and_then(|now| now.try_into().ok()) {
+        Some(now) => now,
+        None => return,
+    };
+    let expired = |dropped_at: &Option<prost_types::Timestamp>, retain_for: Option<Duration>| {
+        let retain_for = match retain_for {
+            Some(retain_for) => retain_for,
+            None => return false,
+        };
+        dropped_at
+            .clone()
+            .and_then(|dropped_at| dropped_at.try_into().ok())
+            .and_then(|dropped_at| now.duration_since(dropped_at).ok())
+            .map(|dropped_for| dropped_for > retain_for)
+            .unwrap_or(false)
+    };
+
+    if let Some(ref mut tasks) = update.task_update {
+        tasks
+            .stats_update
+            .retain(|_, stats| !expired(&stats.dropped_at, retention.longest_task()));
+        let stats = &tasks.stats_update;
+        tasks
+            .new_tasks
+            .retain(|task| matches!(task.id, Some(ref id) if stats.contains_key(&id.id)));
+    }
+
+    if let Some(ref mut resources) = update.resource_update {
+        resources
+            .stats_update
+            .retain(|_, stats| !expired(&stats.dropped_at, retention.resources));
+        let stats = &resources.stats_update;
+        resources
+            .new_resources
+            .retain(|resource| matches!(resource.id, Some(ref id) if stats.contains_key(&id.id)));
+        // The console doesn't display poll ops yet, so there's no need to
+        // keep every poll op ever received.
+        resources.new_poll_ops.clear();
+    }
+
+    if let Some(ref mut async_ops) = update.async_op_update {
+        async_ops
+            .stats_update
+            .retain(|_, stats| !expired(&stats.dropped_at, retention.resources));
+        let stats = &async_ops.stats_update;
+        async_ops
+            .new_async_ops
+            .retain(|async_op| matches!(async_op.id, Some(ref id) if stats.contains_key(&id.id)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an update sent `secs` seconds after the epoch.
+    fn update(secs: i64) -> Update {
+        Update {
+            now: Some(prost_types::Timestamp {
+                seconds: secs,
+                nanos: 0,
+            }),
+            ..Update::default()
+        }
+    }
+
+    fn secs(update: &Update) -> i64 {
+        update.now.as_ref().expect("updates have a time").seconds
+    }
+
+    #[test]
+    fn buffer_is_bounded_while_paused() {
+        let mut history = History::default();
+        history.push(update(0), Retention::default());
+        assert_eq!(history.step_forward().as_ref().map(secs), Some(0));
+
+        // Nothing is applied while paused.
+        let received = History::MAX_UPDATES as i64 * 3;
+        for secs in 1..=received {
+            history.push(update(secs), Retention::default());
+        }
+        assert!(history.updates.len() - history.applied <= History::MAX_UPDATES);
+        assert_eq!(history.position(), (1, received as usize + 1));
+
+        // The oldest buffered updates were merged into one, which is applied
+        // in a single step, and the rest are applied one at a time.
+        let batch = history.step_forward().expect("updates are buffered");
+        let (position, _) = history.position();
+        assert_eq!(secs(&batch) as usize, position - 1);
+        assert!(position > 2);
+        while let Some(next) = history.step_forward() {
+            let (position, _) = history.position();
+            assert_eq!(secs(&next) as usize, position - 1);
+        }
+        assert_eq!(
+            history.position(),
+            (received as usize + 1, received as usize + 1)
+        );
+    }
+
+    #[test]
+    fn seek_lands_before_merged_updates() {
+        let mut history = History::default();
+        history.push(update(0), Retention::default());
+        history.step_forward();
+        let received = History::MAX_UPDATES as i64 * 2;
+        for secs in 1..=received {
+            history.push(update(secs), Retention::default());
+        }
+
+        // Update 2 was merged with the buffered updates after it, so seeking
+        // to it shows the state as of the update before them.
+        let snapshot = history.seek(2).expect("updates were received");
+        assert_eq!(secs(&snapshot), 0);
+        assert_eq!(history.position(), (1, received as usize + 1));
+
+        // Seeking to the last update applies every update.
+        let snapshot = history
+            .seek(received as usize + 1)
+            .expect("updates were received");
+        assert_eq!(secs(&snapshot), received);
+    }
+}
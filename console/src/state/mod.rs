@@ -1,41 +1,107 @@
-use self::resources::ResourcesState;
+use self::{history::History, resources::ResourcesState, runtime::RuntimeState, window::Window};
 use crate::{
     intern::{self, InternedStr},
     view,
-    warnings::Linter,
+    warnings::{self, Linter},
+    watch::{Alert, Lint, Watch, Watches},
 };
 use console_api as proto;
 use std::{
     cell::RefCell,
-    collections::HashMap,
-    convert::TryInto,
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap, HashSet},
+    convert::{TryFrom, TryInto},
     fmt,
     io::Cursor,
     rc::Rc,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 use tasks::{Details, Task, TasksState};
 use tui::{
-    style::{Color, Modifier},
-    text::Span,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
 };
 
+mod history;
 pub mod resources;
+pub(crate) mod runtime;
 pub mod tasks;
+pub(crate) mod window;
 
 pub(crate) type DetailsRef = Rc<RefCell<Option<Details>>>;
+pub(crate) type ResourceDetailsRef = Rc<RefCell<Option<resources::Details>>>;
 
 #[derive(Default, Debug)]
 pub(crate) struct State {
     metas: HashMap<u64, Metadata>,
     last_updated_at: Option<SystemTime>,
     temporality: Temporality,
+    /// When the console was paused, if it is paused.
+    paused_at: Option<Instant>,
+    /// When the displayed update was applied, according to the local clock.
+    applied_at: Option<Instant>,
     tasks_state: TasksState,
     resources_state: ResourcesState,
     current_task_details: DetailsRef,
-    retain_for: Option<Duration>,
+    current_resource_details: ResourceDetailsRef,
+    retention: Retention,
     strings: intern::Strings,
+    process_info: Option<ProcessInfo>,
+    dropped_events: DroppedEvents,
+    history: History,
+    bookmarks: Vec<Bookmark>,
+    window: Option<Window>,
+    /// The IDs of tasks whose completion should pause the console.
+    break_on_completion: HashSet<u64>,
+    watches: Watches,
+    /// The runtime's scheduler metrics, if the process reports them.
+    runtime: Option<RuntimeState>,
+    /// The runtime whose tasks are listed, or `None` to list every task.
+    runtime_filter: Option<InternedStr>,
+    warning_history: warnings::History,
 }
+
+/// How long completed tasks and dropped resources are displayed after they
+/// have been closed, or `None` to display them indefinitely.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Retention {
+    pub(crate) tasks: Option<Duration>,
+    /// How long tasks which completed with warnings are displayed, which may
+    /// be longer than other completed tasks so that they can still be
+    /// investigated.
+    ///
+    /// The instrumented process doesn't report whether a task panicked or
+    /// was cancelled, so warnings are the only way completed tasks are told
+    /// apart.
+    pub(crate) warned_tasks: Option<Duration>,
+    /// How long dropped resources, and their async ops, are displayed.
+    pub(crate) resources: Option<Duration>,
+}
+
+/// A moment in the update history that the user marked, so that they can
+/// return to it later.
+#[derive(Debug)]
+pub(crate) struct Bookmark {
+    /// The number of the update that was displayed when the bookmark was
+    /// added.
+    pub(crate) update: usize,
+    /// The time of that update, according to the instrumented process.
+    pub(crate) at: Option<SystemTime>,
+    pub(crate) label: Option<String>,
+}
+
+/// Total counts of events that the instrumented process dropped because its
+/// event buffer was full.
+///
+/// If any events were dropped, the corresponding statistics may be
+/// incomplete.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct DroppedEvents {
+    pub(crate) tasks: u64,
+    pub(crate) resources: u64,
+    pub(crate) async_ops: u64,
+}
+
 pub(crate) enum Visibility {
     Show,
     Hide,
@@ -49,6 +115,16 @@ pub(crate) struct Metadata {
     //TODO: add more metadata as needed
 }
 
+/// Static information about the instrumented process.
+#[derive(Debug)]
+pub(crate) struct ProcessInfo {
+    pub(crate) pid: u32,
+    pub(crate) executable: Option<String>,
+    pub(crate) hostname: Option<String>,
+    pub(crate) tokio_version: Option<String>,
+    pub(crate) subscriber_version: Option<String>,
+}
+
 #[derive(Debug)]
 pub(crate) struct Field {
     pub(crate) name: InternedStr,
@@ -61,6 +137,11 @@ pub(crate) enum FieldValue {
     Str(String),
     U64(u64),
     I64(i64),
+    F64(f64),
+    U128(u128),
+    I128(i128),
+    Duration(Duration),
+    Timestamp(SystemTime),
     Debug(String),
 }
 
@@ -71,8 +152,8 @@ enum Temporality {
 }
 
 impl State {
-    pub(crate) fn with_retain_for(mut self, retain_for: Option<Duration>) -> Self {
-        self.retain_for = retain_for;
+    pub(crate) fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
         self
     }
 
@@ -84,18 +165,119 @@ impl State {
         self
     }
 
+    pub(crate) fn with_resource_linters(
+        mut self,
+        linters: impl IntoIterator<Item = Linter<resources::Resource>>,
+    ) -> Self {
+        self.resources_state.linters.extend(linters);
+        self
+    }
+
+    pub(crate) fn with_watches(
+        mut self,
+        watches: impl IntoIterator<Item = Watch>,
+        lints: impl IntoIterator<Item = Lint>,
+    ) -> Self {
+        let (watches, linters) = Watches::new(watches, lints);
+        self.watches = watches;
+        self.tasks_state.linters.extend(linters);
+        self
+    }
+
+    /// Counts the polls which take longer than `threshold` as slow.
+    pub(crate) fn with_slow_poll(mut self, threshold: Duration) -> Self {
+        self.tasks_state.slow_poll = Some(threshold);
+        self
+    }
+
     pub(crate) fn last_updated_at(&self) -> Option<SystemTime> {
         self.last_updated_at
     }
 
+    /// Returns how long ago the displayed update was applied, according to
+    /// the local clock.
+    pub(crate) fn update_age(&self) -> Option<Duration> {
+        self.applied_at.map(|applied_at| applied_at.elapsed())
+    }
+
+    /// Records an update from the instrumented process, applying it unless
+    /// the console is paused.
+    ///
+    /// Updates received while paused are buffered, and applied when stepping
+    /// forward or resuming.
+    ///
+    /// Returns `true` if the update completed a task that the console should
+    /// break on, in which case the console is now paused.
     pub(crate) fn update(
         &mut self,
         styles: &view::Styles,
         current_view: &view::ViewState,
         update: proto::instrument::Update,
+    ) -> bool {
+        self.history.push(update, self.retention);
+        if self.is_paused() {
+            return false;
+        }
+        if let Some(update) = self.history.step_forward() {
+            self.apply(styles, current_view, update);
+        }
+        self.check_breaks()
+    }
+
+    /// Pauses the console if any of the tasks to break on have completed,
+    /// returning `true` if it did.
+    fn check_breaks(&mut self) -> bool {
+        if self.break_on_completion.is_empty() {
+            return false;
+        }
+        let tasks = &self.tasks_state;
+        let mut completed = false;
+        self.break_on_completion.retain(|id| match tasks.task(*id) {
+            Some(task) if task.borrow().is_completed() => {
+                completed = true;
+                false
+            }
+            Some(_) => true,
+            // The task is gone, so there's nothing left to break on.
+            None => false,
+        });
+        if completed {
+            tracing::debug!("task completed, pausing");
+            self.pause();
+        }
+        completed
+    }
+
+    fn apply(
+        &mut self,
+        styles: &view::Styles,
+        current_view: &view::ViewState,
+        update: proto::instrument::Update,
     ) {
         if let Some(now) = update.now.map(|v| v.try_into().unwrap()) {
             self.last_updated_at = Some(now);
+            self.watches.set_now(now);
+        }
+        self.applied_at = Some(Instant::now());
+
+        if let Some(process_info) = update.process_info {
+            self.process_info = Some(process_info.into());
+        }
+
+        if let (Some(runtime_update), Some(now)) = (update.runtime_update, self.last_updated_at) {
+            self.runtime
+                .get_or_insert_with(RuntimeState::default)
+                .update(now, runtime_update);
+        }
+
+        if let Some(ref task_update) = update.task_update {
+            self.dropped_events.tasks += task_update.dropped_events;
+        }
+        if let Some(ref resource_update) = update.resource_update {
+            self.dropped_events.resources += resource_update.dropped_events;
+        }
+        if let Some(ref async_op_update) = update.async_op_update {
+            self.dropped_events.async_ops += async_op_update.dropped_events;
         }
 
         let strings = &mut self.strings;
@@ -108,7 +290,7 @@ impl State {
             self.metas.extend(metas);
         }
 
-        if let Some(tasks_update) = update.task_update {
+        if let (Some(tasks_update), Some(now)) = (update.task_update, self.last_updated_at) {
             let visibility = if matches!(current_view, view::ViewState::TasksList) {
                 Visibility::Show
             } else {
@@ -120,10 +302,12 @@ impl State {
                 &self.metas,
                 tasks_update,
                 visibility,
+                now,
             )
         }
 
-        if let Some(resources_update) = update.resource_update {
+        if let (Some(resources_update), Some(now)) = (update.resource_update, self.last_updated_at)
+        {
             let visibility = if matches!(current_view, view::ViewState::ResourcesList) {
                 Visibility::Show
             } else {
@@ -135,8 +319,25 @@ impl State {
                 &self.metas,
                 resources_update,
                 visibility,
+                now,
             )
         }
+
+        if let Some(now) = self.last_updated_at {
+            // A task's busy time changes while it's being polled, and watches
+            // may need a condition to hold for a while before firing, so
+            // check tasks that weren't updated, too.
+            if self.watches.has_task_watches() {
+                self.tasks_state.relint();
+            }
+            self.watches.check(
+                now,
+                &self.tasks_state,
+                &self.resources_state,
+                self.runtime.as_ref(),
+            );
+            self.warning_history.check(now, &self.tasks_state);
+        }
     }
 
     pub(crate) fn retain_active(&mut self) {
@@ -144,9 +345,11 @@ impl State {
             return;
         }
 
-        if let (Some(now), Some(retain_for)) = (self.last_updated_at(), self.retain_for) {
-            self.tasks_state.retain_active(now, retain_for);
-            self.resources_state.retain_active(now, retain_for);
+        if let Some(now) = self.last_updated_at() {
+            self.tasks_state.retain_active(now, self.retention);
+            if let Some(retain_for) = self.retention.resources {
+                self.resources_state.retain_active(now, retain_for);
+            }
         }
 
         // After dropping idle tasks & resources, prune any interned strings
@@ -154,11 +357,51 @@ impl State {
         self.strings.retain_referenced();
     }
 
+    /// Removes every completed task and dropped resource immediately, rather
+    /// than once they have been retained for long enough, and checks the
+    /// remaining tasks for warnings again, so that a bug can be reproduced
+    /// from a clean baseline.
+    ///
+    /// Stepping back through the update history shows the removed tasks and
+    /// resources again.
+    pub(crate) fn clear_closed(&mut self) {
+        self.tasks_state.clear_completed();
+        self.resources_state.clear_dropped();
+        self.tasks_state.relint();
+        self.strings.retain_referenced();
+    }
+
+    /// Returns the log of warnings detected for tasks, including those which
+    /// have stopped firing.
+    pub(crate) fn warning_history(&self) -> &warnings::History {
+        &self.warning_history
+    }
+
+    /// Returns the watches on the process as a whole which are currently
+    /// firing.
+    pub(crate) fn alerts(&self) -> impl Iterator<Item = Alert<'_>> {
+        self.watches.alerts()
+    }
+
+    /// Returns the runtime's scheduler metrics, or `None` if the process
+    /// hasn't reported any.
+    pub(crate) fn runtime(&self) -> Option<&RuntimeState> {
+        self.runtime.as_ref()
+    }
+
+    pub(crate) fn process_info(&self) -> Option<&ProcessInfo> {
+        self.process_info.as_ref()
+    }
+
+    pub(crate) fn dropped_events(&self) -> DroppedEvents {
+        self.dropped_events
+    }
+
     pub(crate) fn task_details_ref(&self) -> DetailsRef {
         self.current_task_details.clone()
     }
 
-    pub(crate) fn tasks_state(&mut self) -> &TasksState {
+    pub(crate) fn tasks_state(&self) -> &TasksState {
         &self.tasks_state
     }
 
@@ -166,6 +409,10 @@ impl State {
         &mut self.tasks_state
     }
 
+    pub(crate) fn resources_state(&self) -> &ResourcesState {
+        &self.resources_state
+    }
+
     pub(crate) fn resources_state_mut(&mut self) -> &mut ResourcesState {
         &mut self.resources_state
     }
@@ -190,19 +437,221 @@ impl State {
         *self.current_task_details.borrow_mut() = None;
     }
 
+    pub(crate) fn resource_details_ref(&self) -> ResourceDetailsRef {
+        self.current_resource_details.clone()
+    }
+
+    pub(crate) fn update_resource_details(&mut self, update: proto::resources::ResourceDetails) {
+        if let Some(id) = update.resource_id {
+            let details = resources::Details {
+                resource_id: id.id,
+                async_ops: update.async_ops,
+                async_op_busy_times_histogram: update.async_op_busy_times_histogram.and_then(
+                    |data| {
+                        hdrhistogram::serialization::Deserializer::new()
+                            .deserialize(&mut Cursor::new(&data))
+                            .ok()
+                    },
+                ),
+            };
+
+            *self.current_resource_details.borrow_mut() = Some(details);
+        }
+    }
+
+    pub(crate) fn unset_resource_details(&mut self) {
+        *self.current_resource_details.borrow_mut() = None;
+    }
+
     // temporality methods
 
     pub(crate) fn pause(&mut self) {
         self.temporality = Temporality::Paused;
+        self.paused_at.get_or_insert_with(Instant::now);
     }
 
-    pub(crate) fn resume(&mut self) {
+    /// Resumes live updates, first applying any updates that were buffered
+    /// or stepped back over while paused.
+    pub(crate) fn resume(&mut self, styles: &view::Styles, current_view: &view::ViewState) {
         self.temporality = Temporality::Live;
+        self.paused_at = None;
+        self.window = None;
+        if let Some(update) = self.history.catch_up() {
+            self.apply(styles, current_view, update);
+        }
     }
 
     pub(crate) fn is_paused(&self) -> bool {
         matches!(self.temporality, Temporality::Paused)
     }
+
+    /// Toggles whether the completion of the task with the given ID pauses
+    /// the console.
+    pub(crate) fn toggle_break_on_completion(&mut self, task_id: u64) {
+        if !self.break_on_completion.remove(&task_id) {
+            self.break_on_completion.insert(task_id);
+        }
+    }
+
+    pub(crate) fn breaks_on_completion(&self, task_id: u64) -> bool {
+        self.break_on_completion.contains(&task_id)
+    }
+
+    /// Returns how long the console has been paused for, if it is paused.
+    pub(crate) fn paused_for(&self) -> Option<Duration> {
+        self.paused_at.map(|paused_at| paused_at.elapsed())
+    }
+
+    /// Applies the next buffered update while paused, returning `false` if
+    /// there is none.
+    pub(crate) fn step_forward(
+        &mut self,
+        styles: &view::Styles,
+        current_view: &view::ViewState,
+    ) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        match self.history.step_forward() {
+            Some(update) => {
+                self.apply(styles, current_view, update);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rolls back the most recently applied update while paused, returning
+    /// `false` if there is no earlier update to roll back to.
+    pub(crate) fn step_back(
+        &mut self,
+        styles: &view::Styles,
+        current_view: &view::ViewState,
+    ) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        match self.history.step_back() {
+            Some(snapshot) => {
+                // The state is rebuilt from the snapshot, since updates can't
+                // be unapplied.
+                self.rewind();
+                self.apply(styles, current_view, snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Jumps to the moment of the bookmark at `index` while paused, returning
+    /// `false` if there is no such bookmark.
+    pub(crate) fn jump_to_bookmark(
+        &mut self,
+        index: usize,
+        styles: &view::Styles,
+        current_view: &view::ViewState,
+    ) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        let update = match self.bookmarks.get(index) {
+            Some(bookmark) => bookmark.update,
+            None => return false,
+        };
+        match self.history.seek(update) {
+            Some(snapshot) => {
+                self.rewind();
+                self.apply(styles, current_view, snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Bookmarks the currently displayed update.
+    pub(crate) fn add_bookmark(&mut self, label: Option<String>) {
+        let (update, _) = self.history.position();
+        tracing::debug!(update, ?label, "adding bookmark");
+        self.bookmarks.push(Bookmark {
+            update,
+            at: self.last_updated_at,
+            label,
+        });
+    }
+
+    pub(crate) fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Starts a statistics window at the currently displayed update while
+    /// paused, or ends the current window if there is one.
+    ///
+    /// While a window is open, task statistics only cover the updates from
+    /// the start of the window to the currently displayed update.
+    pub(crate) fn toggle_window(&mut self) {
+        if self.window.take().is_some() || !self.is_paused() {
+            return;
+        }
+        if let Some(start) = self.last_updated_at {
+            let (start_update, _) = self.history.position();
+            self.window = Some(Window::new(start_update, start, self.tasks_state.tasks()));
+        }
+    }
+
+    pub(crate) fn window(&self) -> Option<&Window> {
+        self.window.as_ref()
+    }
+
+    /// Returns the names of the runtimes executing the current tasks, in
+    /// sorted order.
+    pub(crate) fn runtimes(&self) -> Vec<InternedStr> {
+        let runtimes = self
+            .tasks_state
+            .tasks()
+            .filter_map(|task| task.borrow().runtime().cloned())
+            .collect::<BTreeSet<_>>();
+        runtimes.into_iter().collect()
+    }
+
+    /// Returns the runtime whose tasks are listed, if only one runtime's
+    /// tasks are listed.
+    pub(crate) fn runtime_filter(&self) -> Option<&str> {
+        self.runtime_filter.as_deref()
+    }
+
+    /// Lists only the next runtime's tasks, or every task once each runtime
+    /// has been listed.
+    pub(crate) fn cycle_runtime_filter(&mut self) {
+        let runtimes = self.runtimes();
+        self.runtime_filter = match self.runtime_filter.take() {
+            Some(current) => runtimes.into_iter().find(|runtime| *runtime > current),
+            None => runtimes.into_iter().next(),
+        };
+    }
+
+    /// Lists every runtime's tasks again.
+    pub(crate) fn clear_runtime_filter(&mut self) {
+        self.runtime_filter = None;
+    }
+
+    /// Returns the number of the currently applied update, counting from 1,
+    /// and the total number of updates received.
+    pub(crate) fn update_position(&self) -> (usize, usize) {
+        self.history.position()
+    }
+
+    /// Discards all the data received from the instrumented process, so that
+    /// the state can be rebuilt from an earlier update, keeping the state's
+    /// configuration and the history derived from the updates so far.
+    fn rewind(&mut self) {
+        self.tasks_state.rewind();
+        self.resources_state.rewind();
+        self.metas.clear();
+        self.last_updated_at = None;
+        self.applied_at = None;
+        self.dropped_events = DroppedEvents::default();
+        self.runtime = None;
+    }
 }
 
 impl Default for Temporality {
@@ -225,6 +674,71 @@ impl Metadata {
     }
 }
 
+// === impl Retention ===
+
+impl Retention {
+    /// Returns how long `task` is displayed after it completes, depending
+    /// on how it completed.
+    pub(crate) fn task(&self, task: &Task) -> Option<Duration> {
+        if task.warnings().is_empty() {
+            self.tasks
+        } else {
+            self.warned_tasks
+        }
+    }
+
+    /// Returns the longest that any completed task is displayed for.
+    pub(crate) fn longest_task(&self) -> Option<Duration> {
+        self.tasks.zip(self.warned_tasks).map(|(a, b)| a.max(b))
+    }
+}
+
+// === impl DroppedEvents ===
+
+impl DroppedEvents {
+    pub(crate) fn total(&self) -> u64 {
+        self.tasks + self.resources + self.async_ops
+    }
+}
+
+// === impl ProcessInfo ===
+
+impl ProcessInfo {
+    /// Returns a short description of the process, such as `myapp (pid 1234)
+    /// on myhost`, suitable for displaying in the status bar.
+    pub(crate) fn summary(&self) -> String {
+        let mut summary = match self.executable {
+            Some(ref exe) => format!("{} (pid {})", exe, self.pid),
+            None => format!("pid {}", self.pid),
+        };
+        if let Some(ref hostname) = self.hostname {
+            summary.push_str(" on ");
+            summary.push_str(hostname);
+        }
+        summary
+    }
+}
+
+impl From<proto::instrument::ProcessInfo> for ProcessInfo {
+    fn from(pb: proto::instrument::ProcessInfo) -> Self {
+        fn nonempty(s: String) -> Option<String> {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s)
+            }
+        }
+
+        Self {
+            pid: pb.pid,
+            executable: nonempty(pb.executable),
+            hostname: nonempty(pb.hostname),
+            tokio_version: pb.tokio_version.and_then(nonempty),
+            subscriber_version: nonempty(pb.subscriber_version),
+        }
+    }
+}
+
 // === impl Field ===
 
 impl Field {
@@ -301,12 +815,57 @@ impl Field {
         Some(Self { name, value })
     }
 
+    /// Returns the styles of field names, the `=` delimiters, and field
+    /// values.
+    fn styles(styles: &view::Styles) -> (Style, Style, Style) {
+        (
+            styles.fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+            styles.fg(Color::LightBlue).add_modifier(Modifier::DIM),
+            styles.fg(Color::Yellow),
+        )
+    }
+
+    /// Formats fields one per line, indenting JSON values and
+    /// `Debug`-formatted values' nested structures across multiple lines.
+    pub(crate) fn make_pretty(styles: &view::Styles, fields: &[Field]) -> Vec<Spans<'static>> {
+        let (key_style, delim_style, val_style) = Self::styles(styles);
+        let plain = |lines: Vec<String>| {
+            lines
+                .into_iter()
+                .map(|line| Spans::from(Span::styled(line, val_style)))
+                .collect::<Vec<_>>()
+        };
+        let mut lines = Vec::new();
+        for field in fields {
+            let json = match field.value {
+                FieldValue::Str(ref value) | FieldValue::Debug(ref value) => {
+                    view::pretty::parse_json(value)
+                }
+                _ => None,
+            };
+            let mut value = match (json, &field.value) {
+                // Pretty-printing would reveal the structure of the value.
+                _ if styles.anonymize => plain(vec![styles.redact(field.value.to_string()).into()]),
+                (Some(json), _) => view::pretty::json(styles, &json),
+                (None, FieldValue::Debug(value)) => plain(view::pretty::debug(value)),
+                (None, value) => plain(vec![value.to_string()]),
+            }
+            .into_iter();
+            let mut first = vec![
+                Span::styled(field.name.to_string(), key_style),
+                Span::styled("=", delim_style),
+            ];
+            first.extend(value.next().map(|spans| spans.0).unwrap_or_default());
+            lines.push(Spans::from(first));
+            lines.extend(value);
+        }
+        lines
+    }
+
     fn make_formatted(styles: &view::Styles, fields: &mut Vec<Field>) -> Vec<Vec<Span<'static>>> {
         use std::cmp::Ordering;
 
-        let key_style = styles.fg(Color::LightBlue).add_modifier(Modifier::BOLD);
-        let delim_style = styles.fg(Color::LightBlue).add_modifier(Modifier::DIM);
-        let val_style = styles.fg(Color::Yellow);
+        let (key_style, delim_style, val_style) = Self::styles(styles);
 
         fields.sort_unstable_by(|left, right| {
             if &*left.name == Field::NAME {
@@ -359,6 +918,13 @@ impl fmt::Display for FieldValue {
             FieldValue::U64(v) => fmt::Display::fmt(v, f)?,
             FieldValue::Debug(v) => fmt::Display::fmt(v, f)?,
             FieldValue::I64(v) => fmt::Display::fmt(v, f)?,
+            FieldValue::F64(v) => fmt::Display::fmt(v, f)?,
+            FieldValue::U128(v) => fmt::Display::fmt(v, f)?,
+            FieldValue::I128(v) => fmt::Display::fmt(v, f)?,
+            FieldValue::Duration(v) => write!(f, "{:.3?}", v)?,
+            FieldValue::Timestamp(v) => {
+                fmt::Display::fmt(&humantime::format_rfc3339_millis(*v), f)?
+            }
         }
 
         Ok(())
@@ -377,6 +943,48 @@ impl FieldValue {
         }
     }
 
+    /// Compares two values for sorting: numbers numerically, booleans,
+    /// durations, and timestamps as themselves, and anything else by how it
+    /// is displayed.
+    pub(crate) fn sort_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => a.cmp(b),
+            (FieldValue::Duration(a), FieldValue::Duration(b)) => a.cmp(b),
+            (FieldValue::Timestamp(a), FieldValue::Timestamp(b)) => a.cmp(b),
+            _ => match (self.as_i128(), other.as_i128()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => match (self.as_number(), other.as_number()) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                    _ => self.to_string().cmp(&other.to_string()),
+                },
+            },
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match *self {
+            FieldValue::U64(v) => Some(v.into()),
+            FieldValue::I64(v) => Some(v.into()),
+            FieldValue::U128(v) => i128::try_from(v).ok(),
+            FieldValue::I128(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a number, if it is one, with durations in
+    /// seconds.
+    pub(crate) fn as_number(&self) -> Option<f64> {
+        match *self {
+            FieldValue::U64(v) => Some(v as f64),
+            FieldValue::I64(v) => Some(v as f64),
+            FieldValue::F64(v) => Some(v),
+            FieldValue::U128(v) => Some(v as f64),
+            FieldValue::I128(v) => Some(v as f64),
+            FieldValue::Duration(v) => Some(v.as_secs_f64()),
+            _ => None,
+        }
+    }
+
     /// If `self` is an empty string, returns `None`. Otherwise, returns `Some(self)`.
     fn ensure_nonempty(self) -> Option<Self> {
         match self {
@@ -393,6 +1001,19 @@ impl From<proto::field::Value> for FieldValue {
             proto::field::Value::StrVal(v) => Self::Str(v),
             proto::field::Value::I64Val(v) => Self::I64(v),
             proto::field::Value::U64Val(v) => Self::U64(v),
+            proto::field::Value::F64Val(v) => Self::F64(v),
+            proto::field::Value::U128Val(ref v) => Self::U128(v.into()),
+            proto::field::Value::I128Val(ref v) => Self::I128(v.into()),
+            proto::field::Value::DurationVal(v) => match v.try_into() {
+                Ok(v) => Self::Duration(v),
+                // Negative durations can't be represented, so display them
+                // as they were sent.
+                Err(v) => Self::Debug(format!("-{:.3?}", v)),
+            },
+            proto::field::Value::TimestampVal(v) => match v.clone().try_into() {
+                Ok(v) => Self::Timestamp(v),
+                Err(_) => Self::Debug(format!("{:?}", v)),
+            },
             proto::field::Value::DebugVal(v) => Self::Debug(v),
         }
     }
@@ -416,6 +1037,13 @@ fn truncate_registry_path(s: String) -> String {
     };
 }
 
+/// Formats a location without shortening paths in Cargo's registry, for
+/// displaying in full.
+fn format_full_location(loc: Option<&proto::Location>) -> String {
+    loc.map(|l| l.to_string())
+        .unwrap_or_else(|| "<unknown location>".to_string())
+}
+
 fn format_location(loc: Option<proto::Location>) -> String {
     loc.map(|mut l| {
         if let Some(file) = l.file.take() {
@@ -1,9 +1,14 @@
 use crate::intern::{self, InternedStr};
-use crate::state::{format_location, Field, Metadata, Visibility};
+use crate::state::{
+    format_full_location, format_location, Field, FieldValue, Metadata, Visibility,
+};
 use crate::view;
+use crate::warnings::Linter;
 use console_api as proto;
+use hdrhistogram::Histogram;
 use std::{
     cell::RefCell,
+    cmp::Reverse,
     collections::HashMap,
     convert::{TryFrom, TryInto},
     rc::{Rc, Weak},
@@ -18,6 +23,15 @@ use tui::{
 pub(crate) struct ResourcesState {
     resources: HashMap<u64, Rc<RefCell<Resource>>>,
     new_resources: Vec<ResourceRef>,
+    pub(crate) linters: Vec<Linter<Resource>>,
+    /// When the previous resource update was received.
+    last_update: Option<SystemTime>,
+    /// The rows of the resources which were known before the state was
+    /// rewound, which are reused as those resources are received again.
+    retired: HashMap<u64, Rc<RefCell<Resource>>>,
+    /// The time of the latest update applied before the state was rewound,
+    /// until updates up to then have been applied again.
+    rewound_from: Option<SystemTime>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -28,6 +42,7 @@ pub(crate) enum SortBy {
     ConcreteType = 2,
     Target = 3,
     Total = 4,
+    Runtime = 5,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -45,10 +60,30 @@ pub(crate) struct Resource {
     target: InternedStr,
     concrete_type: InternedStr,
     location: String,
+    /// The location, with paths in Cargo's registry left unshortened.
+    full_location: String,
+    /// The name of the runtime the resource was created on, if it was
+    /// registered with the subscriber.
+    runtime: Option<InternedStr>,
+    /// Currently active warnings for this resource.
+    warnings: Vec<Linter<Resource>>,
+    /// When the resource's attributes last changed, or when it was first
+    /// seen, if they haven't changed since.
+    active_at: SystemTime,
+    /// The time of the latest update, which the resource's warnings are
+    /// checked as of.
+    seen_at: SystemTime,
 }
 
 pub(crate) type ResourceRef = Weak<RefCell<Resource>>;
 
+#[derive(Debug, Default)]
+pub(crate) struct Details {
+    pub(crate) resource_id: u64,
+    pub(crate) async_ops: u64,
+    pub(crate) async_op_busy_times_histogram: Option<Histogram<u64>>,
+}
+
 #[derive(Debug)]
 pub(crate) struct Attribute {
     field: Field,
@@ -60,8 +95,6 @@ struct ResourceStats {
     created_at: SystemTime,
     dropped_at: Option<SystemTime>,
     total: Option<Duration>,
-    // this will be read in a subsequent PR
-    #[allow(dead_code)]
     attributes: Vec<Attribute>,
     formatted_attributes: Vec<Vec<Span<'static>>>,
 }
@@ -89,6 +122,9 @@ impl SortBy {
             }),
             Self::Total => resources
                 .sort_unstable_by_key(|resource| resource.upgrade().map(|r| r.borrow().total(now))),
+            Self::Runtime => resources.sort_unstable_by_key(|resource| {
+                resource.upgrade().map(|r| r.borrow().runtime.clone())
+            }),
         }
     }
 }
@@ -102,6 +138,7 @@ impl TryFrom<usize> for SortBy {
             idx if idx == Self::ConcreteType as usize => Ok(Self::ConcreteType),
             idx if idx == Self::Target as usize => Ok(Self::Target),
             idx if idx == Self::Total as usize => Ok(Self::Total),
+            idx if idx == Self::Runtime as usize => Ok(Self::Runtime),
             _ => Err(()),
         }
     }
@@ -118,6 +155,22 @@ impl ResourcesState {
         self.new_resources.drain(..)
     }
 
+    /// Returns the resource with the given ID, if it is currently known.
+    pub(crate) fn resource(&self, id: u64) -> Option<&RefCell<Resource>> {
+        self.resources.get(&id).map(|resource| &**resource)
+    }
+
+    /// Returns a reference to the resource with the given ID, if it is
+    /// currently known.
+    pub(crate) fn resource_ref(&self, id: u64) -> Option<ResourceRef> {
+        self.resources.get(&id).map(Rc::downgrade)
+    }
+
+    /// Returns every resource that is currently known.
+    pub(crate) fn resources(&self) -> impl Iterator<Item = &RefCell<Resource>> {
+        self.resources.values().map(|resource| &**resource)
+    }
+
     pub(crate) fn update_resources(
         &mut self,
         styles: &view::Styles,
@@ -125,6 +178,7 @@ impl ResourcesState {
         metas: &HashMap<u64, Metadata>,
         update: proto::resources::ResourceUpdate,
         visibility: Visibility,
+        now: SystemTime,
     ) {
         let mut stats_update = update.stats_update;
         let new_list = &mut self.new_resources;
@@ -132,6 +186,13 @@ impl ResourcesState {
             new_list.clear();
         }
 
+        self.last_update = Some(now);
+        if matches!(self.rewound_from, Some(until) if now > until) {
+            self.rewound_from = None;
+            self.retired.clear();
+        }
+        let retired = &mut self.retired;
+
         let new_resources = update.new_resources.into_iter().filter_map(|resource| {
             if resource.id.is_none() {
                 tracing::warn!(?resource, "skipping resource with no id");
@@ -161,9 +222,15 @@ impl ResourcesState {
 
             let id = resource.id?.id;
             let stats = ResourceStats::from_proto(stats_update.remove(&id)?, meta, styles, strings);
+            let full_location = format_full_location(resource.location.as_ref());
             let location = format_location(resource.location);
+            let runtime = if resource.runtime.is_empty() {
+                None
+            } else {
+                Some(strings.string(resource.runtime))
+            };
 
-            let resource = Resource {
+            let mut resource = Resource {
                 id,
                 kind,
                 stats,
@@ -171,8 +238,27 @@ impl ResourcesState {
                 concrete_type: strings.string(resource.concrete_type),
                 meta_id,
                 location,
+                full_location,
+                runtime,
+                warnings: Vec::new(),
+                active_at: now,
+                seen_at: now,
+            };
+            // Reuse the resource's row from before the state was rewound, if
+            // it had one, so that views of it carry over.
+            let resource = match retired.remove(&id) {
+                Some(row) => {
+                    {
+                        let mut row = row.borrow_mut();
+                        // The attributes may have changed again after this
+                        // update, but not before it.
+                        resource.active_at = row.active_at.min(now);
+                        *row = resource;
+                    }
+                    row
+                }
+                None => Rc::new(RefCell::new(resource)),
             };
-            let resource = Rc::new(RefCell::new(resource));
             new_list.push(Rc::downgrade(&resource));
             Some((id, resource))
         });
@@ -182,10 +268,65 @@ impl ResourcesState {
             if let Some(resource) = self.resources.get_mut(&id) {
                 let mut r = resource.borrow_mut();
                 if let Some(meta) = metas.get(&r.meta_id) {
-                    r.stats = ResourceStats::from_proto(stats, meta, styles, strings);
+                    let stats = ResourceStats::from_proto(stats, meta, styles, strings);
+                    if !stats.same_attributes(&r.stats) {
+                        r.active_at = now;
+                    }
+                    r.stats = stats;
                 }
             }
         }
+
+        // Whether a resource has been inactive for too long changes even if
+        // it wasn't updated, so check every resource.
+        let linters = &self.linters;
+        for resource in self.resources.values() {
+            let mut resource = resource.borrow_mut();
+            resource.seen_at = now;
+            resource.lint(linters);
+        }
+    }
+
+    pub(crate) fn warnings(&self) -> impl Iterator<Item = &Linter<Resource>> {
+        self.linters.iter().filter(|linter| linter.count() > 0)
+    }
+
+    /// Returns the resources which have the warning detected by `linter`, the
+    /// worst first.
+    pub(crate) fn warned_by(&self, linter: &Linter<Resource>) -> Vec<&RefCell<Resource>> {
+        let mut resources = self
+            .resources
+            .values()
+            .filter(|resource| {
+                let resource = resource.borrow();
+                resource.warnings().iter().any(|warning| warning.is(linter))
+            })
+            .map(|resource| &**resource)
+            .collect::<Vec<_>>();
+        resources.sort_by_cached_key(|resource| {
+            let resource = resource.borrow();
+            (Reverse(linter.severity(&resource)), resource.id())
+        });
+        resources
+    }
+
+    /// Discards the resources received from the instrumented process, so
+    /// that the state can be rebuilt from an earlier update.
+    ///
+    /// The resources' rows are reused as the resources are received again,
+    /// so that open views of them, and the tables' pins and marks, carry
+    /// over.
+    pub(crate) fn rewind(&mut self) {
+        self.retired.extend(self.resources.drain());
+        self.new_resources.clear();
+        self.rewound_from = self.rewound_from.max(self.last_update);
+        self.last_update = None;
+    }
+
+    /// Removes every dropped resource, however recently it was dropped.
+    pub(crate) fn clear_dropped(&mut self) {
+        self.resources
+            .retain(|_, resource| !resource.borrow().dropped());
     }
 
     pub(crate) fn retain_active(&mut self, now: SystemTime, retain_for: Duration) {
@@ -204,6 +345,20 @@ impl ResourcesState {
     }
 }
 
+impl Details {
+    pub(crate) fn resource_id(&self) -> u64 {
+        self.resource_id
+    }
+
+    pub(crate) fn async_ops(&self) -> u64 {
+        self.async_ops
+    }
+
+    pub(crate) fn async_op_busy_times_histogram(&self) -> Option<&Histogram<u64>> {
+        self.async_op_busy_times_histogram.as_ref()
+    }
+}
+
 impl Resource {
     pub(crate) fn id(&self) -> u64 {
         self.id
@@ -213,6 +368,12 @@ impl Resource {
         &self.target
     }
 
+    /// Returns the name of the runtime the resource was created on, if it
+    /// was registered with the subscriber.
+    pub(crate) fn runtime(&self) -> Option<&InternedStr> {
+        self.runtime.as_ref()
+    }
+
     pub(crate) fn concrete_type(&self) -> &str {
         &self.concrete_type
     }
@@ -224,6 +385,16 @@ impl Resource {
         }
     }
 
+    /// Returns the value of the attribute named `name`, if the resource has
+    /// one.
+    pub(crate) fn attribute(&self, name: &str) -> Option<&FieldValue> {
+        self.stats
+            .attributes
+            .iter()
+            .find(|attribute| &*attribute.field.name == name)
+            .map(|attribute| &attribute.field.value)
+    }
+
     pub(crate) fn formatted_attributes(&self) -> &[Vec<Span<'static>>] {
         &self.stats.formatted_attributes
     }
@@ -234,13 +405,52 @@ impl Resource {
             .unwrap_or_else(|| since.duration_since(self.stats.created_at).unwrap())
     }
 
+    pub(crate) fn created_at(&self) -> SystemTime {
+        self.stats.created_at
+    }
+
+    /// Returns when the resource was dropped, if it has been.
+    pub(crate) fn dropped_at(&self) -> Option<SystemTime> {
+        self.stats.dropped_at
+    }
+
     pub(crate) fn dropped(&self) -> bool {
         self.stats.total.is_some()
     }
 
+    /// Returns `true` if the resource is a timer, such as a `Sleep`.
+    pub(crate) fn is_timer(&self) -> bool {
+        self.kind == Kind::Timer
+    }
+
+    /// Returns how long the resource's attributes had gone unchanged as of
+    /// the latest update, counting from when it was first seen.
+    pub(crate) fn inactive_for(&self) -> Duration {
+        self.seen_at
+            .duration_since(self.active_at)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn warnings(&self) -> &[Linter<Resource>] {
+        &self.warnings[..]
+    }
+
+    fn lint(&mut self, linters: &[Linter<Resource>]) {
+        self.warnings.clear();
+        for lint in linters {
+            if let Some(warning) = lint.check(self) {
+                self.warnings.push(warning)
+            }
+        }
+    }
+
     pub(crate) fn location(&self) -> &str {
         &self.location
     }
+
+    pub(crate) fn full_location(&self) -> &str {
+        &self.full_location
+    }
 }
 
 impl ResourceStats {
@@ -281,6 +491,16 @@ impl ResourceStats {
             formatted_attributes,
         }
     }
+
+    /// Returns `true` if `self` has the same attributes, with the same
+    /// values, as `other`.
+    fn same_attributes(&self, other: &Self) -> bool {
+        self.attributes.len() == other.attributes.len()
+            && self.attributes.iter().zip(&other.attributes).all(|(a, b)| {
+                a.field.name == b.field.name
+                    && a.field.value.to_string() == b.field.value.to_string()
+            })
+    }
 }
 
 impl Kind {
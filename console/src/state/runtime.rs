@@ -0,0 +1,235 @@
+//! Metrics describing the instrumented process' runtime scheduler, for
+//! processes which report them, and the tasks and resources on each runtime.
+use crate::{
+    intern::InternedStr,
+    state::{
+        resources::ResourcesState,
+        tasks::{TaskState, TasksState},
+    },
+};
+use console_api as proto;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    convert::TryInto,
+    time::{Duration, SystemTime},
+};
+
+/// The runtime's current metrics, and recent history of each worker's.
+#[derive(Debug, Default)]
+pub(crate) struct RuntimeState {
+    /// The most recent metrics, and when they were received.
+    last: Option<(SystemTime, proto::runtime::RuntimeUpdate)>,
+    workers: Vec<Worker>,
+}
+
+/// A worker thread's activity since the previous update.
+#[derive(Debug, Default)]
+pub(crate) struct Worker {
+    /// The percentage of the interval the worker spent polling tasks.
+    pub(crate) busy_percent: f64,
+    pub(crate) polls_per_sec: f64,
+    pub(crate) steals_per_sec: f64,
+    pub(crate) parks_per_sec: f64,
+    pub(crate) noops_per_sec: f64,
+    pub(crate) overflows_per_sec: f64,
+    pub(crate) local_queue_depth: u64,
+    /// The worker's busy percentage for recent updates, oldest first.
+    pub(crate) busy_history: VecDeque<u64>,
+}
+
+/// The live tasks executing on one runtime, and the live resources created on
+/// it.
+#[derive(Debug, Default)]
+pub(crate) struct RuntimeSummary {
+    /// The runtime's name, or `None` for tasks which haven't been polled by
+    /// a thread registered with a runtime, and resources created on threads
+    /// which weren't.
+    pub(crate) name: Option<InternedStr>,
+    pub(crate) tasks: usize,
+    pub(crate) running: usize,
+    pub(crate) idle: usize,
+    pub(crate) polls: u64,
+    /// The total time the runtime's live tasks have spent being polled.
+    pub(crate) busy: Duration,
+    pub(crate) resources: usize,
+}
+
+/// A summary of how well the runtime is keeping up with its work.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Health {
+    pub(crate) workers: usize,
+    /// The mean percentage of time the workers spent polling tasks.
+    pub(crate) mean_busy_percent: f64,
+    /// The busiest worker's busy percentage.
+    pub(crate) max_busy_percent: f64,
+    /// The number of tasks waiting in the runtime's global queue.
+    pub(crate) injection_queue_depth: u64,
+    /// The number of tasks waiting in all of the workers' local queues.
+    pub(crate) local_queue_depth: u64,
+    pub(crate) blocking_threads: u64,
+}
+
+impl RuntimeState {
+    /// The number of updates of history to keep for each worker.
+    const HISTORY_LEN: usize = 120;
+
+    pub(crate) fn update(&mut self, now: SystemTime, update: proto::runtime::RuntimeUpdate) {
+        let previous = self
+            .last
+            .as_ref()
+            .and_then(|(at, previous)| Some((now.duration_since(*at).ok()?, previous)))
+            .filter(|(interval, _)| *interval > Duration::ZERO);
+
+        self.workers
+            .resize_with(update.workers.len(), Worker::default);
+        for (i, (worker, stats)) in self.workers.iter_mut().zip(&update.workers).enumerate() {
+            worker.local_queue_depth = stats.local_queue_depth;
+            let (interval, before) = match previous {
+                Some((interval, previous)) => match previous.workers.get(i) {
+                    Some(before) => (interval, before),
+                    None => continue,
+                },
+                None => continue,
+            };
+
+            let secs = interval.as_secs_f64();
+            let per_sec = |after: u64, before: u64| after.saturating_sub(before) as f64 / secs;
+            let busy = busy_duration(stats).saturating_sub(busy_duration(before));
+            worker.busy_percent = (busy.as_secs_f64() / secs * 100.0).min(100.0);
+            worker.polls_per_sec = per_sec(stats.poll_count, before.poll_count);
+            worker.steals_per_sec = per_sec(stats.steal_count, before.steal_count);
+            worker.parks_per_sec = per_sec(stats.park_count, before.park_count);
+            worker.noops_per_sec = per_sec(stats.noop_count, before.noop_count);
+            worker.overflows_per_sec = per_sec(stats.overflow_count, before.overflow_count);
+
+            if worker.busy_history.len() == Self::HISTORY_LEN {
+                worker.busy_history.pop_front();
+            }
+            worker
+                .busy_history
+                .push_back(worker.busy_percent.round() as u64);
+        }
+
+        self.last = Some((now, update));
+    }
+
+    /// Returns each worker's recent activity, indexed by worker.
+    pub(crate) fn workers(&self) -> &[Worker] {
+        &self.workers
+    }
+
+    /// Returns a summary of the runtime's health as of the latest update.
+    pub(crate) fn health(&self) -> Health {
+        let busy = self.workers.iter().map(|worker| worker.busy_percent);
+        let mean_busy_percent = if self.workers.is_empty() {
+            0.0
+        } else {
+            busy.clone().sum::<f64>() / self.workers.len() as f64
+        };
+        Health {
+            workers: self.workers.len(),
+            mean_busy_percent,
+            max_busy_percent: busy.fold(0.0, f64::max),
+            injection_queue_depth: self.injection_queue_depth(),
+            local_queue_depth: self
+                .workers
+                .iter()
+                .map(|worker| worker.local_queue_depth)
+                .sum(),
+            blocking_threads: self.blocking_threads(),
+        }
+    }
+
+    /// Returns the number of tasks waiting in the runtime's global queue.
+    pub(crate) fn injection_queue_depth(&self) -> u64 {
+        self.last
+            .as_ref()
+            .map_or(0, |(_, update)| update.injection_queue_depth)
+    }
+
+    /// Returns the number of threads the runtime has spawned to run blocking
+    /// operations.
+    pub(crate) fn blocking_threads(&self) -> u64 {
+        self.last
+            .as_ref()
+            .map_or(0, |(_, update)| update.blocking_threads)
+    }
+}
+
+/// Returns the live tasks and resources on each runtime, ordered by runtime
+/// name.
+pub(crate) fn summarize_runtimes(
+    tasks: &TasksState,
+    resources: &ResourcesState,
+    now: SystemTime,
+) -> Vec<RuntimeSummary> {
+    let mut runtimes = BTreeMap::<Option<InternedStr>, RuntimeSummary>::new();
+    for task in tasks.tasks() {
+        let task = task.borrow();
+        let state = task.state();
+        if state == TaskState::Completed {
+            continue;
+        }
+        let runtime = runtimes
+            .entry(task.runtime().cloned())
+            .or_insert_with(|| RuntimeSummary {
+                name: task.runtime().cloned(),
+                ..RuntimeSummary::default()
+            });
+        runtime.tasks += 1;
+        match state {
+            TaskState::Running => runtime.running += 1,
+            _ => runtime.idle += 1,
+        }
+        runtime.polls += task.total_polls();
+        runtime.busy += task.busy(now);
+    }
+    for resource in resources.resources() {
+        let resource = resource.borrow();
+        if resource.dropped() {
+            continue;
+        }
+        runtimes
+            .entry(resource.runtime().cloned())
+            .or_insert_with(|| RuntimeSummary {
+                name: resource.runtime().cloned(),
+                ..RuntimeSummary::default()
+            })
+            .resources += 1;
+    }
+    runtimes.into_values().collect()
+}
+
+fn busy_duration(stats: &proto::runtime::WorkerStats) -> Duration {
+    stats
+        .busy_duration
+        .clone()
+        .and_then(|busy| busy.try_into().ok())
+        .unwrap_or_default()
+}
+
+// === impl Health ===
+
+impl Health {
+    /// The mean busy percentage above which the workers are considered
+    /// saturated, so that newly woken tasks are likely to wait to be polled.
+    const SATURATED_PERCENT: f64 = 90.0;
+
+    /// Returns `true` if the workers are too busy to promptly poll tasks that
+    /// are woken.
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.mean_busy_percent >= Self::SATURATED_PERCENT
+    }
+
+    /// Returns a short description of the runtime's health, suitable for
+    /// displaying in the status bar.
+    pub(crate) fn summary(&self) -> String {
+        format!(
+            "{} workers {:.0}% busy (max {:.0}%), {} queued",
+            self.workers,
+            self.mean_busy_percent,
+            self.max_busy_percent,
+            self.injection_queue_depth + self.local_queue_depth,
+        )
+    }
+}
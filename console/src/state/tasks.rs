@@ -1,7 +1,12 @@
 use crate::{
+    computed::ComputedColumn,
     intern::{self, InternedStr},
-    state::{format_location, Field, Metadata, Visibility},
-    util::Percentage,
+    state::{
+        format_full_location, format_location,
+        window::{Totals, Window},
+        Field, FieldValue, Metadata, Retention, Visibility,
+    },
+    util::{self, Percentage},
     view,
     warnings::Linter,
 };
@@ -9,18 +14,63 @@ use console_api as proto;
 use hdrhistogram::Histogram;
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, VecDeque},
     convert::{TryFrom, TryInto},
+    mem,
     rc::{Rc, Weak},
     time::{Duration, SystemTime},
 };
-use tui::{style::Color, text::Span};
+use tui::{
+    style::{Color, Style},
+    text::Span,
+};
 
 #[derive(Default, Debug)]
 pub(crate) struct TasksState {
     tasks: HashMap<u64, Rc<RefCell<Task>>>,
     new_tasks: Vec<TaskRef>,
     pub(crate) linters: Vec<Linter<Task>>,
+    /// The rates at which tasks were spawned and completed during each recent
+    /// update interval, oldest first.
+    spawn_rates: VecDeque<SpawnRate>,
+    /// The number of tasks in each state as of each recent update, oldest
+    /// first.
+    counts: VecDeque<TaskCounts>,
+    /// When the previous task update was received.
+    last_update: Option<SystemTime>,
+    /// The time between the two latest task updates, once there have been
+    /// two.
+    interval: Option<Duration>,
+    /// How long a poll must take to be counted as slow, or `None` if slow
+    /// polls aren't counted.
+    pub(crate) slow_poll: Option<Duration>,
+    /// The rows of the tasks which were known before the state was rewound,
+    /// which are reused as those tasks are received again.
+    retired: HashMap<u64, Rc<RefCell<Task>>>,
+    /// The time of the latest update applied before the state was rewound,
+    /// until updates up to then have been applied again.
+    rewound_from: Option<SystemTime>,
+}
+
+/// The rates at which tasks were spawned and completed during an update
+/// interval.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct SpawnRate {
+    /// When the interval ended.
+    pub(crate) at: SystemTime,
+    /// The number of tasks spawned per second.
+    pub(crate) spawned: f64,
+    /// The number of tasks which completed per second.
+    pub(crate) completed: f64,
+}
+
+/// The number of tasks which were alive as of an update, by their state.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct TaskCounts {
+    pub(crate) at: SystemTime,
+    pub(crate) running: usize,
+    pub(crate) idle: usize,
 }
 
 #[derive(Debug, Default)]
@@ -31,18 +81,30 @@ pub(crate) struct Details {
 }
 
 #[derive(Debug, Copy, Clone)]
-#[repr(usize)]
 pub(crate) enum SortBy {
-    Warns = 0,
-    Tid = 1,
-    State = 2,
-    Name = 3,
-    Total = 4,
-    Busy = 5,
-    Idle = 6,
-    Polls = 7,
-    Target = 8,
-    Location = 9,
+    Warns,
+    Tid,
+    Parent,
+    Children,
+    State,
+    Name,
+    Total,
+    Busy,
+    Idle,
+    Scheduled,
+    Polls,
+    WakeDelay,
+    RecentPoll,
+    SlowPolls,
+    MaxPoll,
+    IdleFor,
+    Activity,
+    Runtime,
+    Target,
+    Location,
+    /// An extra column, by its index among the field columns followed by
+    /// the computed columns.
+    Extra(usize),
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -57,7 +119,7 @@ pub(crate) type TaskRef = Weak<RefCell<Task>>;
 #[derive(Debug)]
 pub(crate) struct Task {
     id: u64,
-    // fields: Vec<Field>,
+    fields: Vec<Field>,
     formatted_fields: Vec<Vec<Span<'static>>>,
     stats: TaskStats,
     target: InternedStr,
@@ -65,6 +127,77 @@ pub(crate) struct Task {
     /// Currently active warnings for this task.
     warnings: Vec<Linter<Task>>,
     location: String,
+    /// The spawn location, with paths in Cargo's registry left unshortened.
+    full_location: String,
+    /// The name of the runtime executing this task, once it has been polled
+    /// by a thread registered with a runtime.
+    runtime: Option<InternedStr>,
+    /// The ID of the task which spawned this task, if it was spawned by a
+    /// task.
+    parent_id: Option<u64>,
+    /// The number of tasks spawned by this task which have not completed.
+    live_children: usize,
+    /// The task's cumulative activity as of each update which changed it,
+    /// oldest first.
+    activity: VecDeque<Activity>,
+    /// The task's recent state transitions, oldest first.
+    transitions: VecDeque<Transition>,
+    /// The task's recent scheduling delays, oldest first.
+    scheduling_delays: VecDeque<SchedulingDelay>,
+    /// The sum of all the task's scheduling delays which are known.
+    scheduled: Duration,
+    /// An exponentially weighted moving average of the task's poll
+    /// durations, if it has been polled.
+    recent_poll: Option<Duration>,
+    /// The number of the task's polls known to have taken longer than the
+    /// slow poll threshold.
+    slow_polls: u64,
+    /// The task's longest poll whose duration is known.
+    max_poll: Option<MaxPoll>,
+}
+
+/// A task's longest poll.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct MaxPoll {
+    /// When the poll started.
+    pub(crate) at: SystemTime,
+    pub(crate) duration: Duration,
+}
+
+/// The time between a task being woken, or spawned, and its next poll
+/// starting, during which it was waiting for the runtime to poll it.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct SchedulingDelay {
+    /// When the poll started.
+    pub(crate) at: SystemTime,
+    pub(crate) delay: Duration,
+}
+
+/// A change in a task's state.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Transition {
+    pub(crate) at: SystemTime,
+    pub(crate) to: TaskState,
+    /// For transitions to `Running`, the number of polls which started since
+    /// the previous transition.
+    ///
+    /// Only the most recent poll in each update is known, so a task that was
+    /// polled several times between updates has a single transition to
+    /// `Running` for all of them.
+    pub(crate) polls: u64,
+}
+
+/// A task's cumulative activity as of an update, from which its activity
+/// between any two updates can be found.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Activity {
+    pub(crate) at: SystemTime,
+    pub(crate) polls: u64,
+    pub(crate) wakes: u64,
+    pub(crate) self_wakes: u64,
+    pub(crate) waker_clones: u64,
+    pub(crate) waker_drops: u64,
+    pub(crate) busy: Duration,
 }
 
 #[derive(Debug)]
@@ -91,14 +224,43 @@ struct TaskStats {
     last_wake: Option<SystemTime>,
     /// Total number of times the task has woken itself.
     self_wakes: u64,
+    /// The ID of the resource which most recently woke the task, if it was
+    /// woken by a resource.
+    last_woken_by: Option<u64>,
 }
 
 impl TasksState {
+    /// The number of update intervals to keep spawn rates for.
+    const SPAWN_RATES_LEN: usize = 600;
+    /// The number of updates to keep task counts for. This is longer than
+    /// the spawn rates are kept for, so that slowly growing counts stand
+    /// out.
+    const COUNTS_LEN: usize = 3600;
+    /// The number of intervals before the latest which a jump in the spawn
+    /// rate is measured against.
+    const SPAWN_RATE_BASELINE: usize = 10;
+
     /// Returns any new tasks that were added since the last task update.
     pub(crate) fn take_new_tasks(&mut self) -> impl Iterator<Item = TaskRef> + '_ {
         self.new_tasks.drain(..)
     }
 
+    /// Returns the task with the given ID, if it is currently known.
+    pub(crate) fn task(&self, id: u64) -> Option<&RefCell<Task>> {
+        self.tasks.get(&id).map(|task| &**task)
+    }
+
+    /// Returns a reference to the task with the given ID, if it is currently
+    /// known.
+    pub(crate) fn task_ref(&self, id: u64) -> Option<TaskRef> {
+        self.tasks.get(&id).map(Rc::downgrade)
+    }
+
+    /// Returns every task that is currently known.
+    pub(crate) fn tasks(&self) -> impl Iterator<Item = &RefCell<Task>> {
+        self.tasks.values().map(|task| &**task)
+    }
+
     pub(crate) fn update_tasks(
         &mut self,
         styles: &view::Styles,
@@ -106,14 +268,26 @@ impl TasksState {
         metas: &HashMap<u64, Metadata>,
         update: proto::tasks::TaskUpdate,
         visibility: Visibility,
+        now: SystemTime,
     ) {
+        let spawned = update.new_tasks.len();
+        let mut completed = 0;
         let mut stats_update = update.stats_update;
         let new_list = &mut self.new_tasks;
         if matches!(visibility, Visibility::Show) {
             new_list.clear();
         }
 
+        // Updates which were applied before the state was rewound already
+        // had their history recorded, so it isn't recorded twice.
+        let replaying = matches!(self.rewound_from, Some(until) if now <= until);
+        if !replaying {
+            self.rewound_from = None;
+            self.retired.clear();
+        }
+        let retired = &mut self.retired;
         let linters = &self.linters;
+        let slow_poll = self.slow_poll;
 
         let new_tasks = update.new_tasks.into_iter().filter_map(|mut task| {
             if task.id.is_none() {
@@ -151,21 +325,56 @@ impl TasksState {
 
             let formatted_fields = Field::make_formatted(styles, &mut fields);
             let id = task.id?.id;
-            let stats = stats_update.remove(&id)?.into();
+            let stats = stats_update.remove(&id)?;
+            let runtime = runtime_name(strings, &stats);
+            let full_location = format_full_location(task.location.as_ref());
             let location = format_location(task.location);
 
-            let mut task = Task {
+            let task = Task {
                 name,
                 id,
-                // fields,
+                fields,
                 formatted_fields,
-                stats,
+                stats: stats.into(),
                 target: meta.target.clone(),
                 warnings: Vec::new(),
                 location,
+                full_location,
+                runtime,
+                parent_id: task.parent_task_id.map(|id| id.id),
+                live_children: 0,
+                activity: VecDeque::new(),
+                transitions: VecDeque::new(),
+                scheduling_delays: VecDeque::new(),
+                scheduled: Duration::ZERO,
+                recent_poll: None,
+                slow_polls: 0,
+                max_poll: None,
+            };
+            // Reuse the task's row from before the state was rewound, if it
+            // had one, so that views of it carry over.
+            let task = match retired.remove(&id) {
+                Some(row) => {
+                    row.borrow_mut().replace(task);
+                    row
+                }
+                None => Rc::new(RefCell::new(task)),
             };
-            task.lint(linters);
-            let task = Rc::new(RefCell::new(task));
+            {
+                let mut task = task.borrow_mut();
+                if !replaying {
+                    task.record_activity(now);
+                    task.record_transitions(None);
+                    task.record_scheduling_delay(None);
+                    task.record_recent_poll(None);
+                    task.record_slow_polls(None, slow_poll);
+                    task.record_max_poll(None);
+                }
+                task.lint(linters);
+                if task.is_completed() {
+                    completed += 1;
+                }
+            }
             new_list.push(Rc::downgrade(&task));
             Some((id, task))
         });
@@ -174,15 +383,160 @@ impl TasksState {
             if let Some(task) = self.tasks.get_mut(&id) {
                 let mut task = task.borrow_mut();
                 tracing::trace!(?task, "processing stats update for");
-                task.stats = stats.into();
+                if task.runtime.is_none() {
+                    task.runtime = runtime_name(strings, &stats);
+                }
+                let previous = mem::replace(&mut task.stats, stats.into());
+                if !replaying {
+                    task.record_activity(now);
+                    task.record_transitions(Some(&previous));
+                    task.record_scheduling_delay(Some(&previous));
+                    task.record_recent_poll(Some(&previous));
+                    task.record_slow_polls(Some(&previous), slow_poll);
+                    task.record_max_poll(Some(&previous));
+                }
                 task.lint(linters);
+                if previous.total.is_none() && task.is_completed() {
+                    completed += 1;
+                }
+            }
+        }
+        self.count_children();
+        if replaying {
+            self.last_update = Some(now);
+        } else {
+            self.record_interval(spawned, completed, now);
+            self.record_counts(now);
+        }
+    }
+
+    /// Discards the tasks received from the instrumented process, so that
+    /// the state can be rebuilt from an earlier update.
+    ///
+    /// The history derived from the updates applied so far is kept, rather
+    /// than recorded again as they're reapplied, and the tasks' rows are
+    /// reused as the tasks are received again, so that open views of them,
+    /// and the tables' pins and marks, carry over.
+    pub(crate) fn rewind(&mut self) {
+        self.retired.extend(self.tasks.drain());
+        self.new_tasks.clear();
+        self.rewound_from = self.rewound_from.max(self.last_update);
+        self.last_update = None;
+    }
+
+    /// Records the number of tasks in each state as of the update at `now`.
+    fn record_counts(&mut self, now: SystemTime) {
+        let mut counts = TaskCounts {
+            at: now,
+            running: 0,
+            idle: 0,
+        };
+        for task in self.tasks.values() {
+            match task.borrow().state() {
+                TaskState::Running => counts.running += 1,
+                TaskState::Idle => counts.idle += 1,
+                TaskState::Completed => {}
             }
         }
+        if self.counts.len() == Self::COUNTS_LEN {
+            self.counts.pop_front();
+        }
+        self.counts.push_back(counts);
+    }
+
+    /// Returns the number of tasks in each state as of each recent update,
+    /// oldest first.
+    pub(crate) fn counts(&self) -> &VecDeque<TaskCounts> {
+        &self.counts
     }
 
-    pub(crate) fn retain_active(&mut self, now: SystemTime, retain_for: Duration) {
+    /// Records the interval since the previous task update, and the rates at
+    /// which `spawned` tasks were spawned and `completed` tasks completed
+    /// during it.
+    fn record_interval(&mut self, spawned: usize, completed: usize, now: SystemTime) {
+        // The first update lists every task spawned before the console
+        // connected, so there is no interval to measure it over.
+        let last_update = self.last_update.replace(now);
+        let elapsed = match last_update.and_then(|last| now.duration_since(last).ok()) {
+            Some(elapsed) if elapsed > Duration::ZERO => elapsed,
+            _ => return,
+        };
+        self.interval = Some(elapsed);
+        if self.spawn_rates.len() == Self::SPAWN_RATES_LEN {
+            self.spawn_rates.pop_front();
+        }
+        let secs = elapsed.as_secs_f64();
+        self.spawn_rates.push_back(SpawnRate {
+            at: now,
+            spawned: spawned as f64 / secs,
+            completed: completed as f64 / secs,
+        });
+    }
+
+    /// Returns the time between the two latest task updates, or one second,
+    /// the instrumented process' default publish interval, until there have
+    /// been two.
+    pub(crate) fn update_interval(&self) -> Duration {
+        self.interval.unwrap_or(Duration::from_secs(1))
+    }
+
+    /// Returns the number of tasks spawned per second during the latest
+    /// update interval, if there has been one.
+    pub(crate) fn spawn_rate(&self) -> Option<f64> {
+        self.spawn_rates.back().map(|rate| rate.spawned)
+    }
+
+    /// Returns the rates at which tasks were spawned and completed during
+    /// each recent update interval, oldest first.
+    pub(crate) fn spawn_rates(&self) -> &VecDeque<SpawnRate> {
+        &self.spawn_rates
+    }
+
+    /// Returns how many times the mean spawn rate of the preceding intervals
+    /// the latest spawn rate is, or `None` until there are enough intervals
+    /// to compare.
+    ///
+    /// The baseline is at least one task per second, so that a process which
+    /// was spawning almost no tasks doesn't appear to jump whenever it spawns
+    /// a few.
+    pub(crate) fn spawn_rate_jump(&self) -> Option<f64> {
+        let mut rates = self.spawn_rates.iter().rev().map(|rate| rate.spawned);
+        let latest = rates.next()?;
+        let previous = rates.take(Self::SPAWN_RATE_BASELINE).collect::<Vec<_>>();
+        if previous.len() < Self::SPAWN_RATE_BASELINE {
+            return None;
+        }
+        let baseline = previous.into_iter().sum::<f64>() / Self::SPAWN_RATE_BASELINE as f64;
+        Some(latest / baseline.max(1.0))
+    }
+
+    /// Counts each task's live children, since tasks may have been spawned or
+    /// completed.
+    fn count_children(&mut self) {
+        let mut children = HashMap::<u64, usize>::new();
+        for task in self.tasks.values() {
+            let task = task.borrow();
+            if task.is_completed() {
+                continue;
+            }
+            if let Some(parent_id) = task.parent_id {
+                *children.entry(parent_id).or_default() += 1;
+            }
+        }
+        for (id, task) in &self.tasks {
+            task.borrow_mut().live_children = children.get(id).copied().unwrap_or(0);
+        }
+    }
+
+    /// Removes the tasks which completed longer ago than `retention` retains
+    /// them for, depending on how they completed.
+    pub(crate) fn retain_active(&mut self, now: SystemTime, retention: Retention) {
         self.tasks.retain(|_, task| {
             let task = task.borrow();
+            let retain_for = match retention.task(&task) {
+                Some(retain_for) => retain_for,
+                None => return true,
+            };
 
             task.stats
                 .dropped_at
@@ -194,9 +548,42 @@ impl TasksState {
         })
     }
 
+    /// Removes every completed task, however recently it completed.
+    pub(crate) fn clear_completed(&mut self) {
+        self.tasks.retain(|_, task| !task.borrow().is_completed());
+    }
+
+    /// Checks every task against the linters again, even if it was not
+    /// updated.
+    pub(crate) fn relint(&mut self) {
+        let linters = &self.linters;
+        for task in self.tasks.values() {
+            task.borrow_mut().lint(linters);
+        }
+    }
+
     pub(crate) fn warnings(&self) -> impl Iterator<Item = &Linter<Task>> {
         self.linters.iter().filter(|linter| linter.count() > 0)
     }
+
+    /// Returns the tasks which have the warning detected by `linter`, the
+    /// worst first.
+    pub(crate) fn warned_by(&self, linter: &Linter<Task>) -> Vec<&RefCell<Task>> {
+        let mut tasks = self
+            .tasks
+            .values()
+            .filter(|task| {
+                let task = task.borrow();
+                task.warnings().iter().any(|warning| warning.is(linter))
+            })
+            .map(|task| &**task)
+            .collect::<Vec<_>>();
+        tasks.sort_by_cached_key(|task| {
+            let task = task.borrow();
+            (Reverse(linter.severity(&task)), task.id())
+        });
+        tasks
+    }
 }
 
 impl Details {
@@ -209,11 +596,390 @@ impl Details {
     }
 }
 
+impl TaskCounts {
+    /// Returns the number of tasks which hadn't completed.
+    pub(crate) fn live(&self) -> usize {
+        self.running + self.idle
+    }
+}
+
 impl Task {
+    /// The number of updates of activity to keep for each task.
+    const ACTIVITY_LEN: usize = 600;
+    /// The number of state transitions to keep for each task.
+    const TRANSITIONS_LEN: usize = 50;
+    /// The number of recent scheduling delays to keep.
+    const SCHEDULING_DELAYS_LEN: usize = 32;
+    /// The number of update intervals to count recent polls over.
+    pub(crate) const RECENT_POLLS_LEN: u32 = 30;
+    /// How much the mean poll duration in each update is weighted in the
+    /// moving average of poll durations, relative to the earlier updates.
+    const RECENT_POLL_WEIGHT: f64 = 0.25;
+
     pub(crate) fn id(&self) -> u64 {
         self.id
     }
 
+    /// Returns the ID of the task which spawned this task, if it was spawned
+    /// by a task.
+    pub(crate) fn parent_id(&self) -> Option<u64> {
+        self.parent_id
+    }
+
+    /// Returns the ID of the resource which most recently woke the task, if
+    /// it was woken by a resource.
+    pub(crate) fn last_woken_by(&self) -> Option<u64> {
+        self.stats.last_woken_by
+    }
+
+    /// Returns when the task was spawned.
+    pub(crate) fn created_at(&self) -> SystemTime {
+        self.stats.created_at
+    }
+
+    /// Returns when the task completed, if it has.
+    pub(crate) fn dropped_at(&self) -> Option<SystemTime> {
+        self.stats.dropped_at
+    }
+
+    /// Returns the task's recorded activity, oldest first.
+    pub(crate) fn activity(&self) -> &VecDeque<Activity> {
+        &self.activity
+    }
+
+    /// Returns the time the task spent being polled between `start` and
+    /// `end`, as far as can be told from its recorded activity.
+    ///
+    /// Since busy time is only reported at the end of each update, time spent
+    /// busy between two updates is assumed to be spread evenly between them.
+    pub(crate) fn busy_between(&self, start: SystemTime, end: SystemTime) -> Duration {
+        self.busy_at(end).saturating_sub(self.busy_at(start))
+    }
+
+    /// Returns `true` if the task was spawned, or its stats changed, in the
+    /// update at `now`.
+    pub(crate) fn changed_in(&self, now: SystemTime) -> bool {
+        matches!(self.activity.back(), Some(latest) if latest.at == now)
+    }
+
+    /// Returns the task's activity as of the update at `now`, and as of the
+    /// update before it, if the task changed in the update at `now`.
+    pub(crate) fn latest_change(&self, now: SystemTime) -> Option<(&Activity, &Activity)> {
+        let mut recent = self.activity.iter().rev();
+        let latest = recent.next().filter(|latest| latest.at == now)?;
+        Some((latest, recent.next()?))
+    }
+
+    /// Returns the task's cumulative activity as of `at`, or `None` if `at`
+    /// is before its earliest recorded activity.
+    pub(crate) fn activity_at(&self, at: SystemTime) -> Option<&Activity> {
+        self.activity
+            .iter()
+            .rev()
+            .find(|activity| activity.at <= at)
+    }
+
+    /// Returns the number of polls which started during each of the recent
+    /// update intervals of length `interval` until `now`, oldest first.
+    ///
+    /// Polls are counted as of each update, so each interval ends halfway
+    /// between updates, so that updates arriving a little early or late are
+    /// still counted in the right interval.
+    pub(crate) fn recent_polls(&self, now: SystemTime, interval: Duration) -> Vec<u64> {
+        let polls_at = |at: SystemTime| self.activity_at(at).map_or(0, |activity| activity.polls);
+        let end = now + interval / 2;
+        (0..Self::RECENT_POLLS_LEN)
+            .rev()
+            .map(|i| {
+                let end = end.checked_sub(interval * i);
+                let start = end.and_then(|end| end.checked_sub(interval));
+                match (start, end) {
+                    (Some(start), Some(end)) => polls_at(end).saturating_sub(polls_at(start)),
+                    _ => 0,
+                }
+            })
+            .collect()
+    }
+
+    fn busy_at(&self, at: SystemTime) -> Duration {
+        let after = self.activity.iter().position(|activity| activity.at > at);
+        let (before, after) = match after {
+            // Before the first update, the task had not been polled.
+            Some(0) => return Duration::ZERO,
+            Some(after) => (self.activity[after - 1], self.activity[after]),
+            None => {
+                return self
+                    .activity
+                    .back()
+                    .map_or(Duration::ZERO, |last| last.busy)
+            }
+        };
+        let span = after.at.duration_since(before.at).unwrap_or_default();
+        let elapsed = at.duration_since(before.at).unwrap_or_default();
+        let added = after.busy.saturating_sub(before.busy);
+        before.busy + added.mul_f64(elapsed.as_secs_f64() / span.as_secs_f64())
+    }
+
+    /// Returns the task's recent state transitions, oldest first.
+    pub(crate) fn transitions(&self) -> &VecDeque<Transition> {
+        &self.transitions
+    }
+
+    /// Records the state transitions between the `previous` stats and the
+    /// current stats.
+    fn record_transitions(&mut self, previous: Option<&TaskStats>) {
+        let stats = &self.stats;
+        let mut transitions = Vec::with_capacity(4);
+        if previous.is_none() {
+            transitions.push((stats.created_at, TaskState::Idle));
+        }
+        let previous_started = previous.and_then(|previous| previous.last_poll_started);
+        if let Some(started) = stats
+            .last_poll_started
+            .filter(|_| stats.last_poll_started > previous_started)
+        {
+            transitions.push((started, TaskState::Running));
+        }
+        let previous_ended = previous.and_then(|previous| previous.last_poll_ended);
+        if let Some(ended) = stats
+            .last_poll_ended
+            .filter(|_| stats.last_poll_ended > previous_ended)
+        {
+            transitions.push((ended, TaskState::Idle));
+        }
+        if let (Some(dropped), None) = (stats.dropped_at, previous.and_then(|p| p.dropped_at)) {
+            transitions.push((dropped, TaskState::Completed));
+        }
+        transitions.sort_by_key(|&(at, _)| at);
+
+        let mut polls = stats
+            .polls
+            .saturating_sub(previous.map_or(0, |previous| previous.polls));
+        for (at, to) in transitions {
+            if self.transitions.back().map(|last| last.to) == Some(to) {
+                continue;
+            }
+            if self.transitions.len() == Self::TRANSITIONS_LEN {
+                self.transitions.pop_front();
+            }
+            let transition_polls = if to == TaskState::Running {
+                mem::take(&mut polls)
+            } else {
+                0
+            };
+            self.transitions.push_back(Transition {
+                at,
+                to,
+                polls: transition_polls,
+            });
+        }
+    }
+
+    /// Returns the task's recent scheduling delays, oldest first.
+    pub(crate) fn scheduling_delays(&self) -> &VecDeque<SchedulingDelay> {
+        &self.scheduling_delays
+    }
+
+    /// Returns the total time the task has spent scheduled: woken, or
+    /// spawned, but waiting for the runtime to poll it.
+    ///
+    /// Only the scheduling delay of the most recent poll in each update is
+    /// known, so this is a lower bound for tasks polled several times
+    /// between updates.
+    pub(crate) fn scheduled(&self) -> Duration {
+        self.scheduled
+    }
+
+    /// Returns a moving average of the task's poll durations, weighted
+    /// towards its most recent polls, or `None` if it hasn't been polled.
+    ///
+    /// Unlike the mean over the task's lifetime, this falls again soon after
+    /// a few slow polls, so that tasks which are slow now stand out from
+    /// tasks which were slow long ago.
+    pub(crate) fn recent_poll(&self) -> Option<Duration> {
+        self.recent_poll
+    }
+
+    /// Folds the mean duration of the polls which completed since the
+    /// `previous` stats into the moving average of poll durations.
+    fn record_recent_poll(&mut self, previous: Option<&TaskStats>) {
+        let (polls, busy) = previous.map_or((0, Duration::ZERO), |previous| {
+            (previous.polls, previous.busy)
+        });
+        let polls = self.stats.polls.saturating_sub(polls);
+        if polls == 0 {
+            return;
+        }
+        let mean = self.stats.busy.saturating_sub(busy).as_secs_f64() / polls as f64;
+        let average = match self.recent_poll {
+            Some(average) => {
+                let weight = Self::RECENT_POLL_WEIGHT;
+                average.as_secs_f64() * (1.0 - weight) + mean * weight
+            }
+            None => mean,
+        };
+        self.recent_poll = Some(Duration::from_secs_f64(average));
+    }
+
+    /// Returns the number of the task's polls known to have taken longer than
+    /// the slow poll threshold.
+    ///
+    /// This is a lower bound, since only the duration of the most recent poll
+    /// in each update is known.
+    pub(crate) fn slow_polls(&self) -> u64 {
+        self.slow_polls
+    }
+
+    /// Counts the polls which completed since the `previous` stats and took
+    /// longer than `threshold`.
+    ///
+    /// Only the duration of the most recent poll in each update is known. If
+    /// the task was polled more than once since the previous update, its
+    /// other polls are counted as a single slow poll when they took longer
+    /// than `threshold` on average, so a task whose slow polls are often
+    /// followed by fast ones before the next update is undercounted.
+    fn record_slow_polls(&mut self, previous: Option<&TaskStats>, threshold: Option<Duration>) {
+        let threshold = match threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        let (polls, busy) = previous.map_or((0, Duration::ZERO), |previous| {
+            (previous.polls, previous.busy)
+        });
+        let mut polls = self.stats.polls.saturating_sub(polls);
+        let mut busy = self.stats.busy.saturating_sub(busy);
+        if let Some((_, latest)) = self.completed_poll(previous) {
+            if latest > threshold {
+                self.slow_polls += 1;
+            }
+            polls = polls.saturating_sub(1);
+            busy = busy.saturating_sub(latest);
+        }
+        if polls > 0 && busy.as_secs_f64() > threshold.as_secs_f64() * polls as f64 {
+            self.slow_polls += 1;
+        }
+    }
+
+    /// Returns the task's longest poll, if the duration of any of its polls
+    /// is known.
+    ///
+    /// Only the duration of the most recent poll in each update is known, so
+    /// a long poll which was followed by another poll before the next update
+    /// is missed.
+    pub(crate) fn max_poll(&self) -> Option<MaxPoll> {
+        self.max_poll
+    }
+
+    /// Records the task's latest poll as its longest, if it ended since the
+    /// `previous` stats and took longer than the longest poll so far.
+    fn record_max_poll(&mut self, previous: Option<&TaskStats>) {
+        let (at, duration) = match self.completed_poll(previous) {
+            Some(poll) => poll,
+            None => return,
+        };
+        match self.max_poll {
+            Some(max) if max.duration >= duration => {}
+            _ => self.max_poll = Some(MaxPoll { at, duration }),
+        }
+    }
+
+    /// Returns when the task's latest poll started and how long it took, if
+    /// it ended since the `previous` stats.
+    fn completed_poll(&self, previous: Option<&TaskStats>) -> Option<(SystemTime, Duration)> {
+        let previous_ended = previous.and_then(|previous| previous.last_poll_ended);
+        let started = self.stats.last_poll_started?;
+        let ended = self
+            .stats
+            .last_poll_ended
+            .filter(|&ended| ended >= started && Some(ended) > previous_ended)?;
+        Some((started, ended.duration_since(started).ok()?))
+    }
+
+    /// Returns the mean of the task's recent scheduling delays, or `None` if
+    /// none have been recorded.
+    pub(crate) fn mean_scheduling_delay(&self) -> Option<Duration> {
+        self.mean_scheduling_delay_since(SystemTime::UNIX_EPOCH)
+    }
+
+    /// Returns the mean scheduling delay of the task's recent polls which
+    /// started at or after `since`, or `None` if none were recorded.
+    pub(crate) fn mean_scheduling_delay_since(&self, since: SystemTime) -> Option<Duration> {
+        let delays = self
+            .scheduling_delays
+            .iter()
+            .filter(|delay| delay.at >= since)
+            .map(|delay| delay.delay);
+        let (count, total) = delays.fold((0u32, Duration::ZERO), |(count, total), delay| {
+            (count + 1, total + delay)
+        });
+        (count > 0).then(|| total / count)
+    }
+
+    /// Returns when the task was last updated.
+    pub(crate) fn updated_at(&self) -> Option<SystemTime> {
+        self.activity.back().map(|activity| activity.at)
+    }
+
+    /// Records the delay before the task's latest poll started, if it started
+    /// since the `previous` stats.
+    ///
+    /// Only the most recent poll and wake in each update are known, so a
+    /// delay is recorded only for the latest poll, and only if the wake which
+    /// scheduled it is known.
+    fn record_scheduling_delay(&mut self, previous: Option<&TaskStats>) {
+        let stats = &self.stats;
+        let previous_started = previous.and_then(|previous| previous.last_poll_started);
+        let started = match stats
+            .last_poll_started
+            .filter(|_| stats.last_poll_started > previous_started)
+        {
+            Some(started) => started,
+            None => return,
+        };
+        let scheduled_at = if stats.polls == 1 {
+            // The first poll was scheduled when the task was spawned.
+            Some(stats.created_at)
+        } else {
+            // Wakes before the previous poll started scheduled that poll
+            // instead.
+            stats.last_wake.filter(|&wake| {
+                wake <= started && matches!(previous_started, Some(previous) if wake > previous)
+            })
+        };
+        let delay = match scheduled_at.and_then(|at| started.duration_since(at).ok()) {
+            Some(delay) => delay,
+            None => return,
+        };
+
+        self.scheduled += delay;
+        if self.scheduling_delays.len() == Self::SCHEDULING_DELAYS_LEN {
+            self.scheduling_delays.pop_front();
+        }
+        self.scheduling_delays
+            .push_back(SchedulingDelay { at: started, delay });
+    }
+
+    fn record_activity(&mut self, now: SystemTime) {
+        if self.activity.len() == Self::ACTIVITY_LEN {
+            self.activity.pop_front();
+        }
+        self.activity.push_back(Activity {
+            at: now,
+            polls: self.stats.polls,
+            wakes: self.stats.wakes,
+            self_wakes: self.stats.self_wakes,
+            waker_clones: self.stats.waker_clones,
+            waker_drops: self.stats.waker_drops,
+            busy: self.stats.busy,
+        });
+    }
+
+    /// Returns the number of tasks spawned by this task which have not
+    /// completed.
+    pub(crate) fn live_children(&self) -> usize {
+        self.live_children
+    }
+
     pub(crate) fn target(&self) -> &str {
         &self.target
     }
@@ -222,6 +988,20 @@ impl Task {
         self.name.as_ref().map(AsRef::as_ref)
     }
 
+    /// Returns the task's fields, other than its name, in the order they
+    /// are displayed.
+    pub(crate) fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Returns the value of the field named `name`, if the task has one.
+    pub(crate) fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|field| &*field.name == name)
+            .map(|field| &field.value)
+    }
+
     pub(crate) fn formatted_fields(&self) -> &[Vec<Span<'static>>] {
         &self.formatted_fields
     }
@@ -285,6 +1065,24 @@ impl Task {
         now.duration_since(self.last_wake()?).ok()
     }
 
+    /// Returns how long the task has been idle, relative to the given `now`
+    /// timestamp, or `None` if it is running or has completed.
+    ///
+    /// A task which has never been polled has been idle since it was spawned.
+    pub(crate) fn idle_for(&self, now: SystemTime) -> Option<Duration> {
+        if self.state() != TaskState::Idle {
+            return None;
+        }
+        let since = self.stats.last_poll_ended.unwrap_or(self.stats.created_at);
+        now.duration_since(since).ok()
+    }
+
+    /// Returns when the task's most recent poll started, if it has been
+    /// polled.
+    pub(crate) fn last_poll_started(&self) -> Option<SystemTime> {
+        self.stats.last_poll_started
+    }
+
     pub(crate) fn last_wake(&self) -> Option<SystemTime> {
         self.stats.last_wake
     }
@@ -332,6 +1130,19 @@ impl Task {
         &self.warnings[..]
     }
 
+    /// Replaces the task's data with `task`'s, keeping the history derived
+    /// from the updates this task was built from.
+    fn replace(&mut self, mut task: Task) {
+        task.activity = mem::take(&mut self.activity);
+        task.transitions = mem::take(&mut self.transitions);
+        task.scheduling_delays = mem::take(&mut self.scheduling_delays);
+        task.scheduled = self.scheduled;
+        task.recent_poll = self.recent_poll;
+        task.slow_polls = self.slow_polls;
+        task.max_poll = self.max_poll;
+        *self = task;
+    }
+
     fn lint(&mut self, linters: &[Linter<Task>]) {
         self.warnings.clear();
         for lint in linters {
@@ -346,6 +1157,25 @@ impl Task {
     pub(crate) fn location(&self) -> &str {
         &self.location
     }
+
+    pub(crate) fn full_location(&self) -> &str {
+        &self.full_location
+    }
+
+    /// Returns the name of the runtime executing this task, if it has been
+    /// polled by a thread registered with a runtime.
+    pub(crate) fn runtime(&self) -> Option<&InternedStr> {
+        self.runtime.as_ref()
+    }
+}
+
+/// Returns the interned name of the runtime in a task's stats, if it has one.
+fn runtime_name(strings: &mut intern::Strings, stats: &proto::tasks::Stats) -> Option<InternedStr> {
+    if stats.runtime.is_empty() {
+        None
+    } else {
+        Some(strings.string(stats.runtime.clone()))
+    }
 }
 
 impl From<proto::tasks::Stats> for TaskStats {
@@ -384,6 +1214,7 @@ impl From<proto::tasks::Stats> for TaskStats {
             waker_drops: pb.waker_drops,
             last_wake: pb.last_wake.map(|v| v.try_into().unwrap()),
             self_wakes: pb.self_wakes,
+            last_woken_by: pb.last_woken_by.map(|id| id.id),
         }
     }
 }
@@ -395,41 +1226,129 @@ impl Default for SortBy {
 }
 
 impl SortBy {
-    pub fn sort(&self, now: SystemTime, tasks: &mut Vec<Weak<RefCell<Task>>>) {
+    /// The column of the first extra column, which follow the built-in
+    /// columns.
+    const EXTRA_COLUMNS: usize = 20;
+
+    /// Sorts `tasks`, looking up the extra column sorted by, if any, in
+    /// `field_columns` and `computed_columns`. Recent activity is counted
+    /// over update intervals of length `interval`.
+    pub fn sort(
+        &self,
+        now: SystemTime,
+        window: Option<&Window>,
+        interval: Duration,
+        field_columns: &[String],
+        computed_columns: &[ComputedColumn],
+        tasks: &mut Vec<Weak<RefCell<Task>>>,
+    ) {
+        let totals = |task: &Weak<RefCell<Task>>| {
+            task.upgrade()
+                .map(|t| Totals::in_window(&t.borrow(), now, window))
+        };
         match self {
             Self::Tid => tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().id)),
-            Self::Name => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().name.clone()))
+            Self::Parent => {
+                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().parent_id))
             }
+            Self::Children => {
+                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().live_children))
+            }
+            Self::Name => sort_by_task(tasks, |a, b| match (&a.name, &b.name) {
+                (Some(a), Some(b)) => util::natural_cmp(a, b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            }),
             Self::State => {
                 tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().state()))
             }
             Self::Warns => tasks
                 .sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().warnings().len())),
-            Self::Total => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().total(now)))
-            }
-            Self::Idle => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().idle(now)))
+            Self::Total => tasks.sort_unstable_by_key(|task| totals(task).map(|t| t.total)),
+            Self::Idle => tasks.sort_unstable_by_key(|task| totals(task).map(|t| t.idle)),
+            Self::Busy => tasks.sort_unstable_by_key(|task| totals(task).map(|t| t.busy)),
+            Self::Scheduled => tasks.sort_unstable_by_key(|task| totals(task).map(|t| t.scheduled)),
+            Self::Polls => tasks.sort_unstable_by_key(|task| totals(task).map(|t| t.polls)),
+            Self::WakeDelay => tasks.sort_unstable_by_key(|task| {
+                task.upgrade().map(|t| t.borrow().mean_scheduling_delay())
+            }),
+            Self::RecentPoll => {
+                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().recent_poll))
             }
-            Self::Busy => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().busy(now)))
+            Self::SlowPolls => {
+                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().slow_polls))
             }
-            Self::Polls => {
-                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().stats.polls))
+            Self::MaxPoll => tasks.sort_unstable_by_key(|task| {
+                task.upgrade()
+                    .map(|t| t.borrow().max_poll.map(|max| max.duration))
+            }),
+            Self::IdleFor => {
+                tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().idle_for(now)))
             }
+            Self::Activity => tasks.sort_by_cached_key(|task| {
+                task.upgrade()
+                    .map(|t| t.borrow().recent_polls(now, interval).iter().sum::<u64>())
+            }),
+            Self::Runtime => tasks
+                .sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().runtime.clone())),
             Self::Target => {
                 tasks.sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().target.clone()))
             }
-            Self::Location => tasks
-                .sort_unstable_by_key(|task| task.upgrade().map(|t| t.borrow().location.clone())),
+            Self::Location => {
+                sort_by_task(tasks, |a, b| util::natural_cmp(&a.location, &b.location))
+            }
+            Self::Extra(idx) => {
+                if let Some(name) = field_columns.get(*idx) {
+                    // Tasks without the field sort before those with it.
+                    sort_by_task(tasks, |a, b| match (a.field(name), b.field(name)) {
+                        (Some(a), Some(b)) => a.sort_cmp(b),
+                        (a, b) => a.is_some().cmp(&b.is_some()),
+                    })
+                } else if let Some(column) = computed_columns.get(*idx - field_columns.len()) {
+                    let value = |task: &Weak<RefCell<Task>>| {
+                        task.upgrade()
+                            .and_then(|t| column.eval(&t.borrow(), now, window))
+                    };
+                    tasks.sort_by(|a, b| ComputedColumn::compare(value(a), value(b)))
+                }
+            }
         }
     }
 }
 
+/// Sorts `tasks` by comparing the tasks they refer to with `cmp`. Tasks
+/// which no longer exist sort first.
+fn sort_by_task(tasks: &mut [Weak<RefCell<Task>>], cmp: impl Fn(&Task, &Task) -> Ordering) {
+    tasks.sort_by(|a, b| match (a.upgrade(), b.upgrade()) {
+        (Some(a), Some(b)) => cmp(&a.borrow(), &b.borrow()),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    })
+}
+
 impl view::SortBy for SortBy {
     fn as_column(&self) -> usize {
-        *self as usize
+        match self {
+            Self::Warns => 0,
+            Self::Tid => 1,
+            Self::Parent => 2,
+            Self::Children => 3,
+            Self::State => 4,
+            Self::Name => 5,
+            Self::Total => 6,
+            Self::Busy => 7,
+            Self::Idle => 8,
+            Self::Scheduled => 9,
+            Self::Polls => 10,
+            Self::WakeDelay => 11,
+            Self::RecentPoll => 12,
+            Self::SlowPolls => 13,
+            Self::MaxPoll => 14,
+            Self::IdleFor => 15,
+            Self::Activity => 16,
+            Self::Runtime => 17,
+            Self::Target => 18,
+            Self::Location => 19,
+            Self::Extra(idx) => Self::EXTRA_COLUMNS + idx,
+        }
     }
 }
 
@@ -437,17 +1356,27 @@ impl TryFrom<usize> for SortBy {
     type Error = ();
     fn try_from(idx: usize) -> Result<Self, Self::Error> {
         match idx {
-            idx if idx == Self::Tid as usize => Ok(Self::Tid),
-            idx if idx == Self::State as usize => Ok(Self::State),
-            idx if idx == Self::Warns as usize => Ok(Self::Warns),
-            idx if idx == Self::Name as usize => Ok(Self::Name),
-            idx if idx == Self::Total as usize => Ok(Self::Total),
-            idx if idx == Self::Busy as usize => Ok(Self::Busy),
-            idx if idx == Self::Idle as usize => Ok(Self::Idle),
-            idx if idx == Self::Polls as usize => Ok(Self::Polls),
-            idx if idx == Self::Target as usize => Ok(Self::Target),
-            idx if idx == Self::Location as usize => Ok(Self::Location),
-            _ => Err(()),
+            0 => Ok(Self::Warns),
+            1 => Ok(Self::Tid),
+            2 => Ok(Self::Parent),
+            3 => Ok(Self::Children),
+            4 => Ok(Self::State),
+            5 => Ok(Self::Name),
+            6 => Ok(Self::Total),
+            7 => Ok(Self::Busy),
+            8 => Ok(Self::Idle),
+            9 => Ok(Self::Scheduled),
+            10 => Ok(Self::Polls),
+            11 => Ok(Self::WakeDelay),
+            12 => Ok(Self::RecentPoll),
+            13 => Ok(Self::SlowPolls),
+            14 => Ok(Self::MaxPoll),
+            15 => Ok(Self::IdleFor),
+            16 => Ok(Self::Activity),
+            17 => Ok(Self::Runtime),
+            18 => Ok(Self::Target),
+            19 => Ok(Self::Location),
+            idx => Ok(Self::Extra(idx - Self::EXTRA_COLUMNS)),
         }
     }
 }
@@ -457,13 +1386,21 @@ impl TaskState {
         const RUNNING_UTF8: &str = "\u{25B6}";
         const IDLE_UTF8: &str = "\u{23F8}";
         const COMPLETED_UTF8: &str = "\u{23F9}";
+        let text = match self {
+            Self::Running => styles.if_utf8(RUNNING_UTF8, "BUSY"),
+            Self::Idle => styles.if_utf8(IDLE_UTF8, "IDLE"),
+            Self::Completed => styles.if_utf8(COMPLETED_UTF8, "DONE"),
+        };
+        Span::styled(text, self.style(styles))
+    }
+
+    /// Returns the style that the state, and anything describing a task in
+    /// the state, is colored with.
+    pub(crate) fn style(self, styles: &crate::view::Styles) -> Style {
         match self {
-            Self::Running => Span::styled(
-                styles.if_utf8(RUNNING_UTF8, "BUSY"),
-                styles.fg(Color::Green),
-            ),
-            Self::Idle => Span::raw(styles.if_utf8(IDLE_UTF8, "IDLE")),
-            Self::Completed => Span::raw(styles.if_utf8(COMPLETED_UTF8, "DONE")),
+            Self::Running => styles.state(Color::Green),
+            Self::Idle => styles.state(Color::Yellow),
+            Self::Completed => styles.state(Color::Gray),
         }
     }
 }
@@ -0,0 +1,98 @@
+//! Statistics for a window of the update history, rather than for the whole
+//! lifetime of each task.
+use crate::state::tasks::Task;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+/// A window of the update history, starting at an update chosen while paused
+/// and ending at the currently displayed update.
+#[derive(Debug)]
+pub(crate) struct Window {
+    /// The number of the update the window starts at.
+    pub(crate) start_update: usize,
+    /// The time of the update the window starts at.
+    pub(crate) start: SystemTime,
+    /// Each task's totals as of the start of the window.
+    baseline: HashMap<u64, Totals>,
+}
+
+/// A task's cumulative statistics.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct Totals {
+    pub(crate) total: Duration,
+    pub(crate) busy: Duration,
+    pub(crate) idle: Duration,
+    /// The time spent waiting to be polled after being woken.
+    pub(crate) scheduled: Duration,
+    pub(crate) polls: u64,
+    pub(crate) wakes: u64,
+}
+
+impl Window {
+    pub(crate) fn new<'a>(
+        start_update: usize,
+        start: SystemTime,
+        tasks: impl IntoIterator<Item = &'a RefCell<Task>>,
+    ) -> Self {
+        let baseline = tasks
+            .into_iter()
+            .map(|task| {
+                let task = task.borrow();
+                (task.id(), Totals::of(&task, start))
+            })
+            .collect();
+        Self {
+            start_update,
+            start,
+            baseline,
+        }
+    }
+
+    /// Returns the statistics for `task` from the start of the window until
+    /// `now`.
+    ///
+    /// Tasks spawned during the window count from when they were spawned.
+    pub(crate) fn totals(&self, task: &Task, now: SystemTime) -> Totals {
+        let totals = Totals::of(task, now);
+        let baseline = match self.baseline.get(&task.id()) {
+            Some(baseline) => baseline,
+            None => return totals,
+        };
+        let total = totals.total.saturating_sub(baseline.total);
+        let busy = totals.busy.saturating_sub(baseline.busy);
+        Totals {
+            total,
+            busy,
+            idle: total.saturating_sub(busy),
+            scheduled: totals.scheduled.saturating_sub(baseline.scheduled),
+            polls: totals.polls.saturating_sub(baseline.polls),
+            wakes: totals.wakes.saturating_sub(baseline.wakes),
+        }
+    }
+}
+
+impl Totals {
+    /// Returns the lifetime statistics for `task`, as of `now`.
+    pub(crate) fn of(task: &Task, now: SystemTime) -> Self {
+        Self {
+            total: task.total(now),
+            busy: task.busy(now),
+            idle: task.idle(now),
+            scheduled: task.scheduled(),
+            polls: task.total_polls(),
+            wakes: task.wakes(),
+        }
+    }
+
+    /// Returns the statistics for `task` within `window`, or over its whole
+    /// lifetime if there is no window.
+    pub(crate) fn in_window(task: &Task, now: SystemTime, window: Option<&Window>) -> Self {
+        match window {
+            Some(window) => window.totals(task, now),
+            None => Self::of(task, now),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Printing the updates from an instrumented process to stdout, instead of
+//! displaying them in the TUI, so that other tools can consume them.
+use crate::{
+    conn::Connection,
+    exit::{FailOn, Outcome, Verdict},
+    report,
+    state::State,
+    view,
+};
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+/// A format to print updates in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Format {
+    /// One JSON object per update, each on its own line.
+    JsonLines,
+}
+
+/// Prints each update received on `conn` to stdout, until stdout is closed.
+///
+/// Each update is printed with the current statistics of the tasks and
+/// resources that it changed, or, if `summary` is set, of every task and
+/// resource. Once stdout is closed, returns whether warnings which `fail_on`
+/// fired while streaming, or, if no update was received within `timeout`,
+/// why.
+pub(crate) async fn run(
+    mut conn: Connection,
+    mut state: State,
+    styles: &view::Styles,
+    format: Format,
+    summary: bool,
+    timeout: Duration,
+    fail_on: FailOn,
+) -> color_eyre::Result<Outcome> {
+    let current_view = view::ViewState::TasksList;
+    let mut verdict = Verdict::new(fail_on);
+    let stdout = io::stdout();
+    loop {
+        // The connection retries forever, so give up if the process stops
+        // sending updates.
+        let update = match tokio::time::timeout(timeout, conn.next_update()).await {
+            Ok(update) => update,
+            Err(_) => {
+                let stats = conn.stats();
+                eprintln!(
+                    "error: no updates were received from {} within {}{}",
+                    conn.target(),
+                    humantime::format_duration(timeout),
+                    stats
+                        .last_error
+                        .as_ref()
+                        .map(|error| format!(" ({})", error))
+                        .unwrap_or_default()
+                );
+                return Ok(Outcome::no_updates(stats));
+            }
+        };
+        let changed_tasks: Vec<u64> = update
+            .task_update
+            .as_ref()
+            .map(|tasks| tasks.stats_update.keys().copied().collect())
+            .unwrap_or_default();
+        let changed_resources: Vec<u64> = update
+            .resource_update
+            .as_ref()
+            .map(|resources| resources.stats_update.keys().copied().collect())
+            .unwrap_or_default();
+
+        state.update(styles, &current_view, update);
+        state.retain_active();
+        // There's no table to add new tasks and resources to.
+        state.tasks_state_mut().take_new_tasks().for_each(drop);
+        state
+            .resources_state_mut()
+            .take_new_resources()
+            .for_each(drop);
+        verdict.check(&state);
+
+        let now = match state.last_updated_at() {
+            Some(now) => now,
+            None => continue,
+        };
+        let tasks_state = state.tasks_state();
+        let resources_state = state.resources_state();
+        let (tasks, resources) = if summary {
+            (
+                tasks_state
+                    .tasks()
+                    .map(|task| report::Task::new(&task.borrow(), now))
+                    .collect(),
+                resources_state
+                    .resources()
+                    .map(|resource| report::Resource::new(&resource.borrow(), now))
+                    .collect(),
+            )
+        } else {
+            (
+                changed_tasks
+                    .iter()
+                    .filter_map(|&id| tasks_state.task(id))
+                    .map(|task| report::Task::new(&task.borrow(), now))
+                    .collect(),
+                changed_resources
+                    .iter()
+                    .filter_map(|&id| resources_state.resource(id))
+                    .map(|resource| report::Resource::new(&resource.borrow(), now))
+                    .collect(),
+            )
+        };
+
+        let snapshot = report::Snapshot::new(now, tasks, resources);
+        let line = match format {
+            Format::JsonLines => serde_json::to_string(&snapshot)?,
+        };
+        match writeln!(stdout.lock(), "{}", line) {
+            Ok(()) => {}
+            // Whatever was reading the output has gone away, so stop.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return Ok(verdict.outcome()),
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("json-lines") => Ok(Format::JsonLines),
+            s => Err(format!("unsupported format `{}`", s)),
+        }
+    }
+}
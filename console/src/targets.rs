@@ -0,0 +1,169 @@
+//! Support for connecting to several instrumented processes at once, and
+//! switching between them.
+use crate::{
+    conn::Connection,
+    export::{Otlp, StatsD},
+    hook::WarningHook,
+    notify::Notifier,
+    state::State,
+    view::View,
+    webhook::Webhook,
+};
+use console_api::instrument::Update;
+use futures::future;
+use std::path::Path;
+use tui::{
+    style::{Color, Modifier},
+    text::{Span, Spans},
+};
+
+/// A connection to an instrumented process, along with the console's state
+/// and view for it.
+///
+/// Targets which aren't currently displayed remain connected, so switching
+/// back to a target doesn't lose its history.
+pub(crate) struct Target {
+    pub(crate) conn: Connection,
+    pub(crate) state: State,
+    pub(crate) view: View,
+    /// The webhook to post warnings to as they fire, if there is one.
+    pub(crate) webhook: Option<Webhook>,
+    /// The desktop notifications to show as warnings fire, if there are any.
+    pub(crate) notifier: Option<Notifier>,
+    /// The command to run as warnings fire, if there is one.
+    pub(crate) hook: Option<WarningHook>,
+    /// The StatsD server to send the target's metrics to, if there is one.
+    pub(crate) statsd: Option<StatsD>,
+    /// The OTLP collector to send the target's metrics to, if there is one.
+    pub(crate) otlp: Option<Otlp>,
+}
+
+pub(crate) struct Targets {
+    targets: Vec<Target>,
+    current: usize,
+}
+
+impl Targets {
+    /// The maximum number of targets that can be switched between, one for
+    /// each of the keys `1` through `9`.
+    pub(crate) const MAX: usize = 9;
+
+    pub(crate) fn new(targets: Vec<Target>) -> Self {
+        assert!(!targets.is_empty(), "at least one target is required");
+        Self {
+            targets,
+            current: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub(crate) fn current(&mut self) -> &mut Target {
+        &mut self.targets[self.current]
+    }
+
+    /// Switches to the target at `index`, returning `true` if the current
+    /// target changed.
+    pub(crate) fn select(&mut self, index: usize) -> bool {
+        if index >= self.targets.len() || index == self.current {
+            return false;
+        }
+        tracing::debug!(from = self.current, to = index, "switching targets");
+        self.current = index;
+        true
+    }
+
+    /// Waits for the next update from any of the targets, returning the
+    /// index of the target it was received from.
+    ///
+    /// The targets that didn't produce the update keep any reconnection
+    /// attempts they were waiting on, so a target that's down still
+    /// reconnects while the others are sending updates.
+    pub(crate) async fn next_update(&mut self) -> (usize, Update) {
+        let updates = self
+            .targets
+            .iter_mut()
+            .enumerate()
+            .map(|(i, target)| Box::pin(async move { (i, target.conn.next_update().await) }));
+        let (update, _, _) = future::select_all(updates).await;
+        update
+    }
+
+    /// Applies an update received from the target at `index`.
+    ///
+    /// If the update completes a task that the target should break on, the
+    /// target is paused. Any warnings that the update caused to fire are
+    /// posted to the target's webhook, shown as desktop notifications, and
+    /// passed to its `--on-warning` command, and its metrics are exported.
+    pub(crate) async fn update(&mut self, index: usize, update: Update) {
+        let Target {
+            conn,
+            state,
+            view,
+            webhook,
+            notifier,
+            hook,
+            statsd,
+            otlp,
+        } = &mut self.targets[index];
+        if state.update(&view.styles, view.current_view(), update) {
+            conn.pause().await;
+        }
+        if let Some(webhook) = webhook {
+            webhook.check(conn.target(), state);
+        }
+        if let Some(notifier) = notifier {
+            notifier.check(conn, state);
+        }
+        if let Some(hook) = hook {
+            hook.check(conn.target(), state);
+        }
+        if let Some(statsd) = statsd {
+            statsd.update(state);
+        }
+        if let Some(otlp) = otlp {
+            otlp.update(state);
+        }
+    }
+
+    /// Saves the UI session for every target in `dir`.
+    pub(crate) fn save_sessions(&self, dir: &Path) {
+        for target in &self.targets {
+            let target_addr = target.conn.target();
+            if let Err(error) = target.view.session().save(dir, target_addr) {
+                tracing::warn!(%error, addr = %target_addr, "failed to save session");
+            }
+        }
+    }
+
+    /// Renders the list of targets to switch between, or nothing if there is
+    /// only one target.
+    pub(crate) fn render(&self) -> Option<Spans<'static>> {
+        if self.targets.len() < 2 {
+            return None;
+        }
+
+        let mut spans = vec![Span::raw("targets: ")];
+        for (i, target) in self.targets.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(", "));
+            }
+            let label = format!("{} = {}", i + 1, target.conn.target());
+            if i == self.current {
+                spans.push(Span::styled(
+                    label,
+                    target
+                        .view
+                        .styles
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            } else {
+                spans.push(Span::raw(label));
+            }
+        }
+        Some(Spans::from(spans))
+    }
+}
@@ -1,9 +1,32 @@
 pub use color_eyre::eyre::WrapErr;
-use std::io;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::{Instant, SystemTime},
+};
 pub use tui::{backend::CrosstermBackend, Terminal};
 
-pub fn init_crossterm() -> color_eyre::Result<(Terminal<CrosstermBackend<io::Stdout>>, OnShutdown)>
-{
+/// The console's output to the terminal, which is also recorded if
+/// `--record-terminal` is set.
+pub struct Output {
+    stdout: io::Stdout,
+    cast: Option<Cast>,
+}
+
+/// A recording of the terminal in the asciinema cast format (version 2),
+/// which can be played back with `asciinema play`.
+struct Cast {
+    file: io::BufWriter<fs::File>,
+    started: Instant,
+    /// The output written since the last flush, which is recorded as one
+    /// event, so that each frame is one event.
+    pending: Vec<u8>,
+}
+
+pub fn init_crossterm(
+    record: Option<&Path>,
+) -> color_eyre::Result<(Terminal<CrosstermBackend<Output>>, OnShutdown)> {
     use crossterm::{
         event::{DisableMouseCapture, EnableMouseCapture},
         terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
@@ -13,7 +36,19 @@ pub fn init_crossterm() -> color_eyre::Result<(Terminal<CrosstermBackend<io::Std
     let mut stdout = std::io::stdout();
     crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
         .wrap_err("Failed to enable crossterm alternate screen and mouse capture")?;
-    let backend = CrosstermBackend::new(io::stdout());
+    let cast = match record {
+        Some(path) => {
+            let (width, height) = terminal::size().wrap_err("Failed to get the terminal size")?;
+            let cast = Cast::create(path, width, height)
+                .wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+            Some(cast)
+        }
+        None => None,
+    };
+    let backend = CrosstermBackend::new(Output {
+        stdout: io::stdout(),
+        cast,
+    });
     let term = Terminal::new(backend).wrap_err("Failed to create crossterm terminal")?;
 
     let cleanup = OnShutdown::new(|| {
@@ -28,6 +63,68 @@ pub fn init_crossterm() -> color_eyre::Result<(Terminal<CrosstermBackend<io::Std
     Ok((term, cleanup))
 }
 
+// === impl Output ===
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.stdout.write(buf)?;
+        if let Some(ref mut cast) = self.cast {
+            cast.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()?;
+        if let Some(ref mut cast) = self.cast {
+            // Stop recording, rather than quitting, if the recording can't be
+            // written.
+            if let Err(error) = cast.record() {
+                tracing::error!(%error, "failed to record the terminal; recording stopped");
+                self.cast = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+// === impl Cast ===
+
+impl Cast {
+    fn create(path: &Path, width: u16, height: u16) -> io::Result<Self> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let header = serde_json::json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": timestamp,
+        });
+        writeln!(file, "{}", header)?;
+        file.flush()?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Records the output written since the last event as a new event.
+    fn record(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let output = String::from_utf8_lossy(&self.pending);
+        let event = (self.started.elapsed().as_secs_f64(), "o", output);
+        writeln!(self.file, "{}", serde_json::to_string(&event)?)?;
+        self.pending.clear();
+        self.file.flush()
+    }
+}
+
 pub struct OnShutdown {
     action: fn() -> color_eyre::Result<()>,
 }
@@ -0,0 +1,202 @@
+//! A compact display of the busiest tasks, like `top`, for small terminals
+//! such as a spare tmux pane.
+use crate::{
+    conn::Connection,
+    input,
+    state::{window::Totals, State},
+    term,
+    view::{self, bold},
+};
+use color_eyre::eyre::eyre;
+use futures::stream::StreamExt;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use tui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Span, Spans},
+    widgets::{Cell, Paragraph, Row, Table},
+};
+
+/// The metric to rank tasks by.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Metric {
+    /// Time spent being polled.
+    Busy,
+    /// Number of times polled.
+    Polls,
+    /// Number of times woken.
+    Wakes,
+}
+
+/// A task's activity since the previous update.
+struct Activity {
+    id: u64,
+    name: String,
+    state: Span<'static>,
+    busy: Duration,
+    polls: u64,
+    wakes: u64,
+}
+
+/// Displays the `count` tasks which were the most active by `metric` since
+/// the previous update, until `q` is pressed.
+pub(crate) async fn run(
+    mut conn: Connection,
+    mut state: State,
+    styles: &view::Styles,
+    count: usize,
+    metric: Metric,
+) -> color_eyre::Result<()> {
+    let current_view = view::ViewState::TasksList;
+    let (mut terminal, _cleanup) = term::init_crossterm(None)?;
+    terminal.clear()?;
+    let mut input = input::EventStream::new();
+
+    let mut previous: HashMap<u64, Totals> = HashMap::new();
+    let mut previous_at: Option<SystemTime> = None;
+    let mut interval: Option<Duration> = None;
+    let mut top: Vec<Activity> = Vec::new();
+    let mut num_tasks = 0;
+
+    loop {
+        tokio::select! {
+            event = input.next() => {
+                let event = event.ok_or_else(|| eyre!("keyboard input stream ended early"))??;
+                if input::should_quit(&event) {
+                    return Ok(());
+                }
+            }
+            update = conn.next_update() => {
+                state.update(styles, &current_view, update);
+                state.retain_active();
+                // There's no table to add new tasks and resources to.
+                state.tasks_state_mut().take_new_tasks().for_each(drop);
+                state.resources_state_mut().take_new_resources().for_each(drop);
+
+                if let Some(now) = state.last_updated_at() {
+                    interval = previous_at
+                        .and_then(|previous_at| now.duration_since(previous_at).ok())
+                        .filter(|interval| *interval > Duration::ZERO);
+                    previous_at = Some(now);
+
+                    let mut current = HashMap::new();
+                    top.clear();
+                    for task in state.tasks_state().tasks() {
+                        let task = task.borrow();
+                        let totals = Totals::of(&task, now);
+                        // Tasks spawned since the previous update count from
+                        // when they were spawned.
+                        let before = previous.get(&task.id()).copied().unwrap_or_default();
+                        top.push(Activity {
+                            id: task.id(),
+                            name: task.name().unwrap_or("").to_string(),
+                            state: task.state().render(styles),
+                            busy: totals.busy.saturating_sub(before.busy),
+                            polls: totals.polls.saturating_sub(before.polls),
+                            wakes: totals.wakes.saturating_sub(before.wakes),
+                        });
+                        current.insert(task.id(), totals);
+                    }
+                    previous = current;
+                    num_tasks = top.len();
+
+                    match metric {
+                        Metric::Busy => top.sort_by_key(|row| Reverse(row.busy)),
+                        Metric::Polls => top.sort_by_key(|row| Reverse(row.polls)),
+                        Metric::Wakes => top.sort_by_key(|row| Reverse(row.wakes)),
+                    }
+                    top.truncate(count);
+                }
+            }
+        }
+
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+                .split(f.size());
+
+            let header = Paragraph::new(Spans::from(vec![
+                bold(conn.target().to_string()),
+                Span::raw(format!(
+                    " {} tasks, top {} by {} ",
+                    num_tasks,
+                    count,
+                    metric.name()
+                )),
+                bold("q"),
+                Span::raw(" = quit"),
+            ]));
+
+            let per_sec = |n: u64| match interval {
+                Some(interval) => format!("{:.1}", n as f64 / interval.as_secs_f64()),
+                None => "-".to_string(),
+            };
+            let rows = top.iter().map(|task| {
+                let busy = match interval {
+                    Some(interval) => format!(
+                        "{:.1}%",
+                        task.busy.as_secs_f64() / interval.as_secs_f64() * 100.0
+                    ),
+                    None => "-".to_string(),
+                };
+                Row::new(vec![
+                    Cell::from(task.id.to_string()),
+                    Cell::from(task.state.clone()),
+                    Cell::from(busy),
+                    Cell::from(per_sec(task.polls)),
+                    Cell::from(per_sec(task.wakes)),
+                    Cell::from(task.name.clone()),
+                ])
+            });
+            let widths = [
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Min(4),
+            ];
+            let table = Table::new(rows)
+                .header(Row::new(vec![
+                    Cell::from(bold("ID")),
+                    Cell::from(bold("State")),
+                    Cell::from(bold("Busy")),
+                    Cell::from(bold("Polls/s")),
+                    Cell::from(bold("Wakes/s")),
+                    Cell::from(bold("Name")),
+                ]))
+                .widths(&widths);
+
+            f.render_widget(header, chunks[0]);
+            f.render_widget(table, chunks[1]);
+        })?;
+    }
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Busy => "busy time",
+            Metric::Polls => "polls",
+            Metric::Wakes => "wakes",
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("busy") => Ok(Metric::Busy),
+            s if s.eq_ignore_ascii_case("polls") => Ok(Metric::Polls),
+            s if s.eq_ignore_ascii_case("wakes") => Ok(Metric::Wakes),
+            s => Err(format!("unsupported metric `{}`", s)),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Helpers for reaching instrumented processes that aren't directly reachable
+//! from the console, by tunneling the connection through an external command.
+use crate::config::KubePod;
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Help, SectionExt,
+};
+use std::{net::TcpListener, process::Stdio, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader, Lines},
+    net::TcpStream,
+    process::{Child, Command},
+    task::JoinHandle,
+};
+use tonic::transport::Uri;
+
+/// A running tunnel to the instrumented process.
+///
+/// The tunnel is torn down when this is dropped.
+#[derive(Debug)]
+pub(crate) enum Tunnel {
+    /// A tunnel command that is not restarted if it exits.
+    Child(Child),
+    /// A task which runs a tunnel command, restarting it if it exits.
+    Supervised(JoinHandle<()>),
+}
+
+impl Tunnel {
+    /// How long to wait for a tunnel to come up before giving up.
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// How long to wait before restarting a tunnel that exited.
+    const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// Runs an SSH local forward to the target address, as seen from the given
+    /// SSH destination, returning the tunnel and the local address that is
+    /// forwarded to the target.
+    ///
+    /// If the SSH connection drops, it is re-established in the background.
+    pub(crate) async fn ssh_forward(
+        destination: &str,
+        target: &Uri,
+    ) -> color_eyre::Result<(Self, Uri)> {
+        const DEFAULT_PORT: u16 = 6669;
+
+        let remote_host = target.host().unwrap_or("127.0.0.1");
+        let remote_port = target.port_u16().unwrap_or(DEFAULT_PORT);
+        // Pick an ephemeral local port for the forward, so that we don't
+        // collide with anything else listening on the instrument port.
+        let local_port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .wrap_err("failed to find a free local port for the SSH tunnel")?
+            .port();
+        let forward = format!("127.0.0.1:{}:{}:{}", local_port, remote_host, remote_port);
+        let destination = destination.to_string();
+        let ssh = {
+            let destination = destination.clone();
+            move || {
+                let mut cmd = Command::new("ssh");
+                // Run non-interactively, since the tunnel may be restarted while
+                // the console owns the terminal, and fail fast if the forward
+                // can't be established or the connection goes away.
+                cmd.args(["-N", "-o", "BatchMode=yes"])
+                    .args(["-o", "ExitOnForwardFailure=yes"])
+                    .args(["-o", "ServerAliveInterval=10"])
+                    .arg("-L")
+                    .arg(&forward)
+                    .arg(&destination)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true);
+                tracing::debug!(?cmd, "starting SSH tunnel");
+                let mut child = cmd.spawn()?;
+                let stderr = child.stderr.take().expect("stderr is piped");
+                drain("ssh", BufReader::new(stderr).lines());
+                Ok::<_, std::io::Error>(child)
+            }
+        };
+
+        let mut child = ssh()
+            .wrap_err("failed to run `ssh`")
+            .suggestion("make sure `ssh` is installed and on your `PATH`")?;
+
+        // Wait until the local end of the forward is accepting connections.
+        let started: Result<color_eyre::Result<()>, _> =
+            tokio::time::timeout(Self::STARTUP_TIMEOUT, async {
+                loop {
+                    if let Some(status) = child.try_wait()? {
+                        return Err(eyre!(
+                            "`ssh` exited with {} before the tunnel was up",
+                            status
+                        ))
+                        .suggestion(
+                            "make sure you can log in to the destination without a password \
+                             prompt, such as with `ssh-agent`",
+                        );
+                    }
+                    if TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+                        return Ok(());
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            })
+            .await;
+        match started {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(eyre!(
+                    "timed out after {:?} waiting for the SSH tunnel",
+                    Self::STARTUP_TIMEOUT
+                ))
+            }
+        }
+
+        let addr = format!("http://127.0.0.1:{}", local_port)
+            .parse()
+            .wrap_err("invalid forwarded address")?;
+        tracing::info!(%addr, %destination, "forwarding over SSH");
+
+        let supervisor = tokio::spawn(async move {
+            loop {
+                match child.wait().await {
+                    Ok(status) => tracing::warn!(%status, %destination, "SSH tunnel exited"),
+                    Err(error) => tracing::warn!(%error, %destination, "SSH tunnel failed"),
+                }
+                loop {
+                    tokio::time::sleep(Self::RESTART_BACKOFF).await;
+                    tracing::info!(%destination, "restarting SSH tunnel");
+                    match ssh() {
+                        Ok(restarted) => {
+                            child = restarted;
+                            break;
+                        }
+                        Err(error) => {
+                            tracing::warn!(%error, "failed to restart SSH tunnel")
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok((Self::Supervised(supervisor), addr))
+    }
+
+    /// Runs `kubectl port-forward` to the given pod, returning the tunnel and
+    /// the local address that is forwarded to the pod.
+    pub(crate) async fn kube_port_forward(
+        pod: &KubePod,
+        namespace: Option<&str>,
+    ) -> color_eyre::Result<(Self, Uri)> {
+        let mut cmd = Command::new("kubectl");
+        cmd.arg("port-forward");
+        if let Some(namespace) = namespace {
+            cmd.args(["--namespace", namespace]);
+        }
+        // Forward from an ephemeral local port, so that we don't collide with
+        // anything else listening on the instrument port.
+        cmd.args(["--address", "127.0.0.1"])
+            .arg(&pod.pod)
+            .arg(format!(":{}", pod.port))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        tracing::debug!(?cmd, "starting `kubectl port-forward`");
+
+        let mut child = cmd
+            .spawn()
+            .wrap_err("failed to run `kubectl port-forward`")
+            .suggestion("make sure `kubectl` is installed and on your `PATH`")?;
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+
+        let forwarded = tokio::time::timeout(Self::STARTUP_TIMEOUT, async {
+            while let Some(line) = stdout.next_line().await? {
+                tracing::debug!(%line, "kubectl");
+                if let Some(port) = parse_forwarded_port(&line) {
+                    return Ok(Some(port));
+                }
+            }
+            Ok::<_, std::io::Error>(None)
+        })
+        .await;
+
+        let port = match forwarded {
+            Ok(Ok(Some(port))) => port,
+            Ok(Ok(None)) => {
+                let mut output = String::new();
+                stderr.read_to_string(&mut output).await?;
+                return Err(eyre!(
+                    "`kubectl port-forward` exited without forwarding a port"
+                ))
+                .with_section(|| output.trim().to_string().header("kubectl output:"));
+            }
+            Ok(Err(error)) => {
+                return Err(error).wrap_err("failed to read `kubectl port-forward` output")
+            }
+            Err(_) => {
+                return Err(eyre!(
+                    "timed out after {:?} waiting for `kubectl port-forward`",
+                    Self::STARTUP_TIMEOUT
+                ))
+            }
+        };
+
+        // `kubectl` logs a line for every forwarded connection, so keep
+        // draining its output to avoid it blocking on a full pipe.
+        drain("kubectl", stdout);
+        drain("kubectl", BufReader::new(stderr).lines());
+
+        let addr = format!("http://127.0.0.1:{}", port)
+            .parse()
+            .wrap_err("invalid forwarded address")?;
+        tracing::info!(%addr, pod = %pod.pod, "forwarding to pod");
+        Ok((Self::Child(child), addr))
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        match self {
+            Tunnel::Child(child) => {
+                let _ = child.start_kill();
+            }
+            // Aborting the supervisor task drops the tunnel command's
+            // `Child`, which kills it.
+            Tunnel::Supervised(task) => task.abort(),
+        }
+    }
+}
+
+/// Parses the local port out of a `kubectl port-forward` line like
+/// `Forwarding from 127.0.0.1:54321 -> 6669`.
+fn parse_forwarded_port(line: &str) -> Option<u16> {
+    let forward = line.trim().strip_prefix("Forwarding from ")?;
+    let (local, _) = forward.split_once(" -> ")?;
+    let (_, port) = local.rsplit_once(':')?;
+    port.parse().ok()
+}
+
+/// Logs each line of a tunnel command's output in the background.
+fn drain<R>(cmd: &'static str, mut lines: Lines<BufReader<R>>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!(%line, "{}", cmd);
+        }
+    });
+}
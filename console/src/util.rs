@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 pub(crate) trait Percentage {
     // Using an extension trait for this is maybe a bit excessive, but making it
     // a method has the nice advantage of making it *really* obvious which is
@@ -32,3 +34,62 @@ pub(crate) fn percentage(total: f64, amount: f64) -> f64 {
     );
     (amount / total) * 100.0
 }
+
+/// Compares strings in natural order, in which runs of digits are compared
+/// by their numeric value, so that `worker-2` sorts before `worker-10`.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (x, y) = match (a.chars().next(), b.chars().next()) {
+            (Some(x), Some(y)) => (x, y),
+            (x, y) => return x.is_some().cmp(&y.is_some()),
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (a_num, a_rest) = split_digits(a);
+            let (b_num, b_rest) = split_digits(b);
+            // Compare the digits without parsing them, so that numbers of
+            // any length can be compared. Ignoring leading zeros, a longer
+            // number is larger, and numbers of the same length compare like
+            // strings.
+            let (a_value, b_value) = (a_num.trim_start_matches('0'), b_num.trim_start_matches('0'));
+            let ordering = a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+                .then_with(|| a_num.len().cmp(&b_num.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            a = a_rest;
+            b = b_rest;
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+        }
+    }
+}
+
+/// Splits the leading run of ASCII digits off of `s`.
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Formats a count with commas between each group of three digits, such as
+/// `8,431`.
+pub(crate) fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        // A comma goes before every digit which is a multiple of three
+        // digits from the end.
+        if i > 0 && i % 3 == digits.len() % 3 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
@@ -0,0 +1,226 @@
+use crate::{
+    state::tasks::{Task, TaskRef},
+    view::{
+        self, bold,
+        mini_histogram::{self, MiniHistogram},
+    },
+};
+use hdrhistogram::Histogram;
+use std::time::{Duration, SystemTime};
+use tui::{
+    layout::{self, Layout},
+    style::{self, Style},
+    widgets::{Cell, Row, Table},
+};
+
+/// A task that other tasks are compared against, side-by-side with the task
+/// being inspected.
+pub(crate) struct Baseline {
+    task: TaskRef,
+    /// The baseline's poll times histogram when it was marked.
+    ///
+    /// Only the inspected task's histogram is streamed from the instrumented
+    /// process, so this isn't updated while other tasks are inspected.
+    poll_times: Option<Histogram<u64>>,
+}
+
+/// A row comparing one statistic of the inspected task and the baseline.
+struct Stat {
+    name: &'static str,
+    task: String,
+    baseline: String,
+    /// The inspected task's value relative to the baseline's, if both are
+    /// known.
+    change: Option<f64>,
+}
+
+impl Baseline {
+    pub(super) fn new(task: TaskRef, poll_times: Option<Histogram<u64>>) -> Self {
+        Self { task, poll_times }
+    }
+
+    /// Returns the baseline task's ID, or `None` if it is no longer known.
+    pub(crate) fn task_id(&self) -> Option<u64> {
+        self.task.upgrade().map(|task| task.borrow().id())
+    }
+
+    /// Renders the statistics of `task` and the baseline in aligned columns,
+    /// and both of their poll times histograms on the same scale.
+    pub(crate) fn render<B: tui::backend::Backend>(
+        &self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        now: SystemTime,
+        task: &Task,
+        poll_times: Option<&Histogram<u64>>,
+    ) {
+        let baseline = match self.task.upgrade() {
+            Some(baseline) => baseline,
+            None => return,
+        };
+        let baseline = &*baseline.borrow();
+        let baseline_poll_times = self.poll_times.as_ref();
+
+        let stats = stats(
+            styles,
+            now,
+            (task, poll_times),
+            (baseline, baseline_poll_times),
+        );
+        // add 3 for the borders and the header
+        let stats_height = stats.len() as u16 + 3;
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(
+                [
+                    layout::Constraint::Length(stats_height),
+                    layout::Constraint::Percentage(50),
+                    layout::Constraint::Percentage(50),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        let label = |task: &Task| match task.name() {
+            Some(name) => format!("{} ({})", task.id(), styles.redact(name)),
+            None => task.id().to_string(),
+        };
+        let header = Row::new(vec![
+            String::new(),
+            label(task),
+            format!("baseline {}", label(baseline)),
+            "change".to_string(),
+        ])
+        .height(1)
+        .style(Style::default().add_modifier(style::Modifier::BOLD));
+        let rows = stats.into_iter().map(|stat| {
+            let change = match stat.change {
+                Some(change) if change.is_finite() => format!("{:+.1}%", change * 100.0),
+                _ => String::new(),
+            };
+            Row::new(vec![
+                Cell::from(bold(stat.name)),
+                Cell::from(stat.task),
+                Cell::from(stat.baseline),
+                Cell::from(change),
+            ])
+        });
+        let table = Table::new(rows)
+            .header(header)
+            .block(styles.border_block().title("Comparison"))
+            .widths(&[
+                layout::Constraint::Length(13),
+                layout::Constraint::Percentage(35),
+                layout::Constraint::Percentage(35),
+                layout::Constraint::Percentage(30),
+            ]);
+        frame.render_widget(table, chunks[0]);
+
+        // If UTF-8 is disabled we can't draw the histogram sparklines.
+        if !styles.utf8 {
+            return;
+        }
+        // Draw both histograms over the range of values recorded by either.
+        let histograms = [poll_times, baseline_poll_times];
+        let recorded = || histograms.iter().flatten().filter(|h| !h.is_empty());
+        let min = recorded().map(|h| h.min()).min().unwrap_or_default();
+        let max = recorded().map(|h| h.max()).max().unwrap_or_default();
+        let titles = [
+            "Poll Times Histogram",
+            "Baseline Poll Times Histogram (as of marking)",
+        ];
+        for ((histogram, title), &area) in histograms
+            .iter()
+            .zip(titles.iter().copied())
+            .zip(&chunks[1..])
+        {
+            let (chart_data, metadata) = histogram
+                .map(|h| {
+                    mini_histogram::chart_data_between(h, area.width.saturating_sub(3), min, max)
+                })
+                .unwrap_or_default();
+            let histogram = MiniHistogram::default()
+                .block(
+                    styles
+                        .border_block()
+                        .title(mini_histogram::title(title, *histogram)),
+                )
+                .data(&chart_data)
+                .metadata(metadata)
+                .duration_precision(2);
+            frame.render_widget(histogram, area);
+        }
+    }
+}
+
+/// Returns the statistics compared between a task and the baseline, each
+/// with its poll times histogram.
+fn stats(
+    styles: &view::Styles,
+    now: SystemTime,
+    (task, poll_times): (&Task, Option<&Histogram<u64>>),
+    (baseline, baseline_poll_times): (&Task, Option<&Histogram<u64>>),
+) -> Vec<Stat> {
+    let dur = |dur: Duration| styles.duration(dur);
+    let change = |task: f64, baseline: f64| (task - baseline) / baseline;
+    let dur_stat = |name, task: Option<Duration>, baseline: Option<Duration>| Stat {
+        name,
+        task: task.map(dur).unwrap_or_default(),
+        baseline: baseline.map(dur).unwrap_or_default(),
+        change: task
+            .zip(baseline)
+            .map(|(task, baseline)| change(task.as_secs_f64(), baseline.as_secs_f64())),
+    };
+    let count_stat = |name, task: u64, baseline: u64| Stat {
+        name,
+        task: task.to_string(),
+        baseline: baseline.to_string(),
+        change: Some(change(task as f64, baseline as f64)),
+    };
+    let mean_poll = |task: &Task| {
+        let polls = task.total_polls();
+        (polls > 0).then(|| task.busy(now) / polls as u32)
+    };
+    let percentile = |histogram: Option<&Histogram<u64>>, percentile: f64| {
+        histogram
+            .filter(|histogram| !histogram.is_empty())
+            .map(|histogram| Duration::from_nanos(histogram.value_at_percentile(percentile)))
+    };
+    let max = |histogram: Option<&Histogram<u64>>| {
+        histogram
+            .filter(|histogram| !histogram.is_empty())
+            .map(|histogram| Duration::from_nanos(histogram.max()))
+    };
+
+    vec![
+        dur_stat("Total", Some(task.total(now)), Some(baseline.total(now))),
+        dur_stat("Busy", Some(task.busy(now)), Some(baseline.busy(now))),
+        dur_stat("Idle", Some(task.idle(now)), Some(baseline.idle(now))),
+        count_stat("Polls", task.total_polls(), baseline.total_polls()),
+        dur_stat("Mean poll", mean_poll(task), mean_poll(baseline)),
+        dur_stat(
+            "p50 poll",
+            percentile(poll_times, 50.0),
+            percentile(baseline_poll_times, 50.0),
+        ),
+        dur_stat(
+            "p90 poll",
+            percentile(poll_times, 90.0),
+            percentile(baseline_poll_times, 90.0),
+        ),
+        dur_stat(
+            "p99 poll",
+            percentile(poll_times, 99.0),
+            percentile(baseline_poll_times, 99.0),
+        ),
+        dur_stat("Max poll", max(poll_times), max(baseline_poll_times)),
+        count_stat("Wakes", task.wakes(), baseline.wakes()),
+        count_stat("Self wakes", task.self_wakes(), baseline.self_wakes()),
+        dur_stat(
+            "Wake to poll",
+            task.mean_scheduling_delay(),
+            baseline.mean_scheduling_delay(),
+        ),
+    ]
+}
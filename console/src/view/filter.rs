@@ -0,0 +1,720 @@
+//! Filtering the rows of the tasks and resources tables by a query typed
+//! into a prompt.
+//!
+//! A query is made of whitespace-separated terms, and a row is shown if it
+//! matches every term. A term is either text, which matches rows containing
+//! it anywhere, ignoring case, or a comparison of one of the row's values,
+//! such as `busy>1s` or `target=my_crate`, or of one of its fields, such as
+//! `field:request_id=abc123`. Comparisons may use `=` and `!=`,
+//! which compare text by whether it contains the value, and `<`, `<=`, `>`,
+//! and `>=`, for numbers, durations, and numeric text. A comparison of a value
+//! the table doesn't have matches rows containing the whole term as text.
+//!
+//! The left side of a comparison may also be an arithmetic expression of the
+//! row's values, fields, and numbers, combined with `+`, `-`, `*`, `/`, and
+//! parentheses, such as `wakes/polls>50` or `busy/total*100>=90`. Durations
+//! are in seconds in expressions, and an expression may be compared with a
+//! number or a duration. The same expressions define computed columns and
+//! the conditions of user-defined lints.
+//!
+//! While the prompt is open, the table is filtered as the query is typed,
+//! once typing pauses for [`DEBOUNCE`], so that a large table isn't filtered
+//! again on every keystroke.
+//!
+//! Queries may be saved under names in the configuration, and applied from
+//! a popup listing them.
+use crate::{
+    input,
+    state::{window::Window, FieldValue},
+    view::{bold, table::TableList},
+};
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Peekable,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
+use tui::{
+    style::{self, Style},
+    text::Span,
+};
+
+/// How long typing must pause before the query being typed is applied.
+pub(crate) const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// The query a table is filtered by, and the prompt for editing it.
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    /// The query the table is currently filtered by.
+    query: Query,
+    /// The prompt the query is being typed in, if it is open.
+    prompt: Option<Prompt>,
+}
+
+/// Terms which a row must all match for the row to be shown.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Query {
+    source: String,
+    terms: Vec<Term>,
+}
+
+/// A query saved under a name, parsed from a string like `hot = busy>1s`.
+#[derive(Debug, Clone)]
+pub(crate) struct SavedFilter {
+    name: String,
+    query: String,
+}
+
+/// One of a row's values, which a term may be compared with.
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    Text(String),
+    Number(f64),
+    Duration(Duration),
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    /// The lowercased term, which is searched for in the row's text if it
+    /// isn't a comparison of one of the row's values.
+    text: String,
+    compare: Option<Compare>,
+}
+
+#[derive(Debug, Clone)]
+struct Compare {
+    key: Key,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone)]
+enum Key {
+    /// One of the values named by [`TableList::FILTER_KEYS`], lowercased.
+    Value(String),
+    /// A field, written as `field:<name>`.
+    Field(String),
+    /// An arithmetic expression of values, fields, and numbers.
+    Expr(Box<Expr>),
+}
+
+/// An arithmetic expression of a row's values, such as `wakes/polls` or
+/// `busy / total * 100`.
+#[derive(Debug, Clone)]
+pub(crate) struct Expr {
+    root: Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Num(f64),
+    /// One of the row's values or fields. This is never an expression.
+    Key(Key),
+    Neg(Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+struct Prompt {
+    text: String,
+    /// When the text was last changed.
+    typed_at: Instant,
+    /// The query before the prompt was opened, which is restored if the
+    /// prompt is cancelled.
+    previous: Query,
+}
+
+// === impl Filter ===
+
+impl Filter {
+    /// Opens the prompt, starting from the current query.
+    pub(crate) fn open(&mut self) {
+        self.prompt = Some(Prompt {
+            text: self.query.source.clone(),
+            typed_at: Instant::now(),
+            previous: self.query.clone(),
+        });
+    }
+
+    /// Returns `true` if the prompt is open, in which case it takes every
+    /// key.
+    pub(crate) fn is_typing(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Returns `true` if the text typed in the prompt hasn't been applied
+    /// yet.
+    pub(crate) fn is_pending(&self) -> bool {
+        matches!(self.prompt, Some(ref prompt) if prompt.text.trim() != self.query.source)
+    }
+
+    /// Applies the text typed in the prompt once typing has paused for
+    /// [`DEBOUNCE`].
+    pub(crate) fn update(&mut self) {
+        if let Some(ref prompt) = self.prompt {
+            if prompt.text.trim() != self.query.source && prompt.typed_at.elapsed() >= DEBOUNCE {
+                self.query = Query::new(&prompt.text);
+            }
+        }
+    }
+
+    /// Stops filtering the table.
+    pub(crate) fn clear(&mut self) {
+        self.query = Query::default();
+    }
+
+    /// Filters the table by `query`, closing the prompt if it is open.
+    pub(crate) fn set(&mut self, query: &str) {
+        self.query = Query::new(query);
+        self.prompt = None;
+    }
+
+    /// Returns the query the table is currently filtered by.
+    pub(crate) fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Handles a key while the prompt is open.
+    pub(crate) fn key_input(&mut self, code: input::KeyCode) {
+        use input::KeyCode::*;
+        let prompt = match self.prompt {
+            Some(ref mut prompt) => prompt,
+            None => return,
+        };
+        match code {
+            Char(c) => {
+                prompt.text.push(c);
+                prompt.typed_at = Instant::now();
+            }
+            Backspace => {
+                prompt.text.pop();
+                prompt.typed_at = Instant::now();
+            }
+            Enter => {
+                self.query = Query::new(&prompt.text);
+                self.prompt = None;
+            }
+            Esc => {
+                self.query = prompt.previous.clone();
+                self.prompt = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the prompt, with the number of rows matching the query, for
+    /// the table's controls.
+    pub(crate) fn controls(&self, matches: usize) -> Vec<Span<'static>> {
+        let text = self
+            .prompt
+            .as_ref()
+            .map(|prompt| prompt.text.clone())
+            .unwrap_or_default();
+        let matches = match matches {
+            1 => "1 match".to_string(),
+            n => format!("{} matches", n),
+        };
+        vec![
+            Span::raw("filter: /"),
+            Span::raw(text),
+            Span::styled(
+                "_",
+                Style::default().add_modifier(style::Modifier::SLOW_BLINK),
+            ),
+            Span::raw(format!(" ({}; ", matches)),
+            bold("enter"),
+            Span::raw(" = done, "),
+            bold("esc"),
+            Span::raw(" = cancel)"),
+        ]
+    }
+}
+
+// === impl Query ===
+
+impl Query {
+    pub(crate) fn new(source: &str) -> Self {
+        Self {
+            source: source.trim().to_string(),
+            terms: source.split_whitespace().map(Term::parse).collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Parses a query whose terms must all compare values that `T`'s rows
+    /// have, returning an error describing the first term that doesn't.
+    ///
+    /// Unlike queries typed into the prompt, which match terms they can't
+    /// compare as text, this is for conditions which are written once, such
+    /// as the conditions of lints.
+    pub(crate) fn comparisons<T: TableList>(source: &str) -> Result<Self, String> {
+        let query = Self::new(source);
+        if query.is_empty() {
+            return Err("expected a comparison such as `busy>1s`".to_string());
+        }
+        for term in &query.terms {
+            let compare = term.compare.as_ref().ok_or_else(|| {
+                format!(
+                    "expected a comparison such as `busy>1s` or `wakes/polls>50`, found `{}`",
+                    term.text
+                )
+            })?;
+            compare.key.check::<T>()?;
+        }
+        Ok(query)
+    }
+
+    /// Returns `true` if `row` matches each of the query's terms, with its
+    /// statistics as of `now`, within `window` if one is open.
+    pub(crate) fn matches<T: TableList>(
+        &self,
+        row: &T::Row,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.matches::<T>(row, now, window))
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// === impl Term ===
+
+impl Term {
+    fn parse(term: &str) -> Self {
+        const OPS: [(&str, Op); 6] = [
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("!=", Op::Ne),
+            ("=", Op::Eq),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        let compare = term.find(&['=', '!', '<', '>'][..]).and_then(|at| {
+            let (key, rest) = term.split_at(at);
+            let &(op_str, op) = OPS.iter().find(|(op, _)| rest.starts_with(op))?;
+            let value = &rest[op_str.len()..];
+            if key.is_empty() || value.is_empty() {
+                return None;
+            }
+            let key = match Expr::parse(key) {
+                Ok(Expr {
+                    root: Node::Key(key),
+                }) => key,
+                Ok(expr) => Key::Expr(Box::new(expr)),
+                Err(_) => Key::parse(key),
+            };
+            Some(Compare {
+                key,
+                op,
+                value: value.to_string(),
+            })
+        });
+        Self {
+            text: term.to_lowercase(),
+            compare,
+        }
+    }
+
+    fn matches<T: TableList>(
+        &self,
+        row: &T::Row,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> bool {
+        let compare = match self.compare {
+            Some(ref compare) => compare,
+            None => return T::contains(row, &self.text),
+        };
+        if compare.key.check::<T>().is_err() {
+            return T::contains(row, &self.text);
+        }
+        let value = compare.key.value::<T>(row, now, window);
+        match value {
+            Some(value) => value.compare(compare.op, &compare.value),
+            // A row without the value is unequal to anything.
+            None => compare.op == Op::Ne,
+        }
+    }
+}
+
+// === impl Value ===
+
+impl Value {
+    fn compare(&self, op: Op, value: &str) -> bool {
+        match self {
+            Value::Text(text) => match op {
+                Op::Eq => contains(text, &value.to_lowercase()),
+                Op::Ne => !contains(text, &value.to_lowercase()),
+                // Text can only be ordered if it's a number.
+                _ => match (text.parse::<f64>(), value.parse::<f64>()) {
+                    (Ok(text), Ok(value)) => op.holds(text.partial_cmp(&value)),
+                    _ => false,
+                },
+            },
+            // Durations are in seconds when they are computed by an
+            // expression.
+            Value::Number(number) => {
+                let value = value
+                    .parse::<f64>()
+                    .ok()
+                    .or_else(|| Some(humantime::parse_duration(value).ok()?.as_secs_f64()));
+                matches!(value, Some(value) if op.holds(number.partial_cmp(&value)))
+            }
+            Value::Duration(duration) => matches!(
+                humantime::parse_duration(value),
+                Ok(value) if op.holds(Some(duration.cmp(&value)))
+            ),
+        }
+    }
+
+    /// Returns the value as a number, with durations in seconds, if it is
+    /// one.
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Text(text) => text.trim().parse().ok(),
+            Value::Number(number) => Some(*number),
+            Value::Duration(duration) => Some(duration.as_secs_f64()),
+        }
+    }
+}
+
+// === impl Key ===
+
+impl Key {
+    /// Parses the name of a value or a field.
+    fn parse(key: &str) -> Self {
+        // Field names are case-sensitive, unlike the names of values.
+        match key.strip_prefix("field:") {
+            Some(name) if !name.is_empty() => Key::Field(name.to_string()),
+            _ => Key::Value(key.to_lowercase()),
+        }
+    }
+
+    /// Returns an error if `T`'s rows don't have the value, or a value used
+    /// by the expression.
+    fn check<T: TableList>(&self) -> Result<(), String> {
+        match self {
+            Key::Value(key) if !T::FILTER_KEYS.contains(&key.as_str()) => Err(format!(
+                "unknown value `{}` (expected one of: {}, or field:<name>)",
+                key,
+                T::FILTER_KEYS.join(", ")
+            )),
+            Key::Value(_) | Key::Field(_) => Ok(()),
+            Key::Expr(expr) => expr.check::<T>(),
+        }
+    }
+
+    fn value<T: TableList>(
+        &self,
+        row: &T::Row,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<Value> {
+        match self {
+            Key::Value(key) => T::value(row, key, now, window),
+            Key::Field(name) => T::field(row, name),
+            Key::Expr(expr) => expr.eval::<T>(row, now, window).map(Value::Number),
+        }
+    }
+}
+
+// === impl Expr ===
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+impl Expr {
+    /// Parses an expression, without checking that the values it uses exist.
+    pub(crate) fn parse(expr: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(expr)?.into_iter().peekable();
+        let root = Node::parse_sum(&mut tokens)?;
+        if let Some(token) = tokens.next() {
+            return Err(format!("unexpected `{}`", token));
+        }
+        Ok(Self { root })
+    }
+
+    /// Returns an error if `T`'s rows don't have one of the values the
+    /// expression uses.
+    pub(crate) fn check<T: TableList>(&self) -> Result<(), String> {
+        self.root.check::<T>()
+    }
+
+    /// Evaluates the expression for `row` as of `now`, within `window` if
+    /// one is open.
+    ///
+    /// This returns `None` if the row doesn't have a value the expression
+    /// uses, one of them isn't a number, or the expression divides by zero.
+    pub(crate) fn eval<T: TableList>(
+        &self,
+        row: &T::Row,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<f64> {
+        self.root
+            .eval::<T>(row, now, window)
+            .filter(|value| value.is_finite())
+    }
+}
+
+impl Node {
+    fn check<T: TableList>(&self) -> Result<(), String> {
+        match self {
+            Node::Num(_) => Ok(()),
+            Node::Key(key) => key.check::<T>(),
+            Node::Neg(node) => node.check::<T>(),
+            Node::Binary(_, lhs, rhs) => {
+                lhs.check::<T>()?;
+                rhs.check::<T>()
+            }
+        }
+    }
+
+    fn eval<T: TableList>(
+        &self,
+        row: &T::Row,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<f64> {
+        match self {
+            Node::Num(value) => Some(*value),
+            Node::Key(key) => key.value::<T>(row, now, window)?.as_number(),
+            Node::Neg(node) => Some(-node.eval::<T>(row, now, window)?),
+            Node::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval::<T>(row, now, window)?;
+                let rhs = rhs.eval::<T>(row, now, window)?;
+                match op {
+                    BinOp::Add => Some(lhs + rhs),
+                    BinOp::Sub => Some(lhs - rhs),
+                    BinOp::Mul => Some(lhs * rhs),
+                    BinOp::Div if rhs == 0.0 => None,
+                    BinOp::Div => Some(lhs / rhs),
+                }
+            }
+        }
+    }
+
+    /// Parses terms separated by `+` or `-`.
+    fn parse_sum(tokens: &mut Tokens) -> Result<Self, String> {
+        let mut node = Self::parse_product(tokens)?;
+        while let Some(op) = Self::next_op(tokens, &['+', '-']) {
+            let rhs = Self::parse_product(tokens)?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    /// Parses factors separated by `*` or `/`.
+    fn parse_product(tokens: &mut Tokens) -> Result<Self, String> {
+        let mut node = Self::parse_factor(tokens)?;
+        while let Some(op) = Self::next_op(tokens, &['*', '/']) {
+            let rhs = Self::parse_factor(tokens)?;
+            node = Node::Binary(op, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_factor(tokens: &mut Tokens) -> Result<Self, String> {
+        match tokens.next() {
+            Some(Token::Num(value)) => Ok(Node::Num(value)),
+            Some(Token::Ident(ident)) => Ok(Node::Key(Key::parse(&ident))),
+            Some(Token::Op('-')) => Ok(Node::Neg(Box::new(Self::parse_factor(tokens)?))),
+            Some(Token::Op('(')) => {
+                let node = Self::parse_sum(tokens)?;
+                match tokens.next() {
+                    Some(Token::Op(')')) => Ok(node),
+                    _ => Err("expected `)`".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Err("expected a value or a number".to_string()),
+        }
+    }
+
+    /// Consumes the next token if it is one of `ops`.
+    fn next_op(tokens: &mut Tokens, ops: &[char]) -> Option<BinOp> {
+        let op = match tokens.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => *op,
+            _ => return None,
+        };
+        tokens.next();
+        Some(match op {
+            '+' => BinOp::Add,
+            '-' => BinOp::Sub,
+            '*' => BinOp::Mul,
+            _ => BinOp::Div,
+        })
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if "+-*/()".contains(c) {
+            chars.next();
+            tokens.push(Token::Op(c));
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut num = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_digit() || **c == '.' || **c == '_')
+            {
+                num.push(c);
+                chars.next();
+            }
+            let value = num
+                .replace('_', "")
+                .parse()
+                .map_err(|_| format!("invalid number `{}`", num))?;
+            tokens.push(Token::Num(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_alphanumeric() || matches!(**c, '_' | '.' | ':'))
+            {
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        }
+    }
+    Ok(tokens)
+}
+
+// === impl Token ===
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(value) => fmt::Display::fmt(value, f),
+            Token::Ident(ident) => f.write_str(ident),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+// === impl Op ===
+
+impl Op {
+    fn holds(self, ordering: Option<Ordering>) -> bool {
+        let ordering = match ordering {
+            Some(ordering) => ordering,
+            None => return self == Op::Ne,
+        };
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+// === impl SavedFilter ===
+
+impl SavedFilter {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn query(&self) -> &str {
+        &self.query
+    }
+}
+
+impl fmt::Display for SavedFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.query)
+    }
+}
+
+impl FromStr for SavedFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, query) = s
+            .split_once('=')
+            .ok_or_else(|| "expected a filter like `hot = busy>1s`".to_string())?;
+        let (name, query) = (name.trim(), query.trim());
+        if name.is_empty() {
+            return Err("expected a name before `=`".to_string());
+        }
+        if query.is_empty() {
+            return Err("expected a query after `=`".to_string());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            query: query.to_string(),
+        })
+    }
+}
+
+impl From<&FieldValue> for Value {
+    fn from(value: &FieldValue) -> Self {
+        match value {
+            FieldValue::Duration(value) => Value::Duration(*value),
+            FieldValue::Str(value) | FieldValue::Debug(value) => Value::Text(value.clone()),
+            value => match value.as_number() {
+                Some(number) => Value::Number(number),
+                None => Value::Text(value.to_string()),
+            },
+        }
+    }
+}
+
+/// Returns `true` if `text` contains the lowercased `term`, ignoring case.
+pub(crate) fn contains(text: &str, term: &str) -> bool {
+    text.to_lowercase().contains(term)
+}
+
+/// Returns `true` if any of `lines` contains the lowercased `term`, ignoring
+/// case.
+pub(crate) fn lines_contain(lines: &[Vec<Span<'_>>], term: &str) -> bool {
+    lines.iter().any(|line| {
+        let line = line.iter().map(|span| &*span.content).collect::<String>();
+        contains(&line, term)
+    })
+}
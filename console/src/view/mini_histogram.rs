@@ -1,9 +1,12 @@
 use std::time::Duration;
 
+use crate::view::{self, bold};
+use hdrhistogram::Histogram;
 use tui::{
     layout::Rect,
     style::Style,
     symbols,
+    text::{Spans, Text},
     widgets::{Block, Widget},
 };
 
@@ -239,3 +242,83 @@ impl<'a> MiniHistogram<'a> {
         self
     }
 }
+
+/// From the histogram, build a visual representation by trying to make as
+/// many buckets as the width of the render area.
+///
+/// Recorded values are binned into evenly sized buckets between the
+/// histogram's minimum and maximum, rather than following the histogram's own
+/// bucket layout, which depends on the precision it was recorded with.
+pub(crate) fn chart_data(histogram: &Histogram<u64>, width: u16) -> (Vec<u64>, HistogramMetadata) {
+    chart_data_between(histogram, width, histogram.min(), histogram.max())
+}
+
+/// Like [`chart_data`], but with buckets covering the range from `min` to
+/// `max`, so that several histograms can be drawn on the same scale.
+pub(crate) fn chart_data_between(
+    histogram: &Histogram<u64>,
+    width: u16,
+    min: u64,
+    max: u64,
+) -> (Vec<u64>, HistogramMetadata) {
+    let max = max.max(min);
+    let data = if histogram.is_empty() || width == 0 {
+        Vec::new()
+    } else {
+        // Don't make more buckets than there are distinct values in the
+        // range, so that narrow ranges aren't drawn with gaps.
+        let range = u128::from(max - min) + 1;
+        let buckets = range.min(u128::from(width));
+        let mut data = vec![0; buckets as usize];
+        for recorded in histogram.iter_recorded() {
+            // Each of the histogram's buckets reports the highest value it
+            // holds, which may be past the maximum recorded value.
+            let value = recorded.value_iterated_to().clamp(min, max);
+            let bucket = u128::from(value - min) * buckets / range;
+            data[bucket as usize] += recorded.count_at_value();
+        }
+        data
+    };
+    let max_bucket = data.iter().max().copied().unwrap_or_default();
+    let min_bucket = data.iter().min().copied().unwrap_or_default();
+    (
+        data,
+        HistogramMetadata {
+            max_value: max,
+            min_value: min,
+            max_bucket,
+            min_bucket,
+        },
+    )
+}
+
+/// Returns a histogram's title, including the number of significant figures
+/// it was recorded with.
+///
+/// The instrumented process records each task's histogram with the precision
+/// requested when the task was spawned, so it can differ between tasks.
+pub(crate) fn title(name: &str, histogram: Option<&Histogram<u64>>) -> String {
+    match histogram {
+        Some(histogram) => format!("{} ({} sig. figs)", name, histogram.sigfig()),
+        None => name.to_string(),
+    }
+}
+
+/// The percentiles displayed and exported for duration histograms.
+pub(crate) const PERCENTILES: [f64; 7] = [10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0];
+
+/// Get the important percentile values from the histogram
+pub(crate) fn percentiles(styles: &view::Styles, histogram: &Histogram<u64>) -> Text<'static> {
+    let mut text = Text::default();
+    let percentiles = PERCENTILES
+        .iter()
+        .map(|i| (*i, histogram.value_at_percentile(*i)))
+        .map(|pair| {
+            Spans::from(vec![
+                bold(format!("p{:>2}: ", pair.0)),
+                styles.time_units(styles.duration(Duration::from_nanos(pair.1))),
+            ])
+        });
+    text.extend(percentiles);
+    text
+}
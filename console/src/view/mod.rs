@@ -1,26 +1,41 @@
-use crate::view::{resources::ResourcesTable, table::TableListState, tasks::TasksTable};
-use crate::{input, state::State};
-use std::{borrow::Cow, cmp};
+use crate::view::{
+    filter::SavedFilter, resources::ResourcesTable, table::TableListState, tasks::TasksTable,
+};
+use crate::{
+    computed::ComputedColumn,
+    conn,
+    debug_log::DebugLog,
+    input,
+    session::Session,
+    state::{Field, State},
+};
+use std::{borrow::Cow, cmp, time::SystemTime};
 use tui::{
     layout,
     style::{self, Style},
-    text::Span,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph, Wrap},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-mod mini_histogram;
+mod compare;
+pub(crate) mod filter;
+pub(crate) mod mini_histogram;
+pub(crate) mod pretty;
+mod resource;
 mod resources;
+mod runtime;
 mod styles;
 mod table;
 mod task;
-mod tasks;
+pub(crate) mod tasks;
+mod timeline;
+mod trends;
 pub(crate) use self::styles::{Palette, Styles};
 pub(crate) use self::table::SortBy;
 
+/// The minimum width of table columns of durations.
 const DUR_LEN: usize = 10;
-// This data is only updated every second, so it doesn't make a ton of
-// sense to have a lot of precision in timestamps (and this makes sure
-// there's room for the unit!)
-const DUR_PRECISION: usize = 4;
 const TABLE_HIGHLIGHT_SYMBOL: &str = ">> ";
 
 pub struct View {
@@ -34,9 +49,51 @@ pub struct View {
     tasks_list: TableListState<TasksTable>,
     resources_list: TableListState<ResourcesTable>,
     state: ViewState,
+    /// The popup displayed on top of the current view, if any.
+    popup: Option<Popup>,
+    /// The task that inspected tasks are compared against, if one has been
+    /// marked.
+    baseline: Option<self::compare::Baseline>,
+    /// Filters for the tables saved in the configuration.
+    saved_filters: Vec<SavedFilter>,
     pub(crate) styles: Styles,
 }
 
+/// Popups which can be displayed on top of any view.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Popup {
+    /// Information about the instrumented process.
+    ProcessInfo,
+    /// Statistics describing the health of the connection.
+    Connection,
+    /// Entering the label for a new bookmark.
+    NewBookmark(String),
+    /// The list of bookmarks, with the index of the selected bookmark.
+    Bookmarks(usize),
+    /// The list of saved filters, with the index of the selected filter.
+    SavedFilters(usize),
+    /// Confirming that completed tasks and dropped resources should be
+    /// cleared.
+    ConfirmClear,
+    /// The complete fields of the task with the given ID.
+    TaskFields(u64, FieldsPopup),
+    /// The complete attributes of the resource with the given ID.
+    ResourceFields(u64, FieldsPopup),
+    /// Every task with each warning, scrolled down by the given number of
+    /// lines.
+    Warnings(u16),
+}
+
+/// How a popup of complete fields is displayed.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+struct FieldsPopup {
+    /// How many lines the popup is scrolled down by.
+    scroll: u16,
+    /// Whether `Debug`-formatted values are re-indented across multiple
+    /// lines.
+    pretty: bool,
+}
+
 pub(crate) enum ViewState {
     /// The table list of all tasks.
     TasksList,
@@ -44,6 +101,14 @@ pub(crate) enum ViewState {
     ResourcesList,
     /// Inspecting a single task instance.
     TaskInstance(self::task::TaskView),
+    /// Inspecting a single resource instance.
+    ResourceInstance(self::resource::ResourceView),
+    /// The runtime's scheduler metrics.
+    Runtime,
+    /// A timeline of every task's activity.
+    Timeline(self::timeline::TimelineView),
+    /// Charts of how the tasks changed over time.
+    Trends,
 }
 
 /// The outcome of the update_input method
@@ -53,6 +118,15 @@ pub(crate) enum UpdateKind {
     SelectTask(u64),
     /// The TaskView is exited
     ExitTaskView,
+    /// A new resource is selected
+    SelectResource(u64),
+    /// The ResourceView is exited
+    ExitResourceView,
+    /// Switched to a different target, so any details being watched belong
+    /// to the previous target
+    SwitchTarget,
+    /// A bookmark was selected, by its index
+    JumpToBookmark(usize),
     /// No significant change
     Other,
 }
@@ -83,18 +157,229 @@ impl View {
             state: ViewState::TasksList,
             tasks_list: TableListState::<TasksTable>::default(),
             resources_list: TableListState::<ResourcesTable>::default(),
+            popup: None,
+            baseline: None,
+            saved_filters: Vec::new(),
             styles,
         }
     }
 
-    pub(crate) fn update_input(&mut self, event: input::Event, state: &State) -> UpdateKind {
+    /// Displays the value of each of the named fields, and each computed
+    /// column, in a column of its own in the tasks table.
+    pub(crate) fn with_extra_columns(
+        mut self,
+        field_columns: Vec<String>,
+        computed_columns: Vec<ComputedColumn>,
+    ) -> Self {
+        self.tasks_list.field_columns = field_columns;
+        self.tasks_list.computed_columns = computed_columns;
+        self
+    }
+
+    /// Lists `saved_filters` in the popup of saved filters, from which they
+    /// can be applied to the tables.
+    pub(crate) fn with_saved_filters(mut self, saved_filters: Vec<SavedFilter>) -> Self {
+        self.saved_filters = saved_filters;
+        self
+    }
+
+    /// Returns the view's settings, to save in the session.
+    pub(crate) fn session(&self) -> Session {
+        let view = match self.state {
+            ViewState::ResourcesList | ViewState::ResourceInstance(_) => "resources",
+            ViewState::TasksList | ViewState::TaskInstance(_) => "tasks",
+            ViewState::Runtime => "runtime",
+            ViewState::Timeline(_) => "timeline",
+            ViewState::Trends => "trends",
+        };
+        Session {
+            view: Some(view.to_string()),
+            tasks: self.tasks_list.session(),
+            resources: self.resources_list.session(),
+        }
+    }
+
+    /// Restores the view's settings from a saved session.
+    ///
+    /// Task and resource instance views aren't restored, since the task or
+    /// resource may not exist anymore, so the corresponding list is displayed
+    /// instead.
+    pub(crate) fn restore(&mut self, session: &Session) {
+        self.tasks_list.restore(&session.tasks);
+        self.resources_list.restore(&session.resources);
+        match session.view.as_deref() {
+            Some("resources") => self.state = ViewState::ResourcesList,
+            Some("runtime") => self.state = ViewState::Runtime,
+            Some("timeline") => {
+                self.state = ViewState::Timeline(self::timeline::TimelineView::new())
+            }
+            Some("trends") => self.state = ViewState::Trends,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event, state: &mut State) -> UpdateKind {
         use ViewState::*;
         let mut update_kind = UpdateKind::Other;
+
+        // Popups which take input capture every key until they are closed.
+        match self.popup {
+            Some(Popup::NewBookmark(ref mut label)) => {
+                match event {
+                    key!(Enter) => {
+                        let label = label.trim();
+                        let label = if label.is_empty() {
+                            None
+                        } else {
+                            Some(label.to_string())
+                        };
+                        state.add_bookmark(label);
+                        self.popup = None;
+                    }
+                    key!(Esc) => self.popup = None,
+                    key!(Backspace) => {
+                        label.pop();
+                    }
+                    _ => {
+                        if let Some(c) = input::text_char(&event) {
+                            label.push(c);
+                        }
+                    }
+                }
+                return update_kind;
+            }
+            Some(Popup::Bookmarks(ref mut selected)) => {
+                let len = state.bookmarks().len();
+                match event {
+                    key!(Up) => *selected = selected.saturating_sub(1),
+                    key!(Down) if *selected + 1 < len => *selected += 1,
+                    key!(Enter) if *selected < len => {
+                        update_kind = UpdateKind::JumpToBookmark(*selected);
+                        self.popup = None;
+                    }
+                    key!(Esc) | key!(Char('B')) => self.popup = None,
+                    _ => {}
+                }
+                return update_kind;
+            }
+            Some(Popup::SavedFilters(ref mut selected)) => {
+                let len = self.saved_filters.len();
+                let apply = match event {
+                    key!(Up) => {
+                        *selected = selected.saturating_sub(1);
+                        None
+                    }
+                    key!(Down) if *selected + 1 < len => {
+                        *selected += 1;
+                        None
+                    }
+                    key!(Enter) => Some(*selected),
+                    key!(Esc) | key!(Char('F')) => {
+                        self.popup = None;
+                        None
+                    }
+                    _ => input::target_index(&event),
+                };
+                let query = apply
+                    .and_then(|i| self.saved_filters.get(i))
+                    .map(|saved| saved.query().to_string());
+                if let Some(query) = query {
+                    match self.state {
+                        TasksList => self.tasks_list.set_filter(&query),
+                        ResourcesList => self.resources_list.set_filter(&query),
+                        _ => {}
+                    }
+                    self.popup = None;
+                }
+                return update_kind;
+            }
+            Some(Popup::ConfirmClear) => {
+                match event {
+                    key!(Char('y')) | key!(Enter) => {
+                        state.clear_closed();
+                        self.popup = None;
+                    }
+                    key!(Char('n')) | key!(Esc) | key!(Char('X')) => self.popup = None,
+                    _ => {}
+                }
+                return update_kind;
+            }
+            Some(Popup::TaskFields(_, ref mut popup))
+            | Some(Popup::ResourceFields(_, ref mut popup)) => {
+                match event {
+                    _ if scroll_popup(&mut popup.scroll, event) => {}
+                    key!(Char('p')) => popup.pretty = !popup.pretty,
+                    key!(Esc) | key!(Char('f')) => self.popup = None,
+                    _ => {}
+                }
+                return update_kind;
+            }
+            Some(Popup::Warnings(ref mut scroll)) => {
+                match event {
+                    _ if scroll_popup(scroll, event) => {}
+                    key!(Esc) | key!(Char('W')) => self.popup = None,
+                    _ => {}
+                }
+                return update_kind;
+            }
+            _ => {}
+        }
+
+        // Popups are displayed on top of every view, so handle them before
+        // passing input on to the current view, unless text is being typed.
+        let typing = match self.state {
+            TaskInstance(ref view) => view.is_typing_search(),
+            _ => self.is_editing(),
+        };
+        match event {
+            _ if typing => {}
+            key!(Char('P')) => {
+                self.toggle_popup(Popup::ProcessInfo);
+                return update_kind;
+            }
+            key!(Char('C')) => {
+                self.toggle_popup(Popup::Connection);
+                return update_kind;
+            }
+            key!(Char('A')) => {
+                self.styles.absolute_times = !self.styles.absolute_times;
+                return update_kind;
+            }
+            key!(Char('Z')) => {
+                self.styles.anonymize = !self.styles.anonymize;
+                return update_kind;
+            }
+            key!(Char('b')) => {
+                self.popup = Some(Popup::NewBookmark(String::new()));
+                return update_kind;
+            }
+            key!(Char('B')) => {
+                self.popup = Some(Popup::Bookmarks(0));
+                return update_kind;
+            }
+            key!(Char('X')) => {
+                self.popup = Some(Popup::ConfirmClear);
+                return update_kind;
+            }
+            key!(Char('f')) => {
+                self.popup = self.fields_popup();
+                return update_kind;
+            }
+            key!(Esc) if self.popup.is_some() => {
+                self.popup = None;
+                return update_kind;
+            }
+            _ => {}
+        }
+
         match self.state {
             TasksList => {
                 // The enter key changes views, so handle here since we can
                 // mutate the currently selected view.
                 match event {
+                    // While the filter is being typed, every key goes to the
+                    // filter.
+                    _ if self.tasks_list.is_filtering() => self.tasks_list.update_input(event),
                     key!(Enter) => {
                         if let Some(task) = self.tasks_list.selected_item().upgrade() {
                             update_kind = UpdateKind::SelectTask(task.borrow().id());
@@ -107,6 +392,46 @@ impl View {
                     key!(Char('r')) => {
                         self.state = ResourcesList;
                     }
+                    key!(Char('m')) => {
+                        self.state = Runtime;
+                    }
+                    key!(Char('R')) => {
+                        state.cycle_runtime_filter();
+                    }
+                    key!(Char('F')) => {
+                        self.popup = Some(Popup::SavedFilters(0));
+                    }
+                    key!(Char('I')) => {
+                        self.tasks_list.hide_idle = !self.tasks_list.hide_idle;
+                    }
+                    key!(Char('o')) => {
+                        self.tasks_list.hide_target = !self.tasks_list.hide_target;
+                    }
+                    key!(Char('L')) => {
+                        self.tasks_list.show_max_poll = !self.tasks_list.show_max_poll;
+                    }
+                    key!(Char('S')) => {
+                        self.tasks_list.show_activity = !self.tasks_list.show_activity;
+                    }
+                    key!(Char('W')) => {
+                        self.popup = Some(Popup::Warnings(0));
+                    }
+                    key!(Char('c')) => {
+                        state.clear_runtime_filter();
+                        self.tasks_list.hide_idle = false;
+                        self.tasks_list.update_input(event);
+                    }
+                    key!(Char('e')) if self.tasks_list.is_selecting() => {
+                        if let Some(now) = state.last_updated_at() {
+                            self.tasks_list.export_marked(now);
+                        }
+                    }
+                    key!(Char('g')) => {
+                        self.state = Timeline(self::timeline::TimelineView::new());
+                    }
+                    key!(Char('s')) => {
+                        self.state = Trends;
+                    }
                     _ => {
                         // otherwise pass on to view
                         self.tasks_list.update_input(event);
@@ -115,23 +440,123 @@ impl View {
             }
             ResourcesList => {
                 match event {
+                    _ if self.resources_list.is_filtering() => {
+                        self.resources_list.update_input(event)
+                    }
+                    key!(Enter) => {
+                        if let Some(resource) = self.resources_list.selected_item().upgrade() {
+                            update_kind = UpdateKind::SelectResource(resource.borrow().id());
+                            self.state = ResourceInstance(self::resource::ResourceView::new(
+                                resource,
+                                state.resource_details_ref(),
+                            ));
+                        }
+                    }
                     key!(Char('t')) => {
                         self.state = TasksList;
                     }
+                    key!(Char('R')) => {
+                        state.cycle_runtime_filter();
+                    }
+                    key!(Char('c')) => {
+                        state.clear_runtime_filter();
+                        self.resources_list.update_input(event);
+                    }
+                    key!(Char('F')) => {
+                        self.popup = Some(Popup::SavedFilters(0));
+                    }
+                    key!(Char('e')) if self.resources_list.is_selecting() => {
+                        if let Some(now) = state.last_updated_at() {
+                            self.resources_list.export_marked(now);
+                        }
+                    }
+                    key!(Char('m')) => {
+                        self.state = Runtime;
+                    }
+                    key!(Char('g')) => {
+                        self.state = Timeline(self::timeline::TimelineView::new());
+                    }
+                    key!(Char('s')) => {
+                        self.state = Trends;
+                    }
                     _ => {
                         // otherwise pass on to view
                         self.resources_list.update_input(event);
                     }
                 }
             }
+            Runtime => match event {
+                key!(Char('t')) | key!(Esc) => self.state = TasksList,
+                key!(Char('r')) => self.state = ResourcesList,
+                _ => {}
+            },
+            Timeline(ref mut view) => match event {
+                key!(Char('t')) | key!(Esc) => self.state = TasksList,
+                key!(Char('r')) => self.state = ResourcesList,
+                _ => view.update_input(event),
+            },
+            Trends => match event {
+                key!(Char('t')) | key!(Esc) => self.state = TasksList,
+                key!(Char('r')) => self.state = ResourcesList,
+                _ => {}
+            },
             TaskInstance(ref mut view) => {
                 // The escape key changes views, so handle here since we can
                 // mutate the currently selected view.
                 match event {
-                    key!(Esc) => {
+                    // While the search query is being typed, every key goes
+                    // to the search.
+                    _ if view.is_typing_search() => view.update_input(event),
+                    // While the fields pane has focus or a search is open,
+                    // escape closes them instead.
+                    key!(Esc) if !view.fields_focused() && !view.is_searching() => {
                         self.state = TasksList;
                         update_kind = UpdateKind::ExitTaskView;
                     }
+                    key!(Char('x')) => {
+                        state.toggle_break_on_completion(view.task_id());
+                    }
+                    key!(Char('u')) => {
+                        let parent = view
+                            .parent_id()
+                            .and_then(|id| state.tasks_state().task_ref(id)?.upgrade());
+                        if let Some(parent) = parent {
+                            update_kind = UpdateKind::SelectTask(parent.borrow().id());
+                            *view = self::task::TaskView::new(parent, state.task_details_ref());
+                        }
+                    }
+                    key!(Char('c')) => {
+                        let id = view.task_id();
+                        if self.baseline.as_ref().and_then(|b| b.task_id()) == Some(id) {
+                            self.baseline = None;
+                        } else {
+                            self.baseline = Some(view.baseline());
+                        }
+                    }
+                    key!(Char('o')) => {
+                        let resource = view
+                            .last_woken_by()
+                            .and_then(|id| state.resources_state().resource_ref(id)?.upgrade());
+                        if let Some(resource) = resource {
+                            update_kind = UpdateKind::SelectResource(resource.borrow().id());
+                            self.state = ResourceInstance(self::resource::ResourceView::new(
+                                resource,
+                                state.resource_details_ref(),
+                            ));
+                        }
+                    }
+                    _ => {
+                        // otherwise pass on to view
+                        view.update_input(event);
+                    }
+                }
+            }
+            ResourceInstance(ref mut view) => {
+                match event {
+                    key!(Esc) => {
+                        self.state = ResourcesList;
+                        update_kind = UpdateKind::ExitResourceView;
+                    }
                     _ => {
                         // otherwise pass on to view
                         view.update_input(event);
@@ -147,6 +572,7 @@ impl View {
         frame: &mut tui::terminal::Frame<B>,
         area: layout::Rect,
         state: &mut State,
+        conn_stats: &conn::Stats,
     ) {
         match self.state {
             ViewState::TasksList => {
@@ -159,29 +585,935 @@ impl View {
                 let now = state
                     .last_updated_at()
                     .expect("task view implies we've received an update");
+                let break_on_completion = state.breaks_on_completion(view.task_id());
+                let parent = view.parent_id().and_then(|id| state.tasks_state().task(id));
+                let parent = parent.map(|parent| parent.borrow());
+                let woken_by = view
+                    .last_woken_by()
+                    .and_then(|id| state.resources_state().resource(id));
+                let woken_by = woken_by.map(|resource| resource.borrow());
+
+                // While a different task is marked as the baseline, compare
+                // against it side-by-side.
+                let baseline_id = self.baseline.as_ref().and_then(|b| b.task_id());
+                let is_baseline = baseline_id == Some(view.task_id());
+                let (task_area, compare_area) = match self.baseline {
+                    Some(ref baseline) if baseline_id.is_some() && !is_baseline => {
+                        let chunks = layout::Layout::default()
+                            .direction(layout::Direction::Horizontal)
+                            .constraints(
+                                [
+                                    layout::Constraint::Percentage(50),
+                                    layout::Constraint::Percentage(50),
+                                ]
+                                .as_ref(),
+                            )
+                            .split(area);
+                        (chunks[0], Some((baseline, chunks[1])))
+                    }
+                    _ => (area, None),
+                };
+                view.render(
+                    &self.styles,
+                    frame,
+                    task_area,
+                    now,
+                    state.window(),
+                    break_on_completion,
+                    is_baseline,
+                    parent.as_ref().and_then(|parent| parent.name()),
+                    woken_by.as_ref().map(|resource| resource.concrete_type()),
+                );
+                if let Some((baseline, compare_area)) = compare_area {
+                    view.render_comparison(&self.styles, frame, compare_area, now, baseline);
+                }
+            }
+            ViewState::ResourceInstance(ref mut view) => {
+                let now = state
+                    .last_updated_at()
+                    .expect("resource view implies we've received an update");
                 view.render(&self.styles, frame, area, now);
             }
+            ViewState::Runtime => {
+                self::runtime::render(&self.styles, frame, area, state);
+            }
+            ViewState::Timeline(ref mut view) => {
+                view.render(&self.styles, frame, area, state);
+            }
+            ViewState::Trends => {
+                self::trends::render(&self.styles, frame, area, state);
+            }
+        }
+
+        match self.popup {
+            Some(Popup::ProcessInfo) => self.render_process_info(frame, area, state),
+            Some(Popup::Connection) => self.render_connection_stats(frame, area, conn_stats),
+            Some(Popup::NewBookmark(ref label)) => {
+                self.render_new_bookmark(frame, area, state, label)
+            }
+            Some(Popup::Bookmarks(selected)) => self.render_bookmarks(frame, area, state, selected),
+            Some(Popup::SavedFilters(selected)) => self.render_saved_filters(frame, area, selected),
+            Some(Popup::ConfirmClear) => self.render_confirm_clear(frame, area, state),
+            Some(Popup::TaskFields(id, popup)) => {
+                let scroll = self.render_task_fields(frame, area, state, id, popup);
+                self.clamp_fields_scroll(scroll);
+            }
+            Some(Popup::ResourceFields(id, popup)) => {
+                let scroll = self.render_resource_fields(frame, area, state, id, popup);
+                self.clamp_fields_scroll(scroll);
+            }
+            Some(Popup::Warnings(scroll)) => {
+                let scroll = self.render_warnings(frame, area, state, scroll);
+                self.clamp_fields_scroll(Some(scroll));
+            }
+            None => {}
         }
 
         state.retain_active();
     }
 
+    fn render_process_info<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+    ) {
+        let field = |name: &'static str, value: Option<&str>| {
+            Spans::from(vec![
+                bold(name),
+                Span::raw(value.unwrap_or("<unknown>").to_string()),
+            ])
+        };
+        let text = match state.process_info() {
+            Some(info) => vec![
+                field("Executable: ", info.executable.as_deref()),
+                field("PID: ", Some(&info.pid.to_string())),
+                field("Hostname: ", info.hostname.as_deref()),
+                field("Tokio version: ", info.tokio_version.as_deref()),
+                field("Subscriber version: ", info.subscriber_version.as_deref()),
+            ],
+            None => vec![Spans::from(
+                "The instrumented process did not provide any information about itself.",
+            )],
+        };
+
+        let popup = Paragraph::new(text)
+            .block(self.styles.border_block().title("Process Info"))
+            .wrap(Wrap { trim: true });
+        // add 2 for the top and bottom borders
+        let area = popup_area(60, 5 + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    /// Renders the console's own recent diagnostics, newest last.
+    pub(crate) fn render_debug_log<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        debug_log: &DebugLog,
+    ) {
+        use tracing::Level;
+
+        // subtract 2 for the top and bottom borders
+        let entries = debug_log.recent(area.height.saturating_sub(2) as usize);
+        let text: Vec<Spans> = entries
+            .into_iter()
+            .map(|entry| {
+                let color = match entry.level {
+                    Level::ERROR => style::Color::Red,
+                    Level::WARN => style::Color::Yellow,
+                    Level::INFO => style::Color::Green,
+                    Level::DEBUG => style::Color::Blue,
+                    Level::TRACE => style::Color::Magenta,
+                };
+                Spans::from(vec![
+                    Span::raw(humantime::format_rfc3339_millis(entry.at).to_string()),
+                    Span::raw(" "),
+                    Span::styled(format!("{:>5}", entry.level), self.styles.fg(color)),
+                    Span::raw(" "),
+                    bold(format!("{}:", entry.target)),
+                    Span::raw(" "),
+                    Span::raw(entry.message),
+                ])
+            })
+            .collect();
+
+        let pane = Paragraph::new(text).block(self.styles.border_block().title("Debug Log (F12)"));
+        frame.render_widget(Clear, area);
+        frame.render_widget(pane, area);
+    }
+
+    fn render_connection_stats<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        stats: &conn::Stats,
+    ) {
+        let field =
+            |name: &'static str, value: String| Spans::from(vec![bold(name), Span::raw(value)]);
+        let dur = |d: std::time::Duration| self.styles.duration(d);
+        let last_update = stats
+            .last_update_at
+            .map(|at| {
+                let ago = SystemTime::now().duration_since(at).unwrap_or_default();
+                format!("{} ago", dur(ago))
+            })
+            .unwrap_or_else(|| "never".to_string());
+        let text = vec![
+            field("Updates received: ", stats.updates.to_string()),
+            field("Bytes received: ", format_bytes(stats.bytes as f64)),
+            field(
+                "Throughput: ",
+                format!("{}/s", format_bytes(stats.bytes_per_sec())),
+            ),
+            field("Last update: ", last_update),
+            field(
+                "Last update latency: ",
+                stats
+                    .last_latency
+                    .map(dur)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            field(
+                "Lag: ",
+                stats
+                    .lag()
+                    .map(|lag| format!("{} (~{} updates behind)", dur(lag.delay), lag.queued))
+                    .unwrap_or_else(|| "none".to_string()),
+            ),
+            field(
+                "Publish interval: ",
+                match (stats.publish_interval, stats.observed_interval()) {
+                    (Some(interval), _) => format!("{} (requested)", dur(interval)),
+                    (None, Some(interval)) => dur(interval),
+                    (None, None) => "-".to_string(),
+                },
+            ),
+            field("Decode errors: ", stats.decode_errors.to_string()),
+            field("Stream errors: ", stats.stream_errors.to_string()),
+            field("Reconnects: ", stats.reconnects().to_string()),
+            field(
+                "Last error: ",
+                stats.last_error.clone().unwrap_or_else(|| "-".to_string()),
+            ),
+        ];
+        let height = text.len() as u16;
+
+        let popup = Paragraph::new(text)
+            .block(self.styles.border_block().title("Connection"))
+            .wrap(Wrap { trim: true });
+        // add 2 for the top and bottom borders, and leave room for a long
+        // error message to wrap.
+        let area = popup_area(70, height + 2 + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    fn render_new_bookmark<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+        label: &str,
+    ) {
+        let (update, _) = state.update_position();
+        let text = vec![
+            Spans::from(vec![
+                bold("Update: "),
+                Span::raw(format!("{}{}", update, format_at(state.last_updated_at()))),
+            ]),
+            Spans::from(vec![
+                bold("Label: "),
+                Span::raw(label.to_string()),
+                Span::styled(
+                    "_",
+                    Style::default().add_modifier(style::Modifier::SLOW_BLINK),
+                ),
+            ]),
+            Spans::from(vec![
+                bold(self.styles.if_utf8("\u{21B5}", "enter")),
+                Span::raw(" = add bookmark, "),
+                bold("esc"),
+                Span::raw(" = cancel"),
+            ]),
+        ];
+
+        let popup = Paragraph::new(text)
+            .block(self.styles.border_block().title("New Bookmark"))
+            .wrap(Wrap { trim: false });
+        // add 2 for the top and bottom borders
+        let area = popup_area(60, 3 + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    fn render_confirm_clear<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+    ) {
+        let tasks = state
+            .tasks_state()
+            .tasks()
+            .filter(|task| task.borrow().is_completed())
+            .count();
+        let resources = state
+            .resources_state()
+            .resources()
+            .filter(|resource| resource.borrow().dropped())
+            .count();
+        let text = vec![
+            Spans::from(format!(
+                "Clear {} completed tasks and {} dropped resources, and check the remaining tasks for warnings again?",
+                tasks, resources
+            )),
+            Spans::from(""),
+            Spans::from(vec![
+                bold("y"),
+                Span::raw(" = clear, "),
+                bold("n"),
+                Span::raw(" = cancel"),
+            ]),
+        ];
+
+        let popup = Paragraph::new(text)
+            .block(self.styles.border_block().title("Clear"))
+            .wrap(Wrap { trim: true });
+        // add 2 for the top and bottom borders
+        let area = popup_area(60, 5 + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    fn render_bookmarks<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+        selected: usize,
+    ) {
+        let bookmarks = state.bookmarks();
+        let mut text: Vec<Spans> = bookmarks
+            .iter()
+            .enumerate()
+            .map(|(i, bookmark)| {
+                let marker = if i == selected {
+                    TABLE_HIGHLIGHT_SYMBOL
+                } else {
+                    "   "
+                };
+                let mut spans = vec![
+                    Span::raw(marker),
+                    bold(format!("update {}", bookmark.update)),
+                    Span::raw(format_at(bookmark.at)),
+                ];
+                if let Some(ref label) = bookmark.label {
+                    spans.push(Span::raw(format!(": {}", label)));
+                }
+                Spans::from(spans)
+            })
+            .collect();
+        if text.is_empty() {
+            text.push(Spans::from(vec![
+                Span::raw("No bookmarks yet. Press "),
+                bold("b"),
+                Span::raw(" to bookmark the current update."),
+            ]));
+        } else {
+            text.push(Spans::from(vec![
+                bold(self.styles.if_utf8("\u{21B5}", "enter")),
+                Span::raw(" = jump to bookmark (pauses), "),
+                bold("esc"),
+                Span::raw(" = close"),
+            ]));
+        }
+        let height = text.len() as u16;
+
+        let popup = Paragraph::new(text).block(self.styles.border_block().title("Bookmarks"));
+        // add 2 for the top and bottom borders
+        let area = popup_area(80, height + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    fn render_saved_filters<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        selected: usize,
+    ) {
+        let mut text: Vec<Spans> = self
+            .saved_filters
+            .iter()
+            .enumerate()
+            .map(|(i, saved)| {
+                let marker = if i == selected {
+                    TABLE_HIGHLIGHT_SYMBOL
+                } else {
+                    "   "
+                };
+                // Only the first nine filters have a number key.
+                let key = if i < 9 {
+                    format!("{} ", i + 1)
+                } else {
+                    "  ".to_string()
+                };
+                Spans::from(vec![
+                    Span::raw(marker),
+                    bold(key),
+                    bold(saved.name().to_string()),
+                    Span::raw(format!(": {}", saved.query())),
+                ])
+            })
+            .collect();
+        if text.is_empty() {
+            text.push(Spans::from(vec![
+                Span::raw("No saved filters. Save filters with "),
+                bold("--filter <NAME = QUERY>"),
+                Span::raw("."),
+            ]));
+        } else {
+            text.push(Spans::from(vec![
+                bold("1-9"),
+                Span::raw(", "),
+                bold(self.styles.if_utf8("\u{21B5}", "enter")),
+                Span::raw(" = apply filter, "),
+                bold("esc"),
+                Span::raw(" = close"),
+            ]));
+        }
+        let height = text.len() as u16;
+
+        let popup = Paragraph::new(text).block(self.styles.border_block().title("Saved Filters"));
+        // add 2 for the top and bottom borders
+        let area = popup_area(80, height + 2, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+
+    /// Returns the popup showing the complete fields of the selected or
+    /// inspected task or resource, if there is one.
+    fn fields_popup(&self) -> Option<Popup> {
+        match self.state {
+            ViewState::TasksList => {
+                let task = self.tasks_list.selected_item().upgrade()?;
+                let id = task.borrow().id();
+                Some(Popup::TaskFields(id, FieldsPopup::default()))
+            }
+            ViewState::TaskInstance(ref view) => {
+                Some(Popup::TaskFields(view.task_id(), FieldsPopup::default()))
+            }
+            ViewState::ResourcesList => {
+                let resource = self.resources_list.selected_item().upgrade()?;
+                let id = resource.borrow().id();
+                Some(Popup::ResourceFields(id, FieldsPopup::default()))
+            }
+            ViewState::ResourceInstance(ref view) => Some(Popup::ResourceFields(
+                view.resource_id(),
+                FieldsPopup::default(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Renders every task with each warning, the worst first, and then the
+    /// warnings which stopped firing, returning how far the popup could be
+    /// scrolled.
+    fn render_warnings<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+        scroll: u16,
+    ) -> u16 {
+        let tasks_state = state.tasks_state();
+        let history = state.warning_history();
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let mut text = Vec::new();
+        for warning in tasks_state.warnings() {
+            if !text.is_empty() {
+                text.push(Spans::from(""));
+            }
+            text.push(Spans::from(vec![
+                self.styles.warning_wide(),
+                bold(format!("{} {}", warning.count(), warning.summary())),
+            ]));
+            for task in tasks_state.warned_by(warning) {
+                let task = task.borrow();
+                let mut line = vec![Span::raw("  task "), bold(task.id().to_string())];
+                if let Some(name) = task.name() {
+                    line.push(Span::raw(format!(" ({})", self.styles.redact(name))));
+                }
+                if let Some(fired_at) = history.fired_at(task.id(), warning.summary()) {
+                    line.push(Span::raw(", fired "));
+                    line.extend(self.styles.timestamp(fired_at, now));
+                }
+                line.push(Span::raw(format!(": {}", warning.format(&task))));
+                text.push(Spans::from(line));
+            }
+        }
+        if text.is_empty() {
+            text.push(Spans::from("No tasks have warnings."));
+        }
+
+        let mut resolved = history.resolved().peekable();
+        if resolved.peek().is_some() {
+            text.push(Spans::from(""));
+            text.push(Spans::from(bold("Resolved")));
+        }
+        for entry in resolved {
+            let mut line = vec![Span::raw("  task "), bold(entry.task_id.to_string())];
+            if let Some(ref name) = entry.task_name {
+                line.push(Span::raw(format!(
+                    " ({})",
+                    self.styles.redact(name.as_str())
+                )));
+            }
+            line.push(Span::raw(", fired "));
+            line.extend(self.styles.timestamp(entry.fired_at, now));
+            if let Some(cleared_at) = entry.cleared_at {
+                line.push(Span::raw(", cleared "));
+                line.extend(self.styles.timestamp(cleared_at, now));
+            }
+            line.push(Span::raw(format!("; at worst: {}", entry.peak)));
+            text.push(Spans::from(line));
+        }
+        self.render_fields(
+            frame,
+            area,
+            "Warnings".to_string(),
+            text,
+            Vec::new(),
+            scroll,
+        )
+    }
+
+    /// Renders the complete fields of a task, returning how far the popup
+    /// could be scrolled, if the task is known.
+    fn render_task_fields<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+        id: u64,
+        popup: FieldsPopup,
+    ) -> Option<u16> {
+        let task = state.tasks_state().task(id)?;
+        let task = task.borrow();
+        let mut text = vec![
+            Spans::from(vec![bold("ID: "), Span::raw(task.id().to_string())]),
+            Spans::from(vec![
+                bold("Target: "),
+                Span::raw(self.styles.redact(task.target()).into_owned()),
+            ]),
+            Spans::from(vec![
+                bold("Location: "),
+                Span::raw(self.styles.redact(task.full_location()).into_owned()),
+            ]),
+        ];
+        if let Some(name) = task.name() {
+            text.push(Spans::from(vec![
+                bold("Name: "),
+                Span::raw(self.styles.redact(name).into_owned()),
+            ]));
+        }
+        text.push(Spans::from(""));
+        if popup.pretty {
+            text.extend(Field::make_pretty(&self.styles, task.fields()));
+        } else {
+            text.extend(
+                task.formatted_fields()
+                    .iter()
+                    .map(|field| Spans::from(self.styles.redact_field(field))),
+            );
+        }
+        let controls = vec![
+            bold("p"),
+            Span::raw(if popup.pretty {
+                " = don't pretty-print, "
+            } else {
+                " = pretty-print, "
+            }),
+        ];
+        Some(self.render_fields(
+            frame,
+            area,
+            format!("Task {} Fields", id),
+            text,
+            controls,
+            popup.scroll,
+        ))
+    }
+
+    /// Renders the complete attributes of a resource, returning how far the
+    /// popup could be scrolled, if the resource is known.
+    fn render_resource_fields<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+        id: u64,
+        popup: FieldsPopup,
+    ) -> Option<u16> {
+        let resource = state.resources_state().resource(id)?;
+        let resource = resource.borrow();
+        let mut text = vec![
+            Spans::from(vec![bold("ID: "), Span::raw(resource.id().to_string())]),
+            Spans::from(vec![
+                bold("Target: "),
+                Span::raw(self.styles.redact(resource.target()).into_owned()),
+            ]),
+            Spans::from(vec![
+                bold("Type: "),
+                Span::raw(resource.concrete_type().to_string()),
+            ]),
+            Spans::from(vec![
+                bold("Location: "),
+                Span::raw(self.styles.redact(resource.full_location()).into_owned()),
+            ]),
+            Spans::from(""),
+        ];
+        text.extend(
+            resource
+                .formatted_attributes()
+                .iter()
+                .cloned()
+                .map(Spans::from),
+        );
+        Some(self.render_fields(
+            frame,
+            area,
+            format!("Resource {} Attributes", id),
+            text,
+            Vec::new(),
+            popup.scroll,
+        ))
+    }
+
+    /// Renders a scrollable popup of fields, wrapping values too long to fit,
+    /// followed by its controls.
+    ///
+    /// Returns `scroll`, limited to how far the popup can be scrolled before
+    /// its last line reaches the top of the popup.
+    fn render_fields<B: tui::backend::Backend>(
+        &self,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        title: String,
+        mut text: Vec<Spans<'static>>,
+        mut controls: Vec<Span<'static>>,
+        scroll: u16,
+    ) -> u16 {
+        controls.extend([
+            bold(self.styles.if_utf8("\u{2191}\u{2193}", "up, down")),
+            Span::raw(" = scroll, "),
+            bold("esc"),
+            Span::raw(" = close"),
+        ]);
+        text.push(Spans::from(""));
+        text.push(Spans::from(controls));
+        let area = popup_area(area.width * 4 / 5, area.height * 3 / 4, area);
+        // subtract 2 for the borders
+        let text = wrap(text, area.width.saturating_sub(2) as usize);
+        let max_scroll = text
+            .len()
+            .saturating_sub(area.height.saturating_sub(2) as usize);
+        let scroll = cmp::min(scroll as usize, max_scroll) as u16;
+        let popup = Paragraph::new(text)
+            .block(self.styles.border_block().title(title))
+            .scroll((scroll, 0));
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+        scroll
+    }
+
+    /// Limits how far the popup of fields is scrolled to `scroll`, so that
+    /// scrolling back up from past its end takes effect immediately.
+    fn clamp_fields_scroll(&mut self, scroll: Option<u16>) {
+        let scroll = match scroll {
+            Some(scroll) => scroll,
+            None => return,
+        };
+        match self.popup {
+            Some(Popup::TaskFields(_, ref mut popup))
+            | Some(Popup::ResourceFields(_, ref mut popup)) => {
+                popup.scroll = popup.scroll.min(scroll)
+            }
+            Some(Popup::Warnings(ref mut popup)) => *popup = (*popup).min(scroll),
+            _ => {}
+        }
+    }
+
+    fn toggle_popup(&mut self, popup: Popup) {
+        if self.popup.as_ref() == Some(&popup) {
+            self.popup = None;
+        } else {
+            self.popup = Some(popup);
+        }
+    }
+
     pub(crate) fn current_view(&self) -> &ViewState {
         &self.state
     }
+
+    /// Returns `true` if rows of the current table are being selected, in
+    /// which case the space key selects rows, rather than pausing.
+    pub(crate) fn is_selecting(&self) -> bool {
+        match self.state {
+            ViewState::TasksList => self.tasks_list.is_selecting(),
+            ViewState::ResourcesList => self.resources_list.is_selecting(),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a popup, a search, or a filter is capturing text
+    /// input, in which case global keys should be passed on to the view
+    /// instead.
+    ///
+    /// The popup of saved filters captures the number keys, which apply
+    /// the filters rather than switching targets, and the confirmation for
+    /// clearing captures `n`, which cancels rather than stepping forward.
+    /// The fields popups capture `p`, which toggles pretty-printing rather
+    /// than stepping back, and like the warnings popup, they stay modal.
+    pub(crate) fn is_editing(&self) -> bool {
+        match self.state {
+            ViewState::TaskInstance(ref view) if view.is_searching() => true,
+            ViewState::TasksList if self.tasks_list.is_filtering() => true,
+            ViewState::ResourcesList if self.resources_list.is_filtering() => true,
+            _ => matches!(
+                self.popup,
+                Some(Popup::NewBookmark(_))
+                    | Some(Popup::SavedFilters(_))
+                    | Some(Popup::ConfirmClear)
+                    | Some(Popup::TaskFields(..))
+                    | Some(Popup::ResourceFields(..))
+                    | Some(Popup::Warnings(_))
+            ),
+        }
+    }
+
+    /// Returns `true` if text typed in the current table's filter is waiting
+    /// to be applied, in which case the view should be redrawn soon.
+    pub(crate) fn is_filter_pending(&self) -> bool {
+        match self.state {
+            ViewState::TasksList => self.tasks_list.is_filter_pending(),
+            ViewState::ResourcesList => self.resources_list.is_filter_pending(),
+            _ => false,
+        }
+    }
+
+    /// Returns the ID of the task being inspected, if the task details view
+    /// is open.
+    pub(crate) fn selected_task(&self) -> Option<u64> {
+        match self.state {
+            ViewState::TaskInstance(ref view) => Some(view.task_id()),
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the resource being inspected, if the resource
+    /// details view is open.
+    pub(crate) fn selected_resource(&self) -> Option<u64> {
+        match self.state {
+            ViewState::ResourceInstance(ref view) => Some(view.resource_id()),
+            _ => None,
+        }
+    }
+}
+
+/// Scrolls a popup of text by `event`, returning `false` if it isn't a key
+/// for scrolling.
+fn scroll_popup(scroll: &mut u16, event: input::Event) -> bool {
+    match event {
+        key!(Up) => *scroll = scroll.saturating_sub(1),
+        key!(Down) => *scroll = scroll.saturating_add(1),
+        key!(PageUp) => *scroll = scroll.saturating_sub(10),
+        key!(PageDown) => *scroll = scroll.saturating_add(10),
+        key!(Home) => *scroll = 0,
+        // The popup stops scrolling at its last line.
+        key!(End) => *scroll = u16::MAX,
+        _ => return false,
+    }
+    true
 }
 
 pub(crate) fn bold<'a>(text: impl Into<Cow<'a, str>>) -> Span<'a> {
     Span::styled(text, Style::default().add_modifier(style::Modifier::BOLD))
 }
 
+/// Formats the time of an update, if it is known.
+fn format_at(at: Option<SystemTime>) -> String {
+    at.map(|at| format!(" at {}", humantime::format_rfc3339_millis(at)))
+        .unwrap_or_default()
+}
+
+/// Formats a number of bytes with a binary unit suffix.
+pub(crate) fn format_bytes(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Draws a scrollbar down the right edge of `area`, over the border of a
+/// block drawn in it, showing which of `total` lines are visible when
+/// `visible` lines fit and the content is scrolled down by `offset` lines.
+///
+/// Nothing is drawn if every line fits.
+pub(crate) fn render_scrollbar<B: tui::backend::Backend>(
+    styles: &Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    offset: usize,
+    visible: usize,
+    total: usize,
+) {
+    if total <= visible || area.width == 0 || area.height <= 2 {
+        return;
+    }
+    // Leave the corners of the border alone.
+    let track = (area.height - 2) as usize;
+    let thumb = (track * visible / total).max(1);
+    let start = offset.min(total - visible) * (track - thumb) / (total - visible);
+    let lines = (0..track)
+        .map(|i| {
+            if (start..start + thumb).contains(&i) {
+                Spans::from(styles.if_utf8("\u{2588}", "#"))
+            } else {
+                Spans::from(styles.if_utf8("\u{2502}", "|"))
+            }
+        })
+        .collect::<Vec<_>>();
+    let area = layout::Rect {
+        x: area.x + area.width - 1,
+        y: area.y + 1,
+        width: 1,
+        height: area.height - 2,
+    };
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Word-wraps `lines` to `width` columns, preserving their styles.
+///
+/// Lines are broken at whitespace where possible, and words too long to fit
+/// on a line of their own are broken wherever they reach the edge. Unlike
+/// wrapping with `Paragraph::wrap`, this makes the number of lines displayed
+/// known up front, so that panes can be sized to fit them.
+pub(crate) fn wrap<'a>(lines: Vec<Spans<'a>>, width: usize) -> Vec<Spans<'a>> {
+    let mut wrapped = Vec::with_capacity(lines.len());
+    for line in lines {
+        let chars = line
+            .0
+            .iter()
+            .flat_map(|span| span.content.chars().map(move |c| (c, span.style)))
+            .collect::<Vec<_>>();
+        let line_width = chars
+            .iter()
+            .map(|&(c, _)| c.width().unwrap_or(0))
+            .sum::<usize>();
+        if width == 0 || line_width <= width {
+            wrapped.push(line);
+            continue;
+        }
+
+        let mut start = 0;
+        while start < chars.len() {
+            // Find how many characters fit, taking at least one so that a
+            // character wider than the line can't stop the wrapping.
+            let mut end = start;
+            let mut used = 0;
+            while let Some(&(c, _)) = chars.get(end) {
+                let c_width = c.width().unwrap_or(0);
+                if used + c_width > width && end > start {
+                    break;
+                }
+                used += c_width;
+                end += 1;
+            }
+            // Break at the last whitespace that fits, or mid-word if there
+            // is none.
+            let split = if end == chars.len() {
+                end
+            } else {
+                (start + 1..=end)
+                    .rev()
+                    .find(|&i| chars[i].0.is_whitespace())
+                    .unwrap_or(end)
+            };
+            wrapped.push(styled_chars(&chars[start..split]));
+            start = split;
+            while matches!(chars.get(start), Some((c, _)) if c.is_whitespace()) {
+                start += 1;
+            }
+        }
+    }
+    wrapped
+}
+
+/// Joins runs of characters with the same style into spans.
+fn styled_chars<'a>(chars: &[(char, Style)]) -> Spans<'a> {
+    let mut spans: Vec<Span<'a>> = Vec::new();
+    for &(c, style) in chars {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push(c),
+            _ => spans.push(Span::styled(c.to_string(), style)),
+        }
+    }
+    Spans::from(spans)
+}
+
+/// Truncates `s` to at most `width` terminal columns, and pads it with spaces
+/// to exactly `width` columns.
+///
+/// Unlike padding with `format!`, which counts characters, this keeps
+/// strings containing wide characters, such as CJK and emoji, aligned.
+pub(crate) fn fit_width(s: &str, width: usize) -> String {
+    let mut fitted = String::with_capacity(width);
+    let mut used = 0;
+    for c in s.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > width {
+            break;
+        }
+        used += c_width;
+        fitted.push(c);
+    }
+    fitted.push_str(&" ".repeat(width - used));
+    fitted
+}
+
+/// Returns a `width` by `height` rectangle centered in `area`, clamped to fit
+/// inside it.
+pub(crate) fn popup_area(width: u16, height: u16, area: layout::Rect) -> layout::Rect {
+    let width = cmp::min(width, area.width);
+    let height = cmp::min(height, area.height);
+    layout::Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 impl Width {
     pub(crate) fn new(curr: u16) -> Self {
         Self { curr }
     }
 
+    /// Widens the column to fit `s`, measured in terminal columns, so that
+    /// wide characters such as CJK and emoji keep the table aligned.
     pub(crate) fn update_str<S: AsRef<str>>(&mut self, s: S) -> S {
-        self.update_len(s.as_ref().len());
+        self.update_len(s.as_ref().width());
         s
     }
     pub(crate) fn update_len(&mut self, len: usize) {
@@ -0,0 +1,161 @@
+//! Pretty-printing of field values which are formatted densely on one line.
+use crate::view;
+use serde_json::Value;
+use std::mem;
+use tui::{
+    style::Color,
+    text::{Span, Spans},
+};
+
+/// The indentation added for each level of nesting.
+const INDENT: &str = "    ";
+
+/// Re-indents a `Debug`-formatted value, such as `Foo { a: 1, b: [2, 3] }`,
+/// so that each field or element of its nested braces, brackets, and
+/// parentheses is on its own line, much like `{:#?}` formats it.
+///
+/// Quoted strings are left untouched. Empty pairs, such as `()` or `{}`, are
+/// kept on one line.
+pub(crate) fn debug(value: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut depth = 0usize;
+    // The quote character of the string or char literal being read, if any.
+    let mut quoted = None;
+    let mut escaped = false;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(quote) = quoted {
+            line.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                quoted = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => {
+                quoted = Some(c);
+                line.push(c);
+            }
+            '{' | '[' | '(' => {
+                line.push(c);
+                let close = closing(c);
+                if chars.peek() == Some(&close) {
+                    line.push(close);
+                    chars.next();
+                    continue;
+                }
+                end_line(&mut lines, &mut line);
+                depth += 1;
+                line.push_str(&INDENT.repeat(depth));
+            }
+            '}' | ']' | ')' => {
+                end_line(&mut lines, &mut line);
+                depth = depth.saturating_sub(1);
+                line.push_str(&INDENT.repeat(depth));
+                line.push(c);
+            }
+            ',' => {
+                line.push(c);
+                end_line(&mut lines, &mut line);
+                line.push_str(&INDENT.repeat(depth));
+            }
+            // Skip the spaces following a line break.
+            ' ' if line.trim().is_empty() => {}
+            c => line.push(c),
+        }
+    }
+    end_line(&mut lines, &mut line);
+    lines
+}
+
+/// Parses a field value as a JSON object or array, if it is one.
+///
+/// A quoted string containing JSON, as a `Debug`-formatted `String` would
+/// be, is parsed as the JSON it contains.
+pub(crate) fn parse_json(value: &str) -> Option<Value> {
+    match serde_json::from_str(value.trim()).ok()? {
+        value @ Value::Object(_) | value @ Value::Array(_) => Some(value),
+        Value::String(inner) => parse_json(&inner),
+        _ => None,
+    }
+}
+
+/// Formats a JSON value indented across multiple lines, with syntax
+/// highlighting.
+pub(crate) fn json(styles: &view::Styles, value: &Value) -> Vec<Spans<'static>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    json_value(styles, value, 0, &mut line, &mut lines);
+    lines.push(Spans::from(line));
+    lines
+}
+
+fn json_value(
+    styles: &view::Styles,
+    value: &Value,
+    depth: usize,
+    line: &mut Vec<Span<'static>>,
+    lines: &mut Vec<Spans<'static>>,
+) {
+    match value {
+        Value::Null => line.push(Span::styled("null", styles.fg(Color::Magenta))),
+        Value::Bool(value) => line.push(Span::styled(value.to_string(), styles.fg(Color::Magenta))),
+        Value::Number(value) => line.push(Span::styled(value.to_string(), styles.fg(Color::Cyan))),
+        Value::String(_) => line.push(Span::styled(value.to_string(), styles.fg(Color::Green))),
+        Value::Array(values) if values.is_empty() => line.push(Span::raw("[]")),
+        Value::Object(members) if members.is_empty() => line.push(Span::raw("{}")),
+        Value::Array(values) => {
+            line.push(Span::raw("["));
+            for (i, value) in values.iter().enumerate() {
+                lines.push(Spans::from(mem::take(line)));
+                line.push(Span::raw(INDENT.repeat(depth + 1)));
+                json_value(styles, value, depth + 1, line, lines);
+                if i + 1 < values.len() {
+                    line.push(Span::raw(","));
+                }
+            }
+            lines.push(Spans::from(mem::take(line)));
+            line.push(Span::raw(format!("{}]", INDENT.repeat(depth))));
+        }
+        Value::Object(members) => {
+            line.push(Span::raw("{"));
+            for (i, (key, value)) in members.iter().enumerate() {
+                lines.push(Spans::from(mem::take(line)));
+                line.push(Span::raw(INDENT.repeat(depth + 1)));
+                line.push(Span::styled(
+                    Value::from(key.as_str()).to_string(),
+                    styles.fg(Color::LightBlue),
+                ));
+                line.push(Span::raw(": "));
+                json_value(styles, value, depth + 1, line, lines);
+                if i + 1 < members.len() {
+                    line.push(Span::raw(","));
+                }
+            }
+            lines.push(Spans::from(mem::take(line)));
+            line.push(Span::raw(format!("{}}}", INDENT.repeat(depth))));
+        }
+    }
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '{' => '}',
+        '[' => ']',
+        _ => ')',
+    }
+}
+
+/// Adds `line` to `lines` unless it is blank, and clears it.
+fn end_line(lines: &mut Vec<String>, line: &mut String) {
+    if !line.trim().is_empty() {
+        lines.push(line.trim_end().to_string());
+    }
+    line.clear();
+}
@@ -0,0 +1,213 @@
+use crate::{
+    input,
+    state::{
+        resources::{Details, Resource},
+        ResourceDetailsRef,
+    },
+    view::{
+        self, bold,
+        mini_histogram::{self, HistogramMetadata, MiniHistogram},
+    },
+};
+use std::{cell::RefCell, rc::Rc, time::SystemTime};
+use tui::{
+    layout::{self, Layout},
+    text::{Span, Spans, Text},
+    widgets::{Block, Paragraph},
+};
+
+pub(crate) struct ResourceView {
+    resource: Rc<RefCell<Resource>>,
+    details: ResourceDetailsRef,
+}
+
+impl ResourceView {
+    pub(super) fn new(resource: Rc<RefCell<Resource>>, details: ResourceDetailsRef) -> Self {
+        ResourceView { resource, details }
+    }
+
+    pub(crate) fn resource_id(&self) -> u64 {
+        self.resource.borrow().id()
+    }
+
+    pub(crate) fn update_input(&mut self, _event: input::Event) {}
+
+    pub(crate) fn render<B: tui::backend::Backend>(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        now: SystemTime,
+    ) {
+        let resource = &*self.resource.borrow();
+        let details_ref = self.details.borrow();
+        let details = details_ref
+            .as_ref()
+            .filter(|details| details.resource_id() == resource.id());
+
+        let controls = Spans::from(vec![
+            Span::raw("controls: "),
+            bold(styles.if_utf8("\u{238B} esc", "esc")),
+            Span::raw(" = return to resource list, "),
+            bold("f"),
+            Span::raw(" = full attributes, "),
+            bold("q"),
+            Span::raw(" = quit"),
+        ]);
+
+        let mut overview = Vec::with_capacity(7);
+        overview.push(Spans::from(vec![
+            bold("ID: "),
+            Span::raw(resource.id().to_string()),
+        ]));
+        overview.push(Spans::from(vec![
+            bold("Kind: "),
+            Span::raw(resource.kind()),
+        ]));
+        overview.push(Spans::from(vec![
+            bold("Type: "),
+            Span::raw(resource.concrete_type()),
+        ]));
+        overview.push(Spans::from(vec![
+            bold("Target: "),
+            Span::raw(styles.redact(resource.target())),
+        ]));
+        overview.push(Spans::from(vec![
+            bold("Location: "),
+            Span::raw(styles.redact(resource.location())),
+        ]));
+        let mut created = vec![bold("Created: ")];
+        created.extend(styles.timestamp(resource.created_at(), now));
+        overview.push(Spans::from(created));
+        if let Some(at) = resource.dropped_at() {
+            let mut dropped = vec![bold("Dropped: ")];
+            dropped.extend(styles.timestamp(at, now));
+            overview.push(Spans::from(dropped));
+        }
+        overview.push(Spans::from(vec![
+            bold("Total Time: "),
+            styles.time_units(styles.duration(resource.total(now))),
+        ]));
+        overview.push(Spans::from(vec![
+            bold("Async Ops: "),
+            Span::raw(
+                details
+                    .map(|details| details.async_ops().to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]));
+        for warning in resource.warnings() {
+            overview.push(Spans::from(vec![
+                styles.warning_wide(),
+                Span::raw(warning.format(resource)),
+            ]));
+        }
+
+        // Wrap long values, such as locations, rather than cutting them off,
+        // and make the stats tall enough to fit every line.
+        // subtract 2 for the borders
+        let overview = view::wrap(overview, area.width.saturating_sub(2) as usize);
+        // add 2 for the borders
+        let stats_height = overview.len() as u16 + 2;
+
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(
+                [
+                    // controls
+                    layout::Constraint::Length(1),
+                    // resource stats
+                    layout::Constraint::Length(stats_height),
+                    // async op busy times
+                    layout::Constraint::Length(9),
+                    // attributes
+                    layout::Constraint::Percentage(60),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        let (controls_area, stats_area, busy_area, attributes_area) =
+            (chunks[0], chunks[1], chunks[2], chunks[3]);
+
+        // Only split the histogram area in half if we're also drawing a
+        // sparkline (which requires UTF-8 characters).
+        let busy_area = if styles.utf8 {
+            Layout::default()
+                .direction(layout::Direction::Horizontal)
+                .constraints(
+                    [
+                        // 24 chars is long enough for the title "Busy Times Percentiles"
+                        layout::Constraint::Length(24),
+                        layout::Constraint::Min(50),
+                    ]
+                    .as_ref(),
+                )
+                .split(busy_area)
+        } else {
+            vec![busy_area]
+        };
+
+        let mut attributes = Text::default();
+        attributes.extend(
+            resource
+                .formatted_attributes()
+                .iter()
+                .cloned()
+                .map(Spans::from),
+        );
+
+        // If UTF-8 is disabled we can't draw the histogram sparkline.
+        if styles.utf8 {
+            let sparkline_area = busy_area[1];
+            let (chart_data, metadata) = details
+                .map(|d| d.make_chart_data(sparkline_area.width - 3))
+                .unwrap_or_default();
+
+            let histogram_sparkline = MiniHistogram::default()
+                .block(styles.border_block().title("Async Op Busy Times Histogram"))
+                .data(&chart_data)
+                .metadata(metadata)
+                .duration_precision(2);
+
+            frame.render_widget(histogram_sparkline, sparkline_area);
+        }
+
+        let resource_widget =
+            Paragraph::new(overview).block(styles.border_block().title("Resource"));
+        let attributes = view::wrap(
+            attributes.lines,
+            attributes_area.width.saturating_sub(2) as usize,
+        );
+        // subtract 2 for the borders
+        let hidden = attributes
+            .len()
+            .saturating_sub(attributes_area.height.saturating_sub(2) as usize);
+        let attributes_title = if hidden > 0 {
+            format!("Attributes ({} more lines, f = show all)", hidden)
+        } else {
+            "Attributes".to_string()
+        };
+        let attributes_widget =
+            Paragraph::new(attributes).block(styles.border_block().title(attributes_title));
+        let percentiles_widget = Paragraph::new(
+            details
+                .and_then(|details| details.async_op_busy_times_histogram())
+                .map(|histogram| mini_histogram::percentiles(styles, histogram))
+                .unwrap_or_default(),
+        )
+        .block(styles.border_block().title("Busy Times Percentiles"));
+
+        frame.render_widget(Block::default().title(controls), controls_area);
+        frame.render_widget(resource_widget, stats_area);
+        frame.render_widget(attributes_widget, attributes_area);
+        frame.render_widget(percentiles_widget, busy_area[0]);
+    }
+}
+
+impl Details {
+    fn make_chart_data(&self, width: u16) -> (Vec<u64>, HistogramMetadata) {
+        self.async_op_busy_times_histogram()
+            .map(|histogram| mini_histogram::chart_data(histogram, width))
+            .unwrap_or_default()
+    }
+}
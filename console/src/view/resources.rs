@@ -1,20 +1,21 @@
 use crate::{
+    report,
     state::{
         resources::{Resource, SortBy},
+        window::Window,
         State,
     },
     view::{
-        self, bold,
-        table::{self, TableList, TableListState},
-        DUR_LEN, DUR_PRECISION,
+        self, bold, filter,
+        table::{TableList, TableListState},
     },
 };
-
+use std::time::{Duration, SystemTime};
 use tui::{
     layout,
     style::{self, Color, Style},
-    text::Spans,
-    widgets::{Cell, Paragraph, Row, Table},
+    text::{Span, Spans, Text},
+    widgets::{self, Cell, ListItem, Paragraph, Row, Table},
 };
 
 #[derive(Debug, Default)]
@@ -22,6 +23,7 @@ pub(crate) struct ResourcesTable {}
 
 impl TableList for ResourcesTable {
     type Row = Resource;
+    type Report = report::Resource;
     type Sort = SortBy;
 
     const HEADER: &'static [&'static str] = &[
@@ -30,9 +32,68 @@ impl TableList for ResourcesTable {
         "Total",
         "Target",
         "Type",
+        "Runtime",
         "Location",
         "Attributes",
     ];
+    const NAME: &'static str = "resources";
+    const TERMINATED: &'static str = "dropped resources";
+    const FILTER_KEYS: &'static [&'static str] = &[
+        "id", "kind", "total", "target", "type", "runtime", "location",
+    ];
+
+    fn report(resource: &Resource, now: SystemTime) -> Self::Report {
+        report::Resource::new(resource, now)
+    }
+
+    fn summarize(styles: &view::Styles, resources: &[&Resource], now: SystemTime) -> String {
+        let dropped = resources
+            .iter()
+            .filter(|resource| resource.dropped())
+            .count();
+        let total = resources
+            .iter()
+            .map(|resource| resource.total(now))
+            .sum::<Duration>();
+        format!("total {}, {} dropped", styles.duration(total), dropped)
+    }
+
+    fn is_terminated(resource: &Resource) -> bool {
+        resource.dropped()
+    }
+
+    fn contains(resource: &Resource, term: &str) -> bool {
+        filter::contains(&resource.id().to_string(), term)
+            || filter::contains(resource.kind(), term)
+            || filter::contains(resource.target(), term)
+            || filter::contains(resource.concrete_type(), term)
+            || matches!(resource.runtime(), Some(runtime) if filter::contains(runtime, term))
+            || filter::contains(resource.location(), term)
+            || filter::lines_contain(resource.formatted_attributes(), term)
+    }
+
+    fn value(
+        resource: &Resource,
+        key: &str,
+        now: SystemTime,
+        _: Option<&Window>,
+    ) -> Option<filter::Value> {
+        let text = |text: &str| filter::Value::Text(text.to_string());
+        Some(match key {
+            "id" => filter::Value::Number(resource.id() as f64),
+            "kind" => text(resource.kind()),
+            "total" => filter::Value::Duration(resource.total(now)),
+            "target" => text(resource.target()),
+            "type" => text(resource.concrete_type()),
+            "runtime" => text(resource.runtime()?),
+            "location" => text(resource.location()),
+            _ => return None,
+        })
+    }
+
+    fn field(resource: &Resource, name: &str) -> Option<filter::Value> {
+        resource.attribute(name).map(filter::Value::from)
+    }
 
     fn render<B: tui::backend::Backend>(
         table_list_state: &mut TableListState<Self>,
@@ -51,27 +112,39 @@ impl TableList for ResourcesTable {
         table_list_state
             .sorted_items
             .extend(state.resources_state_mut().take_new_resources());
+        // Hide the resources the user has hidden, dropped resources if they
+        // are hidden, and other runtimes' resources if only one runtime's are
+        // listed.
+        let runtime_filter = state.runtime_filter();
+        table_list_state.filter(now, state.window(), |resource| {
+            runtime_filter.is_none() || resource.runtime().map(|r| &**r) == runtime_filter
+        });
         table_list_state
             .sort_by
             .sort(now, &mut table_list_state.sorted_items);
+        table_list_state.pin();
 
         let mut id_width = view::Width::new(Self::HEADER[0].len() as u16);
         let mut kind_width = view::Width::new(Self::HEADER[1].len() as u16);
         let mut target_width = view::Width::new(Self::HEADER[3].len() as u16);
         let mut type_width = view::Width::new(Self::HEADER[4].len() as u16);
-        let mut location_width = view::Width::new(Self::HEADER[5].len() as u16);
+        let mut runtime_width = view::Width::new(Self::HEADER[5].len() as u16);
+        let mut location_width = view::Width::new(Self::HEADER[6].len() as u16);
 
         let rows = {
             let id_width = &mut id_width;
             let kind_width = &mut kind_width;
             let target_width = &mut target_width;
             let type_width = &mut type_width;
+            let runtime_width = &mut runtime_width;
             let location_width = &mut location_width;
+            let table_list_state = &*table_list_state;
 
             table_list_state
                 .sorted_items
                 .iter()
                 .filter_map(move |resource| {
+                    let marked = table_list_state.is_marked(resource);
                     let resource = resource.upgrade()?;
                     let resource = resource.borrow();
 
@@ -83,14 +156,29 @@ impl TableList for ResourcesTable {
                         ))),
                         Cell::from(kind_width.update_str(resource.kind()).to_owned()),
                         Cell::from(styles.time_units(format!(
-                            "{:>width$.prec$?}",
-                            resource.total(now),
-                            width = DUR_LEN,
-                            prec = DUR_PRECISION,
+                            "{:>width$}",
+                            styles.duration(resource.total(now)),
+                            width = styles.duration_len(),
                         ))),
-                        Cell::from(target_width.update_str(resource.target()).to_owned()),
+                        Cell::from(
+                            target_width
+                                .update_str(styles.redact(resource.target()))
+                                .into_owned(),
+                        ),
                         Cell::from(type_width.update_str(resource.concrete_type()).to_owned()),
-                        Cell::from(location_width.update_str(resource.location().to_owned())),
+                        Cell::from(
+                            runtime_width.update_str(
+                                resource
+                                    .runtime()
+                                    .map(|r| r.to_string())
+                                    .unwrap_or_default(),
+                            ),
+                        ),
+                        Cell::from(
+                            location_width
+                                .update_str(styles.redact(resource.location()))
+                                .into_owned(),
+                        ),
                         Cell::from(Spans::from(
                             resource
                                 .formatted_attributes()
@@ -101,9 +189,14 @@ impl TableList for ResourcesTable {
                         )),
                     ]);
 
+                    let mut style = Style::default();
                     if resource.dropped() {
-                        row = row.style(styles.terminated());
+                        style = styles.terminated();
+                    }
+                    if marked {
+                        style = style.patch(styles.marked());
                     }
+                    row = row.style(style);
 
                     Some(row)
                 })
@@ -136,47 +229,109 @@ impl TableList for ResourcesTable {
             Table::new(rows.rev())
         };
 
-        let block = styles.border_block().title(vec![bold(format!(
-            "Resources ({}) ",
-            table_list_state.len()
-        ))]);
+        let dropped_events = state.dropped_events();
+        let mut title = vec![bold(format!("Resources ({}) ", table_list_state.len()))];
+        let filters = runtime_filter
+            .map(|runtime| format!("runtime {}", runtime))
+            .into_iter()
+            .collect();
+        title.extend(table_list_state.filter_title(styles, filters));
+        title.extend(table_list_state.selection_title(styles, now));
+        if dropped_events.resources + dropped_events.async_ops > 0 {
+            title.push(styles.warning_narrow());
+            title.push(Span::from(format!(
+                "{} events dropped, stats may be incomplete",
+                dropped_events.resources + dropped_events.async_ops
+            )));
+        }
+        let block = styles.border_block().title(title);
+
+        let resources_state = state.resources_state();
+        let warnings = resources_state
+            .warnings()
+            .map(|warning| {
+                let mut spans = vec![
+                    styles.warning_wide(),
+                    Span::from(format!("{} {}", warning.count(), warning.summary())),
+                ];
+                if let Some(worst) = resources_state.warned_by(warning).first() {
+                    let worst = worst.borrow();
+                    spans.push(Span::raw(format!(
+                        " (worst: resource {} ({}))",
+                        worst.id(),
+                        worst.concrete_type()
+                    )));
+                }
+                ListItem::new(Text::from(Spans::from(spans)))
+            })
+            .collect::<Vec<_>>();
 
         let layout = layout::Layout::default()
             .direction(layout::Direction::Vertical)
             .margin(0);
 
-        let chunks = layout
-            .constraints(
-                [
-                    layout::Constraint::Length(1),
-                    layout::Constraint::Min(area.height - 1),
-                ]
-                .as_ref(),
-            )
-            .split(area);
-        let controls_area = chunks[0];
-        let tasks_area = chunks[1];
+        let (controls_area, tasks_area, warnings_area) = if warnings.is_empty() {
+            let chunks = layout
+                .constraints(
+                    [
+                        layout::Constraint::Length(1),
+                        layout::Constraint::Min(area.height - 1),
+                    ]
+                    .as_ref(),
+                )
+                .split(area);
+            (chunks[0], chunks[1], None)
+        } else {
+            let chunks = layout
+                .constraints(
+                    [
+                        layout::Constraint::Length(1),
+                        layout::Constraint::Length(warnings.len() as u16 + 2),
+                        layout::Constraint::Min(area.height - 1),
+                    ]
+                    .as_ref(),
+                )
+                .split(area);
+            (chunks[0], chunks[2], Some(chunks[1]))
+        };
 
         let attributes_width = layout::Constraint::Percentage(100);
-        let widths = &[
+        let mut widths = [
             id_width.constraint(),
             kind_width.constraint(),
-            layout::Constraint::Length(DUR_LEN as u16),
+            layout::Constraint::Length(styles.duration_len() as u16),
             target_width.constraint(),
             type_width.constraint(),
+            runtime_width.constraint(),
             location_width.constraint(),
             attributes_width,
         ];
+        table_list_state.resize(&mut widths);
 
         let table = table
             .header(header)
             .block(block)
-            .widths(widths)
+            .widths(&widths)
             .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
             .highlight_style(Style::default().add_modifier(style::Modifier::BOLD));
 
         frame.render_stateful_widget(table, tasks_area, &mut table_list_state.table_state);
-        frame.render_widget(Paragraph::new(table::controls(styles)), controls_area);
+        table_list_state.render_position(styles, frame, tasks_area);
+        let mut controls = table_list_state.controls(styles);
+        let filtering = table_list_state.is_filtering();
+        if let Some(spans) = controls.lines.first_mut().filter(|_| !filtering) {
+            // Keep "q = quit" last.
+            let at = spans.0.len().saturating_sub(2);
+            spans
+                .0
+                .splice(at..at, vec![bold("R"), Span::raw(" = filter by runtime, ")]);
+        }
+        frame.render_widget(Paragraph::new(controls), controls_area);
+
+        if let Some(area) = warnings_area {
+            let block = styles.border_block().title(bold("Warnings"));
+            frame.render_widget(widgets::List::new(warnings).block(block), area);
+        }
 
         table_list_state
             .sorted_items
@@ -0,0 +1,219 @@
+use crate::{
+    state::{runtime, State},
+    view::{self, bold},
+};
+use tui::{
+    layout::{self, Layout},
+    style::{self, Style},
+    text::{Span, Spans},
+    widgets::{Cell, Paragraph, Row, Table, Wrap},
+};
+
+/// Renders the tasks and resources on each runtime, and the runtime's scheduler
+/// metrics, with a row for each worker thread.
+pub(crate) fn render<B: tui::backend::Backend>(
+    styles: &view::Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    state: &State,
+) {
+    let runtimes = state
+        .last_updated_at()
+        .map(|now| runtime::summarize_runtimes(state.tasks_state(), state.resources_state(), now))
+        .unwrap_or_default();
+    // add 3 for the borders and the header
+    let runtimes_height = if runtimes.is_empty() {
+        0
+    } else {
+        runtimes.len() as u16 + 3
+    };
+    let chunks = Layout::default()
+        .direction(layout::Direction::Vertical)
+        .constraints(
+            [
+                // controls
+                layout::Constraint::Length(1),
+                // tasks and resources by runtime
+                layout::Constraint::Length(runtimes_height),
+                // workers
+                layout::Constraint::Min(3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let (controls_area, runtimes_area, workers_area) = (chunks[0], chunks[1], chunks[2]);
+
+    let controls = Spans::from(vec![
+        Span::raw("controls: "),
+        bold("t"),
+        Span::raw(" = tasks, "),
+        bold("r"),
+        Span::raw(" = resources, "),
+        bold("q"),
+        Span::raw(" = quit"),
+    ]);
+    frame.render_widget(Paragraph::new(controls), controls_area);
+    if !runtimes.is_empty() {
+        render_runtimes(styles, frame, runtimes_area, &runtimes);
+    }
+
+    let runtime = match state.runtime() {
+        Some(runtime) => runtime,
+        None => {
+            let text = vec![
+                Spans::from("The instrumented process is not reporting runtime metrics."),
+                Spans::from(""),
+                Spans::from(vec![
+                    Span::raw("Runtime metrics can be reported by passing a callback to "),
+                    bold("console_subscriber::Builder::runtime_metrics"),
+                    Span::raw("."),
+                ]),
+            ];
+            let pane = Paragraph::new(text)
+                .block(styles.border_block().title("Runtime"))
+                .wrap(Wrap { trim: true });
+            frame.render_widget(pane, workers_area);
+            return;
+        }
+    };
+
+    let workers = runtime.workers();
+    let health = runtime.health();
+    let mut title = vec![
+        bold(format!("Runtime Workers ({}) ", workers.len())),
+        Span::raw(format!(
+            "{:.0}% busy (max {:.0}%), injection queue: {}, local queues: {}, blocking threads: {}",
+            health.mean_busy_percent,
+            health.max_busy_percent,
+            health.injection_queue_depth,
+            health.local_queue_depth,
+            health.blocking_threads,
+        )),
+    ];
+    if health.is_saturated() {
+        title.push(Span::raw(" "));
+        title.push(styles.warning_narrow());
+        title.push(Span::raw("saturated"));
+    }
+
+    // Leave room for the borders and the other columns.
+    let history_width = workers_area.width.saturating_sub(2 + 58) as usize;
+    let rows = workers.iter().enumerate().map(|(i, worker)| {
+        let history = worker
+            .busy_history
+            .iter()
+            .rev()
+            .take(history_width)
+            .rev()
+            .map(|&busy| spark(styles, busy))
+            .collect::<String>();
+        Row::new(vec![
+            Cell::from(i.to_string()),
+            Cell::from(format!("{:>5.1}%", worker.busy_percent)),
+            Cell::from(format!("{:>8.0}", worker.polls_per_sec)),
+            Cell::from(format!("{:>8.1}", worker.steals_per_sec)),
+            Cell::from(format!("{:>8.1}", worker.parks_per_sec)),
+            Cell::from(format!("{:>8.1}", worker.noops_per_sec)),
+            Cell::from(format!("{:>6}", worker.local_queue_depth)),
+            Cell::from(history),
+        ])
+    });
+
+    let header = Row::new(vec![
+        "Worker",
+        "Busy",
+        "Polls/s",
+        "Steals/s",
+        "Parks/s",
+        "Noops/s",
+        "Queue",
+        "Busy History",
+    ])
+    .height(1)
+    .style(Style::default().add_modifier(style::Modifier::BOLD));
+
+    let widths = &[
+        layout::Constraint::Length(6),
+        layout::Constraint::Length(6),
+        layout::Constraint::Length(8),
+        layout::Constraint::Length(8),
+        layout::Constraint::Length(8),
+        layout::Constraint::Length(8),
+        layout::Constraint::Length(6),
+        // See the comment in the tasks table about Min(0) in tui 0.16.
+        layout::Constraint::Percentage(100),
+    ];
+    let table = Table::new(rows)
+        .header(header)
+        .block(styles.border_block().title(Spans::from(title)))
+        .widths(widths);
+    frame.render_widget(table, workers_area);
+}
+
+/// Renders a table of the live tasks executing on each runtime.
+fn render_runtimes<B: tui::backend::Backend>(
+    styles: &view::Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    runtimes: &[runtime::RuntimeSummary],
+) {
+    let mut name_width = view::Width::new("Runtime".len() as u16);
+    let rows = runtimes
+        .iter()
+        .map(|runtime| {
+            let name = match runtime.name {
+                Some(ref name) => name.to_string(),
+                None => "<unnamed>".to_string(),
+            };
+            Row::new(vec![
+                Cell::from(name_width.update_str(name)),
+                Cell::from(format!("{:>6}", runtime.tasks)),
+                Cell::from(format!("{:>7}", runtime.running)),
+                Cell::from(format!("{:>6}", runtime.idle)),
+                Cell::from(format!("{:>8}", runtime.polls)),
+                Cell::from(styles.time_units(format!(
+                    "{:>width$}",
+                    styles.duration(runtime.busy),
+                    width = styles.duration_len(),
+                ))),
+                Cell::from(format!("{:>9}", runtime.resources)),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let header = Row::new(vec![
+        "Runtime",
+        "Tasks",
+        "Running",
+        "Idle",
+        "Polls",
+        "Busy",
+        "Resources",
+    ])
+    .height(1)
+    .style(Style::default().add_modifier(style::Modifier::BOLD));
+    let widths = &[
+        name_width.constraint(),
+        layout::Constraint::Length(6),
+        layout::Constraint::Length(7),
+        layout::Constraint::Length(6),
+        layout::Constraint::Length(8),
+        layout::Constraint::Length(styles.duration_len() as u16),
+        layout::Constraint::Length(9),
+    ];
+    let title = Spans::from(vec![bold(format!("Runtimes ({})", runtimes.len()))]);
+    let table = Table::new(rows)
+        .header(header)
+        .block(styles.border_block().title(title))
+        .widths(widths);
+    frame.render_widget(table, area);
+}
+
+/// Returns a character whose height represents a busy percentage.
+fn spark(styles: &view::Styles, busy: u64) -> char {
+    const UTF8: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const ASCII: [char; 8] = [' ', '.', '.', ':', ':', '|', '|', '#'];
+    let levels = if styles.utf8 { &UTF8 } else { &ASCII };
+    let level = (busy.min(100) as usize * (levels.len() - 1) + 50) / 100;
+    levels[level]
+}
@@ -1,5 +1,11 @@
-use crate::config;
-use std::{borrow::Cow, str::FromStr};
+use crate::{clock, config};
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
 use tui::{
     style::{Color, Modifier, Style},
     text::Span,
@@ -9,7 +15,15 @@ use tui::{
 pub struct Styles {
     palette: Palette,
     toggles: config::ColorToggles,
+    durations: config::DurationFormat,
     pub(crate) utf8: bool,
+    /// Whether times are displayed as local wall-clock times, rather than
+    /// as how long ago they were.
+    pub(crate) absolute_times: bool,
+    /// Whether task names, targets, locations, and field values are replaced
+    /// by hashes of them, so that screenshots can be shared without leaking
+    /// them.
+    pub(crate) anonymize: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -33,11 +47,17 @@ fn fg_style(color: Color) -> Style {
 // === impl Config ===
 
 impl Styles {
+    /// How long rows which changed in an update stay highlighted for.
+    pub const CHANGED_FADE: Duration = Duration::from_secs(1);
+
     pub fn from_config(config: config::ViewOptions) -> Self {
         Self {
             palette: config.determine_palette(),
             toggles: config.toggles(),
+            durations: config.durations(),
             utf8: config.is_utf8(),
+            absolute_times: false,
+            anonymize: false,
         }
     }
 
@@ -82,6 +102,7 @@ impl Styles {
                 s if s.ends_with("µs") || s.ends_with("us") => fg_style(Color::Yellow),
                 s if s.ends_with("ms") => fg_style(Color::Red),
                 s if s.ends_with('s') => fg_style(Color::Magenta),
+                s if s.ends_with('m') => fg_style(Color::Cyan),
                 _ => Style::default(),
             },
             Palette::Ansi256 | Palette::All => match text.as_ref() {
@@ -90,6 +111,7 @@ impl Styles {
                 s if s.ends_with("µs") || s.ends_with("us") => fg_style(Color::Indexed(42)), // spring green 2
                 s if s.ends_with("ms") => fg_style(Color::Indexed(43)), // cyan 3
                 s if s.ends_with('s') => fg_style(Color::Indexed(44)),  // dark turquoise,
+                s if s.ends_with('m') => fg_style(Color::Indexed(45)),  // turquoise 2
                 _ => Style::default(),
             },
         };
@@ -97,12 +119,152 @@ impl Styles {
         Span::styled(text, style)
     }
 
+    /// Returns `text`, or if anonymizing, a short hash of it.
+    ///
+    /// The same text is always replaced by the same hash, so that names and
+    /// locations can still be told apart, and matched across views.
+    pub fn redact<'a>(&self, text: impl Into<Cow<'a, str>>) -> Cow<'a, str> {
+        let text = text.into();
+        if !self.anonymize || text.is_empty() {
+            return text;
+        }
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        Cow::Owned(format!("#{:08x}", hasher.finish() as u32))
+    }
+
+    /// Returns a formatted `name=value` field, with its value redacted if
+    /// anonymizing.
+    pub fn redact_field(&self, field: &[Span<'static>]) -> Vec<Span<'static>> {
+        let mut field = field.to_vec();
+        if let Some(value) = field.get_mut(2).filter(|_| self.anonymize) {
+            let trimmed = value.content.trim_end();
+            let trailing = &value.content[trimmed.len()..];
+            value.content = format!("{}{}", self.redact(trimmed), trailing).into();
+        }
+        field
+    }
+
+    /// Formats when something happened, as the local wall-clock time if
+    /// absolute times are enabled, or otherwise as how long before `now` it
+    /// was.
+    pub fn timestamp(&self, at: SystemTime, now: SystemTime) -> Vec<Span<'static>> {
+        if self.absolute_times {
+            return vec![Span::raw(clock::format(at))];
+        }
+        let ago = now.duration_since(at).unwrap_or_default();
+        vec![self.time_units(self.duration(ago)), Span::raw(" ago")]
+    }
+
+    /// Formats a duration in the configured unit, with the configured number
+    /// of digits after the decimal point.
+    pub fn duration(&self, dur: Duration) -> String {
+        self.format_duration(dur, self.durations.precision)
+    }
+
+    /// Formats a duration in the configured unit, with at most `precision`
+    /// digits after the decimal point, for narrow columns.
+    pub fn short_duration(&self, dur: Duration, precision: usize) -> String {
+        self.format_duration(dur, precision.min(self.durations.precision))
+    }
+
+    /// Returns how many characters wide table columns of durations are.
+    pub fn duration_len(&self) -> usize {
+        // Leave room for up to three digits before the decimal point, the
+        // decimal point itself, and a two-character unit.
+        super::DUR_LEN.max(self.durations.precision + 6)
+    }
+
+    fn format_duration(&self, dur: Duration, precision: usize) -> String {
+        use config::DurationUnit::*;
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+
+        let (nanos_per_unit, unit) = match self.durations.unit {
+            Some(Nanos) => (1.0, "ns"),
+            Some(Micros) => (1_000.0, self.if_utf8("µs", "us")),
+            Some(Millis) => (1_000_000.0, "ms"),
+            Some(Secs) => (1_000_000_000.0, "s"),
+            None => {
+                // Sub-second precision is just noise for durations of an
+                // hour or more, which long-running processes accumulate.
+                let secs = dur.as_secs();
+                return if secs >= DAY {
+                    format!(
+                        "{}d{}h{}m",
+                        secs / DAY,
+                        secs % DAY / HOUR,
+                        secs % HOUR / MINUTE
+                    )
+                } else if secs >= HOUR {
+                    format!(
+                        "{}h{}m{}s",
+                        secs / HOUR,
+                        secs % HOUR / MINUTE,
+                        secs % MINUTE
+                    )
+                } else {
+                    format!("{:.prec$?}", dur, prec = precision)
+                };
+            }
+        };
+        format!(
+            "{:.prec$}{}",
+            dur.as_nanos() as f64 / nanos_per_unit,
+            unit,
+            prec = precision
+        )
+    }
+
     pub fn terminated(&self) -> Style {
         if !self.toggles.color_terminated {
             return Style::default();
         }
 
-        Style::default().add_modifier(Modifier::DIM)
+        Style::default().add_modifier(Modifier::DIM | Modifier::CROSSED_OUT)
+    }
+
+    /// Returns a style coloring a task state, if state colors are enabled.
+    pub fn state(&self, color: Color) -> Style {
+        if !self.toggles.color_states {
+            return Style::default();
+        }
+
+        self.fg(color)
+    }
+
+    /// The style for table rows which changed in an update applied `age` ago.
+    ///
+    /// The highlight fades out over [`Styles::CHANGED_FADE`], in as many
+    /// steps as the palette allows.
+    pub fn changed(&self, age: Duration) -> Style {
+        if age >= Self::CHANGED_FADE {
+            return Style::default();
+        }
+        // How far the highlight has yet to fade, from 1.0 down to 0.0.
+        let remaining = 1.0 - age.as_secs_f64() / Self::CHANGED_FADE.as_secs_f64();
+        match self.palette {
+            Palette::All => {
+                let level = (remaining * 96.0) as u8;
+                Style::default().bg(Color::Rgb(level, level, level / 2))
+            }
+            // The ANSI 256 palette's grayscale ramp runs from 232 (darkest)
+            // to 255 (lightest).
+            Palette::Ansi256 => Style::default().bg(Color::Indexed(232 + (remaining * 8.0) as u8)),
+            _ => Style::default().add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// The style for the filters applied to a table, in its title.
+    pub fn filtered(&self) -> Style {
+        self.fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+    }
+
+    /// The style for table rows selected for bulk actions.
+    pub fn marked(&self) -> Style {
+        Style::default().add_modifier(Modifier::UNDERLINED)
     }
 
     pub fn fg(&self, color: Color) -> Style {
@@ -1,13 +1,21 @@
 use crate::{
-    input, state,
-    view::{self, bold},
+    computed::ComputedColumn,
+    input, session,
+    state::{self, window::Window},
+    util,
+    view::{
+        self, bold,
+        filter::{self, Filter},
+    },
 };
-use std::convert::TryFrom;
+use serde::Serialize;
+use std::{cmp, collections::HashMap, convert::TryFrom, fs, io, time::SystemTime};
 use tui::{
     layout,
     text::{self, Span, Spans, Text},
-    widgets::TableState,
+    widgets::{Paragraph, TableState},
 };
+use unicode_width::UnicodeWidthStr;
 
 use std::cell::RefCell;
 use std::rc::Weak;
@@ -16,6 +24,53 @@ pub(crate) trait TableList {
     type Row;
     type Sort: SortBy + TryFrom<usize>;
     const HEADER: &'static [&'static str];
+    /// What the rows are, such as "tasks", for naming exported files.
+    const NAME: &'static str;
+    /// What terminated rows are, such as "completed tasks".
+    const TERMINATED: &'static str;
+    /// The names of the row's values which filters may compare.
+    const FILTER_KEYS: &'static [&'static str];
+
+    /// The JSON representation of a row's statistics, for exporting
+    /// selected rows.
+    type Report: Serialize;
+
+    /// Returns a row's statistics as of `now`, for exporting selected rows.
+    fn report(row: &Self::Row, now: SystemTime) -> Self::Report;
+
+    /// Summarizes the combined statistics of the selected rows.
+    fn summarize(styles: &view::Styles, rows: &[&Self::Row], now: SystemTime) -> String;
+
+    /// Returns `true` if the row describes a completed task or a dropped
+    /// resource.
+    fn is_terminated(row: &Self::Row) -> bool;
+
+    /// Returns `true` if any of the row's text contains the lowercased
+    /// `term`, ignoring case, for filtering the table.
+    fn contains(row: &Self::Row, term: &str) -> bool;
+
+    /// Returns the row's value named `key`, one of [`TableList::FILTER_KEYS`],
+    /// as of `now`, for filtering the table, or `None` if the row doesn't
+    /// have a value.
+    fn value(
+        row: &Self::Row,
+        key: &str,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<filter::Value>;
+
+    /// Returns the value of the row's field named `name`, for filtering the
+    /// table, or `None` if the row doesn't have the field.
+    fn field(row: &Self::Row, name: &str) -> Option<filter::Value>;
+
+    /// Returns the indices of the columns collapsed in `state`, which are
+    /// rendered with no width and skipped when selecting a column.
+    fn hidden_columns(_state: &TableListState<Self>) -> Vec<usize>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
 
     fn render<B: tui::backend::Backend>(
         state: &mut TableListState<Self>,
@@ -27,16 +82,66 @@ pub(crate) trait TableList {
         Self: Sized;
 }
 
+/// How many characters a column is widened or narrowed by at a time.
+const RESIZE_STEP: u16 = 4;
+
 pub(crate) trait SortBy {
     fn as_column(&self) -> usize;
 }
 
 pub(crate) struct TableListState<T: TableList> {
     pub(crate) sorted_items: Vec<Weak<RefCell<T::Row>>>,
+    /// Items which are hidden by the current filter, and may be shown again
+    /// if it changes.
+    hidden_items: Vec<Weak<RefCell<T::Row>>>,
     pub(crate) sort_by: T::Sort,
     pub(crate) selected_column: usize,
     pub(crate) sort_descending: bool,
     pub(crate) table_state: TableState,
+    /// Whether rows are being selected for bulk actions. While selecting,
+    /// space toggles whether the highlighted row is selected, rather than
+    /// pausing.
+    selecting: bool,
+    /// The rows selected for bulk actions.
+    marked: Vec<Weak<RefCell<T::Row>>>,
+    /// Rows pinned to the top of the table, however it is sorted.
+    pinned: Vec<Weak<RefCell<T::Row>>>,
+    /// Rows hidden by the user, until they are shown again.
+    dismissed: Vec<Weak<RefCell<T::Row>>>,
+    /// Whether rows for completed tasks or dropped resources are hidden.
+    hide_terminated: bool,
+    /// The names of fields displayed in columns of their own, between the
+    /// built-in columns and the last column.
+    pub(in crate::view) field_columns: Vec<String>,
+    /// Columns computed from each row's statistics, following the field
+    /// columns. Only tasks have computed columns.
+    pub(in crate::view) computed_columns: Vec<ComputedColumn>,
+    /// Whether idle tasks are hidden. Only tasks are ever idle.
+    pub(in crate::view) hide_idle: bool,
+    /// Whether the column of each task's target is collapsed. Only the tasks
+    /// table collapses it.
+    pub(in crate::view) hide_target: bool,
+    /// Whether the column of each task's longest poll is shown. Only the
+    /// tasks table has it, and it is collapsed unless the user shows it.
+    pub(in crate::view) show_max_poll: bool,
+    /// Whether the column of each task's recent activity is shown. Only the
+    /// tasks table has it, and it is collapsed unless the user shows it.
+    pub(in crate::view) show_activity: bool,
+    /// The widths of the columns the user has resized, by column index,
+    /// overriding the widths needed to display their contents.
+    resized_widths: HashMap<usize, u16>,
+    /// The width of each column when the table was last rendered.
+    rendered_widths: Vec<u16>,
+    /// How many rows fit in the table when it was last rendered, which the
+    /// page up and page down keys scroll by.
+    visible_rows: usize,
+    /// The index of the first row displayed, which follows the selected row
+    /// like the table widget scrolls to it.
+    offset: usize,
+    /// The text the rows are filtered by.
+    text_filter: Filter,
+    /// The outcome of the last export of the selected rows.
+    notice: Option<String>,
 }
 
 impl<T: TableList> TableListState<T> {
@@ -60,33 +165,502 @@ impl<T: TableList> TableListState<T> {
 
     pub(in crate::view) fn key_input(&mut self, input::KeyEvent { code, .. }: input::KeyEvent) {
         use input::KeyCode::*;
-        let header_len = T::HEADER.len();
+        // While the filter is being typed, every key goes to the filter.
+        if self.text_filter.is_typing() {
+            self.text_filter.key_input(code);
+            return;
+        }
+        let header_len = self.header().len();
         match code {
-            Left => {
-                if self.selected_column == 0 {
-                    self.selected_column = header_len - 1;
+            Left => self.select_column(|column| {
+                if column == 0 {
+                    header_len - 1
                 } else {
-                    self.selected_column -= 1;
+                    column - 1
                 }
-            }
-            Right => {
-                if self.selected_column == header_len - 1 {
-                    self.selected_column = 0;
+            }),
+            Right => self.select_column(|column| {
+                if column == header_len - 1 {
+                    0
                 } else {
-                    self.selected_column += 1;
+                    column + 1
                 }
-            }
+            }),
             Char('i') => self.sort_descending = !self.sort_descending,
             Down => self.scroll_next(),
             Up => self.scroll_prev(),
+            PageDown => {
+                let page = self.visible_rows.max(1);
+                self.scroll_with(|items, i| cmp::min(i + page, items.len() - 1));
+            }
+            PageUp => {
+                let page = self.visible_rows.max(1);
+                self.scroll_with(|_, i| i.saturating_sub(page));
+            }
+            Home => self.scroll_with(|_, _| 0),
+            End => self.scroll_with(|items, _| items.len() - 1),
+            Char('v') => {
+                self.selecting = !self.selecting;
+                if !self.selecting {
+                    self.marked.clear();
+                }
+            }
+            Char(' ') if self.selecting => {
+                let item = self.selected_item();
+                if contains(&self.marked, &item) {
+                    self.marked.retain(|marked| !marked.ptr_eq(&item));
+                } else if item.strong_count() > 0 {
+                    self.marked.push(item);
+                }
+            }
+            Char('a') if self.selecting => self.marked = self.sorted_items.clone(),
+            Char('T') if self.selecting => {
+                // Unpin the selected rows if they're all pinned already.
+                if self.marked.iter().all(|item| contains(&self.pinned, item)) {
+                    let marked = &self.marked;
+                    self.pinned.retain(|item| !contains(marked, item));
+                } else {
+                    let new = self
+                        .marked
+                        .iter()
+                        .filter(|&item| !contains(&self.pinned, item))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    self.pinned.extend(new);
+                }
+            }
+            Char('h') if self.selecting => self.dismissed.append(&mut self.marked),
+            Char('H') => self.dismissed.clear(),
+            Char('d') => self.hide_terminated = !self.hide_terminated,
+            Char('+') | Char('=') => self.resize_column(RESIZE_STEP as i32),
+            Char('-') => self.resize_column(-(RESIZE_STEP as i32)),
+            Char('0') => self.resized_widths.clear(),
+            Char('/') => self.text_filter.open(),
+            Char('c') => self.text_filter.clear(),
             _ => {} // do nothing for now...
         }
 
+        // The last column, listing every field, can't be sorted by.
+        if self.selected_column + 1 == header_len {
+            return;
+        }
         if let Ok(sort_by) = T::Sort::try_from(self.selected_column) {
             self.sort_by = sort_by;
         }
     }
 
+    /// Moves the selected column with `next` until it reaches a column which
+    /// isn't hidden.
+    fn select_column(&mut self, next: impl Fn(usize) -> usize) {
+        let hidden = T::hidden_columns(self);
+        let mut column = next(self.selected_column);
+        // Every column might be hidden, in theory, so give up after going
+        // around once.
+        for _ in 0..self.header().len() {
+            if !hidden.contains(&column) {
+                break;
+            }
+            column = next(column);
+        }
+        self.selected_column = column;
+    }
+
+    /// Widens or narrows the selected column by `delta` characters, starting
+    /// from its width when the table was last rendered.
+    ///
+    /// The last column fills the rest of the table, so it can't be resized.
+    fn resize_column(&mut self, delta: i32) {
+        let column = self.selected_column;
+        if column + 1 >= self.header().len() {
+            return;
+        }
+        let width = match self.resized_widths.get(&column) {
+            Some(&width) => width,
+            None => match self.rendered_widths.get(column) {
+                Some(&width) => width,
+                None => return,
+            },
+        };
+        let width = (width as i32 + delta).clamp(1, u16::MAX as i32);
+        self.resized_widths.insert(column, width as u16);
+    }
+
+    /// Overrides the widths of the columns the user has resized, and records
+    /// each column's width, so that resizing starts from it.
+    ///
+    /// This should be called with the widths needed to display the columns'
+    /// contents, once every row has been formatted.
+    pub(in crate::view) fn resize(&mut self, widths: &mut [layout::Constraint]) {
+        self.rendered_widths.clear();
+        for (column, width) in widths.iter_mut().enumerate() {
+            if let Some(&resized) = self.resized_widths.get(&column) {
+                *width = layout::Constraint::Length(resized);
+            }
+            let rendered = match *width {
+                layout::Constraint::Length(len) => len,
+                _ => 0,
+            };
+            self.rendered_widths.push(rendered);
+        }
+    }
+
+    /// Renders which rows are displayed, and a scrollbar, on the border of
+    /// the table rendered in `area`.
+    ///
+    /// This also records how many rows fit in the table, so that paging
+    /// scrolls by that many rows.
+    pub(in crate::view) fn render_position<B: tui::backend::Backend>(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+    ) {
+        // subtract 3 for the borders and the header
+        let visible = area.height.saturating_sub(3) as usize;
+        self.visible_rows = visible;
+        let total = self.len();
+        if total == 0 || visible == 0 {
+            return;
+        }
+
+        // The table widget's offset isn't public, so scroll the same way it
+        // does: only as far as needed to display the selected row.
+        let selected = self.table_state.selected().unwrap_or(0).min(total - 1);
+        let offset = self.offset.min(total - 1);
+        self.offset = if selected < offset {
+            selected
+        } else if selected >= offset + visible {
+            selected + 1 - visible
+        } else {
+            offset
+        };
+
+        let last = cmp::min(self.offset + visible, total);
+        let position = format!(
+            " rows {}{}{} of {} ",
+            util::thousands(self.offset + 1),
+            styles.if_utf8("\u{2013}", "-"),
+            util::thousands(last),
+            util::thousands(total),
+        );
+        let width = cmp::min(position.width() as u16, area.width.saturating_sub(2));
+        let position_area = layout::Rect {
+            x: area.x + area.width.saturating_sub(width + 1),
+            y: area.y + area.height.saturating_sub(1),
+            width,
+            height: 1,
+        };
+        frame.render_widget(Paragraph::new(position), position_area);
+
+        // The scrollbar runs alongside the rows, below the header.
+        let rows_area = layout::Rect {
+            y: area.y + 1,
+            height: area.height.saturating_sub(1),
+            ..area
+        };
+        view::render_scrollbar(styles, frame, rows_area, self.offset, visible, total);
+    }
+
+    /// Returns the table's column headers, including any field columns and
+    /// computed columns.
+    pub(in crate::view) fn header(&self) -> Vec<&str> {
+        let (last, builtin) = T::HEADER
+            .split_last()
+            .expect("tables have at least one column");
+        let mut header = builtin.to_vec();
+        header.extend(self.field_columns.iter().map(String::as_str));
+        header.extend(self.computed_columns.iter().map(ComputedColumn::name));
+        header.push(last);
+        header
+    }
+
+    /// Hides the items which don't match `filter`, and shows any previously
+    /// hidden items which now match it.
+    ///
+    /// This should be called before the items are sorted, so that the
+    /// selected row refers to a visible item.
+    ///
+    /// Rows the user has hidden are never shown, and neither are terminated
+    /// rows while they are hidden, or rows which don't match the text filter
+    /// with their statistics as of `now`, within `window` if one is open.
+    pub(in crate::view) fn filter(
+        &mut self,
+        now: SystemTime,
+        window: Option<&Window>,
+        filter: impl Fn(&T::Row) -> bool,
+    ) {
+        self.text_filter.update();
+        for list in [&mut self.marked, &mut self.pinned, &mut self.dismissed] {
+            list.retain(|item| item.strong_count() > 0);
+        }
+        let dismissed = &self.dismissed;
+        let hide_terminated = self.hide_terminated;
+        let query = self.text_filter.query();
+        let items = self
+            .sorted_items
+            .drain(..)
+            .chain(self.hidden_items.drain(..));
+        let (shown, hidden) = items
+            .filter_map(|item| {
+                let row = item.upgrade()?;
+                let row = row.borrow();
+                let excluded =
+                    contains(dismissed, &item) || (hide_terminated && T::is_terminated(&row));
+                let visible = !excluded && query.matches::<T>(&row, now, window) && filter(&row);
+                Some((item, visible))
+            })
+            .partition::<Vec<_>, _>(|&(_, visible)| visible);
+        self.sorted_items = shown.into_iter().map(|(item, _)| item).collect();
+        self.hidden_items = hidden.into_iter().map(|(item, _)| item).collect();
+
+        let len = self.sorted_items.len();
+        if matches!(self.table_state.selected(), Some(i) if i >= len) {
+            self.table_state.select(len.checked_sub(1));
+        }
+    }
+
+    /// Moves pinned rows to the top of the table.
+    ///
+    /// This should be called after the items are sorted.
+    pub(in crate::view) fn pin(&mut self) {
+        if self.pinned.is_empty() {
+            return;
+        }
+        let pinned = &self.pinned;
+        // Items are displayed in reverse order when sorting in ascending
+        // order, so pinned items go at the end.
+        let descending = self.sort_descending;
+        self.sorted_items
+            .sort_by_key(|item| contains(pinned, item) != descending);
+    }
+
+    /// Returns `true` if rows are being selected for bulk actions.
+    pub(in crate::view) fn is_selecting(&self) -> bool {
+        self.selecting
+    }
+
+    /// Returns `true` if the filter is being typed, in which case every key
+    /// goes to the table.
+    pub(in crate::view) fn is_filtering(&self) -> bool {
+        self.text_filter.is_typing()
+    }
+
+    /// Returns `true` if text typed in the filter is waiting to be applied
+    /// once typing pauses.
+    pub(in crate::view) fn is_filter_pending(&self) -> bool {
+        self.text_filter.is_pending()
+    }
+
+    /// Returns `true` if `item` is selected for bulk actions.
+    pub(in crate::view) fn is_marked(&self, item: &Weak<RefCell<T::Row>>) -> bool {
+        contains(&self.marked, item)
+    }
+
+    /// Writes the statistics of the selected rows as of `now` to a JSON file
+    /// in the current directory.
+    pub(in crate::view) fn export_marked(&mut self, now: SystemTime) {
+        let rows = self
+            .marked
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            self.notice = Some(format!("no {} are selected", T::NAME));
+            return;
+        }
+        let reports = rows
+            .iter()
+            .map(|row| T::report(&row.borrow(), now))
+            .collect::<Vec<_>>();
+        let path = format!("{}-selection.json", T::NAME);
+        let result = serde_json::to_string(&reports)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&path, json + "\n"));
+        self.notice = Some(match result {
+            Ok(()) => {
+                tracing::info!(%path, rows = rows.len(), "exported selected {}", T::NAME);
+                format!("exported {} {} to {}", rows.len(), T::NAME, path)
+            }
+            Err(error) => {
+                tracing::warn!(%path, %error, "failed to export selected {}", T::NAME);
+                format!("failed to export {}: {}", T::NAME, error)
+            }
+        });
+    }
+
+    /// Returns a description of the selected, pinned, and hidden rows, with a
+    /// summary of the selected rows' statistics as of `now`, for the table's
+    /// title.
+    pub(in crate::view) fn selection_title(
+        &self,
+        styles: &view::Styles,
+        now: SystemTime,
+    ) -> Option<Span<'static>> {
+        let mut parts = Vec::new();
+        if self.selecting || !self.marked.is_empty() {
+            let rows = self
+                .marked
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>();
+            let rows = rows.iter().map(|row| row.borrow()).collect::<Vec<_>>();
+            let rows = rows.iter().map(|row| &**row).collect::<Vec<_>>();
+            if rows.is_empty() {
+                parts.push("0 selected".to_string());
+            } else {
+                parts.push(format!(
+                    "{} selected: {}",
+                    rows.len(),
+                    T::summarize(styles, &rows, now)
+                ));
+            }
+        }
+        if !self.pinned.is_empty() {
+            parts.push(format!("{} pinned", self.pinned.len()));
+        }
+        if !self.dismissed.is_empty() {
+            parts.push(format!("{} hidden (H = show)", self.dismissed.len()));
+        }
+        if self.hide_terminated {
+            parts.push(format!("{} hidden (d = show)", T::TERMINATED));
+        }
+        if !self.resized_widths.is_empty() {
+            parts.push(format!(
+                "{} columns resized (0 = reset)",
+                self.resized_widths.len()
+            ));
+        }
+        if let Some(ref notice) = self.notice {
+            parts.push(notice.clone());
+        }
+        if parts.is_empty() {
+            return None;
+        }
+        Some(Span::raw(format!(" [{}]", parts.join("; "))))
+    }
+
+    /// Filters the table by `query`, such as a saved filter.
+    pub(in crate::view) fn set_filter(&mut self, query: &str) {
+        self.text_filter.set(query);
+    }
+
+    /// Returns the filters applied to the table, for its title, so that
+    /// it's clear that rows are missing however the table was returned to.
+    ///
+    /// `filters` describes any filters which only some tables have, such as
+    /// the runtime filter, which the same key also clears.
+    pub(in crate::view) fn filter_title(
+        &self,
+        styles: &view::Styles,
+        mut filters: Vec<String>,
+    ) -> Vec<Span<'static>> {
+        let query = self.text_filter.query();
+        if !query.is_empty() {
+            filters.push(format!("/{}", query));
+        }
+        if filters.is_empty() {
+            return Vec::new();
+        }
+        vec![
+            Span::raw(" "),
+            Span::styled(
+                format!(" filtered: {} ", filters.join(", ")),
+                styles.filtered(),
+            ),
+            Span::raw(" ("),
+            bold("c"),
+            Span::raw(" = clear)"),
+        ]
+    }
+
+    /// Returns the controls for the table, which are different while rows
+    /// are being selected, and are replaced by the filter while it is being
+    /// typed.
+    pub(in crate::view) fn controls<'a>(&self, styles: &'a view::Styles) -> Text<'a> {
+        if self.text_filter.is_typing() {
+            return Text::from(Spans::from(self.text_filter.controls(self.len())));
+        }
+        if !self.selecting {
+            return controls(styles);
+        }
+        Text::from(Spans::from(vec![
+            Span::raw("selecting: "),
+            bold("space"),
+            Span::raw(" = select row, "),
+            bold("a"),
+            Span::raw(" = select all, "),
+            bold("e"),
+            Span::raw(" = export selected, "),
+            bold("T"),
+            Span::raw(" = pin to top, "),
+            bold("h"),
+            Span::raw(" = hide selected, "),
+            bold("v"),
+            Span::raw(" = stop selecting, "),
+            bold("q"),
+            Span::raw(" = quit"),
+        ]))
+    }
+
+    /// Returns how the table is sorted, filtered, and resized, to save in
+    /// the session.
+    pub(in crate::view) fn session(&self) -> session::Table {
+        let header = self.header();
+        let sort = session::Sort {
+            column: header[self.sort_by.as_column()].to_string(),
+            descending: self.sort_descending,
+        };
+        let query = self.text_filter.query();
+        let filter = if query.is_empty() {
+            None
+        } else {
+            Some(query.to_string())
+        };
+        let mut widths = self
+            .resized_widths
+            .iter()
+            .filter_map(|(&column, &width)| Some((header.get(column)?.to_string(), width)))
+            .collect::<Vec<_>>();
+        widths.sort();
+        session::Table {
+            sort: Some(sort),
+            filter,
+            widths,
+        }
+    }
+
+    /// Sorts, filters, and resizes the table as it was in a saved session.
+    ///
+    /// If the column the table was sorted by no longer exists, the table's
+    /// sorting is left unchanged, and columns which no longer exist aren't
+    /// resized.
+    pub(in crate::view) fn restore(&mut self, table: &session::Table) {
+        // The last column can't be sorted by or resized.
+        let header = self.header();
+        let column = |name: &str| header[..header.len() - 1].iter().position(|&h| h == name);
+        let sort = table
+            .sort
+            .as_ref()
+            .and_then(|sort| Some((column(&sort.column)?, sort.descending)));
+        let resized_widths = table
+            .widths
+            .iter()
+            .filter_map(|(name, width)| Some((column(name)?, *width)))
+            .collect::<Vec<_>>();
+
+        if let Some((column, descending)) = sort {
+            if let Ok(sort_by) = T::Sort::try_from(column) {
+                self.selected_column = sort_by.as_column();
+                self.sort_by = sort_by;
+                self.sort_descending = descending;
+            }
+        }
+        self.resized_widths.extend(resized_widths);
+        if let Some(ref filter) = table.filter {
+            self.text_filter.set(filter);
+        }
+    }
+
     pub(in crate::view) fn scroll_with(
         &mut self,
         f: impl Fn(&Vec<Weak<RefCell<T::Row>>>, usize) -> usize,
@@ -162,10 +736,24 @@ pub(in crate::view) fn controls(styles: &view::Styles) -> Text {
         text::Span::raw(" = select column (sort), "),
         bold(styles.if_utf8("\u{2191}\u{2193}", "up, down")),
         text::Span::raw(" = scroll, "),
+        bold("pgup, pgdn, home, end"),
+        text::Span::raw(" = page, "),
         bold(styles.if_utf8("\u{21B5}", "enter")),
         text::Span::raw(" = view details, "),
+        bold("f"),
+        text::Span::raw(" = full fields, "),
         bold("i"),
         text::Span::raw(" = invert sort (highest/lowest), "),
+        bold("+ -"),
+        text::Span::raw(" = resize column, "),
+        bold("v"),
+        text::Span::raw(" = select rows, "),
+        bold("d"),
+        text::Span::raw(" = hide terminated, "),
+        bold("/"),
+        text::Span::raw(" = filter, "),
+        bold("F"),
+        text::Span::raw(" = saved filters, "),
         bold("q"),
         text::Span::raw(" = quit"),
     ]))
@@ -181,10 +769,33 @@ where
         let selected_column = sort_by.as_column();
         Self {
             sorted_items: Default::default(),
+            hidden_items: Default::default(),
             sort_by,
             table_state: Default::default(),
             selected_column,
             sort_descending: false,
+            selecting: false,
+            marked: Vec::new(),
+            pinned: Vec::new(),
+            dismissed: Vec::new(),
+            hide_terminated: false,
+            field_columns: Vec::new(),
+            computed_columns: Vec::new(),
+            hide_idle: false,
+            hide_target: false,
+            show_max_poll: false,
+            show_activity: false,
+            resized_widths: HashMap::new(),
+            rendered_widths: Vec::new(),
+            visible_rows: 0,
+            offset: 0,
+            text_filter: Filter::default(),
+            notice: None,
         }
     }
 }
+
+/// Returns `true` if `items` contains a reference to the same row as `item`.
+fn contains<T>(items: &[Weak<RefCell<T>>], item: &Weak<RefCell<T>>) -> bool {
+    items.iter().any(|i| i.ptr_eq(item))
+}
@@ -1,38 +1,225 @@
 use crate::{
-    input,
+    dump, input, report,
     state::{
-        tasks::{Details, Task},
+        tasks::{Activity, Details, Task, TaskState},
+        window::{Totals, Window},
         DetailsRef,
     },
     util::Percentage,
     view::{
         self, bold,
-        mini_histogram::{HistogramMetadata, MiniHistogram},
+        compare::Baseline,
+        mini_histogram::{self, HistogramMetadata, MiniHistogram},
     },
 };
 use std::{
     cell::RefCell,
+    cmp, fs, mem,
     rc::Rc,
     time::{Duration, SystemTime},
 };
 use tui::{
     layout::{self, Layout},
+    style::{self, Color, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, List, ListItem, Paragraph},
+    widgets::{Block, Cell, List, ListItem, Paragraph, Row, Table},
 };
 
 pub(crate) struct TaskView {
     task: Rc<RefCell<Task>>,
     details: DetailsRef,
+    /// The outcome of the last export, shown until the view is closed.
+    notice: Option<String>,
+    /// Whether the fields pane has focus, in which case the arrow keys
+    /// scroll it.
+    fields_focused: bool,
+    /// How many lines the fields pane is scrolled down by.
+    fields_scroll: usize,
+    /// How many lines fit in the fields pane, as of the last render.
+    fields_height: usize,
+    /// The search through the view's text, if one was started with `/`.
+    search: Option<Search>,
+}
+
+/// A search through the text of the task view.
+#[derive(Debug, Default)]
+struct Search {
+    query: String,
+    /// Whether the query is still being typed.
+    editing: bool,
+    /// The index of the selected match.
+    current: usize,
+    /// How many matches there were when the view was last rendered.
+    matches: usize,
+    /// Whether to scroll the fields pane to the selected match when the view
+    /// is next rendered.
+    jump: bool,
+}
+
+/// Highlights the matches of a search in the lines of the view, counting
+/// them in the order the lines are highlighted.
+struct Highlighter {
+    /// The lowercased text searched for, or `None` if nothing is searched
+    /// for.
+    query: Option<String>,
+    /// The index of the selected match.
+    current: usize,
+    /// How many matches have been highlighted so far.
+    seen: usize,
+    matched: Style,
+    selected: Style,
 }
 
 impl TaskView {
     pub(super) fn new(task: Rc<RefCell<Task>>, details: DetailsRef) -> Self {
-        TaskView { task, details }
+        TaskView {
+            task,
+            details,
+            notice: None,
+            fields_focused: false,
+            fields_scroll: 0,
+            fields_height: 0,
+            search: None,
+        }
+    }
+
+    pub(crate) fn task_id(&self) -> u64 {
+        self.task.borrow().id()
+    }
+
+    /// Returns the ID of the task which spawned the task being inspected, if
+    /// any.
+    pub(crate) fn parent_id(&self) -> Option<u64> {
+        self.task.borrow().parent_id()
     }
 
-    pub(crate) fn update_input(&mut self, _event: input::Event) {
-        // TODO :D
+    /// Returns a baseline for comparing other tasks against the task being
+    /// inspected.
+    pub(crate) fn baseline(&self) -> Baseline {
+        let id = self.task_id();
+        let poll_times = self
+            .details
+            .borrow()
+            .as_ref()
+            .filter(|details| details.task_id() == id)
+            .and_then(|details| details.poll_times_histogram().cloned());
+        Baseline::new(Rc::downgrade(&self.task), poll_times)
+    }
+
+    /// Renders a comparison between the task being inspected and `baseline`.
+    pub(crate) fn render_comparison<B: tui::backend::Backend>(
+        &self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        now: SystemTime,
+        baseline: &Baseline,
+    ) {
+        let task = &*self.task.borrow();
+        let details = self.details.borrow();
+        let poll_times = details
+            .as_ref()
+            .filter(|details| details.task_id() == task.id())
+            .and_then(|details| details.poll_times_histogram());
+        baseline.render(styles, frame, area, now, task, poll_times);
+    }
+
+    /// Returns the ID of the resource which most recently woke the task being
+    /// inspected, if any.
+    pub(crate) fn last_woken_by(&self) -> Option<u64> {
+        self.task.borrow().last_woken_by()
+    }
+
+    /// Returns `true` if the fields pane has focus.
+    pub(crate) fn fields_focused(&self) -> bool {
+        self.fields_focused
+    }
+
+    /// Returns `true` if a search is open, in which case typed keys are
+    /// handled by the search.
+    pub(crate) fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Returns `true` if the search query is being typed, in which case
+    /// every key is handled by the search.
+    pub(crate) fn is_typing_search(&self) -> bool {
+        matches!(self.search, Some(ref search) if search.editing)
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event) {
+        use input::KeyCode::*;
+        let code = match event {
+            input::Event::Key(input::KeyEvent { code, .. }) => code,
+            _ => return,
+        };
+        if let Some(ref mut search) = self.search {
+            if search.update_input(code) {
+                return;
+            }
+            if code == Esc {
+                self.search = None;
+                return;
+            }
+        }
+
+        let page = self.fields_height.saturating_sub(1).max(1);
+        match code {
+            Char('/') => {
+                self.search = Some(Search {
+                    editing: true,
+                    ..Search::default()
+                })
+            }
+            Char('e') => self.export_histogram(),
+            Tab => self.fields_focused = !self.fields_focused,
+            Esc => self.fields_focused = false,
+            Up if self.fields_focused => self.fields_scroll = self.fields_scroll.saturating_sub(1),
+            Down if self.fields_focused => self.fields_scroll += 1,
+            PageUp if self.fields_focused => {
+                self.fields_scroll = self.fields_scroll.saturating_sub(page)
+            }
+            PageDown if self.fields_focused => self.fields_scroll += page,
+            Home if self.fields_focused => self.fields_scroll = 0,
+            // Clamped to the last page when rendering.
+            End if self.fields_focused => self.fields_scroll = usize::MAX,
+            _ => {}
+        }
+    }
+
+    /// Writes the task's poll times histogram to CSV and JSON files in the
+    /// current directory, for plotting elsewhere.
+    fn export_histogram(&mut self) {
+        let id = self.task_id();
+        let details = self.details.borrow();
+        let histogram = details
+            .as_ref()
+            .filter(|details| details.task_id() == id)
+            .and_then(|details| details.poll_times_histogram());
+        let histogram = match histogram {
+            Some(histogram) => histogram,
+            None => {
+                self.notice = Some("no poll times have been received yet".to_string());
+                return;
+            }
+        };
+
+        let path = format!("task-{}-poll-times", id);
+        let result =
+            fs::write(format!("{}.csv", path), dump::histogram_csv(histogram)).and_then(|()| {
+                let json = serde_json::to_string(&report::Histogram::new(histogram))?;
+                fs::write(format!("{}.json", path), json)
+            });
+        self.notice = Some(match result {
+            Ok(()) => {
+                tracing::info!(task.id = id, %path, "exported poll times histogram");
+                format!("exported poll times to {0}.csv and {0}.json", path)
+            }
+            Err(error) => {
+                tracing::warn!(task.id = id, %error, "failed to export poll times histogram");
+                format!("failed to export poll times: {}", error)
+            }
+        });
     }
 
     pub(crate) fn render<B: tui::backend::Backend>(
@@ -41,6 +228,11 @@ impl TaskView {
         frame: &mut tui::terminal::Frame<B>,
         area: layout::Rect,
         now: SystemTime,
+        window: Option<&Window>,
+        break_on_completion: bool,
+        is_baseline: bool,
+        parent_name: Option<&str>,
+        woken_by: Option<&str>,
     ) {
         // Rows with the following info:
         // - Task main attributes
@@ -48,25 +240,252 @@ impl TaskView {
         // - metrics
         // - logs?
 
-        let task = &*self.task.borrow();
-        let details_ref = self.details.borrow();
+        let task = self.task.clone();
+        let task = &*task.borrow();
+        let details = self.details.clone();
+        let details_ref = details.borrow();
         let details = details_ref
             .as_ref()
             .filter(|details| details.task_id() == task.id());
+        let mut highlighter = Highlighter::new(styles, self.search.as_ref());
 
         let warnings: Vec<_> = task
             .warnings()
             .iter()
             .map(|linter| {
-                ListItem::new(Text::from(Spans::from(vec![
+                let (line, _) = highlighter.line(Spans::from(vec![
                     styles.warning_wide(),
                     // TODO(eliza): it would be nice to handle singular vs plural...
                     Span::from(linter.format(task)),
-                ])))
+                ]));
+                ListItem::new(Text::from(line))
             })
             .collect();
 
-        let (controls_area, stats_area, poll_dur_area, fields_area, warnings_area) =
+        let mut controls = vec![
+            Span::raw("controls: "),
+            bold(styles.if_utf8("\u{238B} esc", "esc")),
+            Span::raw(" = return to task list, "),
+        ];
+        if task.parent_id().is_some() {
+            controls.push(bold("u"));
+            controls.push(Span::raw(" = inspect parent task, "));
+        }
+        if woken_by.is_some() {
+            controls.push(bold("o"));
+            controls.push(Span::raw(" = inspect waking resource, "));
+        }
+        controls.extend([
+            bold("/"),
+            Span::raw(" = search, "),
+            bold("tab"),
+            Span::raw(if self.fields_focused {
+                " = unfocus fields, "
+            } else {
+                " = focus fields (scroll), "
+            }),
+            bold("f"),
+            Span::raw(" = full fields, "),
+            bold("c"),
+            Span::raw(if is_baseline {
+                " = stop comparing against this task, "
+            } else {
+                " = compare other tasks against this, "
+            }),
+            bold("e"),
+            Span::raw(" = export poll times, "),
+            bold("x"),
+            Span::raw(if break_on_completion {
+                " = don't pause when this task completes, "
+            } else {
+                " = pause when this task completes, "
+            }),
+            bold("q"),
+            Span::raw(" = quit"),
+        ]);
+        if let Some(ref notice) = self.notice {
+            controls.push(Span::raw(format!(" ({})", notice)));
+        }
+        let controls = match self.search {
+            Some(ref search) => Spans::from(search.controls()),
+            None => Spans::from(controls),
+        };
+
+        // Just preallocate capacity for ID, name, target, total, busy, and idle.
+        let mut overview = Vec::with_capacity(7);
+        overview.push(Spans::from(vec![
+            bold("ID: "),
+            Span::raw(format!("{} ", task.id())),
+            task.state().render(styles),
+        ]));
+
+        if let Some(name) = task.name() {
+            overview.push(Spans::from(vec![
+                bold("Name: "),
+                Span::raw(styles.redact(name)),
+            ]));
+        }
+
+        if let Some(parent_id) = task.parent_id() {
+            let mut spawned_by = vec![bold("Spawned by: "), Span::raw(parent_id.to_string())];
+            if let Some(name) = parent_name {
+                spawned_by.push(Span::raw(format!(" ({})", styles.redact(name))));
+            }
+            overview.push(Spans::from(spawned_by));
+        }
+
+        if let (Some(resource_id), Some(concrete_type)) = (task.last_woken_by(), woken_by) {
+            overview.push(Spans::from(vec![
+                bold("Last woken by: "),
+                Span::raw(format!("{} (resource {})", concrete_type, resource_id)),
+            ]));
+        }
+
+        if task.live_children() > 0 {
+            overview.push(Spans::from(vec![
+                bold("Live children: "),
+                Span::raw(task.live_children().to_string()),
+            ]));
+        }
+
+        overview.push(Spans::from(vec![
+            bold("Target: "),
+            Span::raw(styles.redact(task.target())),
+        ]));
+
+        overview.push(Spans::from(vec![
+            bold("Location: "),
+            Span::raw(styles.redact(task.location())),
+        ]));
+
+        let mut timestamp = |name: &'static str, at: SystemTime| {
+            let mut spans = vec![bold(name)];
+            spans.extend(styles.timestamp(at, now));
+            overview.push(Spans::from(spans));
+        };
+        timestamp("Spawned: ", task.created_at());
+        if let Some(at) = task.last_poll_started() {
+            timestamp("Last polled: ", at);
+        }
+        if let Some(at) = task.dropped_at() {
+            timestamp("Completed: ", at);
+        }
+
+        // While a statistics window is open, show the task's statistics within
+        // the window, rather than over its whole lifetime.
+        let totals = Totals::in_window(task, now, window);
+        let total = totals.total;
+
+        let dur_percent = |name: &'static str, amt: Duration| -> Spans {
+            let percent = amt.as_secs_f64().percent_of(total.as_secs_f64());
+            Spans::from(vec![
+                bold(name),
+                dur(styles, amt),
+                Span::from(format!(" ({:.2}%)", percent)),
+            ])
+        };
+
+        overview.push(Spans::from(vec![bold("Total Time: "), dur(styles, total)]));
+        overview.push(dur_percent("Busy: ", totals.busy));
+        overview.push(dur_percent("Idle: ", totals.idle));
+        overview.push(dur_percent("Scheduled: ", totals.scheduled));
+        if let Some(recent) = task.recent_poll() {
+            overview.push(Spans::from(vec![
+                bold("Recent poll: "),
+                dur(styles, recent),
+            ]));
+        }
+        overview.push(Spans::from(vec![
+            bold("Slow polls: "),
+            Span::raw(task.slow_polls().to_string()),
+        ]));
+        if let Some(max) = task.max_poll() {
+            let mut spans = vec![
+                bold("Max poll: "),
+                dur(styles, max.duration),
+                Span::raw(" ("),
+            ];
+            spans.extend(styles.timestamp(max.at, now));
+            spans.push(Span::raw(")"));
+            overview.push(Spans::from(spans));
+        }
+
+        // Wrap long values, such as locations, rather than cutting them off,
+        // and make the stats tall enough to fit every line. Subtract 2 for
+        // the borders of the overview, which takes half the width.
+        let overview = view::wrap(overview, (area.width / 2).saturating_sub(2) as usize)
+            .into_iter()
+            .map(|line| highlighter.line(line).0)
+            .collect::<Vec<_>>();
+
+        // The change in each waker statistic in the latest update, if the
+        // task changed in it.
+        let change = task.latest_change(now);
+        let delta = |f: fn(&Activity) -> u64| {
+            let delta = change.map_or(0, |(latest, previous)| {
+                f(latest) as i64 - f(previous) as i64
+            });
+            Cell::from(format!("{:>+8}", delta))
+        };
+        let waker_row = |label: Spans<'static>, total: u64, f: fn(&Activity) -> u64| {
+            Row::new(vec![
+                Cell::from(label),
+                Cell::from(format!("{:>10}", total)),
+                delta(f),
+            ])
+        };
+        let mut waker_rows = vec![
+            waker_row(highlighter.bold("Current"), task.waker_count(), |a| {
+                a.waker_clones.saturating_sub(a.waker_drops)
+            }),
+            waker_row(highlighter.bold("Clones"), task.waker_clones(), |a| {
+                a.waker_clones
+            }),
+            waker_row(highlighter.bold("Drops"), task.waker_drops(), |a| {
+                a.waker_drops
+            }),
+            waker_row(highlighter.bold("Wakes"), totals.wakes, |a| a.wakes),
+            waker_row(highlighter.bold("Self wakes"), task.self_wakes(), |a| {
+                a.self_wakes
+            }),
+        ];
+
+        // If the task has been woken, add the time since wake to its stats as well.
+        if let Some(since) = task.since_wake(now) {
+            waker_rows.push(Row::new(vec![
+                Cell::from(highlighter.bold("Last woken")),
+                Cell::from(format!("{:>10}", styles.short_duration(since, 2))),
+                Cell::from(" ago"),
+            ]));
+        }
+        if let Some(mean) = task.mean_scheduling_delay() {
+            // The delay before the poll which started in the latest update,
+            // if any.
+            let latest = task
+                .scheduling_delays()
+                .back()
+                .filter(|delay| matches!(change, Some((_, previous)) if delay.at > previous.at));
+            waker_rows.push(Row::new(vec![
+                Cell::from(highlighter.bold("Wake to poll")),
+                Cell::from(format!("{:>10}", styles.short_duration(mean, 2))),
+                Cell::from(
+                    latest
+                        .map(|latest| format!("{:>8}", styles.short_duration(latest.delay, 1)))
+                        .unwrap_or_default(),
+                ),
+            ]));
+        }
+        if task.self_wakes() > 0 {
+            waker_rows.push(Row::new(vec![
+                Cell::from(highlighter.bold("Self wake %")),
+                Cell::from(format!("{:>9}%", task.self_wake_percent())),
+            ]));
+        }
+
+        // add 2 for the borders and 1 for the wakers table's header
+        let stats_height = cmp::max(overview.len(), waker_rows.len() + 1) as u16 + 2;
+
+        let (controls_area, stats_area, poll_dur_area, activity_area, fields_area, warnings_area) =
             if warnings.is_empty() {
                 let chunks = Layout::default()
                     .direction(layout::Direction::Vertical)
@@ -75,16 +494,18 @@ impl TaskView {
                             // controls
                             layout::Constraint::Length(1),
                             // task stats
-                            layout::Constraint::Length(8),
+                            layout::Constraint::Length(stats_height),
                             // poll duration
                             layout::Constraint::Length(9),
+                            // recent activity
+                            layout::Constraint::Length(6),
                             // fields
                             layout::Constraint::Percentage(60),
                         ]
                         .as_ref(),
                     )
                     .split(area);
-                (chunks[0], chunks[1], chunks[2], chunks[3], None)
+                (chunks[0], chunks[1], chunks[2], chunks[3], chunks[4], None)
             } else {
                 let chunks = Layout::default()
                     .direction(layout::Direction::Vertical)
@@ -95,9 +516,11 @@ impl TaskView {
                             // warnings (add 2 for top and bottom borders)
                             layout::Constraint::Length(warnings.len() as u16 + 2),
                             // task stats
-                            layout::Constraint::Length(8),
+                            layout::Constraint::Length(stats_height),
                             // poll duration
                             layout::Constraint::Length(9),
+                            // recent activity
+                            layout::Constraint::Length(6),
                             // fields
                             layout::Constraint::Percentage(60),
                         ]
@@ -105,7 +528,14 @@ impl TaskView {
                     )
                     .split(area);
 
-                (chunks[0], chunks[2], chunks[3], chunks[4], Some(chunks[1]))
+                (
+                    chunks[0],
+                    chunks[2],
+                    chunks[3],
+                    chunks[4],
+                    chunks[5],
+                    Some(chunks[1]),
+                )
             };
 
         let stats_area = Layout::default()
@@ -139,88 +569,12 @@ impl TaskView {
 
         let percentiles_area = poll_dur_area[0];
 
-        let controls = Spans::from(vec![
-            Span::raw("controls: "),
-            bold(styles.if_utf8("\u{238B} esc", "esc")),
-            Span::raw(" = return to task list, "),
-            bold("q"),
-            Span::raw(" = quit"),
-        ]);
-
-        // Just preallocate capacity for ID, name, target, total, busy, and idle.
-        let mut overview = Vec::with_capacity(7);
-        overview.push(Spans::from(vec![
-            bold("ID: "),
-            Span::raw(format!("{} ", task.id())),
-            task.state().render(styles),
-        ]));
-
-        if let Some(name) = task.name() {
-            overview.push(Spans::from(vec![bold("Name: "), Span::raw(name)]));
-        }
-
-        overview.push(Spans::from(vec![
-            bold("Target: "),
-            Span::raw(task.target()),
-        ]));
-
-        overview.push(Spans::from(vec![
-            bold("Location: "),
-            Span::raw(task.location()),
-        ]));
-
-        let total = task.total(now);
-
-        let dur_percent = |name: &'static str, amt: Duration| -> Spans {
-            let percent = amt.as_secs_f64().percent_of(total.as_secs_f64());
-            Spans::from(vec![
-                bold(name),
-                dur(styles, amt),
-                Span::from(format!(" ({:.2}%)", percent)),
-            ])
-        };
-
-        overview.push(Spans::from(vec![bold("Total Time: "), dur(styles, total)]));
-        overview.push(dur_percent("Busy: ", task.busy(now)));
-        overview.push(dur_percent("Idle: ", task.idle(now)));
-
-        let mut waker_stats = vec![Spans::from(vec![
-            bold("Current wakers: "),
-            Span::from(format!("{} (", task.waker_count())),
-            bold("clones: "),
-            Span::from(format!("{}, ", task.waker_clones())),
-            bold("drops: "),
-            Span::from(format!("{})", task.waker_drops())),
-        ])];
-
-        let mut wakeups = vec![
-            bold("Woken: "),
-            Span::from(format!("{} times", task.wakes())),
-        ];
-
-        // If the task has been woken, add the time since wake to its stats as well.
-        if let Some(since) = task.since_wake(now) {
-            wakeups.reserve(3);
-            wakeups.push(Span::raw(", "));
-            wakeups.push(bold("last woken:"));
-            wakeups.push(Span::from(format!(" {:?} ago", since)));
-        }
-
-        waker_stats.push(Spans::from(wakeups));
-
-        if task.self_wakes() > 0 {
-            waker_stats.push(Spans::from(vec![
-                bold("Self Wakes: "),
-                Span::from(format!(
-                    "{} times ({}%)",
-                    task.self_wakes(),
-                    task.self_wake_percent()
-                )),
-            ]));
-        }
-
         let mut fields = Text::default();
-        fields.extend(task.formatted_fields().iter().cloned().map(Spans::from));
+        fields.extend(
+            task.formatted_fields()
+                .iter()
+                .map(|field| Spans::from(styles.redact_field(field))),
+        );
 
         // If UTF-8 is disabled we can't draw the histogram sparklne.
         if styles.utf8 {
@@ -237,8 +591,12 @@ impl TaskView {
                 .map(|d| d.make_chart_data(sparkline_area.width - 3))
                 .unwrap_or_default();
 
+            let title = mini_histogram::title(
+                "Poll Times Histogram",
+                details.and_then(Details::poll_times_histogram),
+            );
             let histogram_sparkline = MiniHistogram::default()
-                .block(styles.border_block().title("Poll Times Histogram"))
+                .block(styles.border_block().title(title))
                 .data(&chart_data)
                 .metadata(metadata)
                 .duration_precision(2);
@@ -251,9 +609,35 @@ impl TaskView {
             frame.render_widget(warnings, warnings_area);
         }
 
-        let task_widget = Paragraph::new(overview).block(styles.border_block().title("Task"));
-        let wakers_widget = Paragraph::new(waker_stats).block(styles.border_block().title("Waker"));
-        let fields_widget = Paragraph::new(fields).block(styles.border_block().title("Fields"));
+        let (task_title, waker_title) = if window.is_some() {
+            ("Task (window)", "Waker (window)")
+        } else {
+            ("Task", "Waker")
+        };
+        let task_widget = Paragraph::new(overview).block(styles.border_block().title(task_title));
+        let wakers_widget = Table::new(waker_rows)
+            .header(
+                Row::new(vec!["", "     Total", "  Update"])
+                    .style(Style::default().add_modifier(style::Modifier::BOLD)),
+            )
+            .block(styles.border_block().title(waker_title))
+            .widths(&[
+                layout::Constraint::Length(12),
+                layout::Constraint::Length(10),
+                layout::Constraint::Length(8),
+            ]);
+        let fields_area = Layout::default()
+            .direction(layout::Direction::Horizontal)
+            .constraints(
+                [
+                    layout::Constraint::Percentage(50),
+                    layout::Constraint::Percentage(50),
+                ]
+                .as_ref(),
+            )
+            .split(fields_area);
+        let history_widget = Paragraph::new(state_history(styles, task, now))
+            .block(styles.border_block().title("State History"));
         let percentiles_widget = Paragraph::new(
             details
                 .map(|details| details.make_percentiles_widget(styles))
@@ -264,80 +648,364 @@ impl TaskView {
         frame.render_widget(Block::default().title(controls), controls_area);
         frame.render_widget(task_widget, stats_area[0]);
         frame.render_widget(wakers_widget, stats_area[1]);
-        frame.render_widget(fields_widget, fields_area);
+        self.render_fields(styles, frame, fields_area[0], fields, &mut highlighter);
+        if let Some(ref mut search) = self.search {
+            search.matches = highlighter.seen;
+            search.current = search.current.min(highlighter.seen.saturating_sub(1));
+        }
+        frame.render_widget(history_widget, fields_area[1]);
         frame.render_widget(percentiles_widget, percentiles_area);
+
+        let activity_widget = Paragraph::new(recent_activity(styles, task, now, activity_area))
+            .block(styles.border_block().title(format!(
+                "Recent Activity (last {})",
+                humantime::format_duration(RECENT_ACTIVITY)
+            )));
+        frame.render_widget(activity_widget, activity_area);
+    }
+}
+
+impl TaskView {
+    /// Renders the task's fields, scrolled independently of the rest of the
+    /// view.
+    fn render_fields<B: tui::backend::Backend>(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        fields: Text<'static>,
+        highlighter: &mut Highlighter,
+    ) {
+        // subtract 2 for the borders
+        let mut selected_line = None;
+        let fields = view::wrap(fields.lines, area.width.saturating_sub(2) as usize)
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let (line, selected) = highlighter.line(line);
+                if selected {
+                    selected_line = Some(i);
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+        self.fields_height = area.height.saturating_sub(2) as usize;
+        let total = fields.len();
+        // Scroll to the selected match when jumping to it.
+        let jump = match self.search {
+            Some(ref mut search) => mem::take(&mut search.jump),
+            None => false,
+        };
+        if let Some(line) = selected_line.filter(|_| jump) {
+            let visible = self.fields_scroll..self.fields_scroll + self.fields_height;
+            if !visible.contains(&line) {
+                self.fields_scroll = line.saturating_sub(self.fields_height / 2);
+            }
+        }
+        self.fields_scroll = self
+            .fields_scroll
+            .min(total.saturating_sub(self.fields_height));
+
+        let mut title = vec![Span::raw("Fields")];
+        if total > self.fields_height {
+            let first = self.fields_scroll + 1;
+            let last = (self.fields_scroll + self.fields_height).min(total);
+            title.push(Span::raw(format!(" ({}-{} of {})", first, last, total)));
+        }
+        let mut block = styles.border_block().title(Spans::from(title));
+        if self.fields_focused {
+            block = block.border_style(styles.fg(Color::Cyan).add_modifier(style::Modifier::BOLD));
+        }
+        let widget = Paragraph::new(fields)
+            .block(block)
+            .scroll((self.fields_scroll as u16, 0));
+        frame.render_widget(widget, area);
+        view::render_scrollbar(
+            styles,
+            frame,
+            area,
+            self.fields_scroll,
+            self.fields_height,
+            total,
+        );
+    }
+}
+
+// === impl Search ===
+
+impl Search {
+    /// Handles a key, returning `true` if the search used it.
+    fn update_input(&mut self, code: input::KeyCode) -> bool {
+        use input::KeyCode::*;
+        if !self.editing {
+            match code {
+                Char('n') if self.matches > 0 => self.current = (self.current + 1) % self.matches,
+                Char('N') if self.matches > 0 => {
+                    self.current = (self.current + self.matches - 1) % self.matches
+                }
+                Char('n') | Char('N') => {}
+                _ => return false,
+            }
+            self.jump = true;
+            return true;
+        }
+
+        match code {
+            Char(c) => {
+                self.query.push(c);
+                self.current = 0;
+            }
+            Backspace => {
+                self.query.pop();
+                self.current = 0;
+            }
+            Enter => {
+                self.editing = false;
+                self.jump = true;
+            }
+            // Let the view close the search.
+            Esc => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn controls(&self) -> Vec<Span<'static>> {
+        if self.editing {
+            return vec![
+                Span::raw("search: /"),
+                Span::raw(self.query.clone()),
+                Span::styled(
+                    "_",
+                    Style::default().add_modifier(style::Modifier::SLOW_BLINK),
+                ),
+                Span::raw(" ("),
+                bold("enter"),
+                Span::raw(" = done, "),
+                bold("esc"),
+                Span::raw(" = cancel)"),
+            ];
+        }
+        let position = if self.matches > 0 {
+            format!("match {} of {}", self.current + 1, self.matches)
+        } else {
+            "no matches".to_string()
+        };
+        vec![
+            Span::raw(format!("search: /{} ({}) ", self.query, position)),
+            bold("n"),
+            Span::raw(" = next match, "),
+            bold("N"),
+            Span::raw(" = previous match, "),
+            bold("/"),
+            Span::raw(" = new search, "),
+            bold("esc"),
+            Span::raw(" = end search"),
+        ]
+    }
+}
+
+// === impl Highlighter ===
+
+impl Highlighter {
+    fn new(styles: &view::Styles, search: Option<&Search>) -> Self {
+        Self {
+            query: search
+                .map(|search| search.query.to_ascii_lowercase())
+                .filter(|query| !query.is_empty()),
+            current: search.map_or(0, |search| search.current),
+            seen: 0,
+            matched: Style::default().add_modifier(style::Modifier::REVERSED),
+            selected: styles
+                .fg(Color::Yellow)
+                .add_modifier(style::Modifier::REVERSED | style::Modifier::BOLD),
+        }
+    }
+
+    /// Highlights the matches in `line`, returning whether it contains the
+    /// selected match.
+    fn line<'a>(&mut self, line: Spans<'a>) -> (Spans<'a>, bool) {
+        let query = match self.query {
+            Some(ref query) => query,
+            None => return (line, false),
+        };
+        // Lowercasing only ASCII keeps the byte offsets of matches the same
+        // as in the original text.
+        let text = line
+            .0
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let matches = text
+            .match_indices(query.as_str())
+            .map(|(at, found)| at..at + found.len())
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return (line, false);
+        }
+
+        let first = self.seen;
+        self.seen += matches.len();
+        let mut chars = Vec::with_capacity(text.len());
+        let mut at = 0;
+        for span in &line.0 {
+            for c in span.content.chars() {
+                let style = match matches.iter().position(|m| m.contains(&at)) {
+                    Some(i) if first + i == self.current => span.style.patch(self.selected),
+                    Some(_) => span.style.patch(self.matched),
+                    None => span.style,
+                };
+                chars.push((c, style));
+                at += c.len_utf8();
+            }
+        }
+        let selected = (first..self.seen).contains(&self.current);
+        (view::styled_chars(&chars), selected)
+    }
+
+    /// Highlights the matches in a bold label.
+    fn bold(&mut self, label: &'static str) -> Spans<'static> {
+        self.line(Spans::from(bold(label))).0
+    }
+}
+
+/// Returns the task's state transitions, most recent first, with how long
+/// ago each happened and how long the task stayed in each state.
+fn state_history(styles: &view::Styles, task: &Task, now: SystemTime) -> Text<'static> {
+    let transitions = task.transitions();
+    let mut lines = Vec::with_capacity(transitions.len());
+    for (i, transition) in transitions.iter().enumerate().rev() {
+        let ago = now.duration_since(transition.at).unwrap_or_default();
+        let mut spans = vec![
+            dur(styles, ago),
+            Span::raw(" ago "),
+            transition.to.render(styles),
+            Span::raw(match transition.to {
+                TaskState::Running => " running",
+                TaskState::Idle => " idle",
+                TaskState::Completed => " completed",
+            }),
+        ];
+        if transition.polls > 1 {
+            spans.push(Span::raw(format!(" ({} polls)", transition.polls)));
+        }
+        if transition.to != TaskState::Completed {
+            let until = transitions.get(i + 1).map_or(now, |next| next.at);
+            spans.push(Span::raw(" for "));
+            spans.push(dur(
+                styles,
+                until.duration_since(transition.at).unwrap_or_default(),
+            ));
+        }
+        lines.push(Spans::from(spans));
     }
+    Text::from(lines)
+}
+
+/// The period shown in a task's recent activity.
+const RECENT_ACTIVITY: Duration = Duration::from_secs(60);
+
+/// Returns a summary of the task's polls and wakeups over the recent past,
+/// and strips showing when they happened, to fit in `area`.
+///
+/// Wakeups and polls are counted as of each update, so each is placed in the
+/// column for the update which reported it.
+fn recent_activity(
+    styles: &view::Styles,
+    task: &Task,
+    now: SystemTime,
+    area: layout::Rect,
+) -> Text<'static> {
+    const LABEL_WIDTH: usize = 7;
+    let start = now.checked_sub(RECENT_ACTIVITY).unwrap_or(now);
+    // subtract 2 for the borders
+    let width = (area.width as usize).saturating_sub(2 + LABEL_WIDTH).max(1);
+    let column = RECENT_ACTIVITY / width as u32;
+
+    let at = |time: SystemTime| task.activity_at(time).copied();
+    let count = |col: usize, f: fn(&Activity) -> u64| {
+        let col_start = start + column * col as u32;
+        let before = at(col_start).map_or(0, |activity| f(&activity));
+        let after = at(col_start + column).map_or(0, |activity| f(&activity));
+        after.saturating_sub(before)
+    };
+    let polls = (0..width)
+        .map(|col| count(col, |activity| activity.polls))
+        .collect::<Vec<_>>();
+    let wakes = (0..width)
+        .map(|col| count(col, |activity| activity.wakes))
+        .collect::<Vec<_>>();
+    let busy = (0..width)
+        .map(|col| {
+            let col_start = start + column * col as u32;
+            let busy = task.busy_between(col_start, col_start + column);
+            busy.as_micros() as u64
+        })
+        .collect::<Vec<_>>();
+
+    let total = |f: fn(&Activity) -> u64| {
+        let before = at(start).map_or(0, |activity| f(&activity));
+        let after = at(now).map_or(0, |activity| f(&activity));
+        after.saturating_sub(before)
+    };
+    let summary = Spans::from(vec![
+        Span::raw(format!("polled {} times (busy ", total(|a| a.polls))),
+        dur(styles, task.busy_between(start, now)),
+        Span::raw(format!(
+            "), woken {} times ({} self-wakes)",
+            total(|a| a.wakes),
+            total(|a| a.self_wakes)
+        )),
+    ]);
+
+    let row = |label: &'static str, counts: &[u64]| {
+        Spans::from(vec![
+            bold(format!("{:width$}", label, width = LABEL_WIDTH)),
+            Span::raw(strip(styles, counts)),
+        ])
+    };
+    Text::from(vec![
+        summary,
+        row("polls", &polls),
+        row("wakes", &wakes),
+        row("busy", &busy),
+    ])
+}
+
+/// Draws each count as a bar whose height is relative to the largest count.
+pub(super) fn strip(styles: &view::Styles, counts: &[u64]) -> String {
+    const UTF8: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const ASCII: [char; 8] = ['.', '.', ':', ':', '|', '|', '#', '#'];
+    let levels = if styles.utf8 { &UTF8 } else { &ASCII };
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                return ' ';
+            }
+            // Any activity at all gets at least the lowest bar.
+            let level = count * (levels.len() as u64 - 1) / max;
+            levels[level as usize]
+        })
+        .collect()
 }
 
 impl Details {
-    /// From the histogram, build a visual representation by trying to make as
-    // many buckets as the width of the render area.
     fn make_chart_data(&self, width: u16) -> (Vec<u64>, HistogramMetadata) {
         self.poll_times_histogram()
-            .map(|histogram| {
-                let step_size =
-                    ((histogram.max() - histogram.min()) as f64 / width as f64).ceil() as u64 + 1;
-                // `iter_linear` panics if step_size is 0
-                let data = if step_size > 0 {
-                    let mut found_first_nonzero = false;
-                    let data: Vec<u64> = histogram
-                        .iter_linear(step_size)
-                        .filter_map(|value| {
-                            let count = value.count_since_last_iteration();
-                            // Remove the 0s from the leading side of the buckets.
-                            // Because HdrHistogram can return empty buckets depending
-                            // on its internal state, as it approximates values.
-                            if count == 0 && !found_first_nonzero {
-                                None
-                            } else {
-                                found_first_nonzero = true;
-                                Some(count)
-                            }
-                        })
-                        .collect();
-                    data
-                } else {
-                    Vec::new()
-                };
-                let max_bucket = data.iter().max().copied().unwrap_or_default();
-                let min_bucket = data.iter().min().copied().unwrap_or_default();
-                (
-                    data,
-                    HistogramMetadata {
-                        max_value: histogram.max(),
-                        min_value: histogram.min(),
-                        max_bucket,
-                        min_bucket,
-                    },
-                )
-            })
+            .map(|histogram| mini_histogram::chart_data(histogram, width))
             .unwrap_or_default()
     }
 
-    /// Get the important percentile values from the histogram
     fn make_percentiles_widget(&self, styles: &view::Styles) -> Text<'static> {
-        let mut text = Text::default();
-        let histogram = self.poll_times_histogram();
-        let percentiles = histogram.iter().flat_map(|histogram| {
-            let pairs = [10f64, 25f64, 50f64, 75f64, 90f64, 95f64, 99f64]
-                .iter()
-                .map(move |i| (*i, histogram.value_at_percentile(*i)));
-            pairs.map(|pair| {
-                Spans::from(vec![
-                    bold(format!("p{:>2}: ", pair.0)),
-                    dur(styles, Duration::from_nanos(pair.1)),
-                ])
-            })
-        });
-        text.extend(percentiles);
-        text
+        self.poll_times_histogram()
+            .map(|histogram| mini_histogram::percentiles(styles, histogram))
+            .unwrap_or_default()
     }
 }
 
 fn dur(styles: &view::Styles, dur: std::time::Duration) -> Span<'static> {
-    const DUR_PRECISION: usize = 4;
-    // TODO(eliza): can we not have to use `format!` to make a string here? is
-    // there a way to just give TUI a `fmt::Debug` implementation, or does it
-    // have to be given a string in order to do layout stuff?
-    styles.time_units(format!("{:.prec$?}", dur, prec = DUR_PRECISION))
+    styles.time_units(styles.duration(dur))
 }
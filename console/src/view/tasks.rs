@@ -1,32 +1,172 @@
 use crate::{
+    computed::ComputedColumn,
+    report,
     state::{
         tasks::{SortBy, Task, TaskState},
+        window::{Totals, Window},
         State,
     },
     view::{
-        self, bold,
-        table::{self, TableList, TableListState},
-        DUR_LEN, DUR_PRECISION,
+        self, bold, filter,
+        table::{SortBy as _, TableList, TableListState},
+        task::strip,
     },
 };
+use std::time::{Duration, SystemTime};
 use tui::{
     layout,
     style::{self, Color, Style},
     text::{Span, Spans, Text},
     widgets::{self, Cell, ListItem, Paragraph, Row, Table},
 };
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug, Default)]
 pub(crate) struct TasksTable {}
 
 impl TableList for TasksTable {
     type Row = Task;
+    type Report = report::Task;
     type Sort = SortBy;
 
     const HEADER: &'static [&'static str] = &[
-        "Warn", "ID", "State", "Name", "Total", "Busy", "Idle", "Polls", "Target", "Location",
+        "Warn",
+        "ID",
+        "Parent",
+        "Children",
+        "State",
+        "Name",
+        "Total",
+        "Busy",
+        "Idle",
+        "Scheduled",
+        "Polls",
+        "Wake Delay",
+        "Recent Poll",
+        "Slow Polls",
+        "Max Poll",
+        "Idle For",
+        "Activity",
+        "Runtime",
+        "Target",
+        "Location",
         "Fields",
     ];
+    const NAME: &'static str = "tasks";
+    const TERMINATED: &'static str = "completed tasks";
+    const FILTER_KEYS: &'static [&'static str] = &[
+        "id",
+        "parent",
+        "children",
+        "state",
+        "name",
+        "total",
+        "busy",
+        "idle",
+        "scheduled",
+        "polls",
+        "wakes",
+        "self_wakes",
+        "wakers",
+        "wake_delay",
+        "recent_poll",
+        "slow_polls",
+        "max_poll",
+        "idle_for",
+        "runtime",
+        "target",
+        "location",
+        "warnings",
+    ];
+
+    fn report(task: &Task, now: SystemTime) -> Self::Report {
+        report::Task::new(task, now)
+    }
+
+    fn summarize(styles: &view::Styles, tasks: &[&Task], now: SystemTime) -> String {
+        let busy = tasks.iter().map(|task| task.busy(now)).sum::<Duration>();
+        let polls = tasks.iter().map(|task| task.total_polls()).sum::<u64>();
+        let wakes = tasks.iter().map(|task| task.wakes()).sum::<u64>();
+        format!(
+            "busy {}, {} polls, {} wakes",
+            styles.duration(busy),
+            polls,
+            wakes,
+        )
+    }
+
+    fn is_terminated(task: &Task) -> bool {
+        task.is_completed()
+    }
+
+    fn contains(task: &Task, term: &str) -> bool {
+        filter::contains(&task.id().to_string(), term)
+            || matches!(task.name(), Some(name) if filter::contains(name, term))
+            || matches!(task.runtime(), Some(runtime) if filter::contains(runtime, term))
+            || filter::contains(task.target(), term)
+            || filter::contains(task.location(), term)
+            || filter::lines_contain(task.formatted_fields(), term)
+    }
+
+    fn value(
+        task: &Task,
+        key: &str,
+        now: SystemTime,
+        window: Option<&Window>,
+    ) -> Option<filter::Value> {
+        use filter::Value;
+        let totals = || Totals::in_window(task, now, window);
+        Some(match key {
+            "id" => Value::Number(task.id() as f64),
+            "parent" => Value::Number(task.parent_id()? as f64),
+            "children" => Value::Number(task.live_children() as f64),
+            "state" => Value::Text(
+                match task.state() {
+                    TaskState::Running => "running",
+                    TaskState::Idle => "idle",
+                    TaskState::Completed => "completed",
+                }
+                .to_string(),
+            ),
+            "name" => Value::Text(task.name()?.to_string()),
+            "total" => Value::Duration(totals().total),
+            "busy" => Value::Duration(totals().busy),
+            "idle" => Value::Duration(totals().idle),
+            "scheduled" => Value::Duration(totals().scheduled),
+            "polls" => Value::Number(totals().polls as f64),
+            "wakes" => Value::Number(totals().wakes as f64),
+            "self_wakes" => Value::Number(task.self_wakes() as f64),
+            "wakers" => Value::Number(task.waker_count() as f64),
+            "wake_delay" => Value::Duration(task.mean_scheduling_delay()?),
+            "recent_poll" => Value::Duration(task.recent_poll()?),
+            "slow_polls" => Value::Number(task.slow_polls() as f64),
+            "max_poll" => Value::Duration(task.max_poll()?.duration),
+            "idle_for" => Value::Duration(task.idle_for(now)?),
+            "runtime" => Value::Text(task.runtime()?.to_string()),
+            "target" => Value::Text(task.target().to_string()),
+            "location" => Value::Text(task.location().to_string()),
+            "warnings" => Value::Number(task.warnings().len() as f64),
+            _ => return None,
+        })
+    }
+
+    fn field(task: &Task, name: &str) -> Option<filter::Value> {
+        task.field(name).map(filter::Value::from)
+    }
+
+    fn hidden_columns(state: &TableListState<Self>) -> Vec<usize> {
+        let mut hidden = Vec::new();
+        if !state.show_max_poll {
+            hidden.push(SortBy::MaxPoll.as_column());
+        }
+        if !state.show_activity {
+            hidden.push(SortBy::Activity.as_column());
+        }
+        if state.hide_target {
+            hidden.push(SortBy::Target.as_column());
+        }
+        hidden
+    }
 
     fn render<B: tui::backend::Backend>(
         table_list_state: &mut TableListState<Self>,
@@ -35,7 +175,7 @@ impl TableList for TasksTable {
         area: layout::Rect,
         state: &mut State,
     ) {
-        let state_len: u16 = Self::HEADER[2].len() as u16;
+        let state_len: u16 = Self::HEADER[4].len() as u16;
         let now = if let Some(now) = state.last_updated_at() {
             now
         } else {
@@ -47,46 +187,92 @@ impl TableList for TasksTable {
             .sorted_items
             .extend(state.tasks_state_mut().take_new_tasks());
 
-        table_list_state
-            .sort_by
-            .sort(now, &mut table_list_state.sorted_items);
+        // While a statistics window is open, show each task's statistics
+        // within the window, rather than over its whole lifetime.
+        let window = state.window();
+        let runtime_filter = state.runtime_filter();
+        let hide_idle = table_list_state.hide_idle;
+        let hide_target = table_list_state.hide_target;
+        let show_max_poll = table_list_state.show_max_poll;
+        let show_activity = table_list_state.show_activity;
+        let interval = state.tasks_state().update_interval();
+        table_list_state.filter(now, window, |task| {
+            (runtime_filter.is_none() || task.runtime().map(|r| &**r) == runtime_filter)
+                && !(hide_idle && task.state() == TaskState::Idle)
+        });
+
+        table_list_state.sort_by.sort(
+            now,
+            window,
+            interval,
+            &table_list_state.field_columns,
+            &table_list_state.computed_columns,
+            &mut table_list_state.sorted_items,
+        );
+        table_list_state.pin();
 
         let dur_cell = |dur: std::time::Duration| -> Cell<'static> {
             Cell::from(styles.time_units(format!(
-                "{:>width$.prec$?}",
-                dur,
-                width = DUR_LEN,
-                prec = DUR_PRECISION,
+                "{:>width$}",
+                styles.duration(dur),
+                width = styles.duration_len(),
             )))
         };
 
         // Start out wide enough to display the column headers...
         let mut warn_width = view::Width::new(Self::HEADER[0].len() as u16);
         let mut id_width = view::Width::new(Self::HEADER[1].len() as u16);
-        let mut name_width = view::Width::new(Self::HEADER[3].len() as u16);
-        let mut polls_width = view::Width::new(Self::HEADER[7].len() as u16);
-        let mut target_width = view::Width::new(Self::HEADER[8].len() as u16);
-        let mut location_width = view::Width::new(Self::HEADER[9].len() as u16);
+        let mut parent_width = view::Width::new(Self::HEADER[2].len() as u16);
+        let mut children_width = view::Width::new(Self::HEADER[3].len() as u16);
+        let mut name_width = view::Width::new(Self::HEADER[5].len() as u16);
+        let mut polls_width = view::Width::new(Self::HEADER[10].len() as u16);
+        let mut slow_polls_width = view::Width::new(Self::HEADER[13].len() as u16);
+        let mut runtime_width = view::Width::new(Self::HEADER[17].len() as u16);
+        let mut target_width = view::Width::new(Self::HEADER[18].len() as u16);
+        let mut location_width = view::Width::new(Self::HEADER[19].len() as u16);
+        let mut field_widths = table_list_state
+            .field_columns
+            .iter()
+            .map(String::as_str)
+            .chain(
+                table_list_state
+                    .computed_columns
+                    .iter()
+                    .map(ComputedColumn::name),
+            )
+            .map(|name| view::Width::new(name.width() as u16))
+            .collect::<Vec<_>>();
+
+        // Rows which changed in the latest update are highlighted briefly.
+        let update_age = state.update_age();
 
         let mut num_idle = 0;
         let mut num_running = 0;
         let rows = {
             let id_width = &mut id_width;
+            let parent_width = &mut parent_width;
+            let children_width = &mut children_width;
             let target_width = &mut target_width;
             let location_width = &mut location_width;
             let name_width = &mut name_width;
             let polls_width = &mut polls_width;
+            let slow_polls_width = &mut slow_polls_width;
+            let runtime_width = &mut runtime_width;
             let warn_width = &mut warn_width;
+            let field_widths = &mut field_widths;
             let num_running = &mut num_running;
             let num_idle = &mut num_idle;
+            let table_list_state = &*table_list_state;
 
             table_list_state
                 .sorted_items
                 .iter()
                 .filter_map(move |task| {
+                    let marked = table_list_state.is_marked(task);
                     let task = task.upgrade()?;
                     let task = task.borrow();
                     let state = task.state();
+                    let totals = Totals::in_window(&task, now, window);
 
                     // Count task states
                     match state {
@@ -106,32 +292,94 @@ impl TableList for TasksTable {
                         Cell::from("")
                     };
 
-                    let mut row = Row::new(vec![
+                    let name = styles.redact(task.name().unwrap_or(""));
+                    let runtime = task.runtime().map(|r| r.to_string()).unwrap_or_default();
+                    let target = styles.redact(task.target());
+                    let location = styles.redact(task.location());
+                    let mut cells = vec![
                         warnings,
                         Cell::from(id_width.update_str(format!(
                             "{:>width$}",
                             task.id(),
                             width = id_width.chars() as usize
                         ))),
+                        Cell::from(
+                            parent_width.update_str(
+                                task.parent_id()
+                                    .map(|id| id.to_string())
+                                    .unwrap_or_default(),
+                            ),
+                        ),
+                        Cell::from(children_width.update_str(format!(
+                            "{:>width$}",
+                            task.live_children(),
+                            width = children_width.chars() as usize
+                        ))),
                         Cell::from(task.state().render(styles)),
-                        Cell::from(name_width.update_str(task.name().unwrap_or("").to_string())),
-                        dur_cell(task.total(now)),
-                        dur_cell(task.busy(now)),
-                        dur_cell(task.idle(now)),
-                        Cell::from(polls_width.update_str(task.total_polls().to_string())),
-                        Cell::from(target_width.update_str(task.target()).to_owned()),
-                        Cell::from(location_width.update_str(task.location().to_owned())),
-                        Cell::from(Spans::from(
-                            task.formatted_fields()
-                                .iter()
-                                .flatten()
-                                .cloned()
-                                .collect::<Vec<_>>(),
-                        )),
-                    ]);
+                        Cell::from(name_width.update_str(name).into_owned()),
+                        dur_cell(totals.total),
+                        dur_cell(totals.busy),
+                        dur_cell(totals.idle),
+                        dur_cell(totals.scheduled),
+                        Cell::from(polls_width.update_str(totals.polls.to_string())),
+                        task.mean_scheduling_delay()
+                            .map(dur_cell)
+                            .unwrap_or_else(|| Cell::from("")),
+                        task.recent_poll()
+                            .map(dur_cell)
+                            .unwrap_or_else(|| Cell::from("")),
+                        Cell::from(slow_polls_width.update_str(task.slow_polls().to_string())),
+                        task.max_poll()
+                            .filter(|_| show_max_poll)
+                            .map(|max| dur_cell(max.duration))
+                            .unwrap_or_else(|| Cell::from("")),
+                        task.idle_for(now)
+                            .map(dur_cell)
+                            .unwrap_or_else(|| Cell::from("")),
+                        if show_activity {
+                            Cell::from(strip(styles, &task.recent_polls(now, interval)))
+                        } else {
+                            Cell::from("")
+                        },
+                        Cell::from(runtime_width.update_str(runtime)),
+                        if hide_target {
+                            Cell::from("")
+                        } else {
+                            Cell::from(target_width.update_str(target).into_owned())
+                        },
+                        Cell::from(location_width.update_str(location).into_owned()),
+                    ];
+                    let field_values = table_list_state
+                        .field_columns
+                        .iter()
+                        .map(|name| {
+                            task.field(name)
+                                .map(|value| styles.redact(value.to_string()).into_owned())
+                        })
+                        .chain(table_list_state.computed_columns.iter().map(|column| {
+                            column.eval(&task, now, window).map(ComputedColumn::format)
+                        }));
+                    for (width, value) in field_widths.iter_mut().zip(field_values) {
+                        cells.push(Cell::from(width.update_str(value.unwrap_or_default())));
+                    }
+                    cells.push(Cell::from(Spans::from(
+                        task.formatted_fields()
+                            .iter()
+                            .flat_map(|field| styles.redact_field(field))
+                            .collect::<Vec<_>>(),
+                    )));
+                    let mut row = Row::new(cells);
+                    let mut style = Style::default();
                     if state == TaskState::Completed {
-                        row = row.style(styles.terminated());
+                        style = styles.terminated();
                     }
+                    if let Some(age) = update_age.filter(|_| task.changed_in(now)) {
+                        style = style.patch(styles.changed(age));
+                    }
+                    if marked {
+                        style = style.patch(styles.marked());
+                    }
+                    row = row.style(style);
                     Some(row)
                 })
         };
@@ -146,8 +394,13 @@ impl TableList for TasksTable {
         };
         let header_style = header_style.add_modifier(style::Modifier::BOLD);
 
-        let header = Row::new(Self::HEADER.iter().enumerate().map(|(idx, &value)| {
-            let cell = Cell::from(value);
+        let hidden_columns = Self::hidden_columns(table_list_state);
+        let mut header = table_list_state.header();
+        for &column in &hidden_columns {
+            header[column] = "";
+        }
+        let header = Row::new(header.into_iter().enumerate().map(|(idx, value)| {
+            let cell = Cell::from(value.to_string());
             if idx == table_list_state.selected_column {
                 cell.style(selected_style)
             } else {
@@ -163,13 +416,38 @@ impl TableList for TasksTable {
             Table::new(rows.rev())
         };
 
-        let block = styles.border_block().title(vec![
+        let dropped_events = state.dropped_events().tasks;
+        let mut title = vec![
             bold(format!("Tasks ({}) ", table_list_state.len())),
             TaskState::Running.render(styles),
             Span::from(format!(" Running ({}) ", num_running)),
             TaskState::Idle.render(styles),
             Span::from(format!(" Idle ({})", num_idle)),
-        ]);
+        ];
+        let mut filters = runtime_filter
+            .map(|runtime| format!("runtime {}", runtime))
+            .into_iter()
+            .collect::<Vec<_>>();
+        if hide_idle {
+            filters.push("not idle".to_string());
+        }
+        title.extend(table_list_state.filter_title(styles, filters));
+        title.extend(table_list_state.selection_title(styles, now));
+        if let Some(window) = window {
+            let (update, _) = state.update_position();
+            title.push(Span::from(format!(
+                " stats for updates {}-{} ({})",
+                window.start_update,
+                update,
+                styles.duration(now.duration_since(window.start).unwrap_or_default()),
+            )));
+        }
+        if dropped_events > 0 {
+            title.push(Span::from(" "));
+            title.push(styles.warning_narrow());
+            title.push(Span::from("stats may be incomplete"));
+        }
+        let block = styles.border_block().title(title);
 
         /* TODO: use this to adjust the max size of name and target columns...
         // How many characters wide are the fixed-length non-field columns?
@@ -182,17 +460,41 @@ impl TableList for TasksTable {
             + POLLS_LEN as u16
             + target_width.chars();
         */
-        let warnings = state
-            .tasks_state()
-            .warnings()
-            .map(|warning| {
+        let mut warnings = state
+            .alerts()
+            .map(|alert| {
                 ListItem::new(Text::from(Spans::from(vec![
                     styles.warning_wide(),
-                    // TODO(eliza): it would be nice to handle singular vs plural...
-                    Span::from(format!("{} {}", warning.count(), warning.summary())),
+                    Span::from(alert.to_string()),
                 ])))
             })
             .collect::<Vec<_>>();
+        warnings.extend(state.tasks_state().warnings().map(|warning| {
+            let mut spans = vec![
+                styles.warning_wide(),
+                // TODO(eliza): it would be nice to handle singular vs plural...
+                Span::from(format!("{} {}", warning.count(), warning.summary())),
+            ];
+            if let Some(worst) = state.tasks_state().warned_by(warning).first() {
+                let worst = worst.borrow();
+                let name = worst
+                    .name()
+                    .map(|name| format!(" ({})", styles.redact(name)))
+                    .unwrap_or_default();
+                spans.push(Span::raw(format!(" (worst: task {}{})", worst.id(), name)));
+            }
+            ListItem::new(Text::from(Spans::from(spans)))
+        }));
+        if dropped_events > 0 {
+            warnings.push(ListItem::new(Text::from(Spans::from(vec![
+                styles.warning_wide(),
+                Span::from(format!(
+                    "{} task events were dropped by the instrumented process; \
+                     task stats may be incomplete",
+                    dropped_events
+                )),
+            ]))));
+        }
 
         let layout = layout::Layout::default()
             .direction(layout::Direction::Vertical)
@@ -230,34 +532,88 @@ impl TableList for TasksTable {
         //
         // See https://github.com/fdehau/tui-rs/issues/525
         let fields_width = layout::Constraint::Percentage(100);
-        let widths = &[
+        let dur_width = layout::Constraint::Length(styles.duration_len() as u16);
+        let activity_width = layout::Constraint::Length(Task::RECENT_POLLS_LEN as u16);
+        let mut widths = vec![
             warn_width.constraint(),
             id_width.constraint(),
+            parent_width.constraint(),
+            children_width.constraint(),
             layout::Constraint::Length(state_len),
             name_width.constraint(),
-            layout::Constraint::Length(DUR_LEN as u16),
-            layout::Constraint::Length(DUR_LEN as u16),
-            layout::Constraint::Length(DUR_LEN as u16),
+            dur_width,
+            dur_width,
+            dur_width,
+            dur_width,
             polls_width.constraint(),
+            dur_width,
+            dur_width,
+            slow_polls_width.constraint(),
+            dur_width,
+            dur_width,
+            activity_width,
+            runtime_width.constraint(),
             target_width.constraint(),
             location_width.constraint(),
-            fields_width,
         ];
+        widths.extend(field_widths.iter().map(view::Width::constraint));
+        widths.push(fields_width);
+        table_list_state.resize(&mut widths);
+        for &column in &hidden_columns {
+            widths[column] = layout::Constraint::Length(0);
+        }
 
         let table = table
             .header(header)
             .block(block)
-            .widths(widths)
+            .widths(&widths)
             .highlight_symbol(view::TABLE_HIGHLIGHT_SYMBOL)
             .highlight_style(Style::default().add_modifier(style::Modifier::BOLD));
 
         frame.render_stateful_widget(table, tasks_area, &mut table_list_state.table_state);
-        frame.render_widget(Paragraph::new(table::controls(styles)), controls_area);
+        table_list_state.render_position(styles, frame, tasks_area);
+        let mut controls = table_list_state.controls(styles);
+        let filtering = table_list_state.is_filtering();
+        if let Some(spans) = controls.lines.first_mut().filter(|_| !filtering) {
+            // Keep "q = quit" last.
+            let at = spans.0.len().saturating_sub(2);
+            spans.0.splice(
+                at..at,
+                vec![
+                    bold("R"),
+                    Span::raw(" = filter by runtime, "),
+                    bold("I"),
+                    Span::raw(" = hide idle, "),
+                    bold("o"),
+                    Span::raw(if hide_target {
+                        " = show target, "
+                    } else {
+                        " = hide target, "
+                    }),
+                    bold("L"),
+                    Span::raw(if show_max_poll {
+                        " = hide max poll, "
+                    } else {
+                        " = show max poll, "
+                    }),
+                    bold("S"),
+                    Span::raw(if show_activity {
+                        " = hide activity, "
+                    } else {
+                        " = show activity, "
+                    }),
+                ],
+            );
+        }
+        frame.render_widget(Paragraph::new(controls), controls_area);
 
         if let Some(area) = warnings_area {
-            let block = styles
-                .border_block()
-                .title(Spans::from(vec![bold("Warnings")]));
+            let block = styles.border_block().title(Spans::from(vec![
+                bold("Warnings"),
+                Span::raw(" ("),
+                bold("W"),
+                Span::raw(" = list all, and history)"),
+            ]));
             frame.render_widget(widgets::List::new(warnings).block(block), area);
         }
 
@@ -0,0 +1,245 @@
+use crate::{
+    input,
+    state::{tasks::Task, State},
+    view::{self, bold},
+};
+use std::time::{Duration, SystemTime};
+use tui::{
+    layout::{self, Layout},
+    style::Color,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+/// A Gantt-style chart with a row for each task, showing when it was alive
+/// and when it was busy.
+pub(crate) struct TimelineView {
+    /// How much time the chart spans.
+    span: Duration,
+    /// How far before the latest update the chart ends.
+    offset: Duration,
+    /// The index of the first task row displayed.
+    scroll: usize,
+}
+
+/// What a task was doing during the time covered by one column of the chart.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Activity {
+    NotAlive,
+    Idle,
+    Busy,
+}
+
+impl TimelineView {
+    const MIN_SPAN: Duration = Duration::from_secs(1);
+    const MAX_SPAN: Duration = Duration::from_secs(24 * 60 * 60);
+    /// The width of the column labelling each row with its task.
+    const LABEL_WIDTH: usize = 24;
+
+    pub(super) fn new() -> Self {
+        Self {
+            span: Duration::from_secs(60),
+            offset: Duration::ZERO,
+            scroll: 0,
+        }
+    }
+
+    pub(crate) fn update_input(&mut self, event: input::Event) {
+        use input::KeyCode::*;
+        let code = match event {
+            input::Event::Key(input::KeyEvent { code, .. }) => code,
+            _ => return,
+        };
+        // Pan by a quarter of the chart at a time.
+        let pan = self.span / 4;
+        match code {
+            Char('+') | Char('=') => self.span = (self.span / 2).max(Self::MIN_SPAN),
+            Char('-') => self.span = (self.span * 2).min(Self::MAX_SPAN),
+            Left => self.offset = (self.offset + pan).min(Self::MAX_SPAN),
+            Right => self.offset = self.offset.saturating_sub(pan),
+            Up => self.scroll = self.scroll.saturating_sub(1),
+            Down => self.scroll += 1,
+            Char('0') => {
+                self.offset = Duration::ZERO;
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn render<B: tui::backend::Backend>(
+        &mut self,
+        styles: &view::Styles,
+        frame: &mut tui::terminal::Frame<B>,
+        area: layout::Rect,
+        state: &State,
+    ) {
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(
+                [
+                    // controls
+                    layout::Constraint::Length(1),
+                    // chart
+                    layout::Constraint::Min(3),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        let (controls_area, chart_area) = (chunks[0], chunks[1]);
+
+        let controls = Spans::from(vec![
+            Span::raw("controls: "),
+            bold(styles.if_utf8("\u{2190}\u{2192}", "left, right")),
+            Span::raw(" = pan, "),
+            bold("+ -"),
+            Span::raw(" = zoom in/out, "),
+            bold(styles.if_utf8("\u{2191}\u{2193}", "up, down")),
+            Span::raw(" = scroll, "),
+            bold("0"),
+            Span::raw(" = reset, "),
+            bold("t"),
+            Span::raw(" = tasks, "),
+            bold("q"),
+            Span::raw(" = quit"),
+        ]);
+        frame.render_widget(Paragraph::new(controls), controls_area);
+
+        let now = match state.last_updated_at() {
+            Some(now) => now,
+            None => return,
+        };
+        let end = now - self.offset;
+        let start = end.checked_sub(self.span).unwrap_or(end);
+
+        // Only tasks which were alive during the chart's span are listed.
+        let mut tasks = state
+            .tasks_state()
+            .tasks()
+            .filter(|task| {
+                let task = task.borrow();
+                task.created_at() <= end && !matches!(task.dropped_at(), Some(at) if at < start)
+            })
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|task| {
+            let task = task.borrow();
+            (task.created_at(), task.id())
+        });
+
+        // subtract 2 for the borders and 1 for the time axis
+        let height = chart_area.height.saturating_sub(3) as usize;
+        self.scroll = self.scroll.min(tasks.len().saturating_sub(height.max(1)));
+        let width = (chart_area.width as usize).saturating_sub(2 + Self::LABEL_WIDTH + 1);
+
+        let mut lines = Vec::with_capacity(height + 1);
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{:width$} ", "", width = Self::LABEL_WIDTH)),
+            Span::raw(axis(start, end, width)),
+        ]));
+        for task in tasks.iter().skip(self.scroll).take(height) {
+            let task = task.borrow();
+            let mut spans = vec![Span::raw(label(styles, &task)), Span::raw(" ")];
+            spans.extend(self.bar(styles, &task, start, end, width));
+            lines.push(Spans::from(spans));
+        }
+
+        let title = Spans::from(vec![
+            bold(format!("Timeline ({} tasks) ", tasks.len())),
+            Span::raw(format!(
+                "{} to {}",
+                humantime::format_rfc3339_seconds(start),
+                humantime::format_rfc3339_seconds(end)
+            )),
+        ]);
+        let chart = Paragraph::new(lines).block(styles.border_block().title(title));
+        frame.render_widget(chart, chart_area);
+    }
+
+    /// Returns the spans drawing a task's activity between `start` and `end`,
+    /// in `width` columns.
+    fn bar(
+        &self,
+        styles: &view::Styles,
+        task: &Task,
+        start: SystemTime,
+        end: SystemTime,
+        width: usize,
+    ) -> Vec<Span<'static>> {
+        let column = self.span / width.max(1) as u32;
+        let activities = (0..width).map(|i| {
+            let col_start = start + column * i as u32;
+            let col_end = col_start + column;
+            let alive = task.created_at() < col_end
+                && task
+                    .dropped_at()
+                    .map_or(col_start < end, |at| at > col_start);
+            if !alive {
+                Activity::NotAlive
+            } else if task.busy_between(col_start, col_end) * 2 >= column {
+                Activity::Busy
+            } else {
+                Activity::Idle
+            }
+        });
+
+        // Group runs of the same activity into spans, so that busy time can
+        // be colored.
+        let mut spans = Vec::new();
+        let mut run = None;
+        for activity in activities {
+            match run {
+                Some((current, len)) if current == activity => run = Some((current, len + 1)),
+                _ => {
+                    if let Some((current, len)) = run.replace((activity, 1)) {
+                        spans.push(Activity::render(current, styles, len));
+                    }
+                }
+            }
+        }
+        if let Some((current, len)) = run {
+            spans.push(Activity::render(current, styles, len));
+        }
+        spans
+    }
+}
+
+impl Activity {
+    fn render(self, styles: &view::Styles, len: usize) -> Span<'static> {
+        match self {
+            Activity::NotAlive => Span::raw(" ".repeat(len)),
+            Activity::Idle => Span::raw(styles.if_utf8("\u{2500}", "-").repeat(len)),
+            Activity::Busy => Span::styled(
+                styles.if_utf8("\u{2588}", "#").repeat(len),
+                styles.fg(Color::Green),
+            ),
+        }
+    }
+}
+
+/// Returns the label for a task's row: its ID, and its name or location.
+fn label(styles: &view::Styles, task: &Task) -> String {
+    let description = styles.redact(task.name().unwrap_or_else(|| task.location()));
+    let label = format!("{:>5} {}", task.id(), description);
+    view::fit_width(&label, TimelineView::LABEL_WIDTH)
+}
+
+/// Returns a time axis for the chart, marking how long before `end` each
+/// quarter of the chart is.
+fn axis(start: SystemTime, end: SystemTime, width: usize) -> String {
+    let span = end.duration_since(start).unwrap_or_default();
+    let mut axis = vec![' '; width];
+    for quarter in 0..4 {
+        let col = width * quarter / 4;
+        let ago = span - span * quarter as u32 / 4;
+        let mark = format!(
+            "|-{}",
+            humantime::format_duration(Duration::from_secs(ago.as_secs()))
+        );
+        for (i, c) in mark.chars().enumerate() {
+            if let Some(slot) = axis.get_mut(col + i) {
+                *slot = c;
+            }
+        }
+    }
+    axis.into_iter().collect()
+}
@@ -0,0 +1,200 @@
+use crate::{
+    state::{
+        tasks::{SpawnRate, TaskCounts, TaskState},
+        State,
+    },
+    view::{self, bold},
+};
+use std::time::SystemTime;
+use tui::{
+    layout::{self, Layout},
+    style::{Color, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{Axis, Chart, Dataset, GraphType, Paragraph},
+};
+
+/// Renders charts of how the instrumented process' tasks changed over the
+/// updates the console has kept: the rates at which tasks were spawned and
+/// completed, and the number of live tasks.
+pub(crate) fn render<B: tui::backend::Backend>(
+    styles: &view::Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    state: &State,
+) {
+    let chunks = Layout::default()
+        .direction(layout::Direction::Vertical)
+        .constraints(
+            [
+                // controls
+                layout::Constraint::Length(1),
+                // spawn rates
+                layout::Constraint::Percentage(50),
+                // task counts
+                layout::Constraint::Percentage(50),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let (controls_area, rates_area, counts_area) = (chunks[0], chunks[1], chunks[2]);
+
+    let controls = Spans::from(vec![
+        Span::raw("controls: "),
+        bold("t"),
+        Span::raw(" = tasks, "),
+        bold("r"),
+        Span::raw(" = resources, "),
+        bold("q"),
+        Span::raw(" = quit"),
+    ]);
+    frame.render_widget(Paragraph::new(controls), controls_area);
+
+    let now = match state.last_updated_at() {
+        Some(now) => now,
+        None => return,
+    };
+    render_spawn_rates(styles, frame, rates_area, now, state);
+    render_counts(styles, frame, counts_area, now, state);
+}
+
+fn render_spawn_rates<B: tui::backend::Backend>(
+    styles: &view::Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    now: SystemTime,
+    state: &State,
+) {
+    let rates = state.tasks_state().spawn_rates();
+    let points = |f: fn(&SpawnRate) -> f64| {
+        rates
+            .iter()
+            .map(|rate| (-secs_before(now, rate.at), f(rate)))
+            .collect::<Vec<_>>()
+    };
+    let spawned = points(|rate| rate.spawned);
+    let completed = points(|rate| rate.completed);
+    let max = spawned
+        .iter()
+        .chain(&completed)
+        .map(|&(_, rate)| rate)
+        .fold(1.0, f64::max);
+
+    let mut title = vec![bold("Spawn Rate")];
+    if let Some(latest) = rates.back() {
+        title.push(Span::raw(format!(
+            " {:.1}/s spawned, {:.1}/s completed",
+            latest.spawned, latest.completed
+        )));
+    }
+    let datasets = vec![
+        dataset(styles, "spawned", styles.fg(Color::LightBlue), &spawned),
+        dataset(
+            styles,
+            "completed",
+            styles.fg(Color::LightGreen),
+            &completed,
+        ),
+    ];
+    let chart = Chart::new(datasets)
+        .block(styles.border_block().title(Spans::from(title)))
+        .x_axis(time_axis(styles, now, rates.front().map(|rate| rate.at)))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}/s", max))]),
+        );
+    frame.render_widget(chart, area);
+}
+
+fn render_counts<B: tui::backend::Backend>(
+    styles: &view::Styles,
+    frame: &mut tui::terminal::Frame<B>,
+    area: layout::Rect,
+    now: SystemTime,
+    state: &State,
+) {
+    let counts = state.tasks_state().counts();
+    let points = |f: fn(&TaskCounts) -> usize| {
+        counts
+            .iter()
+            .map(|count| (-secs_before(now, count.at), f(count) as f64))
+            .collect::<Vec<_>>()
+    };
+    let live = points(TaskCounts::live);
+    let running = points(|count| count.running);
+    let idle = points(|count| count.idle);
+    let max = counts
+        .iter()
+        .map(TaskCounts::live)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut title = vec![bold("Tasks")];
+    if let Some(latest) = counts.back() {
+        title.push(Span::raw(format!(
+            " {} live ({} running, {} idle)",
+            latest.live(),
+            latest.running,
+            latest.idle
+        )));
+    }
+    let datasets = vec![
+        dataset(styles, "live", styles.fg(Color::LightBlue), &live),
+        dataset(
+            styles,
+            "running",
+            TaskState::Running.style(styles),
+            &running,
+        ),
+        dataset(styles, "idle", TaskState::Idle.style(styles), &idle),
+    ];
+    let chart = Chart::new(datasets)
+        .block(styles.border_block().title(Spans::from(title)))
+        .x_axis(time_axis(styles, now, counts.front().map(|count| count.at)))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max as f64])
+                .labels(vec![Span::raw("0"), Span::raw(max.to_string())]),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Returns a line of the given `data` for a chart.
+fn dataset<'a>(
+    styles: &view::Styles,
+    name: &'a str,
+    style: Style,
+    data: &'a [(f64, f64)],
+) -> Dataset<'a> {
+    // Braille dots draw smoother lines, but aren't ASCII.
+    let marker = if styles.utf8 {
+        symbols::Marker::Braille
+    } else {
+        symbols::Marker::Dot
+    };
+    Dataset::default()
+        .name(name)
+        .marker(marker)
+        .graph_type(GraphType::Line)
+        .style(style)
+        .data(data)
+}
+
+/// Returns the time axis for a chart of points since `start`, in seconds
+/// before `now`.
+fn time_axis(styles: &view::Styles, now: SystemTime, start: Option<SystemTime>) -> Axis<'static> {
+    let span = start.map_or(0.0, |start| secs_before(now, start));
+    let start_label = start
+        .and_then(|start| now.duration_since(start).ok())
+        .map(|ago| format!("-{}", styles.short_duration(ago, 0)))
+        .unwrap_or_default();
+    Axis::default()
+        .bounds([-span.max(1.0), 0.0])
+        .labels(vec![Span::raw(start_label), Span::raw("now")])
+}
+
+fn secs_before(now: SystemTime, at: SystemTime) -> f64 {
+    now.duration_since(at).unwrap_or_default().as_secs_f64()
+}
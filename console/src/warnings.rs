@@ -1,5 +1,14 @@
-use crate::state::tasks::Task;
-use std::{fmt::Debug, rc::Rc};
+use crate::state::{
+    resources::Resource,
+    tasks::{Task, TasksState},
+    State,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
 
 /// A warning for a particular type of monitored entity (e.g. task or resource).
 ///
@@ -44,6 +53,31 @@ pub trait Warn<T>: Debug {
     // TODO(eliza): it would be nice if we had separate plural and singular
     // versions of this, like "56 tasks have..." vs "1 task has...".
     fn summary(&self) -> &str;
+
+    /// Returns how severe the warning is for `val`, so that the worst
+    /// instance of a warning detected for many entities can be pointed out.
+    ///
+    /// Higher values are worse. By default, every instance is equally severe,
+    /// and the entity seen first is considered the worst.
+    fn severity(&self, _val: &T) -> u64 {
+        0
+    }
+
+    /// Returns how serious the warning is in general.
+    ///
+    /// Most warnings point out something that may be a problem. Warnings
+    /// which almost certainly mean a bug, such as a task which can never be
+    /// woken again, are critical.
+    fn level(&self) -> Level {
+        Level::Warning
+    }
+}
+
+/// How serious a kind of warning is.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum Level {
+    Warning,
+    Critical,
 }
 
 #[derive(Debug)]
@@ -88,6 +122,166 @@ impl<T> Linter<T> {
     pub(crate) fn summary(&self) -> &str {
         self.0.summary()
     }
+
+    pub(crate) fn severity(&self, val: &T) -> u64 {
+        self.0.severity(val)
+    }
+
+    pub(crate) fn level(&self) -> Level {
+        self.0.level()
+    }
+
+    /// Returns `true` if `self` and `other` are the same linter, or clones of
+    /// it.
+    pub(crate) fn is(&self, other: &Self) -> bool {
+        Rc::as_ptr(&self.0) as *const () == Rc::as_ptr(&other.0) as *const ()
+    }
+}
+
+/// Tracks which warnings and alerts are firing, to find the ones which
+/// started firing since they were last checked.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    /// The task ID (or `None`, for alerts) and summary of each warning that
+    /// was firing when last checked.
+    firing: HashSet<(Option<u64>, String)>,
+}
+
+/// A warning or alert which started firing.
+#[derive(Debug, Clone)]
+pub(crate) struct Fired {
+    /// The task that the warning is for, or `None` if this is an alert on the
+    /// process as a whole.
+    pub(crate) task_id: Option<u64>,
+    /// The summary of the kind of warning, or, for alerts, the watch's
+    /// expression.
+    pub(crate) summary: String,
+    pub(crate) message: String,
+    pub(crate) level: Level,
+}
+
+impl Tracker {
+    /// Returns the warnings and alerts in `state` which were not firing when
+    /// it was last checked.
+    pub(crate) fn check(&mut self, state: &State) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        let mut firing = HashSet::with_capacity(self.firing.len());
+        for alert in state.alerts() {
+            let key = (None, alert.watch().to_string());
+            if !self.firing.contains(&key) {
+                fired.push(Fired {
+                    task_id: None,
+                    summary: key.1.clone(),
+                    message: alert.to_string(),
+                    level: Level::Warning,
+                });
+            }
+            firing.insert(key);
+        }
+        for task in state.tasks_state().tasks() {
+            let task = task.borrow();
+            for warning in task.warnings() {
+                let key = (Some(task.id()), warning.summary().to_string());
+                if !self.firing.contains(&key) {
+                    fired.push(Fired {
+                        task_id: Some(task.id()),
+                        summary: key.1.clone(),
+                        message: warning.format(&task),
+                        level: warning.level(),
+                    });
+                }
+                firing.insert(key);
+            }
+        }
+        self.firing = firing;
+        fired
+    }
+}
+
+/// A log of the warnings detected for tasks, which keeps them after they stop
+/// firing, so that transient warnings can still be read.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    /// The warnings which are firing, by task ID and summary.
+    firing: HashMap<(u64, String), Entry>,
+    /// The warnings which stopped firing, the most recently cleared last.
+    resolved: VecDeque<Entry>,
+}
+
+/// A warning detected for a task, in the [`History`].
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub(crate) task_id: u64,
+    /// The task's name, which is kept in case the task is removed.
+    pub(crate) task_name: Option<String>,
+    pub(crate) fired_at: SystemTime,
+    /// When the warning stopped firing, if it has.
+    pub(crate) cleared_at: Option<SystemTime>,
+    /// The description of the warning when it was most severe.
+    pub(crate) peak: String,
+    peak_severity: u64,
+}
+
+impl History {
+    /// How many warnings which stopped firing are kept.
+    const MAX_RESOLVED: usize = 1000;
+
+    /// Records the warnings which `tasks` have as of `now`, and the time
+    /// that any warnings which no longer apply were cleared at.
+    ///
+    /// A warning is cleared when its task is removed, as well as when the
+    /// task no longer has the warning.
+    pub(crate) fn check(&mut self, now: SystemTime, tasks: &TasksState) {
+        let mut firing = HashMap::with_capacity(self.firing.len());
+        for task in tasks.tasks() {
+            let task = task.borrow();
+            for warning in task.warnings() {
+                let key = (task.id(), warning.summary().to_string());
+                let severity = warning.severity(&task);
+                let entry = match self.firing.remove(&key) {
+                    Some(mut entry) => {
+                        if severity > entry.peak_severity {
+                            entry.peak = warning.format(&task);
+                            entry.peak_severity = severity;
+                        }
+                        entry
+                    }
+                    None => Entry {
+                        task_id: task.id(),
+                        task_name: task.name().map(str::to_string),
+                        fired_at: now,
+                        cleared_at: None,
+                        peak: warning.format(&task),
+                        peak_severity: severity,
+                    },
+                };
+                firing.insert(key, entry);
+            }
+        }
+
+        for (_, mut entry) in self.firing.drain() {
+            entry.cleared_at = Some(now);
+            self.resolved.push_back(entry);
+        }
+        while self.resolved.len() > Self::MAX_RESOLVED {
+            self.resolved.pop_front();
+        }
+        self.firing = firing;
+    }
+
+    /// Returns when the warning with `summary` started firing for the task
+    /// with the given ID, if it is firing.
+    pub(crate) fn fired_at(&self, task_id: u64, summary: &str) -> Option<SystemTime> {
+        self.firing
+            .get(&(task_id, summary.to_string()))
+            .map(|entry| entry.fired_at)
+    }
+
+    /// Returns the warnings which stopped firing, the most recently cleared
+    /// first.
+    pub(crate) fn resolved(&self) -> impl Iterator<Item = &Entry> {
+        self.resolved.iter().rev()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -132,6 +326,154 @@ impl Warn<Task> for SelfWakePercent {
             self.min_percent, self_wakes
         )
     }
+
+    fn severity(&self, task: &Task) -> u64 {
+        task.self_wake_percent()
+    }
+}
+
+/// Warns about tasks which, on average, waited too long to be polled after
+/// being woken recently, which means that the runtime can't keep up.
+#[derive(Clone, Debug)]
+pub(crate) struct ScheduledDelay {
+    max_delay: Duration,
+    /// How long before the task's latest update its delays are averaged over.
+    window: Duration,
+    description: String,
+}
+
+impl ScheduledDelay {
+    pub(crate) const DEFAULT_MAX_DELAY: Duration = Duration::from_millis(50);
+    pub(crate) const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+
+    pub(crate) fn new(max_delay: Duration, window: Duration) -> Self {
+        Self {
+            max_delay,
+            window,
+            description: format!(
+                "tasks have waited over {:?} to be polled after being woken",
+                max_delay
+            ),
+        }
+    }
+
+    fn recent_delay(&self, task: &Task) -> Option<Duration> {
+        let updated_at = task.updated_at()?;
+        let since = updated_at.checked_sub(self.window).unwrap_or(updated_at);
+        task.mean_scheduling_delay_since(since)
+    }
+}
+
+impl Default for ScheduledDelay {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_DELAY, Self::DEFAULT_WINDOW)
+    }
+}
+
+impl Warn<Task> for ScheduledDelay {
+    fn summary(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn check(&self, task: &Task) -> bool {
+        !task.is_completed()
+            && matches!(self.recent_delay(task), Some(delay) if delay > self.max_delay)
+    }
+
+    fn format(&self, task: &Task) -> String {
+        let delay = self.recent_delay(task).unwrap_or_default();
+        format!(
+            "This task has waited {:.3?} on average to be polled after being woken in the last {:?}, \
+             more than {:?}",
+            delay, self.window, self.max_delay
+        )
+    }
+
+    fn severity(&self, task: &Task) -> u64 {
+        self.recent_delay(task).unwrap_or_default().as_micros() as u64
+    }
+}
+
+/// Warns about tasks with many wakers, whose number kept growing recently
+/// without ever falling, which usually means wakers are being cloned into a
+/// collection and never dropped.
+#[derive(Clone, Debug)]
+pub(crate) struct WakerGrowth {
+    min_wakers: u64,
+    /// How long before the task's latest update its wakers must have kept
+    /// growing for.
+    window: Duration,
+    description: String,
+}
+
+impl WakerGrowth {
+    pub(crate) const DEFAULT_MIN_WAKERS: u64 = 1000;
+    pub(crate) const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+
+    pub(crate) fn new(min_wakers: u64, window: Duration) -> Self {
+        Self {
+            min_wakers,
+            window,
+            description: format!(
+                "tasks have over {} wakers, and keep cloning more without dropping them",
+                min_wakers
+            ),
+        }
+    }
+
+    /// Returns how many wakers the task had at the start of the window, if
+    /// the number grew, and never fell, during it.
+    fn grown_from(&self, task: &Task) -> Option<u64> {
+        let updated_at = task.updated_at()?;
+        let since = updated_at.checked_sub(self.window).unwrap_or(updated_at);
+        let mut counts = task
+            .activity()
+            .iter()
+            .filter(|activity| activity.at >= since)
+            .map(|activity| activity.waker_clones.saturating_sub(activity.waker_drops));
+        let first = counts.next()?;
+        let mut last = first;
+        for count in counts {
+            if count < last {
+                return None;
+            }
+            last = count;
+        }
+        Some(first).filter(|&first| last > first)
+    }
+}
+
+impl Default for WakerGrowth {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MIN_WAKERS, Self::DEFAULT_WINDOW)
+    }
+}
+
+impl Warn<Task> for WakerGrowth {
+    fn summary(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn check(&self, task: &Task) -> bool {
+        !task.is_completed()
+            && task.waker_count() > self.min_wakers
+            && self.grown_from(task).is_some()
+    }
+
+    fn format(&self, task: &Task) -> String {
+        let from = self.grown_from(task).unwrap_or_default();
+        format!(
+            "This task has {} wakers, up from {} in the last {:?} without ever falling; \
+             they may be leaking",
+            task.waker_count(),
+            from,
+            self.window
+        )
+    }
+
+    fn severity(&self, task: &Task) -> u64 {
+        task.waker_count()
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -149,4 +491,63 @@ impl Warn<Task> for LostWaker {
     fn format(&self, _: &Task) -> String {
         "This task has lost its waker, and will never be woken again.".into()
     }
+
+    fn level(&self) -> Level {
+        Level::Critical
+    }
+}
+
+/// Warns about resources, such as locks, semaphores, and channels, which have
+/// gone a long time without any activity and haven't been dropped, which
+/// usually means a guard or one half of a channel was leaked.
+///
+/// Timers are never warned about, since waiting a long time is what they're
+/// for.
+#[derive(Clone, Debug)]
+pub(crate) struct NeverReleased {
+    min_inactive: Duration,
+    description: String,
+}
+
+impl NeverReleased {
+    pub(crate) const DEFAULT_MIN_INACTIVE: Duration = Duration::from_secs(5 * 60);
+
+    pub(crate) fn new(min_inactive: Duration) -> Self {
+        Self {
+            min_inactive,
+            description: format!(
+                "resources have gone over {} without any activity, and may have been leaked",
+                humantime::format_duration(min_inactive)
+            ),
+        }
+    }
+}
+
+impl Default for NeverReleased {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MIN_INACTIVE)
+    }
+}
+
+impl Warn<Resource> for NeverReleased {
+    fn summary(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn check(&self, resource: &Resource) -> bool {
+        !resource.dropped() && !resource.is_timer() && resource.inactive_for() >= self.min_inactive
+    }
+
+    fn format(&self, resource: &Resource) -> String {
+        let inactive_for = Duration::from_secs(resource.inactive_for().as_secs());
+        format!(
+            "This resource has gone {} without any activity, and was never dropped; \
+             it may have been leaked",
+            humantime::format_duration(inactive_for)
+        )
+    }
+
+    fn severity(&self, resource: &Resource) -> u64 {
+        resource.inactive_for().as_secs()
+    }
 }
@@ -0,0 +1,670 @@
+//! User-defined watches, which raise alerts through the warnings system when a
+//! metric crosses a threshold.
+//!
+//! A watch is written as `<metric> <op> <value>`, optionally followed by
+//! `for <duration>`, in which case it only fires once the condition has held
+//! for that long. For example:
+//!
+//! ```text
+//! tasks > 10_000
+//! task.busy > 90% for 30s
+//! runtime.busy > 80% for 1m
+//! spawn_rate_jump >= 10
+//! ```
+//!
+//! Watches on the runtime's metrics never fire if the process doesn't report
+//! them.
+//!
+//! Watches on the process as a whole are listed with the warnings, while
+//! watches on individual tasks are raised as warnings on the tasks which
+//! match them.
+//!
+//! Lints are user-defined warnings on individual tasks, whose conditions are
+//! written like a filter on the tasks table, rather than on a single metric.
+//! For example:
+//!
+//! ```text
+//! warn "chatty" when wakes/polls>50 for 10s
+//! warn "hog" when busy/total*100>=90 polls>1000
+//! ```
+use crate::{
+    state::{
+        resources::ResourcesState,
+        runtime::RuntimeState,
+        tasks::{Task, TasksState},
+    },
+    util::Percentage,
+    view::{filter::Query, tasks::TasksTable},
+    warnings::{Linter, Warn},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// A user-defined warning, parsed from a string like
+/// `warn "chatty" when wakes/polls>50 for 10s`.
+#[derive(Debug, Clone)]
+pub(crate) struct Lint {
+    name: String,
+    /// The filter which tasks the lint fires for match.
+    condition: Query,
+    sustained: Option<Duration>,
+    source: String,
+}
+
+/// A condition on a metric, parsed from a string like `tasks > 10_000`.
+#[derive(Debug, Clone)]
+pub(crate) struct Watch {
+    metric: Metric,
+    op: Op,
+    threshold: f64,
+    sustained: Option<Duration>,
+    expr: String,
+    /// What the watch catches, shown instead of its expression when it fires.
+    label: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Metric {
+    // === process-wide metrics ===
+    /// The number of tasks which have not completed.
+    Tasks,
+    /// The number of tasks which are currently being polled.
+    RunningTasks,
+    /// The number of tasks which are neither running nor completed.
+    IdleTasks,
+    /// The number of resources which have not been dropped.
+    Resources,
+    /// The number of tasks spawned per second during the latest update
+    /// interval.
+    SpawnRate,
+    /// How many times the mean spawn rate of the preceding intervals the
+    /// latest spawn rate is.
+    SpawnRateJump,
+
+    // === runtime metrics ===
+    /// The mean percentage of time the runtime's workers spent polling.
+    RuntimeBusy,
+    /// The busiest worker's busy percentage.
+    RuntimeMaxWorkerBusy,
+    /// The number of tasks waiting in the runtime's global queue.
+    RuntimeInjectionQueue,
+    /// The number of tasks waiting in all of the workers' local queues.
+    RuntimeLocalQueue,
+    /// The number of threads spawned to run blocking operations.
+    RuntimeBlockingThreads,
+
+    // === per-task metrics ===
+    /// The percentage of a task's lifetime spent being polled.
+    TaskBusy,
+    /// The number of times a task has been polled.
+    TaskPolls,
+    /// The number of times a task has been woken.
+    TaskWakes,
+    /// The percentage of a task's wakeups which were self-wakes.
+    TaskSelfWakes,
+    /// The number of wakers a task currently has.
+    TaskWakers,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// The watches on the process as a whole, and the clock shared with the
+/// watches on individual tasks.
+#[derive(Debug, Default)]
+pub(crate) struct Watches {
+    clock: Clock,
+    process: Vec<ProcessWatch>,
+    has_task_watches: bool,
+}
+
+/// A process-wide watch which is currently firing.
+#[derive(Debug)]
+pub(crate) struct Alert<'a> {
+    watch: &'a Watch,
+    value: f64,
+}
+
+#[derive(Debug)]
+struct ProcessWatch {
+    watch: Watch,
+    /// When the condition started holding, if it currently holds.
+    since: Option<SystemTime>,
+    /// The metric's value, if the watch is firing.
+    firing: Option<f64>,
+}
+
+/// A watch on individual tasks, raised as a warning on each task it fires
+/// for.
+#[derive(Debug)]
+struct TaskWatch {
+    watch: Watch,
+    clock: Clock,
+    since: Since,
+    summary: String,
+}
+
+/// A user-defined lint, raised as a warning on each task it fires for.
+#[derive(Debug)]
+struct TaskLint {
+    lint: Lint,
+    clock: Clock,
+    since: Since,
+    summary: String,
+}
+
+/// When a condition on tasks started holding for each task it currently
+/// holds for.
+#[derive(Debug, Default)]
+struct Since(RefCell<HashMap<u64, SystemTime>>);
+
+/// The time of the update currently being applied.
+type Clock = Rc<Cell<Option<SystemTime>>>;
+
+// === impl Watches ===
+
+impl Watches {
+    /// Returns the watches on the process as a whole, and linters for the
+    /// watches on individual tasks and for the lints.
+    pub(crate) fn new(
+        watches: impl IntoIterator<Item = Watch>,
+        lints: impl IntoIterator<Item = Lint>,
+    ) -> (Self, Vec<Linter<Task>>) {
+        let mut this = Self::default();
+        let mut linters = Vec::new();
+        for lint in lints {
+            this.has_task_watches = true;
+            linters.push(Linter::new(TaskLint {
+                summary: format!("tasks match the lint \"{}\"", lint.name),
+                lint,
+                clock: this.clock.clone(),
+                since: Since::default(),
+            }));
+        }
+        for watch in watches {
+            if watch.metric.is_per_task() {
+                this.has_task_watches = true;
+                linters.push(Linter::new(TaskWatch {
+                    summary: format!("tasks match the watch `{}`", watch),
+                    watch,
+                    clock: this.clock.clone(),
+                    since: Since::default(),
+                }));
+            } else {
+                this.process.push(ProcessWatch {
+                    watch,
+                    since: None,
+                    firing: None,
+                });
+            }
+        }
+        (this, linters)
+    }
+
+    /// Sets the time of the update being applied, which watches on individual
+    /// tasks are checked as of.
+    pub(crate) fn set_now(&self, now: SystemTime) {
+        self.clock.set(Some(now));
+    }
+
+    /// Returns `true` if there are watches on individual tasks or lints, whose
+    /// conditions may change even for tasks that were not updated.
+    pub(crate) fn has_task_watches(&self) -> bool {
+        self.has_task_watches
+    }
+
+    /// Checks the process-wide watches against the current tasks,
+    /// resources, and runtime metrics.
+    pub(crate) fn check(
+        &mut self,
+        now: SystemTime,
+        tasks: &TasksState,
+        resources: &ResourcesState,
+        runtime: Option<&RuntimeState>,
+    ) {
+        for process in &mut self.process {
+            let value = process.watch.metric.of_process(tasks, resources, runtime);
+            process.firing = match value {
+                Some(value) if process.watch.op.holds(value, process.watch.threshold) => {
+                    let since = *process.since.get_or_insert(now);
+                    Some(value).filter(|_| process.watch.has_held(since, now))
+                }
+                _ => {
+                    process.since = None;
+                    None
+                }
+            };
+        }
+    }
+
+    /// Returns the process-wide watches which are currently firing.
+    pub(crate) fn alerts(&self) -> impl Iterator<Item = Alert<'_>> {
+        self.process.iter().filter_map(|process| {
+            Some(Alert {
+                watch: &process.watch,
+                value: process.firing?,
+            })
+        })
+    }
+}
+
+// === impl Alert ===
+
+impl Alert<'_> {
+    /// Returns the watch which fired.
+    pub(crate) fn watch(&self) -> &Watch {
+        self.watch
+    }
+}
+
+impl fmt::Display for Alert<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.watch.metric.format(self.value);
+        match self.watch.label {
+            Some(ref label) => write!(f, "{} ({})", label, value),
+            None => write!(f, "watch `{}` fired ({})", self.watch, value),
+        }
+    }
+}
+
+// === impl TaskWatch ===
+
+impl Warn<Task> for TaskWatch {
+    fn summary(&self) -> &str {
+        self.summary.as_str()
+    }
+
+    fn check(&self, task: &Task) -> bool {
+        let now = match self.clock.get() {
+            Some(now) => now,
+            None => return false,
+        };
+        let watch = &self.watch;
+        self.since.check(task, now, watch.sustained, || {
+            watch
+                .op
+                .holds(watch.metric.of_task(task, now), watch.threshold)
+        })
+    }
+
+    fn format(&self, task: &Task) -> String {
+        let now = self.clock.get().unwrap_or_else(SystemTime::now);
+        format!(
+            "This task matches the watch `{}` ({})",
+            self.watch,
+            self.watch
+                .metric
+                .format(self.watch.metric.of_task(task, now))
+        )
+    }
+}
+
+// === impl TaskLint ===
+
+impl Warn<Task> for TaskLint {
+    fn summary(&self) -> &str {
+        self.summary.as_str()
+    }
+
+    fn check(&self, task: &Task) -> bool {
+        let now = match self.clock.get() {
+            Some(now) => now,
+            None => return false,
+        };
+        let lint = &self.lint;
+        self.since.check(task, now, lint.sustained, || {
+            lint.condition.matches::<TasksTable>(task, now, None)
+        })
+    }
+
+    fn format(&self, _: &Task) -> String {
+        format!(
+            "This task matches the lint \"{}\" (`{}`)",
+            self.lint.name, self.lint.condition
+        )
+    }
+}
+
+// === impl Since ===
+
+impl Since {
+    /// Checks whether `holds` for `task` as of `now`, returning `true` if it
+    /// has held for at least `sustained`, if that is set.
+    fn check(
+        &self,
+        task: &Task,
+        now: SystemTime,
+        sustained: Option<Duration>,
+        holds: impl FnOnce() -> bool,
+    ) -> bool {
+        let mut since = self.0.borrow_mut();
+        // Completed tasks have nothing left to alert about, so stop tracking
+        // them.
+        if task.is_completed() || !holds() {
+            since.remove(&task.id());
+            return false;
+        }
+        let since = *since.entry(task.id()).or_insert(now);
+        has_held(sustained, since, now)
+    }
+}
+
+/// Returns `true` if a condition which started holding at `since` has held
+/// for at least `sustained` as of `now`.
+fn has_held(sustained: Option<Duration>, since: SystemTime, now: SystemTime) -> bool {
+    match sustained {
+        Some(sustained) => now.duration_since(since).unwrap_or_default() >= sustained,
+        None => true,
+    }
+}
+
+// === impl Lint ===
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let rest = source
+            .strip_prefix("warn ")
+            .ok_or_else(|| {
+                "expected a lint like `warn \"chatty\" when wakes/polls>50`".to_string()
+            })?
+            .trim_start();
+        let (name, rest) = rest
+            .strip_prefix('"')
+            .and_then(|rest| rest.split_once('"'))
+            .ok_or_else(|| "expected a quoted name after `warn`".to_string())?;
+        if name.trim().is_empty() {
+            return Err("expected a name after `warn`".to_string());
+        }
+        let condition = rest
+            .trim_start()
+            .strip_prefix("when ")
+            .ok_or_else(|| "expected `when` after the name".to_string())?;
+
+        let (condition, sustained) = match condition.rsplit_once(" for ") {
+            Some((condition, duration)) => {
+                let duration = duration
+                    .parse::<humantime::Duration>()
+                    .map_err(|error| format!("invalid duration `{}`: {}", duration, error))?;
+                (condition, Some(duration.into()))
+            }
+            None => (condition, None),
+        };
+        let condition = Query::comparisons::<TasksTable>(condition)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            condition,
+            sustained,
+            source,
+        })
+    }
+}
+
+// === impl Watch ===
+
+impl Watch {
+    /// Returns a watch which is described by `label`, rather than by its
+    /// expression, when it fires.
+    pub(crate) fn with_label(self, label: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    fn has_held(&self, since: SystemTime, now: SystemTime) -> bool {
+        has_held(self.sustained, since, now)
+    }
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl FromStr for Watch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expr = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut tokens = expr.split(' ');
+        let metric = tokens
+            .next()
+            .filter(|metric| !metric.is_empty())
+            .ok_or_else(|| "expected a watch like `tasks > 10_000`".to_string())?
+            .parse::<Metric>()?;
+        let op = tokens
+            .next()
+            .ok_or_else(|| format!("expected a comparison after `{}`", metric.name()))?
+            .parse::<Op>()?;
+        let threshold = tokens
+            .next()
+            .ok_or_else(|| "expected a value to compare against".to_string())?;
+        let threshold = threshold
+            .trim_end_matches('%')
+            .replace('_', "")
+            .parse::<f64>()
+            .map_err(|_| format!("invalid value `{}`", threshold))?;
+        let sustained = match tokens.next() {
+            None => None,
+            Some("for") => {
+                let duration = tokens
+                    .next()
+                    .ok_or_else(|| "expected a duration after `for`".to_string())?;
+                let duration = duration
+                    .parse::<humantime::Duration>()
+                    .map_err(|error| format!("invalid duration `{}`: {}", duration, error))?;
+                Some(duration.into())
+            }
+            Some(token) => return Err(format!("unexpected `{}`", token)),
+        };
+        if let Some(token) = tokens.next() {
+            return Err(format!("unexpected `{}`", token));
+        }
+
+        Ok(Self {
+            metric,
+            op,
+            threshold,
+            sustained,
+            expr,
+            label: None,
+        })
+    }
+}
+
+// === impl Metric ===
+
+impl Metric {
+    const ALL: &'static [Metric] = &[
+        Metric::Tasks,
+        Metric::RunningTasks,
+        Metric::IdleTasks,
+        Metric::Resources,
+        Metric::SpawnRate,
+        Metric::SpawnRateJump,
+        Metric::RuntimeBusy,
+        Metric::RuntimeMaxWorkerBusy,
+        Metric::RuntimeInjectionQueue,
+        Metric::RuntimeLocalQueue,
+        Metric::RuntimeBlockingThreads,
+        Metric::TaskBusy,
+        Metric::TaskPolls,
+        Metric::TaskWakes,
+        Metric::TaskSelfWakes,
+        Metric::TaskWakers,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Tasks => "tasks",
+            Metric::RunningTasks => "running_tasks",
+            Metric::IdleTasks => "idle_tasks",
+            Metric::Resources => "resources",
+            Metric::SpawnRate => "spawn_rate",
+            Metric::SpawnRateJump => "spawn_rate_jump",
+            Metric::RuntimeBusy => "runtime.busy",
+            Metric::RuntimeMaxWorkerBusy => "runtime.max_worker_busy",
+            Metric::RuntimeInjectionQueue => "runtime.injection_queue",
+            Metric::RuntimeLocalQueue => "runtime.local_queue",
+            Metric::RuntimeBlockingThreads => "runtime.blocking_threads",
+            Metric::TaskBusy => "task.busy",
+            Metric::TaskPolls => "task.polls",
+            Metric::TaskWakes => "task.wakes",
+            Metric::TaskSelfWakes => "task.self_wakes",
+            Metric::TaskWakers => "task.wakers",
+        }
+    }
+
+    fn is_per_task(self) -> bool {
+        matches!(
+            self,
+            Metric::TaskBusy
+                | Metric::TaskPolls
+                | Metric::TaskWakes
+                | Metric::TaskSelfWakes
+                | Metric::TaskWakers
+        )
+    }
+
+    fn is_percentage(self) -> bool {
+        matches!(
+            self,
+            Metric::TaskBusy
+                | Metric::TaskSelfWakes
+                | Metric::RuntimeBusy
+                | Metric::RuntimeMaxWorkerBusy
+        )
+    }
+
+    /// Returns the metric's value for the process as a whole, or `None` if
+    /// the process doesn't report it.
+    fn of_process(
+        self,
+        tasks: &TasksState,
+        resources: &ResourcesState,
+        runtime: Option<&RuntimeState>,
+    ) -> Option<f64> {
+        let runtime = runtime.map(RuntimeState::health);
+        let count_tasks =
+            |f: fn(&Task) -> bool| tasks.tasks().filter(|task| f(&task.borrow())).count();
+        let count = match self {
+            Metric::SpawnRate => return tasks.spawn_rate(),
+            Metric::SpawnRateJump => return tasks.spawn_rate_jump(),
+            Metric::RuntimeBusy => return Some(runtime?.mean_busy_percent),
+            Metric::RuntimeMaxWorkerBusy => return Some(runtime?.max_busy_percent),
+            Metric::RuntimeInjectionQueue => return Some(runtime?.injection_queue_depth as f64),
+            Metric::RuntimeLocalQueue => return Some(runtime?.local_queue_depth as f64),
+            Metric::RuntimeBlockingThreads => return Some(runtime?.blocking_threads as f64),
+            Metric::Tasks => count_tasks(|task| !task.is_completed()),
+            Metric::RunningTasks => count_tasks(Task::is_running),
+            Metric::IdleTasks => count_tasks(|task| !task.is_completed() && !task.is_running()),
+            Metric::Resources => resources
+                .resources()
+                .filter(|resource| !resource.borrow().dropped())
+                .count(),
+            _ => 0,
+        };
+        Some(count as f64)
+    }
+
+    fn of_task(self, task: &Task, now: SystemTime) -> f64 {
+        match self {
+            Metric::TaskBusy => {
+                let total = task.total(now).as_secs_f64();
+                if total > 0.0 {
+                    task.busy(now).as_secs_f64().min(total).percent_of(total)
+                } else {
+                    0.0
+                }
+            }
+            Metric::TaskPolls => task.total_polls() as f64,
+            Metric::TaskWakes => task.wakes() as f64,
+            Metric::TaskSelfWakes => task.self_wake_percent() as f64,
+            Metric::TaskWakers => task.waker_count() as f64,
+            _ => 0.0,
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        if self.is_percentage() {
+            format!("{:.0}%", value)
+        } else if self == Metric::SpawnRate {
+            format!("{:.1}/s", value)
+        } else if self == Metric::SpawnRateJump {
+            format!("{:.1}x", value)
+        } else {
+            format!("{}", value)
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metric::ALL
+            .iter()
+            .copied()
+            .find(|metric| metric.name() == s)
+            .ok_or_else(|| {
+                let names = Metric::ALL.iter().map(|metric| metric.name());
+                format!(
+                    "unknown metric `{}` (expected one of: {})",
+                    s,
+                    names.collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+}
+
+// === impl Op ===
+
+impl Op {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Gt => value > threshold,
+            Op::Ge => value >= threshold,
+            Op::Lt => value < threshold,
+            Op::Le => value <= threshold,
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "<" => Ok(Op::Lt),
+            "<=" => Ok(Op::Le),
+            s => Err(format!(
+                "unknown comparison `{}` (expected >, >=, <, or <=)",
+                s
+            )),
+        }
+    }
+}
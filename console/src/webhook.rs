@@ -0,0 +1,147 @@
+//! Posting warnings and alerts to a webhook as they fire, so that they can be
+//! noticed without watching the console.
+use crate::{
+    http, report,
+    state::State,
+    warnings::{Fired, Tracker},
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime},
+};
+use tonic::transport::Uri;
+
+/// Posts the warnings and alerts which start firing for a target to a
+/// webhook, posting each kind of warning, or each watch's alert, at most
+/// once every `interval`.
+#[derive(Debug)]
+pub(crate) struct Webhook {
+    url: Uri,
+    interval: Duration,
+    tracker: Tracker,
+    /// When each kind of warning or watch was last posted, by its summary.
+    last_posted: HashMap<String, Instant>,
+}
+
+impl Webhook {
+    pub(crate) fn new(url: Uri, interval: Duration) -> Self {
+        Self {
+            url,
+            interval,
+            tracker: Tracker::default(),
+            last_posted: HashMap::new(),
+        }
+    }
+
+    /// Posts any warnings and alerts which started firing in `state` since it
+    /// was last checked, except those of a kind which was posted less than
+    /// the interval ago.
+    ///
+    /// All of them are posted together, in a single request. The request is
+    /// sent in the background, and failures are logged rather than returned.
+    pub(crate) fn check(&mut self, target: &Uri, state: &State) {
+        let mut fired = self.tracker.check(state);
+        if fired.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let interval = self.interval;
+        let last_posted = &mut self.last_posted;
+        last_posted.retain(|_, posted| now.duration_since(*posted) < interval);
+        let before = fired.len();
+        fired.retain(|fired| match last_posted.get(&fired.summary) {
+            // Warnings of the same kind posted in this batch are kept.
+            Some(&posted) => posted == now,
+            None => {
+                last_posted.insert(fired.summary.clone(), now);
+                true
+            }
+        });
+        if fired.len() < before {
+            tracing::debug!(
+                skipped = before - fired.len(),
+                "not posting warnings to the webhook, since they were posted recently"
+            );
+        }
+        if fired.is_empty() {
+            return;
+        }
+
+        let now = state.last_updated_at().unwrap_or_else(SystemTime::now);
+        let payload = payload(target, state, now, &fired);
+        tokio::spawn(post(self.url.clone(), payload));
+    }
+}
+
+/// The JSON payload posted for warnings and alerts that fired.
+#[derive(Debug, Serialize)]
+struct Payload {
+    /// A summary of every warning and alert, so that the payload can be sent
+    /// to chat services' incoming webhooks as-is.
+    text: String,
+    target: String,
+    /// When the warnings and alerts fired, in RFC 3339 format.
+    at: String,
+    warnings: Vec<Warning>,
+}
+
+#[derive(Debug, Serialize)]
+struct Warning {
+    /// `lint` for a task's warning, or `watch` for an alert.
+    kind: &'static str,
+    message: String,
+    /// The task the warning is about, if it is a lint.
+    task: Option<report::Task>,
+}
+
+/// Returns the payload for warnings and alerts that fired.
+fn payload(target: &Uri, state: &State, now: SystemTime, fired: &[Fired]) -> Payload {
+    let mut text = format!("tokio-console: {}", target);
+    for fired in fired {
+        text.push_str("\n- ");
+        if let Some(task) = fired.task_id.and_then(|id| state.tasks_state().task(id)) {
+            let task = task.borrow();
+            text.push_str(&format!("task {}", task.id()));
+            if let Some(name) = task.name() {
+                text.push_str(&format!(" ({})", name));
+            }
+            text.push_str(": ");
+        }
+        text.push_str(&fired.message);
+    }
+
+    let warnings = fired
+        .iter()
+        .map(|fired| Warning {
+            kind: if fired.task_id.is_some() {
+                "lint"
+            } else {
+                "watch"
+            },
+            message: fired.message.clone(),
+            task: fired
+                .task_id
+                .and_then(|id| state.tasks_state().task(id))
+                .map(|task| report::Task::new(&task.borrow(), now)),
+        })
+        .collect();
+    Payload {
+        text,
+        target: target.to_string(),
+        at: humantime::format_rfc3339_nanos(now).to_string(),
+        warnings,
+    }
+}
+
+async fn post(url: Uri, payload: Payload) {
+    let result = match serde_json::to_string(&payload) {
+        Ok(payload) => http::post_json(&url, payload).await,
+        Err(error) => Err(error.to_string()),
+    };
+    match result {
+        Ok(()) => tracing::debug!("posted to the webhook"),
+        Err(error) => tracing::warn!(%error, "failed to post to the webhook"),
+    }
+}